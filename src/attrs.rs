@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+// Elements usvg turns into paths
+pub const SHAPE_TAGS: [&str; 7] = ["path", "rect", "circle", "ellipse", "line", "polyline", "polygon"];
+
+/// `data-*` attributes of one shape element, keyed without the `data-` prefix,
+/// and under `class` the classes of the element and its ancestors.
+pub type Attrs = HashMap<String, String>;
+
+/// usvg drops unknown attributes, and classes, so they are collected from the
+/// raw XML beforehand and keyed by element id. Shapes carrying `data-*`
+/// attributes or classes (directly or inherited from an ancestor group) but
+/// no id get a generated one spliced into the returned document, so usvg
+/// paths can be matched back.
+pub fn annotate(xml: &str) -> Result<(String, HashMap<String, Attrs>), roxmltree::Error> {
+    let doc = roxmltree::Document::parse_with_options(xml, roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() })?;
+
+    let mut by_id = HashMap::new();
+    let mut inserts = Vec::new();
+    for node in doc.descendants().filter(|n| n.is_element()) {
+        if !SHAPE_TAGS.contains(&node.tag_name().name()) {
+            continue;
+        }
+
+        // walk up so the nearest declaration of an attribute wins
+        let mut attrs = Attrs::new();
+        let mut classes = Vec::new();
+        for n in node.ancestors() {
+            for a in n.attributes() {
+                if let Some(key) = a.name().strip_prefix("data-") {
+                    attrs.entry(key.to_string()).or_insert_with(|| a.value().to_string());
+                }
+            }
+            classes.extend(n.attribute("class").into_iter().flat_map(str::split_whitespace));
+        }
+        if !classes.is_empty() {
+            attrs.insert("class".into(), classes.join(" "));
+        }
+        if attrs.is_empty() {
+            continue;
+        }
+
+        let id = match node.attribute("id") {
+            Some(id) => id.to_string(),
+            None => {
+                let id = format!("__sc{}", inserts.len());
+                // right after `<tag`
+                let start = node.range().start + 1;
+                let name_len = xml[start..].find(|c: char| c.is_whitespace() || c == '/' || c == '>').unwrap_or(0);
+                inserts.push((start + name_len, format!(r#" id="{id}""#)));
+                id
+            }
+        };
+        by_id.insert(id, attrs);
+    }
+
+    let mut out = String::with_capacity(xml.len() + inserts.len() * 16);
+    let mut last = 0;
+    for (pos, text) in inserts {
+        out.push_str(&xml[last..pos]);
+        out.push_str(&text);
+        last = pos;
+    }
+    out.push_str(&xml[last..]);
+
+    Ok((out, by_id))
+}
+
+/// The element's own first class, or else the nearest one above it.
+pub fn class(attrs: Option<&Attrs>) -> Option<String> {
+    attrs?.get("class")?.split(' ').next().map(str::to_string)
+}
+
+/// Whether the element or one of its ancestors has class `name`.
+pub fn has_class(attrs: Option<&Attrs>, name: &str) -> bool {
+    attrs.and_then(|a| a.get("class")).is_some_and(|c| c.split(' ').any(|c| c == name))
+}
+
+/// Parse a numeric attribute, ignoring values that aren't numbers.
+pub fn number(attrs: Option<&Attrs>, key: &str) -> Option<f64> {
+    attrs?.get(key)?.trim().parse().ok()
+}
+
+/// Parse two numbers separated by a comma and/or whitespace, e.g. `"1 5"`.
+pub fn parse_pair(s: &str) -> Option<(f64, f64)> {
+    let mut it = s.split(|c: char| c == ',' || c.is_whitespace()).filter(|v| !v.is_empty());
+    let pair = (it.next()?.parse().ok()?, it.next()?.parse().ok()?);
+    it.next().is_none().then_some(pair)
+}
+
+/// Parse a length in user units, or in `mm`, `cm`, `in`, `pt` or `px` at
+/// CSS's 96 per inch, e.g. `"3mm"`.
+pub fn parse_length(s: &str) -> Result<f64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value: f64 = value.trim().parse().map_err(|_| format!("bad length {s:?}"))?;
+    let per_unit = match unit {
+        "" | "px" => 1.0,
+        "mm" => 96.0 / 25.4,
+        "cm" => 96.0 / 2.54,
+        "in" => 96.0,
+        "pt" => 96.0 / 72.0,
+        _ => return Err(format!("unknown unit {unit:?} in {s:?}")),
+    };
+    Ok(value * per_unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lengths_are_user_units_at_96_per_inch() {
+        assert_eq!(parse_length("12"), Ok(12.0));
+        assert_eq!(parse_length(" 2in "), Ok(192.0));
+        assert_eq!(parse_length("25.4mm"), Ok(96.0));
+        assert_eq!(parse_length("3ft"), Err("unknown unit \"ft\" in \"3ft\"".to_string()));
+        assert_eq!(parse_length("mm"), Err("bad length \"mm\"".to_string()));
+    }
+
+    #[test]
+    fn pairs_are_exactly_two_numbers() {
+        assert_eq!(parse_pair("1 5"), Some((1.0, 5.0)));
+        assert_eq!(parse_pair(" 1.5, -2 "), Some((1.5, -2.0)));
+        assert_eq!(parse_pair("1"), None);
+        assert_eq!(parse_pair("1 2 3"), None);
+        assert_eq!(parse_pair("1 x"), None);
+    }
+}
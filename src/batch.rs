@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// what was last written into the output directory, and with which options
+const MANIFEST: &str = ".svg-combiner-batch";
+
+/// `svg-combiner batch [--out-dir DIR] [--name-template T] [--jobs N]
+/// [--force] [--tui] INPUTS... [-- OPTIONS...]`: every input SVG (or every
+/// SVG in an input directory) combined on its own with OPTIONS, into DIR
+/// (`combined` by default), N at a time. Like make, a file whose output is
+/// newer than it and was made with the same options is skipped, so an
+/// interrupted batch picks up where it stopped; `--force` makes everything
+/// again. `--tui` shows how many files are made and failed as they finish.
+pub fn run(args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let (ours, options) = match args.iter().position(|a| a == "--") {
+        Some(i) => (&args[..i], &args[i + 1..]),
+        None => (&args[..], &[][..]),
+    };
+    let mut out_dir = PathBuf::from("combined");
+    let mut template = "{stem}.{ext}".to_string();
+    let mut force = false;
+    let mut jobs = 1;
+    let mut tui = false;
+    let mut inputs = Vec::new();
+    let mut ours = ours.iter();
+    while let Some(arg) = ours.next() {
+        match arg.as_str() {
+            "--out-dir" => out_dir = ours.next().ok_or("--out-dir needs a directory")?.into(),
+            "--name-template" => template = ours.next().ok_or("--name-template needs a template")?.clone(),
+            "--force" => force = true,
+            "--tui" => tui = true,
+            "--jobs" | "-j" => jobs = ours.next().and_then(|n| n.parse().ok()).filter(|&n| n > 0).ok_or("--jobs needs a number above 0")?,
+            // the default, for saying so
+            "--resume" => force = false,
+            other if other.starts_with('-') => {
+                return Err(format!("unknown batch option {other}; options for every run go after --").into());
+            }
+            input => inputs.extend(svgs(Path::new(input))?),
+        }
+    }
+    if inputs.is_empty() {
+        return Err("batch needs input SVGs or directories of them".into());
+    }
+    let spec = match options.iter().position(|a| a == "--format") {
+        Some(i) => options.get(i + 1).ok_or("--format needs a name")?.as_str(),
+        None => "svg",
+    };
+    let format = crate::export::parse(spec)?;
+    let stamp = format!("{:016x}", crate::cache::hash(format!("{} {}", env!("CARGO_PKG_VERSION"), options.join("\0")).as_bytes()));
+
+    fs::create_dir_all(&out_dir)?;
+    let manifest = read_manifest(&out_dir.join(MANIFEST));
+    let mut todo = Vec::new();
+    for (n, input) in inputs.iter().enumerate() {
+        let stem = input.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let name = crate::naming::expand(&template, |name| match name {
+            "stem" => Some(stem.clone()),
+            "index" => Some((n + 1).to_string()),
+            "ext" => Some(format.extension().into()),
+            _ => None,
+        })?;
+        let output = out_dir.join(&name);
+        if !force && manifest.get(&name) == Some(&stamp) && newer(&output, input) {
+            tracing::debug!("  {} is up to date", output.display());
+            continue;
+        }
+        todo.push((input, name, output));
+    }
+    let skipped = inputs.len() - todo.len();
+
+    let exe = std::env::current_exe()?;
+    let mut progress = crate::progress::Progress::new(tui);
+    progress.batch(todo.len());
+    let progress = Mutex::new(progress);
+    let manifest = Mutex::new(manifest);
+    let failures = Mutex::new(Vec::new());
+    let next = AtomicUsize::new(0);
+    // one run per file, so a file that breaks the combiner only fails itself
+    let combine = |input: &Path, output: &Path| -> Result<(), String> {
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let run = Command::new(&exe)
+            .arg(input)
+            .args(options)
+            // named, since an extension like .json can stand for more than one
+            .args(["-o".to_string(), format!("{spec}:{}", output.display())])
+            // whether to make it again was decided here
+            .args(["--quiet", "--force"])
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !run.status.success() {
+            return Err(String::from_utf8_lossy(&run.stderr).trim().to_string());
+        }
+        Ok(())
+    };
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(todo.len()) {
+            scope.spawn(|| {
+                while let Some((input, name, output)) = todo.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let result = combine(input, output);
+                    progress.lock().unwrap_or_else(|e| e.into_inner()).file(input.display().to_string(), result.is_ok());
+                    let mut manifest = manifest.lock().unwrap_or_else(|e| e.into_inner());
+                    match result {
+                        Ok(()) => {
+                            tracing::info!("✓ {} → {}", input.display(), output.display());
+                            manifest.insert(name.clone(), stamp.clone());
+                        }
+                        Err(e) => {
+                            tracing::warn!("⚠️ {}: {e}", input.display());
+                            manifest.remove(name);
+                            failures.lock().unwrap_or_else(|e| e.into_inner()).push(input.display().to_string());
+                        }
+                    }
+                    // kept after every file, so an interruption loses at most the runs going on
+                    if let Err(e) = write_manifest(&out_dir.join(MANIFEST), &manifest) {
+                        tracing::warn!("⚠️ can't keep track of the batch: {e}");
+                    }
+                }
+            });
+        }
+    });
+
+    let mut failures = failures.into_inner().unwrap_or_else(|e| e.into_inner());
+    failures.sort();
+    let made = todo.len() - failures.len();
+    tracing::info!("\n✅ Batch done: {made} made, {skipped} up to date, {} failed", failures.len());
+    if !failures.is_empty() {
+        tracing::warn!("Failed:");
+        for file in &failures {
+            tracing::warn!("  {file}");
+        }
+        return Err(format!("{} file(s) failed", failures.len()).into());
+    }
+    Ok(())
+}
+
+/// `path` itself, or the SVG files right inside it if it's a directory.
+fn svgs(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut found: Vec<PathBuf> = fs::read_dir(path)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "svg" || e == "svgz"))
+        .collect();
+    found.sort();
+    Ok(found)
+}
+
+/// Whether `output` exists and was written after `input` last changed.
+fn newer(output: &Path, input: &Path) -> bool {
+    let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+    matches!((modified(output), modified(input)), (Some(o), Some(i)) if o >= i)
+}
+
+fn read_manifest(path: &Path) -> HashMap<String, String> {
+    let text = fs::read_to_string(path).unwrap_or_default();
+    text.lines().filter_map(|line| line.split_once('\t')).map(|(stamp, name)| (name.to_string(), stamp.to_string())).collect()
+}
+
+fn write_manifest(path: &Path, manifest: &HashMap<String, String>) -> std::io::Result<()> {
+    let mut lines: Vec<String> = manifest.iter().map(|(name, stamp)| format!("{stamp}\t{name}\n")).collect();
+    lines.sort();
+    fs::write(path, lines.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("svg-combiner-batch-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn bad_batches_are_refused_before_anything_runs() {
+        let error = |list: &[&str]| run(args(list)).err().unwrap().to_string();
+        assert_eq!(error(&["--out-dir"]), "--out-dir needs a directory");
+        assert_eq!(error(&["--jobs", "0", "a.svg"]), "--jobs needs a number above 0");
+        assert_eq!(error(&["--gap", "2", "a.svg"]), "unknown batch option --gap; options for every run go after --");
+        assert_eq!(error(&["--", "--gap", "2"]), "batch needs input SVGs or directories of them");
+    }
+
+    #[test]
+    fn directories_give_their_svgs_in_order() {
+        let dir = scratch("inputs");
+        for file in ["b.svg", "a.svgz", "notes.txt", "c.SVG"] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        fs::create_dir_all(dir.join("nested.svg")).unwrap();
+        let found = svgs(&dir).unwrap();
+        assert_eq!(found, [dir.join("a.svgz"), dir.join("b.svg"), dir.join("nested.svg")]);
+        // a file is taken as it is, whatever it is called
+        assert_eq!(svgs(&dir.join("notes.txt")).unwrap(), [dir.join("notes.txt")]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn outputs_are_newer_once_written_after_their_input() {
+        let dir = scratch("newer");
+        let (input, output) = (dir.join("in.svg"), dir.join("out.svg"));
+        fs::write(&input, "").unwrap();
+        assert!(!newer(&output, &input));
+        fs::write(&output, "").unwrap();
+        assert!(newer(&output, &input));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn the_manifest_keeps_each_outputs_stamp() {
+        let dir = scratch("manifest");
+        let path = dir.join(MANIFEST);
+        assert!(read_manifest(&path).is_empty());
+        let manifest = HashMap::from([("b.svg".to_string(), "0002".to_string()), ("a b.svg".to_string(), "0001".to_string())]);
+        write_manifest(&path, &manifest).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "0001\ta b.svg\n0002\tb.svg\n");
+        assert_eq!(read_manifest(&path), manifest);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
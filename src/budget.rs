@@ -0,0 +1,134 @@
+use clipper2::*;
+
+use crate::density::reduce;
+use crate::labels::segment_distance;
+
+type Ring = Vec<(f64, f64)>;
+
+// halvings of the search interval, which leaves it at a millionth of the
+// drawing's size
+const STEPS: usize = 20;
+
+/// The result simplified, with the largest tolerance used and how far
+/// apart the rings before and after get at most.
+pub struct Simplified<P: PointScaler> {
+    pub rings: Paths<P>,
+    pub epsilon: f64,
+    pub error: f64,
+}
+
+/// Search for the smallest Douglas-Peucker tolerance bringing `rings`
+/// within `target` vertices. Every ring keeps at least three, so a target
+/// below that many per ring can't be met.
+pub fn to_target<P: PointScaler>(rings: &Paths<P>, target: usize) -> Result<Simplified<P>, String> {
+    let before = points(rings);
+    let floor = before.iter().map(|r| r.len().min(3)).sum::<usize>();
+    if target < floor {
+        return Err(format!("{} ring(s) need at least {floor} vertices, over --target-vertices {target}", before.len()));
+    }
+    let count = |rings: &[Ring]| rings.iter().map(Vec::len).sum::<usize>();
+    if count(&before) <= target {
+        return Ok(Simplified { rings: rings.clone(), epsilon: 0.0, error: 0.0 });
+    }
+    let b = rings.bounds();
+    let (mut low, mut high) = (0.0, b.size().x().hypot(b.size().y()).max(1.0));
+    let simplify = |epsilon: f64| -> Vec<Ring> { before.iter().map(|r| reduce(r, epsilon, true)).collect() };
+    let mut best = simplify(high);
+    for _ in 0..STEPS {
+        let mid = (low + high) / 2.0;
+        let tried = simplify(mid);
+        if count(&tried) <= target {
+            (high, best) = (mid, tried);
+        } else {
+            low = mid;
+        }
+    }
+    let error = deviation(&before, &best, high / 4.0);
+    Ok(Simplified { rings: best.into(), epsilon: high, error })
+}
+
+/// How far apart matching rings of `before` and `after` get at most, both
+/// ways: every vertex of one to the other ring, and points along the
+/// other's edges no more than `step` apart, so a shortcut the vertices
+/// don't show is measured too.
+pub fn deviation(before: &[Ring], after: &[Ring], step: f64) -> f64 {
+    before.iter().zip(after).map(|(old, new)| ring_deviation(old, new, step)).fold(0.0, f64::max)
+}
+
+fn ring_deviation(a: &[(f64, f64)], b: &[(f64, f64)], step: f64) -> f64 {
+    // `b` keeps some of `a`'s vertices, so every edge of `b` stands in for
+    // the run of `a` between its ends, and only needs measuring against it
+    let mut kept = Vec::with_capacity(b.len());
+    let mut j = 0;
+    for (i, p) in a.iter().enumerate() {
+        if b.get(j) == Some(p) {
+            kept.push(i);
+            j += 1;
+        }
+    }
+    if j < b.len() || kept.len() < 2 {
+        return one_way(a, b, step, true).max(one_way(b, a, step, true));
+    }
+    let mut worst: f64 = 0.0;
+    for (n, &start) in kept.iter().enumerate() {
+        let end = kept.get(n + 1).copied().unwrap_or(kept[0] + a.len());
+        let run: Vec<(f64, f64)> = (start..=end).map(|i| a[i % a.len()]).collect();
+        let chord = [run[0], run[run.len() - 1]];
+        worst = worst.max(one_way(&run, &chord, step, false)).max(one_way(&chord, &run, step, false));
+    }
+    worst
+}
+
+// the farthest any point along `from` gets from `to`, both rings if
+// `closed`, otherwise open lines
+fn one_way(from: &[(f64, f64)], to: &[(f64, f64)], step: f64, closed: bool) -> f64 {
+    let edges = |line: &[(f64, f64)]| if closed { line.len() } else { line.len().saturating_sub(1) };
+    let distance = |p| (0..edges(to).max(1)).map(|i| segment_distance(p, to[i], to[(i + 1) % to.len()])).fold(f64::INFINITY, f64::min);
+    let mut worst: f64 = 0.0;
+    for i in 0..edges(from) {
+        let (p, q) = (from[i], from[(i + 1) % from.len()]);
+        let n = ((q.0 - p.0).hypot(q.1 - p.1) / step.max(1e-9)).ceil().clamp(1.0, 1e4) as usize;
+        for k in 0..=n {
+            let t = k as f64 / n as f64;
+            let d = distance((p.0 + (q.0 - p.0) * t, p.1 + (q.1 - p.1) * t));
+            if d.is_finite() {
+                worst = worst.max(d);
+            }
+        }
+    }
+    worst
+}
+
+/// Every ring of `rings` simplified as far as it goes while staying within
+/// `max` of where it was, measured, ring by ring.
+pub fn within<P: PointScaler>(rings: &Paths<P>, max: f64) -> Simplified<P> {
+    let step = max / 4.0;
+    let (mut epsilon, mut error) = (0.0_f64, 0.0_f64);
+    let out: Vec<Ring> = points(rings)
+        .into_iter()
+        .map(|ring| {
+            // Douglas-Peucker keeps dropped vertices within its tolerance,
+            // so no tolerance above `max` can do
+            let (mut low, mut high) = (0.0, max);
+            let mut best = (ring.clone(), 0.0);
+            for _ in 0..STEPS {
+                let mid = (low + high) / 2.0;
+                let tried = reduce(&ring, mid, true);
+                let d = ring_deviation(&ring, &tried, step);
+                if d <= max {
+                    (low, best) = (mid, (tried, d));
+                } else {
+                    high = mid;
+                }
+            }
+            epsilon = epsilon.max(low);
+            error = error.max(best.1);
+            best.0
+        })
+        .collect();
+    Simplified { rings: out.into(), epsilon, error }
+}
+
+pub fn points<P: PointScaler>(rings: &Paths<P>) -> Vec<Ring> {
+    rings.iter().map(|r| r.iter().map(|p| (p.x(), p.y())).collect()).collect()
+}
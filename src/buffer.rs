@@ -0,0 +1,30 @@
+use clipper2::*;
+
+use crate::Contour;
+
+/// `--buffer 2 --end-cap round`: open paths outlined `width` wide, ends
+/// finished the way clipper's end types do. Joins are round along with
+/// round caps and mitered otherwise.
+#[derive(Clone, Copy)]
+pub struct Buffer {
+    pub width: f64,
+    pub cap: EndType,
+}
+
+pub fn parse_cap(spec: &str) -> Result<EndType, String> {
+    match spec {
+        "butt" => Ok(EndType::Butt),
+        "square" => Ok(EndType::Square),
+        "round" => Ok(EndType::Round),
+        // the last point joined back to the first, outlined on both sides
+        "joined" => Ok(EndType::Joined),
+        _ => Err(format!("unknown end cap {spec:?}, expected butt, square, round or joined")),
+    }
+}
+
+/// The area an open polyline covers when drawn `buffer.width` wide.
+pub fn outline<P: PointScaler>(line: &[(f64, f64)], buffer: Buffer) -> Vec<Contour> {
+    let join = if buffer.cap == EndType::Round { JoinType::Round } else { JoinType::Miter };
+    let outline: Paths<P> = inflate(vec![line.to_vec()], buffer.width / 2.0, join, buffer.cap, 2.0);
+    outline.into()
+}
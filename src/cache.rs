@@ -0,0 +1,284 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use clipper2::*;
+
+use crate::FlatShape;
+
+// told apart from the result it was dropped from
+const DROPPED: u64 = 0xd209_9ed0;
+// a whole group's result, rather than one shape's piece of it
+const WHOLE: u64 = 0xc1ea_5ed0;
+
+/// How much an earlier run may leave behind: results beyond `bytes` in
+/// all, oldest first, and any not used for `age`, are removed.
+#[derive(Clone, Copy)]
+pub struct Limits {
+    pub bytes: u64,
+    pub age: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits { bytes: 256 * 1024 * 1024, age: Duration::from_secs(30 * 24 * 3600) }
+    }
+}
+
+/// Results of earlier runs on disk. What every shape still owns once the
+/// gaps of the shapes combined after it are carved out depends only on it
+/// and on those of them reaching it, so it is kept under a hash of just
+/// these: a changed shape leaves the pieces of every shape it doesn't
+/// reach cached, wherever it is in the drawing. The pieces are merged and
+/// cleaned up as `crate::combine` does its result, which they make up to
+/// the rounding, whether they come from the cache or not.
+pub struct Cache {
+    dir: PathBuf,
+    limits: Limits,
+    /// Shapes, or whole groups, whose results were found.
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl Cache {
+    pub fn open(dir: &str, limits: Limits) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Cache { dir: dir.into(), limits, hits: 0, misses: 0 })
+    }
+
+    /// Same as `crate::combine`, using every piece an earlier run already
+    /// carved.
+    pub fn combine<P: PointScaler>(&mut self, shapes: &[FlatShape]) -> Result<(Paths<P>, Paths<P>), ClipperError> {
+        let grid = fnv(FNV_OFFSET, &P::MULTIPLIER.to_le_bytes());
+        let own: Vec<u64> = shapes.iter().map(|s| shape_hash(FNV_OFFSET, s)).collect();
+        let whole = whole::<P>(shapes);
+        if let (Some(done), Some(dropped)) = (self.load(whole), self.load(whole ^ DROPPED)) {
+            self.hits += 1;
+            return Ok((done, dropped));
+        }
+
+        let bounds: Vec<(f64, f64, f64, f64)> = shapes.iter().map(|s| extent(&s.contours)).collect();
+        // shapes with their gaps, grown once and only when a piece is carved
+        let mut expanded: Vec<Option<Paths<P>>> = vec![None; shapes.len()];
+        let mut pieces: Vec<Vec<(f64, f64)>> = Vec::new();
+        for (i, shape) in shapes.iter().enumerate() {
+            // the later shapes whose gap reaches this one
+            let reaching: Vec<usize> = (i + 1..shapes.len()).filter(|&j| meet(bounds[i], grow(bounds[j], shapes[j].offset))).collect();
+            let key = reaching.iter().fold(fnv(grid, &own[i].to_le_bytes()), |h, &j| fnv(h, &own[j].to_le_bytes()));
+            let piece = match self.load::<P>(key) {
+                Some(piece) => {
+                    self.hits += 1;
+                    piece
+                }
+                None => {
+                    self.misses += 1;
+                    let mut piece: Paths<P> = shape.contours.clone().into();
+                    for &j in &reaching {
+                        let gap = expanded[j].get_or_insert_with(|| {
+                            inflate(shapes[j].contours.clone(), shapes[j].offset, JoinType::Round, EndType::Polygon, 0.0)
+                        });
+                        piece = difference(piece, gap.clone(), FillRule::NonZero)?;
+                    }
+                    self.save(key, &piece);
+                    piece
+                }
+            };
+            pieces.extend(piece.iter().map(|p| p.iter().map(|q| (q.x(), q.y())).collect::<Vec<_>>()));
+        }
+        let merged = union(Paths::<P>::from(pieces), Paths::new(vec![]), FillRule::NonZero)?;
+        let (done, dropped) = crate::clean(merged)?;
+        self.save(whole, &done);
+        self.save(whole ^ DROPPED, &dropped);
+        Ok((done, dropped))
+    }
+
+    /// Remove what is past the limits, oldest first. A result counts as
+    /// used when it was last written or read.
+    pub fn evict(&self) -> std::io::Result<usize> {
+        let now = SystemTime::now();
+        let mut files: Vec<(SystemTime, u64, PathBuf)> = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().is_none_or(|e| e != "json") {
+                continue;
+            }
+            let meta = entry.metadata()?;
+            files.push((used(&meta), meta.len(), path));
+        }
+        // newest first, so what goes over the size is the oldest
+        files.sort_by_key(|f| std::cmp::Reverse(f.0));
+        let mut total = 0;
+        let mut removed = 0;
+        for (when, size, path) in files {
+            total += size;
+            let stale = now.duration_since(when).is_ok_and(|age| age > self.limits.age);
+            if (stale || total > self.limits.bytes) && fs::remove_file(&path).is_ok() {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn file(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{key:016x}.json"))
+    }
+
+    fn load<P: PointScaler>(&self, key: u64) -> Option<Paths<P>> {
+        let file = self.file(key);
+        let text = fs::read_to_string(&file).ok()?;
+        let rings: Vec<Vec<(f64, f64)>> = serde_json::from_str(&text).ok()?;
+        // read times aren't kept everywhere, so a hit is marked by hand
+        let _ = fs::File::options().append(true).open(&file).and_then(|f| f.set_modified(SystemTime::now()));
+        Some(rings.into())
+    }
+
+    // a cache that can't be written only costs time, so failures are ignored
+    fn save<P: PointScaler>(&self, key: u64, paths: &Paths<P>) {
+        let rings: Vec<Vec<(f64, f64)>> = paths.iter().map(|p| p.iter().map(|q| (q.x(), q.y())).collect()).collect();
+        if let Ok(text) = serde_json::to_string(&rings) {
+            let _ = fs::write(self.file(key), text);
+        }
+    }
+}
+
+/// The key of the result of combining all of `shapes`, on this clipper grid.
+fn whole<P: PointScaler>(shapes: &[FlatShape]) -> u64 {
+    let grid = fnv(FNV_OFFSET, &P::MULTIPLIER.to_le_bytes());
+    shapes.iter().fold(grid, |h, s| fnv(h, &shape_hash(FNV_OFFSET, s).to_le_bytes())) ^ WHOLE
+}
+
+fn used(meta: &fs::Metadata) -> SystemTime {
+    meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+// FNV-1a, stable across builds unlike std's hasher
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+pub fn hash(bytes: &[u8]) -> u64 {
+    fnv(FNV_OFFSET, bytes)
+}
+
+fn fnv(mut h: u64, bytes: &[u8]) -> u64 {
+    for b in bytes {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    h
+}
+
+/// Geometry and gap of one shape, chained onto `h`.
+fn shape_hash(mut h: u64, shape: &FlatShape) -> u64 {
+    h = fnv(h, &shape.offset.to_le_bytes());
+    for contour in &shape.contours {
+        h = fnv(h, &(contour.len() as u64).to_le_bytes());
+        for (x, y) in contour {
+            h = fnv(h, &x.to_le_bytes());
+            h = fnv(h, &y.to_le_bytes());
+        }
+    }
+    h
+}
+
+/// Smallest and largest x and y of `contours`.
+fn extent(contours: &[crate::Contour]) -> (f64, f64, f64, f64) {
+    contours.iter().flatten().fold((f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY), |(x0, y0, x1, y1), &(x, y)| {
+        (x0.min(x), y0.min(y), x1.max(x), y1.max(y))
+    })
+}
+
+fn grow((x0, y0, x1, y1): (f64, f64, f64, f64), by: f64) -> (f64, f64, f64, f64) {
+    (x0 - by, y0 - by, x1 + by, y1 + by)
+}
+
+fn meet(a: (f64, f64, f64, f64), b: (f64, f64, f64, f64)) -> bool {
+    a.0 <= b.2 && b.0 <= a.2 && a.1 <= b.3 && b.1 <= a.3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64) -> FlatShape {
+        FlatShape {
+            id: format!("{x},{y}"),
+            contours: vec![vec![(x, y), (x + size, y), (x + size, y + size), (x, y + size)]],
+            offset: 2.0,
+            style: crate::style::Style::default(),
+            layer: None,
+            class: None,
+            lines: Vec::new(),
+        }
+    }
+
+    fn open(name: &str, limits: Limits) -> Cache {
+        let dir = std::env::temp_dir().join(format!("svg-combiner-cache-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Cache::open(dir.to_str().unwrap(), limits).unwrap()
+    }
+
+    fn points<P: PointScaler>(paths: &Paths<P>) -> Vec<Vec<(f64, f64)>> {
+        paths.iter().map(|p| p.iter().map(|q| (q.x(), q.y())).collect()).collect()
+    }
+
+    #[test]
+    fn a_changed_shape_only_carves_again_what_it_reaches() {
+        let mut cache = open("reach", Limits::default());
+        // a pair overlapping, and two far off on their own
+        let shapes = || vec![square(0.0, 0.0, 20.0), square(10.0, 10.0, 20.0), square(100.0, 0.0, 20.0), square(200.0, 0.0, 20.0)];
+        let (first, _) = cache.combine::<Centi>(&shapes()).unwrap();
+        assert_eq!((cache.hits, cache.misses), (0, 4));
+
+        let (again, _) = cache.combine::<Centi>(&shapes()).unwrap();
+        assert_eq!(points(&again), points(&first));
+        assert_eq!((cache.hits, cache.misses), (1, 4));
+
+        // the second of the pair moves: the first, which it carves, and
+        // it are carved again, the others are reused
+        let mut moved = shapes();
+        moved[1] = square(12.0, 10.0, 20.0);
+        cache.combine::<Centi>(&moved).unwrap();
+        assert_eq!((cache.hits, cache.misses), (3, 6));
+        let _ = fs::remove_dir_all(&cache.dir);
+    }
+
+    #[test]
+    fn reused_pieces_give_what_carving_them_gave() {
+        let mut cold = open("cold", Limits::default());
+        let shapes: Vec<FlatShape> = (0..12).map(|i| square(i as f64 * 9.0, (i % 3) as f64 * 7.0, 15.0)).collect();
+        let (carved, dropped) = cold.combine::<Centi>(&shapes).unwrap();
+        // the whole result gone, so only the pieces are found
+        fs::remove_file(cold.file(whole::<Centi>(&shapes))).unwrap();
+        let (reused, reused_dropped) = cold.combine::<Centi>(&shapes).unwrap();
+        assert_eq!(cold.hits, shapes.len());
+        assert_eq!(points(&reused), points(&carved));
+        assert_eq!(points(&reused_dropped), points(&dropped));
+        let _ = fs::remove_dir_all(&cold.dir);
+    }
+
+    #[test]
+    fn eviction_removes_the_oldest_past_the_size_and_the_unused() {
+        let mut cache = open("evict", Limits { bytes: u64::MAX, age: Duration::from_secs(3600) });
+        let ring: Paths<Centi> = vec![vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]].into();
+        let size = serde_json::to_string(&points(&ring)).unwrap().len() as u64;
+        let touch = |cache: &Cache, key: u64, ago: u64| {
+            let file = fs::File::options().append(true).open(cache.file(key)).unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(ago)).unwrap();
+        };
+        // key 0 last used longest ago
+        for key in 0..3 {
+            cache.save(key, &ring);
+            touch(&cache, key, 60 - key * 10);
+        }
+        cache.limits.bytes = 2 * size;
+        assert_eq!(cache.evict().unwrap(), 1);
+        assert!(!cache.file(0).exists() && cache.file(1).exists() && cache.file(2).exists());
+
+        // unused past the age, however little room it takes
+        cache.limits.bytes = u64::MAX;
+        touch(&cache, 1, 7200);
+        assert_eq!(cache.evict().unwrap(), 1);
+        assert!(!cache.file(1).exists() && cache.file(2).exists());
+        let _ = fs::remove_dir_all(&cache.dir);
+    }
+}
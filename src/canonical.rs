@@ -0,0 +1,74 @@
+use std::cmp::Ordering;
+
+use clipper2::*;
+
+/// The same rings in a fixed order, independent of how clipper happened to
+/// hand them back: sorted by bounding box (top, left, bottom, right) and then
+/// area, each starting at its lowest vertex by x then y. Winding is kept.
+pub fn canonicalize<P: PointScaler>(paths: &Paths<P>) -> Paths<P> {
+    let mut rings: Vec<_> = paths
+        .iter()
+        .map(|ring| {
+            let mut pts: Vec<(f64, f64)> = ring.iter().map(|p| (p.x(), p.y())).collect();
+            if let Some(first) = (0..pts.len()).min_by(|&i, &j| lexical(pts[i], pts[j])) {
+                pts.rotate_left(first);
+            }
+            (ring.bounds(), ring.signed_area().abs(), pts)
+        })
+        .collect();
+    rings.sort_by(|a, b| {
+        let (p, q) = (&a.0, &b.0);
+        p.min
+            .y()
+            .total_cmp(&q.min.y())
+            .then(p.min.x().total_cmp(&q.min.x()))
+            .then(p.max.y().total_cmp(&q.max.y()))
+            .then(p.max.x().total_cmp(&q.max.x()))
+            .then(a.1.total_cmp(&b.1))
+            .then_with(|| a.2.len().cmp(&b.2.len()))
+            .then_with(|| a.2.iter().zip(&b.2).map(|(p, q)| lexical(*p, *q)).find(|o| o.is_ne()).unwrap_or(Ordering::Equal))
+    });
+    rings.into_iter().map(|(_, _, pts)| pts).collect::<Vec<_>>().into()
+}
+
+fn lexical(a: (f64, f64), b: (f64, f64)) -> Ordering {
+    a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(paths: &Paths<Centi>) -> Vec<Vec<(f64, f64)>> {
+        paths.iter().map(|p| p.iter().map(|q| (q.x(), q.y())).collect()).collect()
+    }
+
+    #[test]
+    fn a_ring_starts_at_its_lowest_vertex_and_keeps_its_winding() {
+        let ring: Paths<Centi> = vec![vec![(5.0, 5.0), (0.0, 5.0), (0.0, 0.0), (5.0, 0.0)]].into();
+        assert_eq!(points(&canonicalize(&ring)), vec![vec![(0.0, 0.0), (5.0, 0.0), (5.0, 5.0), (0.0, 5.0)]]);
+        let reversed: Paths<Centi> = vec![vec![(5.0, 0.0), (0.0, 0.0), (0.0, 5.0), (5.0, 5.0)]].into();
+        assert_eq!(points(&canonicalize(&reversed)), vec![vec![(0.0, 0.0), (0.0, 5.0), (5.0, 5.0), (5.0, 0.0)]]);
+    }
+
+    #[test]
+    fn rings_are_ordered_by_top_then_left() {
+        let square = |x: f64, y: f64| vec![(x, y), (x + 1.0, y), (x + 1.0, y + 1.0), (x, y + 1.0)];
+        let rings: Paths<Centi> = vec![square(5.0, 5.0), square(9.0, 0.0), square(0.0, 5.0)].into();
+        let firsts: Vec<(f64, f64)> = points(&canonicalize(&rings)).iter().map(|r| r[0]).collect();
+        assert_eq!(firsts, vec![(9.0, 0.0), (0.0, 5.0), (5.0, 5.0)]);
+    }
+
+    #[test]
+    fn the_same_rings_in_any_order_give_the_same_result() {
+        let a = vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        // a hole within it, same bounds top and left as nothing else
+        let b = vec![(2.0, 2.0), (2.0, 4.0), (4.0, 4.0), (4.0, 2.0)];
+        let c = vec![(20.0, 0.0), (25.0, 0.0), (25.0, 3.0)];
+        let one: Paths<Centi> = vec![a.clone(), b.clone(), c.clone()].into();
+        let mut rotated = c.clone();
+        rotated.rotate_left(1);
+        let other: Paths<Centi> = vec![rotated, b, a].into();
+        assert_eq!(points(&canonicalize(&one)), points(&canonicalize(&other)));
+    }
+}
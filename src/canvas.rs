@@ -0,0 +1,53 @@
+use clipper2::*;
+use serde_json::{Value, json};
+
+use crate::export::Stroke;
+
+/// Paper.js `exportJSON` data: one black `CompoundPath` per part, its
+/// outline and holes as closed child paths, in drawing units. Score and
+/// hatch lines follow as open stroked paths named after their layer.
+pub fn paper<P: PointScaler>(rings: &Paths<P>, strokes: &[Stroke]) -> Value {
+    let mut parts: Vec<Value> = crate::parts::parts(rings)
+        .iter()
+        .map(|part| {
+            let children: Vec<Value> = part
+                .paths(rings)
+                .iter()
+                .map(|ring| {
+                    let segments: Vec<Value> = ring.iter().map(|p| json!([p.x(), p.y()])).collect();
+                    json!(["Path", { "applyMatrix": true, "segments": segments, "closed": true }])
+                })
+                .collect();
+            json!(["CompoundPath", { "applyMatrix": true, "children": children, "fillColor": [0, 0, 0] }])
+        })
+        .collect();
+    parts.extend(strokes.iter().map(|stroke| {
+        let segments: Vec<Value> = stroke.points.iter().map(|p| json!([p.0, p.1])).collect();
+        json!(["Path", { "applyMatrix": true, "name": stroke.layer, "segments": segments, "strokeColor": [0, 0, 0] }])
+    }));
+    json!(["Layer", { "applyMatrix": true, "children": parts }])
+}
+
+/// Fabric.js canvas data: one black `path` object per part with its
+/// commands as arrays, holes cut by the even-odd rule. Score and hatch
+/// lines follow as unfilled stroked paths named after their layer.
+pub fn fabric<P: PointScaler>(rings: &Paths<P>, strokes: &[Stroke]) -> Value {
+    let mut objects: Vec<Value> = crate::parts::parts(rings)
+        .iter()
+        .map(|part| {
+            let mut path = Vec::new();
+            for ring in part.paths(rings).iter() {
+                for (i, p) in ring.iter().enumerate() {
+                    path.push(json!([if i == 0 { "M" } else { "L" }, p.x(), p.y()]));
+                }
+                path.push(json!(["Z"]));
+            }
+            json!({ "type": "path", "path": path, "fill": "#000000", "fillRule": "evenodd" })
+        })
+        .collect();
+    objects.extend(strokes.iter().map(|stroke| {
+        let path: Vec<Value> = stroke.points.iter().enumerate().map(|(i, p)| json!([if i == 0 { "M" } else { "L" }, p.0, p.1])).collect();
+        json!({ "type": "path", "name": stroke.layer, "path": path, "fill": "", "stroke": "#000000" })
+    }));
+    json!({ "version": "5.3.0", "objects": objects })
+}
@@ -0,0 +1,84 @@
+use clipper2::*;
+
+type Pt = (f64, f64);
+
+/// Pieces of an open polyline lying inside (`keep_inside`) or outside the
+/// region covered by `paths` under the nonzero rule. clipper2 doesn't hand
+/// back open paths, so this intersects the segments directly.
+pub fn clip_polyline<P: PointScaler>(line: &[Pt], paths: &Paths<P>, keep_inside: bool) -> Vec<Vec<Pt>> {
+    let rings: Vec<Vec<Pt>> = paths.iter().map(|p| p.iter().map(|q| (q.x(), q.y())).collect()).collect();
+
+    let mut out: Vec<Vec<Pt>> = Vec::new();
+    let mut current: Vec<Pt> = Vec::new();
+    for w in line.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        let mut ts = vec![0.0, 1.0];
+        for ring in &rings {
+            for i in 0..ring.len() {
+                if let Some(t) = intersection(a, b, ring[i], ring[(i + 1) % ring.len()]) {
+                    ts.push(t);
+                }
+            }
+        }
+        ts.sort_by(f64::total_cmp);
+        ts.dedup_by(|x, y| (*x - *y).abs() < 1e-9);
+
+        for span in ts.windows(2) {
+            let (p, q) = (lerp(a, b, span[0]), lerp(a, b, span[1]));
+            let mid = lerp(a, b, (span[0] + span[1]) / 2.0);
+            if (winding(&rings, mid) != 0) == keep_inside {
+                if current.last() != Some(&p) {
+                    if current.len() >= 2 {
+                        out.push(std::mem::take(&mut current));
+                    }
+                    current = vec![p];
+                }
+                current.push(q);
+            } else if current.len() >= 2 {
+                out.push(std::mem::take(&mut current));
+            } else {
+                current.clear();
+            }
+        }
+    }
+    if current.len() >= 2 {
+        out.push(current);
+    }
+    out
+}
+
+/// Winding number of `p` over all rings.
+pub fn winding(rings: &[Vec<Pt>], p: Pt) -> i32 {
+    let mut w = 0;
+    for ring in rings {
+        for i in 0..ring.len() {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            let side = (b.0 - a.0) * (p.1 - a.1) - (p.0 - a.0) * (b.1 - a.1);
+            if a.1 <= p.1 {
+                if b.1 > p.1 && side > 0.0 {
+                    w += 1;
+                }
+            } else if b.1 <= p.1 && side < 0.0 {
+                w -= 1;
+            }
+        }
+    }
+    w
+}
+
+/// Parameter along `a→b` where it crosses `c→d`, if it does.
+fn intersection(a: Pt, b: Pt, c: Pt, d: Pt) -> Option<f64> {
+    let r = (b.0 - a.0, b.1 - a.1);
+    let s = (d.0 - c.0, d.1 - c.1);
+    let denom = r.0 * s.1 - r.1 * s.0;
+    if denom.abs() < 1e-12 {
+        return None;
+    }
+    let t = ((c.0 - a.0) * s.1 - (c.1 - a.1) * s.0) / denom;
+    let u = ((c.0 - a.0) * r.1 - (c.1 - a.1) * r.0) / denom;
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then_some(t)
+}
+
+fn lerp(a: Pt, b: Pt, t: f64) -> Pt {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
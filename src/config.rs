@@ -0,0 +1,440 @@
+use std::fs;
+
+use crate::operation::Operation;
+
+/// Settings read from `--config FILE`, a small subset of TOML:
+///
+/// ```toml
+/// [group."engrave"]
+/// offset = 0.0
+/// [group."cut-*"]
+/// offset = 0.1
+/// [gap]
+/// "logo > background" = 4
+/// [dimension."bracket"]
+/// width = 120
+/// height = 40
+/// tolerance = 0.2
+/// [tool."#ff0000"]
+/// number = 2
+/// feed = 600
+/// [operation."#0000ff"]
+/// kind = "score"
+/// [fold]
+/// stroke = "#00ff00"
+/// dashed = true
+/// [material."acrylic-3mm"]
+/// kerf = 0.15
+/// min-feature = 1
+/// sheet = "600x400"
+/// feed = 1200
+/// power = 800
+/// ```
+///
+/// Every shape carves its gap out of the shapes drawn before it, so the
+/// later shape wins. A `[gap]` entry `"UPPER > LOWER"` sets the gap carved
+/// around shapes matching UPPER out of those matching LOWER before them.
+/// A `[dimension]` is checked against the bounding box of every part that
+/// came from a matching shape, or has a matching label like `part-3`. A
+/// `[tool]` picks the pen or tool G-code and HPGL output draws a group with,
+/// and an `[operation]` what the machine does with it. Shapes in the
+/// `[fold]` style are fold annotations, scored rather than cut. A
+/// `[material]` is what `--material NAME` brings along.
+#[derive(Default)]
+pub struct Config {
+    /// Overrides for shapes whose layer, fill color, class or id matches, in file
+    /// order; the first match wins.
+    pub groups: Vec<GroupRule>,
+    /// Gaps between pairs of shapes, the first match winning too.
+    pub gaps: Vec<GapRule>,
+    /// Sizes parts have to come out at.
+    pub dimensions: Vec<DimensionRule>,
+    /// Pens or tools machine output draws each group with.
+    pub tools: Vec<ToolRule>,
+    /// Whether each group is cut, scored or engraved.
+    pub operations: Vec<OperationRule>,
+    /// How fold lines are drawn, if the drawing marks any.
+    pub fold: Option<FoldStyle>,
+    /// Defaults for what the job is cut from.
+    pub materials: Vec<Material>,
+}
+
+pub struct GroupRule {
+    /// Matched with `*` standing for any run of characters.
+    pub pattern: String,
+    /// Gap around matching shapes, in place of their own.
+    pub offset: Option<f64>,
+}
+
+pub struct DimensionRule {
+    pub pattern: String,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    /// How far off either may be, 0.1 unless given.
+    pub tolerance: f64,
+}
+
+/// `[tool."PATTERN"]`: the pen or tool for groups whose name (layer or
+/// color) matches, with its own feed and spindle speed or laser power.
+pub struct ToolRule {
+    pub pattern: String,
+    pub number: u32,
+    pub feed: Option<f64>,
+    pub speed: Option<f64>,
+}
+
+/// `[operation."PATTERN"]`: what is done with groups whose name matches.
+/// Cut outlines grow by half the `kerf`, score lines stay open and
+/// engraved areas are filled with lines `hatch` apart at `angle` degrees.
+pub struct OperationRule {
+    pub pattern: String,
+    pub kind: Operation,
+    pub kerf: f64,
+    pub hatch: f64,
+    pub angle: f64,
+}
+
+/// `[fold]`: shapes stroked in this color, or dashed, or both when both
+/// are given.
+#[derive(Default)]
+pub struct FoldStyle {
+    pub stroke: Option<String>,
+    pub dashed: bool,
+}
+
+impl FoldStyle {
+    /// Whether a shape stroked `stroke`, dashed or not, is a fold line.
+    pub fn matches(&self, stroke: Option<&str>, dashed: bool) -> bool {
+        (self.stroke.is_some() || self.dashed)
+            && self.stroke.as_deref().is_none_or(|s| stroke.is_some_and(|t| t.eq_ignore_ascii_case(s)))
+            && (!self.dashed || dashed)
+    }
+}
+
+/// `[material."NAME"]`: the kerf cut outlines grow by half of unless
+/// their `[operation]` gives one, the narrowest part the material holds
+/// together at, the largest sheet it comes in, and the G-code feed and
+/// laser power for tools without their own.
+#[derive(Clone)]
+pub struct Material {
+    pub name: String,
+    pub kerf: f64,
+    pub min_feature: Option<f64>,
+    pub sheet: Option<(f64, f64)>,
+    pub feed: Option<f64>,
+    pub power: Option<f64>,
+}
+
+pub struct GapRule {
+    pub upper: String,
+    pub lower: String,
+    pub gap: f64,
+}
+
+pub fn read(path: &str) -> Result<Config, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    parse(&text).map_err(|e| format!("{path}:{e}"))
+}
+
+// which kind of section settings go to
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    None,
+    Group,
+    Gap,
+    Dimension,
+    Tool,
+    Operation,
+    Fold,
+    Material,
+}
+
+fn parse(text: &str) -> Result<Config, String> {
+    let mut config = Config::default();
+    // settings go to the last section of its kind, or are gaps
+    let mut section = Section::None;
+    for (n, line) in text.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let at = |e: String| format!("{}: {e}", n + 1);
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if header.trim() == "gap" {
+                section = Section::Gap;
+                continue;
+            }
+            if header.trim() == "fold" {
+                config.fold.get_or_insert_with(FoldStyle::default);
+                section = Section::Fold;
+                continue;
+            }
+            let Some((table, key)) = header.split_once('.') else {
+                return Err(at(format!("unknown section [{header}]")));
+            };
+            let pattern = unquote(key.trim()).to_string();
+            section = match table.trim() {
+                "group" => {
+                    config.groups.push(GroupRule { pattern, offset: None });
+                    Section::Group
+                }
+                "dimension" => {
+                    config.dimensions.push(DimensionRule { pattern, width: None, height: None, tolerance: 0.1 });
+                    Section::Dimension
+                }
+                "tool" => {
+                    config.tools.push(ToolRule { pattern, number: 1, feed: None, speed: None });
+                    Section::Tool
+                }
+                "operation" => {
+                    config.operations.push(OperationRule { pattern, kind: Operation::Cut, kerf: 0.0, hatch: 0.5, angle: 45.0 });
+                    Section::Operation
+                }
+                "material" => {
+                    config.materials.push(Material { name: pattern, kerf: 0.0, min_feature: None, sheet: None, feed: None, power: None });
+                    Section::Material
+                }
+                _ => return Err(at(format!("unknown section [{header}]"))),
+            };
+            continue;
+        }
+        let (key, value) = line.rsplit_once('=').ok_or_else(|| at(format!("expected KEY = VALUE, got {line:?}")))?;
+        let (key, value) = (unquote(key.trim()), value.trim());
+        let number = |what: &str| value.parse::<f64>().map_err(|_| at(format!("bad {what} {value:?}")));
+        let unknown = || Err(at(format!("unknown setting {key:?}")));
+        match section {
+            Section::None => return Err(at("settings need a [group.\"...\"] section".into())),
+            Section::Gap => {
+                let (upper, lower) = key.split_once('>').ok_or_else(|| at(format!("expected \"UPPER > LOWER\", got {key:?}")))?;
+                let gap = number("gap")?;
+                config.gaps.push(GapRule { upper: upper.trim().to_string(), lower: lower.trim().to_string(), gap });
+            }
+            Section::Group => {
+                let Some(rule) = config.groups.last_mut() else { continue };
+                match key {
+                    "offset" => rule.offset = Some(number("offset")?),
+                    _ => return unknown(),
+                }
+            }
+            Section::Dimension => {
+                let Some(rule) = config.dimensions.last_mut() else { continue };
+                match key {
+                    "width" => rule.width = Some(number("width")?),
+                    "height" => rule.height = Some(number("height")?),
+                    "tolerance" => rule.tolerance = number("tolerance")?,
+                    _ => return unknown(),
+                }
+            }
+            Section::Tool => {
+                let Some(rule) = config.tools.last_mut() else { continue };
+                match key {
+                    "number" => rule.number = value.parse().map_err(|_| at(format!("bad tool number {value:?}")))?,
+                    "feed" => rule.feed = Some(number("feed")?),
+                    "speed" => rule.speed = Some(number("speed")?),
+                    _ => return unknown(),
+                }
+            }
+            Section::Operation => {
+                let Some(rule) = config.operations.last_mut() else { continue };
+                match key {
+                    "kind" => rule.kind = crate::operation::parse(unquote(value)).map_err(at)?,
+                    "kerf" => rule.kerf = number("kerf")?,
+                    "hatch" => {
+                        rule.hatch = number("hatch")?;
+                        if rule.hatch <= 0.0 {
+                            return Err(at(format!("hatch spacing has to be above 0, got {value}")));
+                        }
+                    }
+                    "angle" => rule.angle = number("angle")?,
+                    _ => return unknown(),
+                }
+            }
+            Section::Fold => {
+                let Some(fold) = config.fold.as_mut() else { continue };
+                match key {
+                    "stroke" => fold.stroke = Some(unquote(value).to_string()),
+                    "dashed" => fold.dashed = value.parse().map_err(|_| at(format!("expected true or false, got {value:?}")))?,
+                    _ => return unknown(),
+                }
+            }
+            Section::Material => {
+                let Some(material) = config.materials.last_mut() else { continue };
+                match key {
+                    "kerf" => material.kerf = number("kerf")?,
+                    "min-feature" | "min_feature" => material.min_feature = Some(number("min-feature")?),
+                    "sheet" => material.sheet = Some(crate::transform::parse_size(unquote(value)).map_err(at)?),
+                    "feed" => material.feed = Some(number("feed")?),
+                    "power" => material.power = Some(number("power")?),
+                    _ => return unknown(),
+                }
+            }
+        }
+    }
+    Ok(config)
+}
+
+impl Config {
+    /// The first group rule matching any of `names`.
+    pub fn group_for<'a>(&self, names: impl IntoIterator<Item = &'a str> + Clone) -> Option<&GroupRule> {
+        self.groups.iter().find(|rule| matches(&rule.pattern, names.clone()))
+    }
+
+    /// The dimension rule for a part known by any of `names`.
+    pub fn dimension_for<'a>(&self, names: impl IntoIterator<Item = &'a str> + Clone) -> Option<&DimensionRule> {
+        self.dimensions.iter().find(|rule| matches(&rule.pattern, names.clone()))
+    }
+
+    /// The tool rule for a group called `name`.
+    pub fn tool_for(&self, name: &str) -> Option<&ToolRule> {
+        self.tools.iter().find(|rule| glob(&rule.pattern, name))
+    }
+
+    /// The operation rule for a group called `name`.
+    pub fn operation_for(&self, name: &str) -> Option<&OperationRule> {
+        self.operations.iter().find(|rule| glob(&rule.pattern, name))
+    }
+
+    /// The material called `name`.
+    pub fn material(&self, name: &str) -> Option<&Material> {
+        self.materials.iter().find(|m| m.name == name)
+    }
+
+    /// Whether some group is scored, so open subpaths have to be kept.
+    pub fn scores(&self) -> bool {
+        self.operations.iter().any(|rule| rule.kind == Operation::Score)
+    }
+
+    /// The gap carved around a shape known by `upper` out of one known by
+    /// `lower` drawn before it, if a pair sets one.
+    pub fn gap_between<'a>(
+        &self,
+        upper: impl IntoIterator<Item = &'a str> + Clone,
+        lower: impl IntoIterator<Item = &'a str> + Clone,
+    ) -> Option<f64> {
+        self.gaps.iter().find(|rule| matches(&rule.upper, upper.clone()) && matches(&rule.lower, lower.clone())).map(|rule| rule.gap)
+    }
+}
+
+fn matches<'a>(pattern: &str, names: impl IntoIterator<Item = &'a str>) -> bool {
+    names.into_iter().any(|name| glob(pattern, name))
+}
+
+// a `#` outside quotes starts a comment
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn unquote(s: &str) -> &str {
+    s.strip_prefix('"').and_then(|s| s.strip_suffix('"')).unwrap_or(s)
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any run of characters.
+pub fn glob(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((head, rest)) => {
+            let Some(tail) = name.strip_prefix(head) else {
+                return false;
+            };
+            (0..=tail.len()).filter(|&i| tail.is_char_boundary(i)).any(|i| glob(rest, &tail[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_and_settings_are_read_in_order() {
+        let config = parse(
+            r##"
+# gaps for the two kinds of layer
+[group."engrave"]
+offset = 0.0
+[group."cut-*"]   # any cut layer
+offset = 0.1
+
+[gap]
+"logo > background" = 4
+
+[dimension."bracket"]
+width = 120
+height = 40
+
+[tool."#ff0000"]
+number = 2
+feed = 600
+
+[operation."#0000ff"]
+kind = "score"
+
+[fold]
+stroke = "#00ff00"
+dashed = true
+
+[material."acrylic-3mm"]
+kerf = 0.15
+min-feature = 1
+sheet = "600x400"
+"##,
+        )
+        .unwrap();
+        assert_eq!(
+            config.groups.iter().map(|g| (g.pattern.as_str(), g.offset)).collect::<Vec<_>>(),
+            [("engrave", Some(0.0)), ("cut-*", Some(0.1))]
+        );
+        assert_eq!(config.gap_between(["logo"], ["background"]), Some(4.0));
+        assert_eq!(config.gap_between(["background"], ["logo"]), None);
+        let bracket = config.dimension_for(["bracket"]).unwrap();
+        assert_eq!((bracket.width, bracket.height, bracket.tolerance), (Some(120.0), Some(40.0), 0.1));
+        let tool = config.tool_for("#ff0000").unwrap();
+        assert_eq!((tool.number, tool.feed, tool.speed), (2, Some(600.0), None));
+        assert!(config.operation_for("#0000ff").is_some_and(|op| op.kind == Operation::Score) && config.scores());
+        let fold = config.fold.as_ref().unwrap();
+        assert!(fold.matches(Some("#00FF00"), true) && !fold.matches(Some("#00ff00"), false));
+        let material = config.material("acrylic-3mm").unwrap();
+        assert_eq!((material.kerf, material.min_feature, material.sheet), (0.15, Some(1.0), Some((600.0, 400.0))));
+    }
+
+    #[test]
+    fn the_first_matching_group_wins() {
+        let config = parse("[group.\"cut-*\"]\noffset = 1\n[group.\"cut-outer\"]\noffset = 2\n").unwrap();
+        assert_eq!(config.group_for(["layer-x", "cut-outer"]).and_then(|g| g.offset), Some(1.0));
+        assert!(config.group_for(["engrave"]).is_none());
+    }
+
+    #[test]
+    fn a_hash_within_quotes_isnt_a_comment() {
+        let config = parse("[tool.\"#00f\"] # blue\nnumber = 3 # the fine pen\n").unwrap();
+        assert_eq!(config.tool_for("#00f").map(|t| t.number), Some(3));
+    }
+
+    #[test]
+    fn errors_give_the_line() {
+        let error = |text: &str| parse(text).err().unwrap();
+        assert_eq!(error("offset = 1"), "1: settings need a [group.\"...\"] section");
+        assert_eq!(error("[group.\"a\"]\n\noffset = x"), "3: bad offset \"x\"");
+        assert_eq!(error("[group.\"a\"]\nwidth = 1"), "2: unknown setting \"width\"");
+        assert_eq!(error("[layer.\"a\"]"), "1: unknown section [layer.\"a\"]");
+        assert_eq!(error("[gap]\n\"a\" = 1"), "2: expected \"UPPER > LOWER\", got \"a\"");
+        assert_eq!(error("[operation.\"a\"]\nhatch = 0"), "2: hatch spacing has to be above 0, got 0");
+        assert_eq!(error("[fold]\ndashed = yes"), "2: expected true or false, got \"yes\"");
+    }
+
+    #[test]
+    fn glob_stars_match_any_run() {
+        assert!(glob("cut-*", "cut-") && glob("cut-*", "cut-outer"));
+        assert!(glob("*-3mm", "acrylic-3mm") && glob("a*b*c", "aXXbYc"));
+        assert!(!glob("cut-*", "engrave") && !glob("a*b", "ab-") && !glob("part", "part-1"));
+        assert!(glob("é*", "été"));
+    }
+}
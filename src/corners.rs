@@ -0,0 +1,113 @@
+use clipper2::*;
+
+/// Fillet every corner with radius `r`: closing (out, in) rounds concave
+/// corners, opening (in, out) rounds convex ones. Gaps and features
+/// thinner than `2 * r` disappear, as they would under a bit of that radius.
+pub fn round_corners<P: PointScaler>(paths: Paths<P>, r: f64) -> Paths<P> {
+    let grown = paths.inflate(r, JoinType::Round, EndType::Polygon, 0.0);
+    let shrunk = grown.inflate(-2.0 * r, JoinType::Round, EndType::Polygon, 0.0);
+    shrunk.inflate(r, JoinType::Round, EndType::Polygon, 0.0)
+}
+
+// direction changes below this are curve flattening, not corners
+const CORNER_ANGLE: f64 = 15.0 * std::f64::consts::PI / 180.0;
+
+/// Cut every convex corner with a straight segment `length` long, shortened
+/// where the neighbouring edges are too short to hold it. Inside corners,
+/// the material's, are left sharp: outlines are wound with a positive area
+/// and holes a negative one, as clipper leaves them, so the material always
+/// lies to the left and a convex corner turns left.
+pub fn chamfer_corners<P: PointScaler>(paths: Paths<P>, length: f64) -> Paths<P> {
+    paths
+        .iter()
+        .map(|ring| {
+            let pts: Vec<(f64, f64)> = ring.iter().map(|p| (p.x(), p.y())).collect();
+            let n = pts.len();
+            let mut out = Vec::with_capacity(n * 2);
+            for i in 0..n {
+                let (prev, v, next) = (pts[(i + n - 1) % n], pts[i], pts[(i + 1) % n]);
+                let (a, b) = ((prev.0 - v.0, prev.1 - v.1), (next.0 - v.0, next.1 - v.1));
+                let (la, lb) = (a.0.hypot(a.1), b.0.hypot(b.1));
+                if la == 0.0 || lb == 0.0 {
+                    continue;
+                }
+                // interior angle between both edges at v
+                let cos = ((a.0 * b.0 + a.1 * b.1) / (la * lb)).clamp(-1.0, 1.0);
+                let angle = cos.acos();
+                let turn = a.1 * b.0 - a.0 * b.1;
+                if std::f64::consts::PI - angle < CORNER_ANGLE || turn <= 0.0 {
+                    out.push(v);
+                    continue;
+                }
+                // setback giving a cut of `length` across the corner
+                let setback = (length / (2.0 * (angle / 2.0).sin())).min(la / 2.0).min(lb / 2.0);
+                out.push((v.0 + a.0 / la * setback, v.1 + a.1 / la * setback));
+                out.push((v.0 + b.0 / lb * setback, v.1 + b.1 / lb * setback));
+            }
+            Path::from(out)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(points: &[(f64, f64)]) -> Paths<Centi> {
+        vec![points.to_vec()].into()
+    }
+
+    fn area(paths: &Paths<Centi>) -> f64 {
+        paths.iter().map(|r| r.signed_area()).sum()
+    }
+
+    fn vertices(paths: &Paths<Centi>) -> Vec<usize> {
+        paths.iter().map(|r| r.len()).collect()
+    }
+
+    #[test]
+    fn chamfers_cut_the_convex_corners_of_outlines() {
+        let cut = chamfer_corners(ring(&[(0.0, 0.0), (20.0, 0.0), (20.0, 20.0), (0.0, 20.0)]), 2.0f64.sqrt());
+        assert_eq!(vertices(&cut), [8]);
+        // four corners of 1 × 1 / 2 off
+        assert!((area(&cut) - (400.0 - 2.0)).abs() < 1e-2);
+    }
+
+    #[test]
+    fn chamfers_leave_inside_corners_sharp() {
+        // an L, its one reflex corner at (10, 10)
+        let l = ring(&[(0.0, 0.0), (20.0, 0.0), (20.0, 10.0), (10.0, 10.0), (10.0, 20.0), (0.0, 20.0)]);
+        let cut = chamfer_corners(l, 2.0f64.sqrt());
+        assert_eq!(vertices(&cut), [11]);
+        assert!(cut.iter().flat_map(|r| r.iter()).any(|p| (p.x() - 10.0).abs() < 1e-2 && (p.y() - 10.0).abs() < 1e-2));
+        // and a hole's corners are the material's inside corners
+        let hole: Paths<Centi> =
+            vec![vec![(0.0, 0.0), (40.0, 0.0), (40.0, 40.0), (0.0, 40.0)], vec![(10.0, 10.0), (10.0, 30.0), (30.0, 30.0), (30.0, 10.0)]]
+                .into();
+        let cut = chamfer_corners(hole, 2.0f64.sqrt());
+        assert_eq!(vertices(&cut), [8, 4]);
+    }
+
+    #[test]
+    fn chamfers_are_shortened_to_fit_short_edges() {
+        let cut = chamfer_corners(ring(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]), 10.0);
+        // setbacks of half an edge, leaving the diamond through the midpoints
+        assert!((area(&cut) - 8.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn flattened_curves_are_not_corners() {
+        let circle: Vec<(f64, f64)> =
+            (0..64).map(|i| i as f64 * std::f64::consts::TAU / 64.0).map(|a| (20.0 * a.cos(), 20.0 * a.sin())).collect();
+        assert_eq!(vertices(&chamfer_corners(ring(&circle), 1.0)), [64]);
+    }
+
+    #[test]
+    fn round_corners_fillet_both_ways() {
+        let l = ring(&[(0.0, 0.0), (40.0, 0.0), (40.0, 20.0), (20.0, 20.0), (20.0, 40.0), (0.0, 40.0)]);
+        let rounded = round_corners(l, 2.0);
+        // five convex corners lose, the inside one gains, (1 - π/4) r² each
+        let each = (1.0 - std::f64::consts::FRAC_PI_4) * 4.0;
+        assert!((area(&rounded) - (1200.0 - 5.0 * each + each)).abs() < 0.5, "{}", area(&rounded));
+    }
+}
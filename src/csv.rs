@@ -0,0 +1,27 @@
+use std::fmt::Write as _;
+
+use clipper2::*;
+
+use crate::export::Page;
+
+/// One `part,ring,x,y` row per vertex, in millimetres with y going down;
+/// ring 0 is the part's outline and the ones after it its holes. Score and
+/// hatch lines come after, with their layer for a part and numbered.
+pub fn write<P: PointScaler>(rings: &Paths<P>, page: &Page) -> String {
+    let mut out = String::from("part,ring,x,y\n");
+    for (n, part) in crate::parts::parts(rings).iter().enumerate() {
+        for (r, ring) in part.paths(rings).iter().enumerate() {
+            for p in ring.iter() {
+                let (x, y) = page.down((p.x(), p.y()));
+                let _ = writeln!(out, "{},{r},{x:.4},{y:.4}", n + 1);
+            }
+        }
+    }
+    for (n, stroke) in page.strokes.iter().enumerate() {
+        for &p in &stroke.points {
+            let (x, y) = page.down(p);
+            let _ = writeln!(out, "{},{},{x:.4},{y:.4}", stroke.layer, n + 1);
+        }
+    }
+    out
+}
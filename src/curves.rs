@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use clipper2::*;
+use lyon::path::Event;
+
+use crate::labels::segment_distance;
+use crate::transform::Affine;
+
+type Pt = (f64, f64);
+
+// how far result vertices may sit from a source curve (rounding to clipper's
+// grid plus the cleanup simplification) and still count as lying on it
+const NEAR: f64 = 0.5;
+// points sampled along every curve for the comparison
+const SAMPLES: usize = 16;
+
+/// One quadratic or cubic segment of the source artwork.
+pub struct Curve {
+    from: Pt,
+    ctrl: Vec<Pt>,
+    to: Pt,
+    samples: Vec<Pt>,
+}
+
+/// Source curves, found again in the result so that runs of vertices that
+/// still follow one of them whole are written as that curve instead of a
+/// polyline. Anything clipped into the middle of a curve stays flattened.
+#[derive(Default)]
+pub struct Curves {
+    list: Vec<Curve>,
+    // curve ends by grid cell, with whether the curve runs backwards from there
+    ends: HashMap<(i64, i64), Vec<(usize, bool)>>,
+}
+
+impl Curves {
+    /// Remember every curved segment of a source path.
+    pub fn add(&mut self, path: &lyon::path::Path) {
+        for event in path.iter() {
+            let (from, ctrl, to) = match event {
+                Event::Quadratic { from, ctrl, to } => (from, vec![pt(ctrl)], to),
+                Event::Cubic { from, ctrl1, ctrl2, to } => (from, vec![pt(ctrl1), pt(ctrl2)], to),
+                _ => continue,
+            };
+            self.push(pt(from), ctrl, pt(to));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    /// The same curves after mapping by `t`, Béziers being affine invariant.
+    pub fn transformed(&self, t: &Affine) -> Curves {
+        let mut out = Curves::default();
+        for c in &self.list {
+            out.push(t.apply(c.from), c.ctrl.iter().map(|&p| t.apply(p)).collect(), t.apply(c.to));
+        }
+        out
+    }
+
+    fn push(&mut self, from: Pt, ctrl: Vec<Pt>, to: Pt) {
+        let samples = (0..=SAMPLES).map(|i| eval(from, &ctrl, to, i as f64 / SAMPLES as f64)).collect();
+        let index = self.list.len();
+        self.ends.entry(cell(from)).or_default().push((index, false));
+        self.ends.entry(cell(to)).or_default().push((index, true));
+        self.list.push(Curve { from, ctrl, to, samples });
+    }
+
+    /// Path data for closed rings, with curve commands wherever a run of
+    /// vertices matches a source curve end to end.
+    pub fn polygon_data<P: PointScaler>(&self, paths: &Paths<P>) -> String {
+        let mut d = String::new();
+        for ring in paths.iter() {
+            let mut pts = self.with_ends(ring);
+            let n = pts.len();
+            if n == 0 {
+                continue;
+            }
+            // start at a curve end so no curve straddles the first vertex
+            if let Some(s) = (0..n).find(|&i| self.is_end(pts[i])) {
+                pts.rotate_left(s);
+            }
+            d.push_str(&format!("M{} {}", round::<P>(pts[0].0), round::<P>(pts[0].1)));
+            let mut pos = 0;
+            while pos < n {
+                match self.run_at(&pts, pos, n - pos) {
+                    Some((len, index, backwards)) => {
+                        let c = &self.list[index];
+                        let end = pts[(pos + len) % n];
+                        let ctrl: Vec<Pt> = if backwards { c.ctrl.iter().rev().copied().collect() } else { c.ctrl.clone() };
+                        match ctrl.as_slice() {
+                            [q] => {
+                                d.push_str(&format!("Q{} {} {} {}", round::<P>(q.0), round::<P>(q.1), round::<P>(end.0), round::<P>(end.1)))
+                            }
+                            [a, b] => d.push_str(&format!(
+                                "C{} {} {} {} {} {}",
+                                round::<P>(a.0),
+                                round::<P>(a.1),
+                                round::<P>(b.0),
+                                round::<P>(b.1),
+                                round::<P>(end.0),
+                                round::<P>(end.1)
+                            )),
+                            _ => {}
+                        }
+                        pos += len;
+                    }
+                    None => {
+                        // the closing line is implied by Z
+                        if pos + 1 < n {
+                            d.push_str(&format!("L{} {}", round::<P>(pts[pos + 1].0), round::<P>(pts[pos + 1].1)));
+                        }
+                        pos += 1;
+                    }
+                }
+            }
+            d.push('Z');
+        }
+        d
+    }
+
+    /// The ring's vertices, plus every curve end lying on one of its edges:
+    /// cleanup tends to drop the vertex where two smooth curves meet.
+    fn with_ends<P: PointScaler>(&self, ring: &Path<P>) -> Vec<Pt> {
+        let pts: Vec<Pt> = ring.iter().map(|p| (p.x(), p.y())).collect();
+        let n = pts.len();
+        let mut out = Vec::with_capacity(n);
+        for i in 0..n {
+            let (a, b) = (pts[i], pts[(i + 1) % n]);
+            out.push(a);
+            let mut found: Vec<(f64, Pt)> = Vec::new();
+            let (x0, x1) = (cell(a).0.min(cell(b).0) - 1, cell(a).0.max(cell(b).0) + 1);
+            let (y0, y1) = (cell(a).1.min(cell(b).1) - 1, cell(a).1.max(cell(b).1) + 1);
+            for cx in x0..=x1 {
+                for cy in y0..=y1 {
+                    for &(index, backwards) in self.ends.get(&(cx, cy)).into_iter().flatten() {
+                        let c = &self.list[index];
+                        let e = if backwards { c.to } else { c.from };
+                        if distance(e, a) > NEAR && distance(e, b) > NEAR && segment_distance(e, a, b) <= NEAR {
+                            let len2 = (b.0 - a.0).powi(2) + (b.1 - a.1).powi(2);
+                            let t = ((e.0 - a.0) * (b.0 - a.0) + (e.1 - a.1) * (b.1 - a.1)) / len2;
+                            found.push((t, e));
+                        }
+                    }
+                }
+            }
+            found.sort_by(|p, q| p.0.total_cmp(&q.0));
+            found.dedup_by(|p, q| distance(p.1, q.1) <= NEAR);
+            out.extend(found.into_iter().map(|(_, e)| e));
+        }
+        out
+    }
+
+    fn is_end(&self, p: Pt) -> bool {
+        let (cx, cy) = cell(p);
+        (-1..=1).any(|dx| {
+            (-1..=1).any(|dy| {
+                self.ends.get(&(cx + dx, cy + dy)).into_iter().flatten().any(|&(index, backwards)| {
+                    let c = &self.list[index];
+                    distance(p, if backwards { c.to } else { c.from }) <= NEAR
+                })
+            })
+        })
+    }
+
+    /// A curve the ring follows from vertex `pos` for at most `room` edges:
+    /// its length in edges, which curve and whether it runs backwards.
+    fn run_at(&self, pts: &[Pt], pos: usize, room: usize) -> Option<(usize, usize, bool)> {
+        let n = pts.len();
+        let start = pts[pos];
+        let (cx, cy) = cell(start);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for &(index, backwards) in self.ends.get(&(cx + dx, cy + dy)).into_iter().flatten() {
+                    let c = &self.list[index];
+                    let (a, b) = if backwards { (c.to, c.from) } else { (c.from, c.to) };
+                    if distance(start, a) > NEAR {
+                        continue;
+                    }
+                    let limit = room.min(2 * SAMPLES + 2);
+                    for len in 1..=limit {
+                        let v = pts[(pos + len) % n];
+                        if distance(v, b) <= NEAR {
+                            let run: Vec<Pt> = (0..=len).map(|k| pts[(pos + k) % n]).collect();
+                            // the curve must hug the run as well, not just share its ends
+                            if c.samples.iter().all(|&s| polyline_distance(s, &run) <= NEAR) {
+                                return Some((len, index, backwards));
+                            }
+                            break;
+                        }
+                        if polyline_distance(v, &c.samples) > NEAR {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+fn pt(p: lyon::math::Point) -> Pt {
+    (p.x as f64, p.y as f64)
+}
+
+// to clipper's grid, like every other coordinate written
+fn round<P: PointScaler>(v: f64) -> f64 {
+    (v * P::MULTIPLIER).round() / P::MULTIPLIER
+}
+
+fn cell(p: Pt) -> (i64, i64) {
+    (p.0.floor() as i64, p.1.floor() as i64)
+}
+
+fn distance(a: Pt, b: Pt) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+fn polyline_distance(p: Pt, line: &[Pt]) -> f64 {
+    line.windows(2).map(|w| segment_distance(p, w[0], w[1])).fold(f64::INFINITY, f64::min)
+}
+
+/// Point at `t` on a quadratic (one control) or cubic (two) Bézier.
+fn eval(from: Pt, ctrl: &[Pt], to: Pt, t: f64) -> Pt {
+    let u = 1.0 - t;
+    match ctrl {
+        [q] => (u * u * from.0 + 2.0 * u * t * q.0 + t * t * to.0, u * u * from.1 + 2.0 * u * t * q.1 + t * t * to.1),
+        [a, b] => (
+            u * u * u * from.0 + 3.0 * u * u * t * a.0 + 3.0 * u * t * t * b.0 + t * t * t * to.0,
+            u * u * u * from.1 + 3.0 * u * u * t * a.1 + 3.0 * u * t * t * b.1 + t * t * t * to.1,
+        ),
+        _ => (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t),
+    }
+}
@@ -0,0 +1,257 @@
+use lyon::geom::{CubicBezierSegment, QuadraticBezierSegment};
+use lyon::math::{Point, point};
+use lyon::path::path::Builder;
+use lyon::path::{Event, Path};
+
+use crate::labels::segment_distance;
+
+type Pt = (f64, f64);
+
+// the most a flattened curve turns from one vertex to the next
+const MAX_TURN: f64 = std::f64::consts::PI / 6.0;
+// the finest share of the tolerance a curve is flattened with, so a cusp
+// doesn't ask for endless vertices
+const FINEST: f64 = 0.02;
+// points along a curve its curvature is taken at
+const SAMPLES: usize = 16;
+// halvings of a piece of a curve at most
+const MAX_DEPTH: usize = 8;
+
+/// `--curvature`: `path` with every curve flattened with a tolerance of
+/// its own, as fine as it takes for no step to turn more than MAX_TURN
+/// where it bends tightly, and `tolerance` where it bends gently. Runs of
+/// straight lines have the vertices within `tolerance` of a line between
+/// their neighbours dropped. Either way nothing moves further than
+/// `tolerance` from the path.
+pub fn flatten(path: &Path, tolerance: f32) -> Path {
+    let mut builder = Path::builder();
+    // vertices of the lines since the last curve, thinned before they go in
+    let mut run: Vec<Pt> = Vec::new();
+    let flush = |run: &mut Vec<Pt>, builder: &mut Builder| {
+        for &(x, y) in reduce(run, tolerance as f64, false).iter().skip(1) {
+            builder.line_to(point(x as f32, y as f32));
+        }
+        run.clear();
+    };
+    for event in path.iter() {
+        match event {
+            Event::Begin { at } => {
+                builder.begin(at);
+                run = vec![pt(at)];
+            }
+            Event::Line { to, .. } => run.push(pt(to)),
+            Event::Quadratic { from, ctrl, to } => {
+                flush(&mut run, &mut builder);
+                curve(QuadraticBezierSegment { from, ctrl, to }.to_cubic(), tolerance, &mut builder);
+                run = vec![pt(to)];
+            }
+            Event::Cubic { from, ctrl1, ctrl2, to } => {
+                flush(&mut run, &mut builder);
+                curve(CubicBezierSegment { from, ctrl1, ctrl2, to }, tolerance, &mut builder);
+                run = vec![pt(to)];
+            }
+            Event::End { close, .. } => {
+                flush(&mut run, &mut builder);
+                builder.end(close);
+            }
+        }
+    }
+    builder.build()
+}
+
+// `segment` as lines, with a tolerance for how tightly it bends at most
+fn curve(segment: CubicBezierSegment<f32>, tolerance: f32, builder: &mut Builder) {
+    let (p0, p1, p2, p3) = (pt(segment.from), pt(segment.ctrl1), pt(segment.ctrl2), pt(segment.to));
+    let tightest = (0..=SAMPLES)
+        .map(|i| {
+            let t = i as f64 / SAMPLES as f64;
+            let d = pt_v(segment.derivative(t as f32));
+            let bend = |a: f64, b: f64, c: f64, d: f64| 6.0 * ((1.0 - t) * (a - 2.0 * b + c) + t * (b - 2.0 * c + d));
+            let dd = (bend(p0.0, p1.0, p2.0, p3.0), bend(p0.1, p1.1, p2.1, p3.1));
+            let speed = d.0.hypot(d.1);
+            if speed < 1e-9 { f64::INFINITY } else { (d.0 * dd.1 - d.1 * dd.0).abs() / speed.powi(3) }
+        })
+        .fold(0.0, f64::max);
+    // steps on a circle that tight kept to MAX_TURN, within the tolerance
+    let sagitta = (1.0 - (MAX_TURN / 2.0).cos()) / tightest;
+    subdivide(segment, sagitta.clamp(tolerance as f64 * FINEST, tolerance as f64), builder);
+}
+
+// `segment` in lines no further than `tolerance` from it: cut evenly into
+// as many pieces as `bound` suggests, and any piece still too far from its
+// chord halved until none is
+fn subdivide(segment: CubicBezierSegment<f32>, tolerance: f64, builder: &mut Builder) {
+    let n = (bound(&segment) / tolerance).sqrt().ceil().clamp(1.0, 1024.0) as usize;
+    for k in 0..n {
+        let piece = segment.split_range(k as f32 / n as f32..(k + 1) as f32 / n as f32);
+        halve(piece, tolerance, 0, builder);
+    }
+}
+
+fn halve(segment: CubicBezierSegment<f32>, tolerance: f64, depth: usize, builder: &mut Builder) {
+    if bound(&segment) <= tolerance || depth == MAX_DEPTH {
+        builder.line_to(segment.to);
+        return;
+    }
+    let (a, b) = segment.split(0.5);
+    halve(a, tolerance, depth + 1, builder);
+    halve(b, tolerance, depth + 1, builder);
+}
+
+// the most `segment` strays from its chord: written as a cubic the chord
+// has its control points a third and two thirds along, and the curve
+// strays from it by no more than three quarters of the furthest offset of
+// its own control points from those
+fn bound(segment: &CubicBezierSegment<f32>) -> f64 {
+    let (p0, p1, p2, p3) = (pt(segment.from), pt(segment.ctrl1), pt(segment.ctrl2), pt(segment.to));
+    let e1 = (p1.0 - (2.0 * p0.0 + p3.0) / 3.0, p1.1 - (2.0 * p0.1 + p3.1) / 3.0);
+    let e2 = (p2.0 - (p0.0 + 2.0 * p3.0) / 3.0, p2.1 - (p0.1 + 2.0 * p3.1) / 3.0);
+    0.75 * e1.0.hypot(e1.1).max(e2.0.hypot(e2.1))
+}
+
+fn pt(p: Point) -> Pt {
+    (p.x as f64, p.y as f64)
+}
+
+fn pt_v(v: lyon::math::Vector) -> Pt {
+    (v.x as f64, v.y as f64)
+}
+
+/// Drop vertices lying within `tolerance` of the line through their
+/// neighbours (Douglas-Peucker), so near-straight runs keep few points and
+/// tight bends keep many. Closed rings are split at the vertex farthest
+/// from the first one and each half is reduced on its own.
+pub fn reduce(points: &[Pt], tolerance: f64, closed: bool) -> Vec<Pt> {
+    let min = if closed { 3 } else { 2 };
+    if points.len() <= min {
+        return points.to_vec();
+    }
+    if !closed {
+        let mut keep = vec![false; points.len()];
+        keep[0] = true;
+        keep[points.len() - 1] = true;
+        mark(points, 0, points.len() - 1, tolerance, &mut keep);
+        return kept(points, &keep);
+    }
+
+    let far = (1..points.len()).max_by(|&i, &j| distance(points[0], points[i]).total_cmp(&distance(points[0], points[j]))).unwrap_or(1);
+    // the ring as one line from 0 to `far` and back to 0
+    let mut ring = points.to_vec();
+    ring.push(points[0]);
+    let mut keep = vec![false; ring.len()];
+    keep[0] = true;
+    keep[far] = true;
+    mark(&ring, 0, far, tolerance, &mut keep);
+    mark(&ring, far, ring.len() - 1, tolerance, &mut keep);
+    keep.pop();
+    ring.pop();
+    if keep.iter().filter(|k| **k).count() < 3 {
+        // too loose a tolerance leaves a line; the vertex farthest from
+        // it keeps the ring a triangle
+        let (d, apex) = (1..points.len())
+            .map(|i| (segment_distance(points[i], points[0], points[far]), i))
+            .fold((0.0, 0), |best, c| if c.0 > best.0 { c } else { best });
+        if d == 0.0 {
+            return points.to_vec();
+        }
+        keep[apex] = true;
+    }
+    kept(&ring, &keep)
+}
+
+fn mark(points: &[Pt], first: usize, last: usize, tolerance: f64, keep: &mut [bool]) {
+    if last <= first + 1 {
+        return;
+    }
+    let (mut worst, mut index) = (0.0, first);
+    for (i, &p) in points.iter().enumerate().take(last).skip(first + 1) {
+        let d = segment_distance(p, points[first], points[last]);
+        if d > worst {
+            worst = d;
+            index = i;
+        }
+    }
+    if worst > tolerance {
+        keep[index] = true;
+        mark(points, first, index, tolerance, keep);
+        mark(points, index, last, tolerance, keep);
+    }
+}
+
+fn kept(points: &[Pt], keep: &[bool]) -> Vec<Pt> {
+    points.iter().zip(keep).filter(|(_, k)| **k).map(|(p, _)| *p).collect()
+}
+
+fn distance(a: Pt, b: Pt) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertices(path: &Path) -> Vec<Pt> {
+        path.iter()
+            .filter_map(|e| match e {
+                Event::Begin { at } => Some(pt(at)),
+                Event::Line { to, .. } => Some(pt(to)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // a quarter circle of radius `r` as the usual cubic, and its
+    // deviation from the polyline
+    fn quarter(r: f32) -> (Path, CubicBezierSegment<f32>) {
+        let k = 0.552_284_8 * r;
+        let curve = CubicBezierSegment { from: point(r, 0.0), ctrl1: point(r, k), ctrl2: point(k, r), to: point(0.0, r) };
+        let mut builder = Path::builder();
+        builder.begin(curve.from);
+        builder.cubic_bezier_to(curve.ctrl1, curve.ctrl2, curve.to);
+        builder.end(false);
+        (builder.build(), curve)
+    }
+
+    fn deviation(curve: &CubicBezierSegment<f32>, line: &[Pt]) -> f64 {
+        (0..=1000)
+            .map(|i| pt(curve.sample(i as f32 / 1000.0)))
+            .map(|p| line.windows(2).map(|w| segment_distance(p, w[0], w[1])).fold(f64::MAX, f64::min))
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn tight_curves_get_more_vertices_than_the_tolerance_would_give() {
+        let (path, curve) = quarter(1.0);
+        // the chord alone is within the tolerance, yet a quarter turn
+        // takes steps of no more than MAX_TURN
+        assert!(bound(&curve) <= 0.5);
+        let line = vertices(&flatten(&path, 0.5));
+        assert!(line.len() > 3, "{} vertices", line.len());
+        for w in line.windows(3) {
+            let turn = (w[2].1 - w[1].1).atan2(w[2].0 - w[1].0) - (w[1].1 - w[0].1).atan2(w[1].0 - w[0].0);
+            assert!(turn.abs() <= MAX_TURN * 1.05, "turns {turn}");
+        }
+        assert!(deviation(&curve, &line) <= 0.5);
+    }
+
+    #[test]
+    fn gentle_curves_keep_to_the_tolerance() {
+        let (path, curve) = quarter(1000.0);
+        let line = vertices(&flatten(&path, 0.5));
+        // steps turning 3.6° each would do, 25 of them
+        assert!(line.len() <= 40, "{} vertices", line.len());
+        assert!(deviation(&curve, &line) <= 0.5 + 1e-3);
+    }
+
+    #[test]
+    fn straight_runs_of_lines_are_thinned() {
+        let mut builder = Path::builder();
+        builder.begin(point(0.0, 0.0));
+        for i in 1..=20 {
+            builder.line_to(point(i as f32, if i % 2 == 0 { 0.0 } else { 0.05 }));
+        }
+        builder.line_to(point(20.0, 10.0));
+        builder.end(true);
+        assert_eq!(vertices(&flatten(&builder.build(), 0.1)), [(0.0, 0.0), (20.0, 0.0), (20.0, 10.0)]);
+    }
+}
@@ -0,0 +1,44 @@
+use clipper2::*;
+
+/// Which way outlines go round as seen on the page, `--direction`; holes
+/// and islands alternate, so holes go the other way.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    OuterClockwise,
+    OuterCounterclockwise,
+}
+
+pub fn parse(spec: &str) -> Result<Direction, String> {
+    match spec {
+        "outer-cw" | "cw" => Ok(Direction::OuterClockwise),
+        "outer-ccw" | "ccw" => Ok(Direction::OuterCounterclockwise),
+        _ => Err(format!("unknown direction {spec:?}, expected outer-cw or outer-ccw")),
+    }
+}
+
+/// `rings` each turned to go round as `direction` says for its nesting
+/// depth, keeping its start vertex.
+pub fn orient<P: PointScaler>(rings: &Paths<P>, direction: Direction) -> Paths<P> {
+    let depths = crate::parts::depths(&crate::parts::containment(rings));
+    let out: Vec<Vec<(f64, f64)>> = rings
+        .iter()
+        .zip(depths)
+        .map(|(ring, depth)| {
+            let mut points: Vec<(f64, f64)> = ring.iter().map(|p| (p.x(), p.y())).collect();
+            // with y going down, a positive shoelace sum is clockwise
+            let area: f64 = (0..points.len())
+                .map(|i| {
+                    let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                    a.0 * b.1 - b.0 * a.1
+                })
+                .sum();
+            let outline = depth.is_multiple_of(2);
+            let clockwise = (direction == Direction::OuterClockwise) == outline;
+            if (area > 0.0) != clockwise && points.len() > 2 {
+                points[1..].reverse();
+            }
+            points
+        })
+        .collect();
+    out.into()
+}
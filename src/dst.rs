@@ -0,0 +1,116 @@
+use clipper2::*;
+
+use crate::export::Page;
+
+// the longest move one record holds, in 0.1 mm
+const REACH: i64 = 121;
+
+/// Tajima DST with every ring as a running-stitch outline, after the
+/// score and hatch lines, stitches at most `page.stitch` millimetres long
+/// and jumps between them.
+pub fn write<P: PointScaler>(rings: &Paths<P>, page: &Page) -> Vec<u8> {
+    let tenths = |p: (f64, f64)| {
+        let (x, y) = page.up(p);
+        ((x * 10.0).round() as i64, (y * 10.0).round() as i64)
+    };
+    // open lines first, then every ring back round to its start
+    let mut paths: Vec<Vec<(i64, i64)>> = page.strokes.iter().map(|s| s.points.iter().copied().map(tenths).collect()).collect();
+    for ring in rings.iter() {
+        let mut points: Vec<(i64, i64)> = ring.iter().map(|p| tenths((p.x(), p.y()))).collect();
+        points.extend(points.first().copied());
+        paths.push(points);
+    }
+    // the design starts where the needle is, on the first path
+    let start = paths.iter().find_map(|p| p.first().copied()).unwrap_or((0, 0));
+    let mut records = Vec::new();
+    let mut at = start;
+    let (mut low, mut high) = (start, start);
+    let mut travel = |records: &mut Vec<[u8; 3]>, at: &mut (i64, i64), to: (i64, i64), jump: bool| {
+        // moves further than a record reaches are split evenly
+        let steps = ((to.0 - at.0).abs().max((to.1 - at.1).abs()) + REACH - 1) / REACH;
+        let (from, steps) = (*at, steps.max(1));
+        for i in 1..=steps {
+            let next = (from.0 + (to.0 - from.0) * i / steps, from.1 + (to.1 - from.1) * i / steps);
+            records.push(record(next.0 - at.0, next.1 - at.1, jump));
+            *at = next;
+        }
+        (low, high) = ((low.0.min(to.0), low.1.min(to.1)), (high.0.max(to.0), high.1.max(to.1)));
+    };
+    let stitch = (page.stitch * 10.0).max(1.0);
+    for points in &paths {
+        let Some(&first) = points.first() else {
+            continue;
+        };
+        if first != at {
+            travel(&mut records, &mut at, first, true);
+        }
+        for &to in points.iter().skip(1) {
+            let from = at;
+            let length = (((to.0 - from.0).pow(2) + (to.1 - from.1).pow(2)) as f64).sqrt();
+            let n = (length / stitch).ceil().max(1.0) as i64;
+            for i in 1..=n {
+                travel(&mut records, &mut at, (from.0 + (to.0 - from.0) * i / n, from.1 + (to.1 - from.1) * i / n), false);
+            }
+        }
+    }
+    records.push([0, 0, 0xf3]);
+
+    let mut out = format!(
+        "LA:{:<16}\rST:{:>7}\rCO:{:>3}\r+X:{:>5}\r-X:{:>5}\r+Y:{:>5}\r-Y:{:>5}\rAX:{}\rAY:{}\rMX:{}\rMY:{}\rPD:******\r\x1a",
+        "svg-combiner",
+        records.len() - 1,
+        0,
+        high.0 - start.0,
+        start.0 - low.0,
+        high.1 - start.1,
+        start.1 - low.1,
+        signed(at.0 - start.0),
+        signed(at.1 - start.1),
+        signed(0),
+        signed(0)
+    )
+    .into_bytes();
+    out.resize(512, b' ');
+    out.extend(records.concat());
+    out
+}
+
+// as DST headers have it, sign first: "+   12"
+fn signed(v: i64) -> String {
+    format!("{}{:>5}", if v < 0 { '-' } else { '+' }, v.abs())
+}
+
+/// One stitch or jump of (dx, dy), each within `REACH`, in DST's balanced
+/// ternary bits.
+fn record(mut dx: i64, mut dy: i64, jump: bool) -> [u8; 3] {
+    let mut b = [0u8, 0, 0x03];
+    if jump {
+        b[2] |= 0x80;
+    }
+    // weights of each digit with the byte and bits for + and -
+    let digits = [
+        (81, 2, 0x04, 0x08, 0x20, 0x10),
+        (27, 1, 0x04, 0x08, 0x20, 0x10),
+        (9, 0, 0x04, 0x08, 0x20, 0x10),
+        (3, 1, 0x01, 0x02, 0x80, 0x40),
+        (1, 0, 0x01, 0x02, 0x80, 0x40),
+    ];
+    for (weight, byte, x_plus, x_minus, y_plus, y_minus) in digits {
+        let half = weight / 2;
+        if dx > half {
+            b[byte] |= x_plus;
+            dx -= weight;
+        } else if dx < -half {
+            b[byte] |= x_minus;
+            dx += weight;
+        }
+        if dy > half {
+            b[byte] |= y_plus;
+            dy -= weight;
+        } else if dy < -half {
+            b[byte] |= y_minus;
+            dy += weight;
+        }
+    }
+    b
+}
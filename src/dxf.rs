@@ -0,0 +1,62 @@
+use std::fmt::Write as _;
+
+use clipper2::*;
+
+use crate::export::Page;
+
+/// An R12 DXF with every ring as a closed polyline on layer 0, in
+/// calibrated millimetres with y going up. With score or hatch lines the
+/// rings go on layer `cut` and the lines, open, on `score` and `engrave`.
+pub fn write<P: PointScaler>(rings: &Paths<P>, page: &Page) -> String {
+    let mut out = String::from("0\nSECTION\n2\nENTITIES\n");
+    let mut polyline = |layer: &str, points: &mut dyn Iterator<Item = (f64, f64)>, closed: bool| {
+        let _ = writeln!(out, "0\nPOLYLINE\n8\n{layer}\n66\n1\n70\n{}", u8::from(closed));
+        for p in points {
+            let (x, y) = page.machine(p);
+            let _ = writeln!(out, "0\nVERTEX\n8\n{layer}\n10\n{x:.6}\n20\n{y:.6}");
+        }
+        out.push_str("0\nSEQEND\n");
+    };
+    let cut = if page.strokes.is_empty() { "0" } else { "cut" };
+    for stroke in page.strokes {
+        polyline(stroke.layer, &mut stroke.points.iter().copied(), false);
+    }
+    for ring in rings.iter() {
+        polyline(cut, &mut ring.iter().map(|p| (p.x(), p.y())), true);
+    }
+    out.push_str("0\nENDSEC\n0\nEOF\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{Stroke, Tool};
+
+    #[test]
+    fn rings_are_closed_polylines_on_layer_0() {
+        let rings: Paths<Centi> = vec![vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]].into();
+        let out = write(&rings, &Page::plain((0.0, 0.0, 1.0, 1.0), 10.0));
+        let vertex = |x: &str, y: &str| format!("0\nVERTEX\n8\n0\n10\n{x}\n20\n{y}\n");
+        let polyline = [vertex("0.000000", "10.000000"), vertex("10.000000", "10.000000"), vertex("10.000000", "0.000000")].concat();
+        assert_eq!(out, format!("0\nSECTION\n2\nENTITIES\n0\nPOLYLINE\n8\n0\n66\n1\n70\n1\n{polyline}0\nSEQEND\n0\nENDSEC\n0\nEOF\n"));
+    }
+
+    #[test]
+    fn with_lines_rings_go_on_cut_after_them() {
+        let rings: Paths<Centi> = vec![vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]].into();
+        let strokes = [Stroke { layer: "score", tool: Tool::default(), points: vec![(0.0, 0.0), (1.0, 1.0)] }];
+        let page = Page { strokes: &strokes, ..Page::plain((0.0, 0.0, 1.0, 1.0), 1.0) };
+        let out = write(&rings, &page);
+        let score = out.find("POLYLINE\n8\nscore\n66\n1\n70\n0\n").unwrap();
+        let cut = out.find("POLYLINE\n8\ncut\n66\n1\n70\n1\n").unwrap();
+        assert!(score < cut);
+    }
+
+    #[test]
+    fn calibration_scales_each_axis() {
+        let rings: Paths<Centi> = vec![vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]].into();
+        let page = Page { calibrate: (2.0, 0.5), ..Page::plain((0.0, 0.0, 1.0, 1.0), 10.0) };
+        assert!(write(&rings, &page).contains("10\n20.000000\n20\n5.000000\n"));
+    }
+}
@@ -0,0 +1,245 @@
+use clipper2::*;
+
+// Edges closer than this (in user units) are treated as coincident
+const EDGE_EPSILON: f64 = 0.02;
+
+type Pt = (f64, f64);
+
+/// Outlines of the combined result split into edges cut once per polygon
+/// and edges shared by two touching polygons, which are emitted only once.
+pub struct CutLayers {
+    pub cuts: Vec<Vec<Pt>>,
+    pub shared: Vec<Vec<Pt>>,
+    pub shared_length: f64,
+}
+
+struct Edge {
+    poly: usize,
+    a: Pt,
+    b: Pt,
+    min_x: f64,
+    max_x: f64,
+}
+
+/// Walk every polygon edge and split it into pieces that coincide with an
+/// edge of another polygon and pieces that don't.
+pub fn dedup_shared_edges<P: PointScaler>(paths: &Paths<P>) -> CutLayers {
+    let rings: Vec<Vec<Pt>> = paths.iter().map(|p| p.iter().map(|pt| (pt.x(), pt.y())).collect()).collect();
+
+    let mut edges = Vec::new();
+    for (poly, ring) in rings.iter().enumerate() {
+        for i in 0..ring.len() {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            edges.push(Edge { poly, a, b, min_x: a.0.min(b.0), max_x: a.0.max(b.0) });
+        }
+    }
+
+    // sweep candidates by x so only nearby edges are compared
+    let mut by_x: Vec<usize> = (0..edges.len()).collect();
+    by_x.sort_by(|&i, &j| edges[i].min_x.total_cmp(&edges[j].min_x));
+
+    let mut cuts = Vec::new();
+    let mut shared_segments = Vec::new();
+    let mut shared_length = 0.0;
+
+    let mut first_edge = 0;
+    for ring in &rings {
+        let mut run: Vec<Pt> = Vec::new();
+        let mut ring_runs: Vec<Vec<Pt>> = Vec::new();
+
+        for e in &edges[first_edge..first_edge + ring.len()] {
+            let mut overlaps: Vec<(f64, f64, usize)> = Vec::new();
+            let end = by_x.partition_point(|&k| edges[k].min_x <= e.max_x + EDGE_EPSILON);
+            for &k in &by_x[..end] {
+                let other = &edges[k];
+                if other.poly == e.poly || other.max_x < e.min_x - EDGE_EPSILON {
+                    continue;
+                }
+                if let Some((t0, t1)) = overlap(e, other) {
+                    overlaps.push((t0, t1, other.poly));
+                }
+            }
+
+            for (t0, t1, shared_with) in split_edge(&mut overlaps) {
+                let (p0, p1) = (lerp(e.a, e.b, t0), lerp(e.a, e.b, t1));
+                match shared_with {
+                    None => {
+                        if !run.last().is_some_and(|&q| near(q, p0)) {
+                            if run.len() >= 2 {
+                                ring_runs.push(std::mem::take(&mut run));
+                            }
+                            run = vec![p0];
+                        }
+                        run.push(p1);
+                    }
+                    Some(other) => {
+                        if run.len() >= 2 {
+                            ring_runs.push(std::mem::take(&mut run));
+                        }
+                        run.clear();
+                        // the polygon with the lower index owns the shared piece
+                        if e.poly < other {
+                            shared_length += distance(p0, p1);
+                            shared_segments.push((p0, p1));
+                        }
+                    }
+                }
+            }
+        }
+        if run.len() >= 2 {
+            ring_runs.push(run);
+        }
+
+        // join the last run with the first when the ring closes through them
+        if ring_runs.len() >= 2 && near(ring_runs[0][0], *ring_runs.last().unwrap().last().unwrap()) {
+            let first = ring_runs.remove(0);
+            ring_runs.last_mut().unwrap().extend(first.into_iter().skip(1));
+        }
+        cuts.extend(ring_runs);
+        first_edge += ring.len();
+    }
+
+    CutLayers { cuts, shared: chain(shared_segments), shared_length }
+}
+
+/// Parameter interval on `e` covered by `other` when both edges are collinear.
+fn overlap(e: &Edge, other: &Edge) -> Option<(f64, f64)> {
+    let d = (e.b.0 - e.a.0, e.b.1 - e.a.1);
+    let len2 = d.0 * d.0 + d.1 * d.1;
+    if len2 == 0.0 {
+        return None;
+    }
+    let len = len2.sqrt();
+
+    // both endpoints of the other edge must lie on the line through e
+    let off = |p: Pt| ((p.0 - e.a.0) * d.1 - (p.1 - e.a.1) * d.0).abs() / len;
+    if off(other.a) > EDGE_EPSILON || off(other.b) > EDGE_EPSILON {
+        return None;
+    }
+
+    let param = |p: Pt| ((p.0 - e.a.0) * d.0 + (p.1 - e.a.1) * d.1) / len2;
+    let (s0, s1) = (param(other.a), param(other.b));
+    let (t0, t1) = (s0.min(s1).max(0.0), s0.max(s1).min(1.0));
+    if (t1 - t0) * len <= EDGE_EPSILON {
+        return None;
+    }
+    Some((t0, t1))
+}
+
+/// Cut [0, 1] at the overlap boundaries, tagging each piece with the polygon
+/// sharing it (if any).
+fn split_edge(overlaps: &mut [(f64, f64, usize)]) -> Vec<(f64, f64, Option<usize>)> {
+    overlaps.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut pieces = Vec::new();
+    let mut t = 0.0;
+    for &(t0, t1, poly) in overlaps.iter() {
+        if t1 <= t {
+            continue;
+        }
+        if t0 > t {
+            pieces.push((t, t0, None));
+        }
+        pieces.push((t0.max(t), t1, Some(poly)));
+        t = t1;
+    }
+    if t < 1.0 {
+        pieces.push((t, 1.0, None));
+    }
+    pieces
+}
+
+/// Greedily join segments sharing endpoints into polylines.
+fn chain(mut segments: Vec<(Pt, Pt)>) -> Vec<Vec<Pt>> {
+    let mut lines = Vec::new();
+    while let Some((a, b)) = segments.pop() {
+        let mut line = vec![a, b];
+        while let Some(i) = segments.iter().position(|&(p, q)| near(p, *line.last().unwrap()) || near(q, *line.last().unwrap())) {
+            let (p, q) = segments.swap_remove(i);
+            line.push(if near(p, *line.last().unwrap()) { q } else { p });
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn lerp(a: Pt, b: Pt, t: f64) -> Pt {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+fn distance(a: Pt, b: Pt) -> f64 {
+    ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+}
+
+fn near(a: Pt, b: Pt) -> bool {
+    distance(a, b) <= EDGE_EPSILON
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn squares(squares: &[(f64, f64, f64)]) -> Paths<Centi> {
+        squares.iter().map(|&(x, y, s)| vec![(x, y), (x + s, y), (x + s, y + s), (x, y + s)]).collect::<Vec<_>>().into()
+    }
+
+    fn length(lines: &[Vec<Pt>]) -> f64 {
+        lines.iter().flat_map(|line| line.windows(2).map(|w| distance(w[0], w[1]))).sum()
+    }
+
+    #[test]
+    fn apart_every_edge_is_cut_once() {
+        let layers = dedup_shared_edges(&squares(&[(0.0, 0.0, 10.0), (20.0, 0.0, 10.0)]));
+        assert!(layers.shared.is_empty());
+        assert_eq!(layers.shared_length, 0.0);
+        // each ring one closed run, around from its first corner
+        assert_eq!(layers.cuts.len(), 2);
+        assert!((length(&layers.cuts) - 80.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_shared_edge_is_cut_once_for_both() {
+        let layers = dedup_shared_edges(&squares(&[(0.0, 0.0, 10.0), (10.0, 0.0, 10.0)]));
+        assert!((layers.shared_length - 10.0).abs() < 1e-9);
+        assert_eq!(layers.shared.len(), 1);
+        assert!((length(&layers.shared) - 10.0).abs() < 1e-9);
+        // the other three sides of each, in one run apiece
+        assert_eq!(layers.cuts.len(), 2);
+        assert!((length(&layers.cuts) - 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn only_the_overlapping_part_of_an_edge_is_shared() {
+        // the smaller square against the middle of the larger one's side
+        let layers = dedup_shared_edges(&squares(&[(0.0, 0.0, 20.0), (20.0, 5.0, 10.0)]));
+        assert!((layers.shared_length - 10.0).abs() < 1e-9);
+        assert!((length(&layers.cuts) - (80.0 - 10.0 + 40.0 - 10.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn edges_within_the_epsilon_count_as_touching() {
+        let layers = dedup_shared_edges(&squares(&[(0.0, 0.0, 10.0), (10.01, 0.0, 10.0)]));
+        assert!((layers.shared_length - 10.0).abs() < 0.05);
+        let layers = dedup_shared_edges(&squares(&[(0.0, 0.0, 10.0), (10.5, 0.0, 10.0)]));
+        assert_eq!(layers.shared_length, 0.0);
+    }
+
+    #[test]
+    fn split_edge_tags_covered_pieces() {
+        let mut overlaps = vec![(0.6, 0.8, 2), (0.2, 0.4, 1)];
+        assert_eq!(
+            split_edge(&mut overlaps),
+            vec![(0.0, 0.2, None), (0.2, 0.4, Some(1)), (0.4, 0.6, None), (0.6, 0.8, Some(2)), (0.8, 1.0, None)]
+        );
+        // overlapping overlaps don't cover anything twice
+        let mut overlaps = vec![(0.0, 0.5, 1), (0.3, 1.0, 2)];
+        assert_eq!(split_edge(&mut overlaps), vec![(0.0, 0.5, Some(1)), (0.5, 1.0, Some(2))]);
+    }
+
+    #[test]
+    fn chain_joins_segments_end_to_end() {
+        // taken from the back, then followed on from its end
+        let lines = chain(vec![((1.0, 0.0), (1.0, 1.0)), ((5.0, 5.0), (6.0, 5.0)), ((0.0, 0.0), (1.0, 0.0))]);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]);
+    }
+}
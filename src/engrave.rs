@@ -0,0 +1,46 @@
+use clipper2::*;
+
+use crate::Contour;
+use crate::clip;
+use crate::style::Style;
+use crate::transform::Affine;
+
+/// A source element as drawn: its flattened subpaths before any gaps.
+pub struct Artwork {
+    pub style: Style,
+    pub closed: Vec<Contour>,
+    pub open: Vec<Contour>,
+}
+
+/// What is left of one artwork inside the silhouette.
+pub struct Piece<P: PointScaler> {
+    pub style: Style,
+    pub fill: Paths<P>,
+    pub lines: Vec<Contour>,
+}
+
+/// Clip every artwork to the silhouette; artwork left with nothing inside is
+/// dropped.
+pub fn engrave<P: PointScaler>(artwork: &[Artwork], silhouette: &Paths<P>) -> Result<Vec<Piece<P>>, ClipperError> {
+    let mut pieces = Vec::new();
+    for art in artwork {
+        let fill =
+            if art.closed.is_empty() { Paths::new(vec![]) } else { intersect(art.closed.clone(), silhouette.clone(), FillRule::NonZero)? };
+        let lines: Vec<Contour> = art.open.iter().flat_map(|line| clip::clip_polyline(line, silhouette, true)).collect();
+        if fill.is_empty() && lines.is_empty() {
+            continue;
+        }
+        pieces.push(Piece { style: art.style.clone(), fill, lines });
+    }
+    Ok(pieces)
+}
+
+impl<P: PointScaler> Piece<P> {
+    pub fn transformed(self, t: &Affine) -> Piece<P> {
+        Piece {
+            style: self.style,
+            fill: t.apply_paths(&self.fill),
+            lines: self.lines.iter().map(|l| l.iter().map(|&p| t.apply(p)).collect()).collect(),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt::Write as _;
+
+use clipper2::*;
+
+use crate::export::Page;
+
+/// Encapsulated PostScript with the result as one even-odd filled path,
+/// in points with the bounding box around the page, and score and hatch
+/// lines stroked thinly.
+pub fn write<P: PointScaler>(rings: &Paths<P>, page: &Page) -> String {
+    let pt = |p: (f64, f64)| {
+        let (x, y) = page.up(p);
+        (x * 72.0 / 25.4, y * 72.0 / 25.4)
+    };
+    let (width, height) = (page.view_box.2 * page.mm * 72.0 / 25.4, page.view_box.3 * page.mm * 72.0 / 25.4);
+    let mut out = String::new();
+    out.push_str("%!PS-Adobe-3.0 EPSF-3.0\n%%Creator: svg-combiner\n");
+    let _ = writeln!(out, "%%BoundingBox: 0 0 {} {}", width.ceil(), height.ceil());
+    let _ = writeln!(out, "%%HiResBoundingBox: 0 0 {width:.3} {height:.3}");
+    out.push_str("%%EndComments\nnewpath\n");
+    for ring in rings.iter() {
+        for (i, p) in ring.iter().enumerate() {
+            let (x, y) = pt((p.x(), p.y()));
+            let op = if i == 0 { "moveto" } else { "lineto" };
+            let _ = writeln!(out, "{x:.3} {y:.3} {op}");
+        }
+        out.push_str("closepath\n");
+    }
+    out.push_str("0 setgray eofill\n");
+    // score and hatch lines stroked on top
+    if !page.strokes.is_empty() {
+        out.push_str("newpath\n");
+        for stroke in page.strokes {
+            for (i, &p) in stroke.points.iter().enumerate() {
+                let (x, y) = pt(p);
+                let op = if i == 0 { "moveto" } else { "lineto" };
+                let _ = writeln!(out, "{x:.3} {y:.3} {op}");
+            }
+        }
+        out.push_str("0.25 setlinewidth stroke\n");
+    }
+    out.push_str("showpage\n%%EOF\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{Stroke, Tool};
+
+    #[test]
+    fn rings_are_one_even_odd_path_in_points() {
+        let rings: Paths<Centi> = vec![vec![(0.0, 0.0), (1.0, 0.0), (1.0, 2.0)]].into();
+        let out = write(&rings, &Page::plain((0.0, 0.0, 1.0, 2.0), 25.4));
+        assert!(out.starts_with("%!PS-Adobe-3.0 EPSF-3.0\n%%Creator: svg-combiner\n%%BoundingBox: 0 0 72 144\n"));
+        let path = "newpath\n0.000 144.000 moveto\n72.000 144.000 lineto\n72.000 0.000 lineto\nclosepath\n0 setgray eofill\n";
+        assert!(out.contains(path));
+        assert!(out.ends_with("eofill\nshowpage\n%%EOF\n"));
+    }
+
+    #[test]
+    fn the_bounding_box_rounds_up() {
+        let out = write(&Paths::<Centi>::new(vec![]), &Page::plain((0.0, 0.0, 10.0, 10.0), 1.0));
+        assert!(out.contains("%%BoundingBox: 0 0 29 29\n%%HiResBoundingBox: 0 0 28.346 28.346\n"));
+    }
+
+    #[test]
+    fn lines_are_stroked_after_the_fill() {
+        let strokes = [Stroke { layer: "score", tool: Tool::default(), points: vec![(0.0, 0.0), (1.0, 1.0)] }];
+        let page = Page { strokes: &strokes, ..Page::plain((0.0, 0.0, 1.0, 1.0), 25.4) };
+        let out = write(&Paths::<Centi>::new(vec![]), &page);
+        assert!(out.contains("eofill\nnewpath\n0.000 72.000 moveto\n72.000 0.000 lineto\n0.25 setlinewidth stroke\nshowpage"));
+    }
+}
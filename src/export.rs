@@ -0,0 +1,312 @@
+use clipper2::*;
+
+/// What the result is written as, `--format NAME`; everything but SVG
+/// carries the combined outlines and the score and hatch lines only.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Svg,
+    /// RS-274X, as filled regions.
+    Gerber,
+    /// A KiCad footprint, `.kicad_mod`.
+    Kicad,
+    /// Encapsulated PostScript, as one filled path.
+    Eps,
+    /// Vertices as CSV rows.
+    Csv,
+    /// Tajima DST embroidery, as running stitches.
+    Dst,
+    /// Paper.js JSON, a compound path per part.
+    Paper,
+    /// Fabric.js JSON, a path object per part.
+    Fabric,
+    /// R12 DXF, as closed polylines.
+    Dxf,
+    /// G-code tracing every ring.
+    Gcode,
+    /// HPGL for pen plotters and cutters.
+    Hpgl,
+    /// GeoJSON polygons, in real-world coordinates with `--georef`.
+    GeoJson,
+}
+
+pub fn parse(spec: &str) -> Result<Format, String> {
+    match spec {
+        "svg" => Ok(Format::Svg),
+        "gerber" | "gbr" => Ok(Format::Gerber),
+        "kicad" | "kicad_mod" => Ok(Format::Kicad),
+        "eps" | "ps" => Ok(Format::Eps),
+        "csv" => Ok(Format::Csv),
+        "dst" => Ok(Format::Dst),
+        "paper" => Ok(Format::Paper),
+        "fabric" => Ok(Format::Fabric),
+        "dxf" => Ok(Format::Dxf),
+        "gcode" | "nc" => Ok(Format::Gcode),
+        "hpgl" | "plt" => Ok(Format::Hpgl),
+        "geojson" => Ok(Format::GeoJson),
+        _ => {
+            Err(format!("unknown format {spec:?}, expected svg, gerber, kicad, eps, csv, dst, paper, fabric, dxf, gcode, hpgl or geojson"))
+        }
+    }
+}
+
+impl Format {
+    /// Whether the format only draws lines, so shared edges can be drawn
+    /// once rather than as part of every ring.
+    pub fn traces(self) -> bool {
+        matches!(self, Format::Dxf | Format::Gcode | Format::Hpgl | Format::Dst)
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Svg => "svg",
+            Format::Gerber => "gbr",
+            Format::Kicad => "kicad_mod",
+            Format::Eps => "eps",
+            Format::Csv => "csv",
+            Format::Dst => "dst",
+            Format::Paper | Format::Fabric => "json",
+            Format::Dxf => "dxf",
+            Format::Gcode => "gcode",
+            Format::Hpgl => "plt",
+            Format::GeoJson => "geojson",
+        }
+    }
+}
+
+/// One file the result is written to, `-o PATH`.
+pub struct Output {
+    pub path: String,
+    pub format: Format,
+    pub compress: bool,
+    /// Given with `-o`, so not overwritten without `--force` or `--backup`;
+    /// the default output.svg is.
+    pub named: bool,
+}
+
+/// `-o PATH`, written as what its extension stands for (`.svgz` is
+/// compressed SVG, `.json` Paper.js), or as a format named before a
+/// colon, like `fabric:shapes.json`.
+pub fn parse_output(spec: &str) -> Result<Output, String> {
+    if let Some((name, path)) = spec.split_once(':')
+        && let Ok(format) = parse(name)
+    {
+        let compress = path.ends_with(".svgz");
+        return Ok(Output { path: path.to_string(), format, compress, named: true });
+    }
+    let extension = std::path::Path::new(spec).extension().and_then(|e| e.to_str()).unwrap_or("");
+    let format = match extension {
+        "svgz" => Format::Svg,
+        "json" => Format::Paper,
+        "ger" => Format::Gerber,
+        other => parse(other).map_err(|_| format!("can't tell what to write {spec:?} as; name a format, like svg:{spec}"))?,
+    };
+    Ok(Output { path: spec.to_string(), format, compress: extension == "svgz", named: true })
+}
+
+/// `--calibrate x=1.0025,y=0.998`: scale corrections along x and y, 1
+/// for an axis left out.
+pub fn parse_calibration(spec: &str) -> Result<(f64, f64), String> {
+    let mut factors = (1.0, 1.0);
+    for item in spec.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+        let (axis, value) = item.split_once('=').ok_or_else(|| format!("expected x=FACTOR or y=FACTOR, got {item:?}"))?;
+        let value: f64 = value.trim().parse().ok().filter(|v: &f64| *v > 0.0).ok_or_else(|| format!("bad factor {value:?}"))?;
+        match axis.trim() {
+            "x" => factors.0 = value,
+            "y" => factors.1 = value,
+            other => return Err(format!("unknown axis {other:?}, expected x or y")),
+        }
+    }
+    Ok(factors)
+}
+
+/// Millimetres per drawing unit for a unit name, `--units`.
+pub fn parse_unit(spec: &str) -> Result<f64, String> {
+    match spec {
+        "px" => Ok(25.4 / 96.0),
+        "mm" => Ok(1.0),
+        "cm" => Ok(10.0),
+        "in" => Ok(25.4),
+        "pt" => Ok(25.4 / 72.0),
+        _ => Err(format!("unknown unit {spec:?}, expected px, mm, cm, in or pt")),
+    }
+}
+
+/// Where drawing coordinates land in an export: the page they sit on and
+/// how many millimetres a unit is.
+#[derive(Clone, Copy)]
+pub struct Page<'a> {
+    /// x, y, width, height, as for the SVG viewBox.
+    pub view_box: (f64, f64, f64, f64),
+    pub mm: f64,
+    /// The KiCad layer to put the result on, `--kicad-layer`.
+    pub layer: &'a str,
+    /// Longest embroidery stitch in millimetres, `--stitch-length`.
+    pub stitch: f64,
+    /// Cutting feed rate in millimetres per minute, `--feed`.
+    pub feed: f64,
+    /// x and y scale corrections for machine formats, `--calibrate`.
+    pub calibrate: (f64, f64),
+    /// How G-code enters and leaves every ring.
+    pub leads: Leads,
+    /// The pen or tool for every ring, from the config's `[tool]`s; rings
+    /// past the end use tool 1.
+    pub tools: &'a [Tool],
+    /// Open lines of scored and engraved groups, drawn before the rings.
+    pub strokes: &'a [Stroke],
+    /// Where GeoJSON puts drawing coordinates, `--georef`.
+    pub geo: Option<&'a crate::geo::Reference>,
+}
+
+/// An open line on the layer of its operation, drawn with its group's
+/// tool.
+#[derive(Clone)]
+pub struct Stroke {
+    pub layer: &'static str,
+    pub tool: Tool,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// `--lead-in`, `--lead-out`, `--lead-arc` and `--pierce-dwell`: moves
+/// into and out of every ring from the waste side, so the pierce and the
+/// stop don't mark the part.
+#[derive(Clone, Copy, Default)]
+pub struct Leads {
+    /// Lengths (arc radii) in millimetres, 0 for none.
+    pub lead_in: f64,
+    pub lead_out: f64,
+    /// Quarter arcs meeting the ring at a tangent, rather than straight
+    /// lines along its first and last edges.
+    pub arc: bool,
+    /// Seconds to wait after the tool comes on.
+    pub dwell: f64,
+}
+
+/// A pen or tool, with its feed and spindle speed or laser power if its
+/// own.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Tool {
+    pub number: u32,
+    pub feed: Option<f64>,
+    pub speed: Option<f64>,
+}
+
+impl Default for Tool {
+    fn default() -> Self {
+        Tool { number: 1, feed: None, speed: None }
+    }
+}
+
+impl Page<'_> {
+    /// A point in millimetres with y going up from the page's bottom edge.
+    pub fn up(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        ((x - self.view_box.0) * self.mm, (self.view_box.1 + self.view_box.3 - y) * self.mm)
+    }
+
+    /// Indices of the rings in the order of their tool numbers, so each
+    /// tool is picked up once.
+    pub fn by_tool(&self, rings: usize) -> Vec<(usize, Tool)> {
+        let mut order: Vec<(usize, Tool)> = (0..rings).map(|i| (i, self.tools.get(i).copied().unwrap_or_default())).collect();
+        order.sort_by_key(|(_, tool)| tool.number);
+        order
+    }
+
+    /// As `up`, corrected for the machine's mechanical error.
+    pub fn machine(&self, p: (f64, f64)) -> (f64, f64) {
+        let (x, y) = self.up(p);
+        (x * self.calibrate.0, y * self.calibrate.1)
+    }
+
+    /// A point in millimetres with y going down from the page's top edge.
+    pub fn down(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        ((x - self.view_box.0) * self.mm, (y - self.view_box.1) * self.mm)
+    }
+}
+
+#[cfg(test)]
+impl Page<'static> {
+    /// A page of `view_box` with everything else left at its default, for
+    /// the writers' tests.
+    pub fn plain(view_box: (f64, f64, f64, f64), mm: f64) -> Page<'static> {
+        Page {
+            view_box,
+            mm,
+            layer: "F.SilkS",
+            stitch: 3.0,
+            feed: 1000.0,
+            calibrate: (1.0, 1.0),
+            leads: Leads::default(),
+            tools: &[],
+            strokes: &[],
+            geo: None,
+        }
+    }
+}
+
+/// The result's rings written as `format`.
+pub fn write<P: PointScaler>(format: Format, rings: &Paths<P>, page: &Page) -> Vec<u8> {
+    match format {
+        Format::Gerber => crate::gerber::write(rings, page).into_bytes(),
+        Format::Kicad => crate::kicad::write(rings, page).into_bytes(),
+        Format::Eps => crate::eps::write(rings, page).into_bytes(),
+        Format::Csv => crate::csv::write(rings, page).into_bytes(),
+        Format::Dst => crate::dst::write(rings, page),
+        Format::Paper => crate::canvas::paper(rings, page.strokes).to_string().into_bytes(),
+        Format::Fabric => crate::canvas::fabric(rings, page.strokes).to_string().into_bytes(),
+        Format::Dxf => crate::dxf::write(rings, page).into_bytes(),
+        Format::Gcode => crate::gcode::write(rings, page).into_bytes(),
+        Format::Hpgl => crate::hpgl::write(rings, page).into_bytes(),
+        Format::GeoJson => crate::geo::geojson(rings, page).to_string().into_bytes(),
+        Format::Svg => unreachable!("SVG output is written with its layers"),
+    }
+}
+
+/// Rings as point lists, with their nesting depth; even depths are
+/// outlines, odd ones holes.
+pub fn rings_by_depth<P: PointScaler>(rings: &Paths<P>) -> Vec<(usize, Vec<(f64, f64)>)> {
+    let depths = crate::parts::depths(&crate::parts::containment(rings));
+    rings.iter().zip(depths).map(|(r, d)| (d, r.iter().map(|p| (p.x(), p.y())).collect())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_parse_by_name_and_alias() {
+        assert!(parse("gbr") == Ok(Format::Gerber));
+        assert!(parse("plt") == Ok(Format::Hpgl));
+        assert!(parse("ps").unwrap().extension() == "eps");
+        assert!(parse("pdf").err().unwrap().starts_with("unknown format \"pdf\""));
+    }
+
+    #[test]
+    fn units_are_millimetres_each() {
+        assert_eq!(parse_unit("in"), Ok(25.4));
+        assert_eq!(parse_unit("px"), Ok(25.4 / 96.0));
+        assert!(parse_unit("ft").is_err());
+    }
+
+    #[test]
+    fn up_flips_y_from_the_bottom_edge_and_down_does_not() {
+        let page = Page::plain((10.0, 20.0, 100.0, 50.0), 2.0);
+        assert_eq!(page.up((10.0, 20.0)), (0.0, 100.0));
+        assert_eq!(page.up((60.0, 70.0)), (100.0, 0.0));
+        assert_eq!(page.down((60.0, 70.0)), (100.0, 100.0));
+    }
+
+    #[test]
+    fn rings_nest_outline_hole_island() {
+        let square = |x: f64, size: f64| vec![(x, x), (x + size, x), (x + size, x + size), (x, x + size)];
+        let rings: Paths<Centi> = vec![square(4.0, 2.0), square(0.0, 10.0), square(2.0, 6.0)].into();
+        let depths: Vec<usize> = rings_by_depth(&rings).into_iter().map(|(d, _)| d).collect();
+        assert_eq!(depths, [2, 0, 1]);
+    }
+
+    #[test]
+    fn calibration_names_its_axes() {
+        assert_eq!(parse_calibration("x=1.0025, y=0.998"), Ok((1.0025, 0.998)));
+        assert_eq!(parse_calibration("y=2"), Ok((1.0, 2.0)));
+        assert_eq!(parse_calibration("x=0"), Err("bad factor \"0\"".to_string()));
+        assert_eq!(parse_calibration("z=1"), Err("unknown axis \"z\", expected x or y".to_string()));
+    }
+}
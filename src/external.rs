@@ -0,0 +1,331 @@
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+// files linked from linked files, before a loop is taken for one
+const MAX_DEPTH: usize = 8;
+
+/// `--allow-external`: files a drawing links to, loaded as part of it
+/// when they are under `root`, the drawing's own directory unless
+/// `--external-root` says otherwise.
+#[derive(Clone)]
+pub struct Sandbox {
+    pub root: Option<PathBuf>,
+}
+
+impl Sandbox {
+    /// The file `href` names, seen from a document in `dir`, if it is a
+    /// file and within the sandbox.
+    fn resolve(&self, dir: &Path, href: &str) -> Result<PathBuf, String> {
+        if href.contains("://") {
+            return Err("only local files are loaded".into());
+        }
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        let path = dir.join(href).canonicalize().map_err(|e| e.to_string())?;
+        let root = self.root.as_deref().unwrap_or(dir).canonicalize().map_err(|e| e.to_string())?;
+        if !path.starts_with(&root) {
+            return Err(format!("outside {}", root.display()));
+        }
+        Ok(path)
+    }
+
+    /// usvg's options for the document at `input`: its raster images
+    /// found next to it, and only within the sandbox.
+    pub fn options(&self, input: &str) -> usvg::Options<'static> {
+        let dir = Path::new(input).parent().map(Path::to_path_buf).unwrap_or_default();
+        let sandbox = self.clone();
+        let within = dir.clone();
+        let load = usvg::ImageHrefResolver::default_string_resolver();
+        let resolve_string: usvg::ImageHrefStringResolverFn<'static> = Box::new(move |href, opts| match sandbox.resolve(&within, href) {
+            Ok(path) => load(&path.display().to_string(), opts),
+            Err(e) => {
+                // SVG images have been reported when inlined
+                if !(href.ends_with(".svg") || href.ends_with(".svgz")) {
+                    tracing::warn!("⚠ {href} left out: {e}");
+                }
+                None
+            }
+        });
+        usvg::Options {
+            resources_dir: Some(dir),
+            image_href_resolver: usvg::ImageHrefResolver { resolve_string, ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    /// `xml`, of the document at `input`, with the `<use>`s of elements in
+    /// other files and its `<image>`s of SVG files replaced by what they
+    /// show, so the linked parts are combined like any other.
+    pub fn inline(&self, xml: &str, input: &str) -> String {
+        let dir = Path::new(input).parent().map(Path::to_path_buf).unwrap_or_default();
+        self.inline_from(xml, &dir, 0)
+    }
+
+    fn inline_from(&self, xml: &str, dir: &Path, depth: usize) -> String {
+        let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+        let Ok(doc) = roxmltree::Document::parse_with_options(xml, options) else {
+            return xml.to_string();
+        };
+        let mut replaced: Vec<(std::ops::Range<usize>, String)> = Vec::new();
+        for node in doc.descendants().filter(|n| n.has_tag_name("use") || n.has_tag_name("image")) {
+            let Some(href) = node.attributes().find(|a| a.name() == "href").map(|a| a.value()) else { continue };
+            if href.starts_with('#') || href.starts_with("data:") {
+                continue;
+            }
+            let (file, fragment) = href.split_once('#').map_or((href, None), |(f, id)| (f, Some(id)));
+            // images of anything but SVG are left to usvg
+            if node.has_tag_name("image") && !(file.ends_with(".svg") || file.ends_with(".svgz")) {
+                continue;
+            }
+            if depth == MAX_DEPTH {
+                tracing::warn!("⚠ {href} left out: linked more than {MAX_DEPTH} files deep");
+                continue;
+            }
+            let linked = match self.resolve(dir, file) {
+                Ok(path) => path,
+                Err(e) => {
+                    tracing::warn!("⚠ {href} left out: {e}");
+                    continue;
+                }
+            };
+            let text = match crate::read_svg(&linked.display().to_string()) {
+                Ok(text) => self.inline_from(&text, linked.parent().unwrap_or(Path::new("")), depth + 1),
+                Err(e) => {
+                    tracing::warn!("⚠ {href} left out: {e}");
+                    continue;
+                }
+            };
+            // ids kept apart from the document's own, by the file they came from
+            let stem = linked.file_stem().map_or("linked".into(), |s| s.to_string_lossy().into_owned());
+            match shown(&text, fragment, &format!("{stem}-")) {
+                Some(shown) => replaced.push((node.range(), wrap(node, shown))),
+                None => tracing::warn!("⚠ {href} left out: nothing there to show"),
+            }
+        }
+        let mut out = xml.to_string();
+        for (range, content) in replaced.into_iter().rev() {
+            out.replace_range(range, &content);
+        }
+        out
+    }
+}
+
+/// What a link shows: the content, and for a whole document its viewport,
+/// its `viewBox` and own size as far as it gives them.
+struct Shown {
+    content: String,
+    /// Namespaces the content's prefixes stand for.
+    namespaces: Vec<(String, String)>,
+    viewport: Option<(String, Option<String>, Option<String>)>,
+}
+
+/// What `xml` has to show: the element `fragment` names, after every
+/// `<defs>` it may need, or the whole document; ids prefixed with `prefix`
+/// throughout.
+fn shown(xml: &str, fragment: Option<&str>, prefix: &str) -> Option<Shown> {
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let doc = roxmltree::Document::parse_with_options(xml, options).ok()?;
+    let root = doc.root_element();
+    let renamed = Renamed { prefix, ids: doc.descendants().filter_map(|n| n.attribute("id")).collect() };
+    let mut content = String::new();
+    match fragment {
+        Some(id) => {
+            let target = doc.descendants().find(|n| n.attribute("id") == Some(id))?;
+            for defs in doc.descendants().filter(|n| n.has_tag_name("defs")) {
+                renamed.write(defs, root, &mut content);
+            }
+            renamed.write(target, root, &mut content);
+        }
+        None => root.children().filter(|n| n.is_element()).for_each(|n| renamed.write(n, root, &mut content)),
+    }
+    let namespaces = root.namespaces().filter_map(|ns| Some((ns.name()?.to_string(), ns.uri().to_string()))).collect();
+    if fragment.is_some() {
+        return Some(Shown { content, namespaces, viewport: None });
+    }
+    let (width, height) = (root.attribute("width").map(str::to_string), root.attribute("height").map(str::to_string));
+    let view_box = root.attribute("viewBox").map(str::to_string).or_else(|| {
+        let length = |v: &Option<String>| v.as_deref()?.trim_end_matches("px").parse::<f64>().ok();
+        Some(format!("0 0 {} {}", length(&width)?, length(&height)?))
+    })?;
+    Some(Shown { content, namespaces, viewport: Some((view_box, width, height)) })
+}
+
+/// A linked document's ids, given `prefix` wherever they are defined or
+/// referred to (`href="#id"` and `url(#id)`), and nowhere else.
+struct Renamed<'a> {
+    prefix: &'a str,
+    ids: HashSet<&'a str>,
+}
+
+impl Renamed<'_> {
+    /// `node` as XML, declaring the namespaces it uses that `outer` doesn't;
+    /// comments and processing instructions are left out.
+    fn write(&self, node: roxmltree::Node, outer: roxmltree::Node, out: &mut String) {
+        if node.is_text() {
+            let text = node.text().unwrap_or("");
+            // stylesheets refer to ids too
+            let text = if node.parent().is_some_and(|p| p.has_tag_name("style")) { self.urls(text) } else { text.to_string() };
+            out.push_str(&crate::svg::escape(&text));
+            return;
+        }
+        if !node.is_element() {
+            return;
+        }
+        let name = qualified(node, node.tag_name().namespace(), node.tag_name().name(), true);
+        let _ = write!(out, "<{name}");
+        for ns in node.namespaces().filter(|ns| !outer.namespaces().any(|o| o == *ns)) {
+            match ns.name() {
+                Some(prefix) => write!(out, r#" xmlns:{prefix}="{}""#, crate::svg::escape(ns.uri())),
+                None => write!(out, r#" xmlns="{}""#, crate::svg::escape(ns.uri())),
+            }
+            .unwrap_or_default();
+        }
+        for a in node.attributes() {
+            let value = match a.name() {
+                "id" if a.namespace().is_none() && self.ids.contains(a.value()) => format!("{}{}", self.prefix, a.value()),
+                "href" => match a.value().strip_prefix('#').filter(|id| self.ids.contains(id)) {
+                    Some(id) => format!("#{}{id}", self.prefix),
+                    None => a.value().to_string(),
+                },
+                _ => self.urls(a.value()),
+            };
+            let _ = write!(out, r#" {}="{}""#, qualified(node, a.namespace(), a.name(), false), crate::svg::escape(&value));
+        }
+        out.push('>');
+        for child in node.children() {
+            self.write(child, node, out);
+        }
+        let _ = write!(out, "</{name}>");
+    }
+
+    /// `value` with the ids its `url(#id)`s refer to renamed.
+    fn urls(&self, value: &str) -> String {
+        let mut out = String::new();
+        let mut rest = value;
+        while let Some(at) = rest.find("url(") {
+            let (head, tail) = rest.split_at(at + 4);
+            out.push_str(head);
+            let quoted = usize::from(tail.starts_with(['\'', '"']));
+            out.push_str(&tail[..quoted]);
+            let inner = &tail[quoted..];
+            let end = inner.find([')', '\'', '"']).unwrap_or(inner.len());
+            match inner[..end].strip_prefix('#').filter(|id| self.ids.contains(id)) {
+                Some(id) => {
+                    let _ = write!(out, "#{}{id}", self.prefix);
+                }
+                None => out.push_str(&inner[..end]),
+            }
+            rest = &inner[end..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// `name` with the prefix `namespace` has where `node` is, unless it is
+/// the default namespace, which only elements take.
+fn qualified(node: roxmltree::Node, namespace: Option<&str>, name: &str, element: bool) -> String {
+    match namespace {
+        Some(uri) if !(element && node.default_namespace() == Some(uri)) => match node.lookup_prefix(uri) {
+            Some(prefix) => format!("{prefix}:{name}"),
+            None => name.to_string(),
+        },
+        _ => name.to_string(),
+    }
+}
+
+/// What `link` shows where it was: moved where the link puts it, with the
+/// link's other attributes, like its style and id, on a group around it.
+/// A whole document fills the link's box, or keeps its own size.
+fn wrap(link: roxmltree::Node, shown: Shown) -> String {
+    let number = |name| link.attribute(name).and_then(|v: &str| v.trim_end_matches("px").parse::<f64>().ok());
+    let (x, y) = (number("x").unwrap_or(0.0), number("y").unwrap_or(0.0));
+    let mut g = String::from("<g");
+    for a in link.attributes().filter(|a| !["href", "x", "y", "width", "height", "transform", "preserveAspectRatio"].contains(&a.name())) {
+        let _ = write!(g, r#" {}="{}""#, a.name(), a.value().replace('"', "&quot;"));
+    }
+    for (name, uri) in &shown.namespaces {
+        let _ = write!(g, r#" xmlns:{name}="{uri}""#);
+    }
+    let transform = link.attribute("transform").unwrap_or("");
+    let Some((view_box, width, height)) = shown.viewport else {
+        let _ = write!(g, r#" transform="{transform} translate({x} {y})">{}</g>"#, shown.content);
+        return g;
+    };
+    let _ = write!(g, r#" transform="{transform}"><svg x="{x}" y="{y}" viewBox="{view_box}""#);
+    for (name, own) in [("width", width), ("height", height), ("preserveAspectRatio", None)] {
+        if let Some(value) = link.attribute(name).map(str::to_string).or(own) {
+            let _ = write!(g, r#" {name}="{value}""#);
+        }
+    }
+    let _ = write!(g, ">{}</svg></g>", shown.content);
+    g
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINKED: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" viewBox="0 0 50 50">
+        <defs><clipPath id="b"><rect width="30" height="30"/></clipPath><linearGradient id="fff"/></defs>
+        <g id="p-b" clip-path="url(#b)" style="fill:url('#fff')"><rect id="r" fill="#fff"/><text>#fff url(#b) &amp; id="b"</text></g>
+        <use xlink:href="#r"/>
+    </svg>"##;
+
+    #[test]
+    fn ids_are_prefixed_where_defined_and_referred_to_only() {
+        let content = shown(LINKED, None, "p-").unwrap().content;
+        for renamed in [
+            r#"<clipPath id="p-b">"#,
+            r#"id="p-fff""#,
+            r#"id="p-p-b" clip-path="url(#p-b)""#,
+            "fill:url('#p-fff')",
+            r##"xlink:href="#p-r""##,
+        ] {
+            assert!(content.contains(renamed), "{renamed} in {content}");
+        }
+        // paint, text and the prefixed id itself are left as they are
+        assert!(content.contains(r##"<rect id="p-r" fill="#fff">"##));
+        assert!(content.contains("<text>#fff url(#b) &amp; id=&quot;b&quot;</text>"));
+        assert!(!content.contains("p-p-p-b"));
+    }
+
+    #[test]
+    fn a_fragment_comes_after_the_defs_it_may_need() {
+        let shown = shown(LINKED, Some("p-b"), "lib-").unwrap();
+        assert!(shown.viewport.is_none());
+        assert!(shown.content.starts_with(r#"<defs><clipPath id="lib-b">"#));
+        assert!(shown.content.ends_with("</text></g>"));
+        assert!(!shown.content.contains("<use"));
+        assert_eq!(shown.namespaces, [("xlink".to_string(), "http://www.w3.org/1999/xlink".to_string())]);
+        assert!(super::shown(LINKED, Some("none"), "lib-").is_none());
+    }
+
+    #[test]
+    fn only_local_files_within_the_root_are_reached() {
+        let base = std::env::temp_dir().join(format!("svg-combiner-sandbox-{}", std::process::id()));
+        let (root, outside) = (base.join("root"), base.join("outside"));
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(root.join("lib")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(root.join("lib/part.svg"), "<svg/>").unwrap();
+        std::fs::write(outside.join("secret.svg"), "<svg/>").unwrap();
+        let sandbox = Sandbox { root: None };
+
+        assert_eq!(sandbox.resolve(&root, "lib/part.svg").unwrap(), root.join("lib/part.svg").canonicalize().unwrap());
+        assert!(sandbox.resolve(&root, "lib/../lib/part.svg").is_ok());
+        assert!(sandbox.resolve(&root, "../outside/secret.svg").unwrap_err().starts_with("outside "));
+        assert!(sandbox.resolve(&root.join("lib"), "../../outside/secret.svg").is_err());
+        assert!(sandbox.resolve(&root, "missing.svg").is_err());
+        assert_eq!(sandbox.resolve(&root, "https://example.com/a.svg").unwrap_err(), "only local files are loaded");
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(outside.join("secret.svg"), root.join("link.svg")).unwrap();
+            assert!(sandbox.resolve(&root, "link.svg").unwrap_err().starts_with("outside "));
+        }
+        // a wider root lets the same links through
+        let wider = Sandbox { root: Some(base.clone()) };
+        assert!(wider.resolve(&root, "../outside/secret.svg").is_ok());
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
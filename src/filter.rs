@@ -0,0 +1,40 @@
+/// `--filters`: what becomes of content drawn through a filter effect,
+/// which a combined shape can't have: its shapes as if unfiltered, as
+/// before (`unfiltered`), left out (`skip`), or with drop shadows made
+/// shapes of their own, a copy of the content moved by the shadow's
+/// offset (`shadow`).
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Policy {
+    #[default]
+    Unfiltered,
+    Skip,
+    Shadow,
+}
+
+pub fn parse(spec: &str) -> Result<Policy, String> {
+    match spec {
+        "unfiltered" => Ok(Policy::Unfiltered),
+        "skip" => Ok(Policy::Skip),
+        "shadow" => Ok(Policy::Shadow),
+        _ => Err(format!("unknown filter policy {spec:?}, expected unfiltered, skip or shadow")),
+    }
+}
+
+/// How far a group's filters throw its shadows, in the group's units:
+/// `feDropShadow`s and the `feOffset`s shadows are made with by hand.
+pub fn shadows(group: &usvg::Group) -> Vec<(f64, f64)> {
+    let mut offsets = Vec::new();
+    for filter in group.filters() {
+        for primitive in filter.primitives() {
+            let (dx, dy) = match primitive.kind() {
+                usvg::filter::Kind::DropShadow(s) => (s.dx(), s.dy()),
+                usvg::filter::Kind::Offset(o) => (o.dx(), o.dy()),
+                _ => continue,
+            };
+            if (dx, dy) != (0.0, 0.0) && !offsets.contains(&(dx as f64, dy as f64)) {
+                offsets.push((dx as f64, dy as f64));
+            }
+        }
+    }
+    offsets
+}
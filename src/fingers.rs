@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use clipper2::*;
+
+use crate::Contour;
+use crate::attrs::parse_length;
+
+/// `--finger-joints "size=10 thickness=3 clearance=0.1"`: box edges, each
+/// marked by a line with a `data-mate` name lying along it, cut into
+/// fingers about `size` wide and `thickness` deep, the material's, to slot
+/// into the fingers of the one other edge of that name. The slots are
+/// `clearance` wider than the fingers; kerf is made up for afterwards,
+/// like for any other cut.
+#[derive(Clone, Copy)]
+pub struct Fingers {
+    pub size: f64,
+    pub thickness: f64,
+    pub clearance: f64,
+}
+
+pub fn parse(spec: &str) -> Result<Fingers, String> {
+    let mut keys = HashMap::new();
+    for word in spec.split_whitespace() {
+        let (key, value) = word.split_once('=').ok_or_else(|| format!("expected KEY=VALUE, got {word:?}"))?;
+        if !matches!(key, "size" | "thickness" | "clearance") {
+            return Err(format!("unknown finger joint setting {key:?}"));
+        }
+        keys.insert(key, parse_length(value)?);
+    }
+    let thickness = *keys.get("thickness").ok_or("finger joints need the material's thickness=")?;
+    let size = keys.get("size").copied().unwrap_or(thickness * 2.0);
+    if size <= 0.0 || thickness <= 0.0 {
+        return Err("size and thickness need to be above 0".into());
+    }
+    Ok(Fingers { size, thickness, clearance: keys.get("clearance").copied().unwrap_or(0.0) })
+}
+
+/// One edge to cut: a mate line, its slots, as many and as wide as its
+/// mate's, and whether it is the first of its pair, keeping its ends.
+pub struct Edge {
+    pub name: String,
+    a: (f64, f64),
+    b: (f64, f64),
+    slots: usize,
+    width: f64,
+    first: bool,
+}
+
+impl Fingers {
+    /// The same joints with every length multiplied by `factor`.
+    pub fn scaled(self, factor: f64) -> Fingers {
+        Fingers { size: self.size * factor, thickness: self.thickness * factor, clearance: self.clearance * factor }
+    }
+
+    /// The edges of `mates`, the lines by name in document order, that
+    /// pair up; and the names that don't, with how many lines they have.
+    pub fn edges(&self, mates: &[(String, Contour)]) -> (Vec<Edge>, Vec<(String, usize)>) {
+        let mut names: Vec<&str> = Vec::new();
+        for (name, _) in mates {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+        let mut edges = Vec::new();
+        let mut unpaired = Vec::new();
+        for name in names {
+            let lines: Vec<((f64, f64), (f64, f64))> =
+                mates.iter().filter(|(n, _)| n == name).filter_map(|(_, line)| Some((*line.first()?, *line.last()?))).collect();
+            let [first, second] = lines[..] else {
+                unpaired.push((name.to_string(), lines.len()));
+                continue;
+            };
+            // an odd count, so the first edge keeps both its ends
+            let length = |(a, b): ((f64, f64), (f64, f64))| (b.0 - a.0).hypot(b.1 - a.1);
+            let shorter = length(first).min(length(second));
+            let mut slots = ((shorter / self.size).round() as usize).max(3);
+            if slots.is_multiple_of(2) {
+                slots -= 1;
+            }
+            let width = shorter / slots as f64;
+            for (line, first) in [(first, true), (second, false)] {
+                edges.push(Edge { name: name.to_string(), a: line.0, b: line.1, slots, width, first });
+            }
+        }
+        (edges, unpaired)
+    }
+
+    /// `rings` with slots cut into every part along the `edges` lying on
+    /// its outline; and which of the edges those were.
+    pub fn cut<P: PointScaler>(&self, rings: &Paths<P>, edges: &[Edge]) -> Result<(Paths<P>, Vec<usize>), ClipperError> {
+        let outlines: Vec<Contour> = rings.iter().map(|r| r.iter().map(|p| (p.x(), p.y())).collect()).collect();
+        let mut slots: Vec<Contour> = Vec::new();
+        let mut done = Vec::new();
+        for (n, edge) in edges.iter().enumerate() {
+            let length = (edge.b.0 - edge.a.0).hypot(edge.b.1 - edge.a.1);
+            if length <= 0.0 {
+                continue;
+            }
+            let along = ((edge.b.0 - edge.a.0) / length, (edge.b.1 - edge.a.1) / length);
+            let mut across = (-along.1, along.0);
+            // inwards, with the part on one side of the line only
+            let mid = ((edge.a.0 + edge.b.0) / 2.0, (edge.a.1 + edge.b.1) / 2.0);
+            let probe = |s: f64| crate::clip::winding(&outlines, (mid.0 + across.0 * s, mid.1 + across.1 * s)) != 0;
+            let step = self.thickness / 4.0;
+            match (probe(step), probe(-step)) {
+                (true, false) => {}
+                (false, true) => across = (-across.0, -across.1),
+                _ => continue,
+            }
+            let at = |u: f64, v: f64| (edge.a.0 + along.0 * u + across.0 * v, edge.a.1 + along.1 * u + across.1 * v);
+            let (width, end) = (edge.width, edge.width * edge.slots as f64);
+            // the first edge gives up its odd slots, its mate the even ones
+            for k in (0..edge.slots).filter(|k| (k % 2 == 1) == edge.first) {
+                // the clearance split between the two edges
+                let half = self.clearance / 4.0;
+                let u0 = if k == 0 { -self.thickness } else { k as f64 * width - half };
+                let u1 = if k + 1 == edge.slots { end + self.thickness } else { (k + 1) as f64 * width + half };
+                slots.push(vec![at(u0, -self.thickness), at(u1, -self.thickness), at(u1, self.thickness), at(u0, self.thickness)]);
+            }
+            done.push(n);
+        }
+        if slots.is_empty() {
+            return Ok((rings.clone(), done));
+        }
+        Ok((difference(rings.clone(), Paths::from(slots), FillRule::NonZero)?, done))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, size: f64) -> Contour {
+        vec![(x, 0.0), (x + size, 0.0), (x + size, size), (x, size)]
+    }
+
+    fn mate(name: &str, line: Contour) -> (String, Contour) {
+        (name.to_string(), line)
+    }
+
+    fn area(paths: &Paths<Centi>) -> f64 {
+        paths.iter().map(|r| r.signed_area()).sum::<f64>().abs()
+    }
+
+    #[test]
+    fn fingers_are_two_thicknesses_wide_unless_told() {
+        let f = parse("thickness=3").unwrap();
+        assert_eq!((f.size, f.thickness, f.clearance), (6.0, 3.0, 0.0));
+        let f = parse("size=10 thickness=3 clearance=0.1").unwrap();
+        assert_eq!((f.size, f.thickness, f.clearance), (10.0, 3.0, 0.1));
+    }
+
+    #[test]
+    fn bad_specs_are_refused() {
+        assert!(parse("size=10").is_err());
+        assert!(parse("thickness=0").is_err());
+        assert!(parse("thickness").is_err());
+        assert_eq!(parse("thickness=3 depth=2").err().unwrap(), "unknown finger joint setting \"depth\"");
+    }
+
+    #[test]
+    fn edges_pair_by_name_into_an_odd_number_of_slots() {
+        let f = parse("size=10 thickness=3").unwrap();
+        let mates = [
+            mate("lid", vec![(0.0, 0.0), (50.0, 0.0)]),
+            mate("side", vec![(0.0, 0.0), (0.0, 40.0)]),
+            mate("lid", vec![(0.0, 10.0), (60.0, 10.0)]),
+            mate("side", vec![(5.0, 0.0), (5.0, 40.0)]),
+            mate("back", vec![(0.0, 0.0), (9.0, 0.0)]),
+        ];
+        let (edges, unpaired) = f.edges(&mates);
+        assert_eq!(unpaired, [("back".to_string(), 1)]);
+        let found: Vec<_> = edges.iter().map(|e| (e.name.as_str(), e.slots, e.width, e.first)).collect();
+        // by the shorter edge, and 4 slots of 10 become 3 of 13⅓
+        assert_eq!(found, [("lid", 5, 10.0, true), ("lid", 5, 10.0, false), ("side", 3, 40.0 / 3.0, true), ("side", 3, 40.0 / 3.0, false)]);
+    }
+
+    #[test]
+    fn mates_give_up_alternate_slots_inwards() {
+        let f = parse("size=10 thickness=3").unwrap();
+        let mates = [mate("join", vec![(50.0, 0.0), (50.0, 50.0)]), mate("join", vec![(60.0, 0.0), (60.0, 50.0)])];
+        let (edges, _) = f.edges(&mates);
+        let rings: Paths<Centi> = vec![square(0.0, 50.0), square(60.0, 50.0)].into();
+        let (cut, done) = f.cut(&rings, &edges).unwrap();
+        assert_eq!(done, [0, 1]);
+        // the first edge loses two slots of five into its part, its mate three
+        let left: f64 = cut.iter().filter(|r| r.bounds().max.x() <= 51.0).map(|r| r.signed_area()).sum();
+        let right: f64 = cut.iter().filter(|r| r.bounds().min.x() >= 59.0).map(|r| r.signed_area()).sum();
+        assert!((left.abs() - (2500.0 - 2.0 * 10.0 * 3.0)).abs() < 0.5);
+        assert!((right.abs() - (2500.0 - 3.0 * 10.0 * 3.0)).abs() < 0.5);
+    }
+
+    #[test]
+    fn edges_off_every_outline_are_not_cut() {
+        let f = parse("size=10 thickness=3").unwrap();
+        let mates = [mate("join", vec![(20.0, 80.0), (20.0, 90.0)]), mate("join", vec![(30.0, 80.0), (30.0, 90.0)])];
+        let (edges, _) = f.edges(&mates);
+        let rings: Paths<Centi> = vec![square(0.0, 50.0)].into();
+        let (cut, done) = f.cut(&rings, &edges).unwrap();
+        assert!(done.is_empty());
+        assert!((area(&cut) - 2500.0).abs() < 1e-6);
+    }
+}
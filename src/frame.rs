@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use clipper2::*;
+
+use crate::attrs::parse_length;
+use crate::corners;
+
+/// `--frame "rect width=5 gap=3 radius=2"`: a border `width` wide, `gap`
+/// away from the result, with corners rounded to `radius`. A `rect` frame
+/// goes around the bounding box, a `follow` frame around the outline.
+#[derive(Clone, Copy)]
+pub struct Frame {
+    pub follow: bool,
+    pub width: f64,
+    pub gap: f64,
+    pub radius: f64,
+}
+
+pub fn parse(spec: &str) -> Result<Frame, String> {
+    let mut words = spec.split_whitespace().peekable();
+    // rect unless told otherwise
+    let follow = words.peek() == Some(&"follow");
+    if matches!(words.peek(), Some(&"follow" | &"rect")) {
+        words.next();
+    }
+    let mut keys = HashMap::new();
+    for word in words {
+        let (key, value) = word.split_once('=').ok_or_else(|| format!("expected KEY=VALUE, got {word:?}"))?;
+        if !matches!(key, "width" | "gap" | "radius") {
+            return Err(format!("unknown frame setting {key:?}"));
+        }
+        keys.insert(key, parse_length(value)?);
+    }
+    let width = *keys.get("width").ok_or("a frame needs width=")?;
+    if width <= 0.0 {
+        return Err("a frame needs a width above 0".into());
+    }
+    Ok(Frame { follow, width, gap: keys.get("gap").copied().unwrap_or(0.0), radius: keys.get("radius").copied().unwrap_or(0.0) })
+}
+
+impl Frame {
+    /// The same frame with every length multiplied by `factor`.
+    pub fn scaled(self, factor: f64) -> Frame {
+        Frame { width: self.width * factor, gap: self.gap * factor, radius: self.radius * factor, ..self }
+    }
+
+    /// The frame's rings around `paths`: what the outer edge encloses less
+    /// what the inner one does.
+    pub fn around<P: PointScaler>(&self, paths: &Paths<P>) -> Result<Paths<P>, ClipperError> {
+        if paths.is_empty() {
+            return Ok(Paths::new(vec![]));
+        }
+        let base: Paths<P> = if self.follow {
+            union(paths.clone(), Paths::new(vec![]), FillRule::NonZero)?
+        } else {
+            let b = paths.bounds();
+            vec![vec![(b.min.x(), b.min.y()), (b.max.x(), b.min.y()), (b.max.x(), b.max.y()), (b.min.x(), b.max.y())]].into()
+        };
+        let rounded = |p: Paths<P>| if self.radius > 0.0 { corners::round_corners(p, self.radius) } else { p };
+        let inner = rounded(base.inflate(self.gap, JoinType::Miter, EndType::Polygon, 2.0));
+        let outer = rounded(inner.inflate(self.width, JoinType::Miter, EndType::Polygon, 2.0));
+        // a following frame leaves no holes of the outline inside itself
+        let inner = if self.follow { crate::parts::silhouette(&inner) } else { inner };
+        difference(crate::parts::silhouette(&outer), inner, FillRule::NonZero)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(size: f64) -> Paths<Centi> {
+        vec![vec![(0.0, 0.0), (size, 0.0), (size, size), (0.0, size)]].into()
+    }
+
+    #[test]
+    fn frames_are_rects_without_gap_or_radius_unless_told() {
+        let f = parse("width=5").unwrap();
+        assert!(!f.follow);
+        assert_eq!((f.width, f.gap, f.radius), (5.0, 0.0, 0.0));
+        let f = parse("follow width=2 gap=3 radius=1").unwrap();
+        assert!(f.follow);
+        assert_eq!((f.width, f.gap, f.radius), (2.0, 3.0, 1.0));
+    }
+
+    #[test]
+    fn bad_frames_are_refused() {
+        assert!(parse("rect").is_err());
+        assert!(parse("rect width=0").is_err());
+        assert!(parse("rect width").is_err());
+        assert_eq!(parse("rect width=5 margin=2").err().unwrap(), "unknown frame setting \"margin\"");
+    }
+
+    #[test]
+    fn a_frame_rings_the_result_at_the_gap() {
+        let frame = parse("rect width=5 gap=3").unwrap();
+        let ring = frame.around(&square(20.0)).unwrap();
+        assert_eq!(ring.len(), 2);
+        let b = ring.bounds();
+        assert!((b.min.x() + 8.0).abs() < 1e-2 && (b.max.y() - 28.0).abs() < 1e-2);
+        let area: f64 = ring.iter().map(|r| r.signed_area()).sum::<f64>().abs();
+        assert!((area - (36.0 * 36.0 - 26.0 * 26.0)).abs() < 1.0);
+        assert!(frame.around(&Paths::<Centi>::new(vec![])).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt::Write as _;
+
+use clipper2::*;
+
+use crate::export::Page;
+
+/// G-code tracing every ring once, in calibrated millimetres with y going
+/// up: a rapid move to its start, the tool on (M3), cuts around at the
+/// feed rate and the tool off (M5). Rings are cut grouped by tool, with a
+/// tool change (`T.. M6`) between them and each tool's own feed and speed.
+/// Leads come in and go out on the waste side, outside outlines and
+/// inside holes. Score and hatch lines are traced before any ring.
+pub fn write<P: PointScaler>(rings: &Paths<P>, page: &Page) -> String {
+    let leads = page.leads;
+    let depths = crate::parts::depths(&crate::parts::containment(rings));
+    let mut out = String::from("; svg-combiner\nG21\nG90\n");
+    let mut current = None;
+    // score and hatch lines first, so parts stay put for them, traced
+    // once without leads
+    let mut strokes: Vec<_> = page.strokes.iter().collect();
+    strokes.sort_by_key(|s| s.tool.number);
+    for stroke in strokes {
+        let points: Vec<(f64, f64)> = stroke.points.iter().map(|&p| page.machine(p)).collect();
+        let [start, rest @ ..] = points.as_slice() else {
+            continue;
+        };
+        change(&mut out, &mut current, stroke.tool.number);
+        let feed = format!(" F{}", stroke.tool.feed.unwrap_or(page.feed));
+        let speed = stroke.tool.speed.map_or(String::new(), |s| format!(" S{s}"));
+        let _ = writeln!(out, "G0 X{:.4} Y{:.4}\nM3{speed}", start.0, start.1);
+        if leads.dwell > 0.0 {
+            let _ = writeln!(out, "G4 P{}", leads.dwell);
+        }
+        for (k, (x, y)) in rest.iter().enumerate() {
+            let _ = writeln!(out, "G1 X{x:.4} Y{y:.4}{}", if k == 0 { feed.as_str() } else { "" });
+        }
+        out.push_str("M5\n");
+    }
+    for (i, tool) in page.by_tool(rings.len()) {
+        let Some(ring) = rings.get(i) else {
+            continue;
+        };
+        let mut points: Vec<(f64, f64)> = ring.iter().map(|p| page.machine((p.x(), p.y()))).collect();
+        if points.len() < 2 {
+            continue;
+        }
+        // leads meet the middle of the first edge, where both sides of it
+        // are clear, rather than a corner
+        if leads.lead_in > 0.0 || leads.lead_out > 0.0 {
+            let (a, b) = (points[0], points[1]);
+            points.insert(1, ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0));
+            points.rotate_left(1);
+        }
+        let (start, second, last) = (points[0], points[1], points[points.len() - 1]);
+        change(&mut out, &mut current, tool.number);
+        let feed = format!(" F{}", tool.feed.unwrap_or(page.feed));
+        let speed = tool.speed.map_or(String::new(), |s| format!(" S{s}"));
+
+        // waste is outside outlines and inside holes, whichever way round
+        // the ring goes
+        let area: f64 = (0..points.len())
+            .map(|k| {
+                let (a, b) = (points[k], points[(k + 1) % points.len()]);
+                a.0 * b.1 - b.0 * a.1
+            })
+            .sum();
+        let (first, closing) = (unit(start, second), unit(last, start));
+        let outline = depths.get(i).is_none_or(|d| d.is_multiple_of(2));
+        let side = if (area > 0.0) == outline { 1.0 } else { -1.0 };
+        let right = |d: (f64, f64)| (d.1 * side, -d.0 * side);
+
+        let entry = if leads.lead_in > 0.0 {
+            let l = leads.lead_in;
+            if leads.arc {
+                let c = (start.0 + right(first).0 * l, start.1 + right(first).1 * l);
+                (c.0 - first.0 * l, c.1 - first.1 * l)
+            } else {
+                (start.0 - first.0 * l, start.1 - first.1 * l)
+            }
+        } else {
+            start
+        };
+        let _ = writeln!(out, "G0 X{:.4} Y{:.4}\nM3{speed}", entry.0, entry.1);
+        if leads.dwell > 0.0 {
+            let _ = writeln!(out, "G4 P{}", leads.dwell);
+        }
+        let arc = if side > 0.0 { "G2" } else { "G3" };
+        if leads.lead_in > 0.0 {
+            let l = leads.lead_in;
+            if leads.arc {
+                let _ = writeln!(out, "{arc} X{:.4} Y{:.4} I{:.4} J{:.4}{feed}", start.0, start.1, first.0 * l, first.1 * l);
+            } else {
+                let _ = writeln!(out, "G1 X{:.4} Y{:.4}{feed}", start.0, start.1);
+            }
+        }
+        for (k, (x, y)) in points.iter().skip(1).chain([&start]).enumerate() {
+            let feed = if k == 0 && leads.lead_in <= 0.0 { feed.as_str() } else { "" };
+            let _ = writeln!(out, "G1 X{x:.4} Y{y:.4}{feed}");
+        }
+        if leads.lead_out > 0.0 {
+            let l = leads.lead_out;
+            if leads.arc {
+                let (i, j) = (right(closing).0 * l, right(closing).1 * l);
+                let end = (start.0 + i + closing.0 * l, start.1 + j + closing.1 * l);
+                let _ = writeln!(out, "{arc} X{:.4} Y{:.4} I{i:.4} J{j:.4}", end.0, end.1);
+            } else {
+                let _ = writeln!(out, "G1 X{:.4} Y{:.4}", start.0 + closing.0 * l, start.1 + closing.1 * l);
+            }
+        }
+        out.push_str("M5\n");
+    }
+    out.push_str("G0 X0 Y0\nM2\n");
+    out
+}
+
+// a tool change to `number` if it isn't in already; one tool throughout
+// needs none
+fn change(out: &mut String, current: &mut Option<u32>, number: u32) {
+    if *current != Some(number) && (current.is_some() || number != 1) {
+        let _ = writeln!(out, "T{number} M6");
+    }
+    *current = Some(number);
+}
+
+// the direction from `a` to `b`
+fn unit(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    let length = (b.0 - a.0).hypot(b.1 - a.1).max(1e-12);
+    ((b.0 - a.0) / length, (b.1 - a.1) / length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{Leads, Tool};
+
+    fn square() -> Paths<Centi> {
+        vec![vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]].into()
+    }
+
+    #[test]
+    fn a_ring_is_cut_once_around_with_the_tool_on() {
+        let out = write(&square(), &Page::plain((0.0, 0.0, 1.0, 1.0), 10.0));
+        let cut = "G0 X0.0000 Y10.0000\nM3\nG1 X10.0000 Y10.0000 F1000\nG1 X10.0000 Y0.0000\nG1 X0.0000 Y0.0000\nG1 X0.0000 Y10.0000\nM5\n";
+        assert_eq!(out, format!("; svg-combiner\nG21\nG90\n{cut}G0 X0 Y0\nM2\n"));
+    }
+
+    #[test]
+    fn other_tools_are_changed_to_with_their_own_feed_and_speed() {
+        let tools = [Tool { number: 2, feed: Some(300.0), speed: Some(12000.0) }];
+        let page = Page { tools: &tools, ..Page::plain((0.0, 0.0, 1.0, 1.0), 10.0) };
+        let out = write(&square(), &page);
+        assert!(out.contains("T2 M6\nG0 X0.0000 Y10.0000\nM3 S12000\nG1 X10.0000 Y10.0000 F300\n"));
+    }
+
+    #[test]
+    fn arc_leads_come_in_and_go_out_on_the_waste_side() {
+        let leads = Leads { lead_in: 2.0, lead_out: 2.0, arc: true, dwell: 0.5 };
+        let page = Page { leads, ..Page::plain((0.0, 0.0, 1.0, 1.0), 10.0) };
+        let out = write(&square(), &page);
+        // from above the top edge, outside the square, to the middle of it
+        assert!(out.contains("G0 X3.0000 Y12.0000\nM3\nG4 P0.5\nG3 X5.0000 Y10.0000 I2.0000 J0.0000 F1000\nG1 X10.0000 Y10.0000\n"));
+        assert!(out.contains("G1 X5.0000 Y10.0000\nG3 X7.0000 Y12.0000 I"));
+        assert!(out.contains(" J2.0000\nM5\n"));
+    }
+
+    #[test]
+    fn calibration_scales_each_axis() {
+        let page = Page { calibrate: (2.0, 0.5), ..Page::plain((0.0, 0.0, 1.0, 1.0), 10.0) };
+        assert!(write(&square(), &page).contains("G0 X0.0000 Y5.0000\nM3\nG1 X20.0000 Y5.0000 F1000\n"));
+    }
+}
@@ -0,0 +1,396 @@
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+use clipper2::*;
+use serde_json::{Value, json};
+
+use crate::export::Page;
+use crate::transform::Affine;
+
+/// `--georef`, `--crs` and `--to-crs`: where the drawing lies on the
+/// earth, for GeoJSON output. `georef` takes drawing units to `from`'s
+/// coordinates; they are reprojected into `to`, WGS84 longitude and
+/// latitude unless said otherwise.
+#[derive(Clone)]
+pub struct Reference {
+    pub georef: Affine,
+    pub from: Projection,
+    pub to: Projection,
+}
+
+/// A coordinate system, from the parts of a proj4 string this knows:
+/// `+proj=longlat`, `merc`, `tmerc` and `utm`, on one ellipsoid, with
+/// false eastings and northings and a linear unit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Projection {
+    kind: Kind,
+    /// Semi-major axis and flattening.
+    a: f64,
+    f: f64,
+    lat_0: f64,
+    lon_0: f64,
+    k_0: f64,
+    x_0: f64,
+    y_0: f64,
+    /// Metres per unit.
+    to_meter: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    LongLat,
+    Mercator,
+    TransverseMercator,
+}
+
+/// `--georef a,b,c,d,e,f`, an affine matrix as SVG writes them, or a
+/// world file: its six lines A, D, B, E, C, F, for the centre of the
+/// top-left pixel.
+pub fn parse_georef(spec: &str) -> Result<Affine, String> {
+    let (text, world) = match std::fs::read_to_string(spec) {
+        Ok(text) => (text, true),
+        Err(_) => (spec.to_string(), false),
+    };
+    let numbers: Vec<f64> = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(|_| format!("bad --georef number {s:?}")))
+        .collect::<Result<_, _>>()?;
+    let [a, b, c, d, e, f] = numbers[..] else {
+        return Err(format!("--georef needs six numbers, got {}", numbers.len()));
+    };
+    // A D B E C F is SVG's order too, but from the pixel's centre
+    let georef = Affine { a, b, c, d, e, f };
+    Ok(if world { Affine::translate(-0.5, -0.5).then(georef) } else { georef })
+}
+
+/// A proj4 string, or `EPSG:4326`, `EPSG:3857` or a UTM zone's
+/// `EPSG:326NN` and `EPSG:327NN`.
+pub fn parse_crs(spec: &str) -> Result<Projection, String> {
+    let spec = spec.trim();
+    if let Some(code) = spec.strip_prefix("EPSG:").or_else(|| spec.strip_prefix("epsg:")) {
+        let proj4 = match code.parse::<u32>().map_err(|_| format!("bad EPSG code {code:?}"))? {
+            4326 => "+proj=longlat +datum=WGS84".to_string(),
+            3857 => "+proj=merc +a=6378137 +b=6378137 +lon_0=0 +k=1 +x_0=0 +y_0=0".to_string(),
+            n @ 32601..=32660 => format!("+proj=utm +zone={} +datum=WGS84", n - 32600),
+            n @ 32701..=32760 => format!("+proj=utm +zone={} +south +datum=WGS84", n - 32700),
+            n => return Err(format!("EPSG:{n} isn't known; give its proj4 string")),
+        };
+        return parse_crs(&proj4);
+    }
+    let mut params: Vec<(&str, Option<&str>)> = Vec::new();
+    for item in spec.split_whitespace() {
+        let item = item.strip_prefix('+').ok_or_else(|| format!("bad proj4 parameter {item:?}, expected +name=value"))?;
+        params.push(item.split_once('=').map_or((item, None), |(k, v)| (k, Some(v))));
+    }
+    let get = |name: &str| params.iter().find(|(k, _)| *k == name).map(|(_, v)| *v);
+    let number = |name: &str| -> Result<Option<f64>, String> {
+        match get(name) {
+            Some(Some(v)) => v.parse().map(Some).map_err(|_| format!("bad +{name}={v}")),
+            Some(None) => Err(format!("+{name} needs a value")),
+            None => Ok(None),
+        }
+    };
+
+    let (mut a, mut f) = match get("ellps").flatten().or(get("datum").flatten()) {
+        None | Some("WGS84") => (6_378_137.0, 1.0 / 298.257_223_563),
+        Some("GRS80" | "NAD83") => (6_378_137.0, 1.0 / 298.257_222_101),
+        Some("sphere") => (6_370_997.0, 0.0),
+        Some(other) => return Err(format!("ellipsoid or datum {other:?} isn't known; give +a and +b")),
+    };
+    if get("towgs84").is_some() || get("nadgrids").is_some() {
+        return Err("datum shifts aren't supported; every system is taken to be on the same datum".into());
+    }
+    if let Some(r) = number("R")? {
+        (a, f) = (r, 0.0);
+    }
+    if let Some(major) = number("a")? {
+        a = major;
+        f = match (number("b")?, number("rf")?, number("f")?) {
+            (Some(b), _, _) => 1.0 - b / major,
+            (_, Some(rf), _) => 1.0 / rf,
+            (_, _, Some(flattening)) => flattening,
+            _ => 0.0,
+        };
+    }
+    let to_meter = match (number("to_meter")?, get("units").flatten()) {
+        (Some(m), _) => m,
+        (None, None | Some("m")) => 1.0,
+        (None, Some("km")) => 1000.0,
+        (None, Some("ft")) => 0.3048,
+        (None, Some("us-ft")) => 1200.0 / 3937.0,
+        (None, Some(other)) => return Err(format!("unit {other:?} isn't known; give +to_meter")),
+    };
+    let mut projection = Projection {
+        kind: Kind::LongLat,
+        a,
+        f,
+        lat_0: number("lat_0")?.unwrap_or(0.0).to_radians(),
+        lon_0: number("lon_0")?.unwrap_or(0.0).to_radians(),
+        k_0: number("k_0")?.or(number("k")?).unwrap_or(1.0),
+        x_0: number("x_0")?.unwrap_or(0.0),
+        y_0: number("y_0")?.unwrap_or(0.0),
+        to_meter,
+    };
+    match get("proj").flatten() {
+        Some("longlat" | "latlong" | "lonlat" | "latlon") => {}
+        Some("merc") => projection.kind = Kind::Mercator,
+        Some("tmerc") => projection.kind = Kind::TransverseMercator,
+        Some("utm") => {
+            let zone =
+                number("zone")?.filter(|z| (1.0..=60.0).contains(z) && z.fract() == 0.0).ok_or("+proj=utm needs a +zone from 1 to 60")?;
+            projection.kind = Kind::TransverseMercator;
+            projection.lat_0 = 0.0;
+            projection.lon_0 = (zone * 6.0 - 183.0).to_radians();
+            projection.k_0 = 0.9996;
+            projection.x_0 = 500_000.0;
+            projection.y_0 = if get("south").is_some() { 10_000_000.0 } else { 0.0 };
+        }
+        Some(other) => return Err(format!("projection {other:?} isn't supported, only longlat, merc, tmerc and utm")),
+        None => return Err(format!("{spec:?} has no +proj")),
+    }
+    Ok(projection)
+}
+
+/// WGS84 longitude and latitude, in degrees.
+pub fn wgs84() -> Projection {
+    parse_crs("EPSG:4326").expect("longlat is known")
+}
+
+impl Reference {
+    /// A drawing point in the target system.
+    pub fn place(&self, p: (f64, f64)) -> (f64, f64) {
+        self.to.forward(self.from.inverse(self.georef.apply(p)))
+    }
+}
+
+impl Projection {
+    fn e(&self) -> f64 {
+        (self.f * (2.0 - self.f)).sqrt()
+    }
+
+    /// Longitude and latitude in radians to this system's coordinates.
+    fn forward(&self, (lon, lat): (f64, f64)) -> (f64, f64) {
+        let (x, y) = match self.kind {
+            Kind::LongLat => return (lon.to_degrees(), lat.to_degrees()),
+            Kind::Mercator => {
+                let (e, s) = (self.e(), lat.sin());
+                let y = ((FRAC_PI_4 + lat / 2.0).tan() * ((1.0 - e * s) / (1.0 + e * s)).powf(e / 2.0)).ln();
+                (self.k_0 * self.a * (lon - self.lon_0), self.k_0 * self.a * y)
+            }
+            Kind::TransverseMercator => {
+                let (x, y) = self.kruger(lon - self.lon_0, lat);
+                let (_, y0) = self.kruger(0.0, self.lat_0);
+                (x, y - y0)
+            }
+        };
+        ((x + self.x_0) / self.to_meter, (y + self.y_0) / self.to_meter)
+    }
+
+    /// This system's coordinates to longitude and latitude in radians.
+    fn inverse(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let (x, y) = match self.kind {
+            Kind::LongLat => return (x.to_radians(), y.to_radians()),
+            _ => (x * self.to_meter - self.x_0, y * self.to_meter - self.y_0),
+        };
+        match self.kind {
+            Kind::Mercator => {
+                let (e, t) = (self.e(), (-y / (self.k_0 * self.a)).exp());
+                let mut lat = FRAC_PI_2 - 2.0 * t.atan();
+                for _ in 0..15 {
+                    let s = lat.sin();
+                    lat = FRAC_PI_2 - 2.0 * (t * ((1.0 - e * s) / (1.0 + e * s)).powf(e / 2.0)).atan();
+                }
+                (x / (self.k_0 * self.a) + self.lon_0, lat)
+            }
+            _ => {
+                let (_, y0) = self.kruger(0.0, self.lat_0);
+                let (lon, lat) = self.kruger_inverse(x, y + y0);
+                (lon + self.lon_0, lat)
+            }
+        }
+    }
+
+    /// Krüger's series to the third order of the flattening: coordinates
+    /// in metres, scaled by `k_0`, from the central meridian and equator.
+    fn series(&self) -> (f64, [f64; 3], [f64; 3], [f64; 3]) {
+        let n = self.f / (2.0 - self.f);
+        let (n2, n3) = (n * n, n * n * n);
+        let big_a = self.a / (1.0 + n) * (1.0 + n2 / 4.0 + n2 * n2 / 64.0);
+        let alpha = [n / 2.0 - 2.0 * n2 / 3.0 + 5.0 * n3 / 16.0, 13.0 * n2 / 48.0 - 3.0 * n3 / 5.0, 61.0 * n3 / 240.0];
+        let beta = [n / 2.0 - 2.0 * n2 / 3.0 + 37.0 * n3 / 96.0, n2 / 48.0 + n3 / 15.0, 17.0 * n3 / 480.0];
+        let delta = [2.0 * n - 2.0 * n2 / 3.0 - 2.0 * n3, 7.0 * n2 / 3.0 - 8.0 * n3 / 5.0, 56.0 * n3 / 15.0];
+        (self.k_0 * big_a, alpha, beta, delta)
+    }
+
+    fn kruger(&self, dlon: f64, lat: f64) -> (f64, f64) {
+        let (scale, alpha, _, _) = self.series();
+        let e = self.e();
+        let t = (lat.sin().atanh() - e * (e * lat.sin()).atanh()).sinh();
+        let xi = t.atan2(dlon.cos());
+        let eta = (dlon.sin() / (1.0 + t * t).sqrt()).atanh();
+        let (mut x, mut y) = (eta, xi);
+        for (j, a) in alpha.iter().enumerate() {
+            let k = 2.0 * (j + 1) as f64;
+            x += a * (k * xi).cos() * (k * eta).sinh();
+            y += a * (k * xi).sin() * (k * eta).cosh();
+        }
+        (scale * x, scale * y)
+    }
+
+    fn kruger_inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let (scale, _, beta, delta) = self.series();
+        let (xi, eta) = (y / scale, x / scale);
+        let (mut xi1, mut eta1) = (xi, eta);
+        for (j, b) in beta.iter().enumerate() {
+            let k = 2.0 * (j + 1) as f64;
+            xi1 -= b * (k * xi).sin() * (k * eta).cosh();
+            eta1 -= b * (k * xi).cos() * (k * eta).sinh();
+        }
+        let chi = (xi1.sin() / eta1.cosh()).asin();
+        let mut lat = chi;
+        for (j, d) in delta.iter().enumerate() {
+            lat += d * (2.0 * (j + 1) as f64 * chi).sin();
+        }
+        (eta1.sinh().atan2(xi1.cos()), lat)
+    }
+}
+
+/// A GeoJSON FeatureCollection: a Polygon feature per part, outline
+/// first and counterclockwise, holes after it, placed by `page.geo`.
+/// Score and hatch lines follow as LineStrings with their layer.
+pub fn geojson<P: PointScaler>(rings: &Paths<P>, page: &Page) -> Value {
+    let place = |p: (f64, f64)| page.geo.map_or(p, |geo| geo.place(p));
+    let round = |(x, y): (f64, f64)| json!([(x * 1e9).round() / 1e9, (y * 1e9).round() / 1e9]);
+    let mut features: Vec<Value> = crate::parts::parts(rings)
+        .iter()
+        .enumerate()
+        .map(|(n, part)| {
+            let polygon: Vec<Value> = part
+                .paths(rings)
+                .iter()
+                .enumerate()
+                .map(|(r, ring)| {
+                    let mut points: Vec<(f64, f64)> = ring.iter().map(|p| place((p.x(), p.y()))).collect();
+                    // the outline counterclockwise, holes clockwise
+                    if (area(&points) > 0.0) != (r == 0) {
+                        points.reverse();
+                    }
+                    points.push(points[0]);
+                    Value::Array(points.into_iter().map(round).collect())
+                })
+                .collect();
+            json!({ "type": "Feature", "properties": { "part": n + 1 }, "geometry": { "type": "Polygon", "coordinates": polygon } })
+        })
+        .collect();
+    features.extend(page.strokes.iter().map(|stroke| {
+        let line: Vec<Value> = stroke.points.iter().map(|&p| round(place(p))).collect();
+        json!({ "type": "Feature", "properties": { "layer": stroke.layer }, "geometry": { "type": "LineString", "coordinates": line } })
+    }));
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+/// Twice the signed area, positive counterclockwise with y going up.
+fn area(points: &[(f64, f64)]) -> f64 {
+    points.iter().zip(points.iter().cycle().skip(1)).map(|(a, b)| a.0 * b.1 - b.0 * a.1).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{Stroke, Tool};
+
+    fn near(a: (f64, f64), b: (f64, f64), within: f64) -> bool {
+        (a.0 - b.0).abs() <= within && (a.1 - b.1).abs() <= within
+    }
+
+    fn onto(crs: &str) -> Reference {
+        Reference { georef: Affine::IDENTITY, from: wgs84(), to: parse_crs(crs).unwrap() }
+    }
+
+    #[test]
+    fn georefs_are_six_numbers_or_a_world_file() {
+        let georef = parse_georef("2, 0 0 -2 100,200").unwrap();
+        assert_eq!(georef.apply((1.0, 1.0)), (102.0, 198.0));
+        assert!(parse_georef("1 0 0 1 0").is_err());
+        assert!(parse_georef("1 0 0 1 0 x").is_err());
+        // a world file places the centre of the top-left pixel
+        let path = std::env::temp_dir().join(format!("svg-combiner-world-{}.pgw", std::process::id()));
+        std::fs::write(&path, "2\n0\n0\n-2\n100\n200\n").unwrap();
+        let georef = parse_georef(&path.display().to_string()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(georef.apply((0.5, 0.5)), (100.0, 200.0));
+    }
+
+    #[test]
+    fn epsg_codes_stand_for_their_proj4_strings() {
+        assert_eq!(parse_crs("EPSG:32633").unwrap(), parse_crs("+proj=utm +zone=33 +datum=WGS84").unwrap());
+        assert_eq!(parse_crs("epsg:4326").unwrap(), wgs84());
+        assert!(parse_crs("EPSG:2154").is_err());
+        assert!(parse_crs("EPSG:x").is_err());
+    }
+
+    #[test]
+    fn bad_systems_are_refused() {
+        assert!(parse_crs("+proj=utm").is_err());
+        assert!(parse_crs("+proj=utm +zone=61").is_err());
+        assert!(parse_crs("+proj=lcc").is_err());
+        assert!(parse_crs("+ellps=WGS84").is_err());
+        assert!(parse_crs("proj=merc").is_err());
+        assert!(parse_crs("+proj=merc +lon_0").is_err());
+        assert!(parse_crs("+proj=merc +ellps=bessel").is_err());
+        assert!(parse_crs("+proj=merc +units=mi").is_err());
+        assert!(parse_crs("+proj=tmerc +towgs84=0,0,0").is_err());
+    }
+
+    #[test]
+    fn web_mercator_spans_half_the_equator_each_way() {
+        let x = onto("EPSG:3857").place((180.0, 0.0));
+        assert!(near(x, (std::f64::consts::PI * 6_378_137.0, 0.0), 1e-6));
+        let feet = Reference { georef: Affine::IDENTITY, from: wgs84(), to: parse_crs("+proj=merc +R=1000 +units=ft").unwrap() };
+        assert!(near(feet.place((90.0, 0.0)), (1000.0 * FRAC_PI_2 / 0.3048, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn utm_northings_follow_the_meridian() {
+        let utm = onto("EPSG:32633");
+        assert!(near(utm.place((15.0, 0.0)), (500_000.0, 0.0), 1e-6));
+        // 0.9996 of the meridian's 4 984 944.378 m from the equator to 45°
+        assert!(near(utm.place((15.0, 45.0)), (500_000.0, 4_982_950.400), 1e-3));
+        let south = onto("EPSG:32733").place((15.0, -45.0));
+        assert!(near(south, (500_000.0, 10_000_000.0 - 4_982_950.400), 1e-3));
+    }
+
+    #[test]
+    fn projections_come_back_to_where_they_started() {
+        for crs in ["EPSG:32634", "+proj=tmerc +lat_0=49 +lon_0=-2 +k=0.9996012717 +x_0=400000 +y_0=-100000 +ellps=GRS80", "EPSG:3857"] {
+            let there = onto(crs);
+            let back = Reference { georef: Affine::IDENTITY, from: there.to.clone(), to: wgs84() };
+            for p in [(21.0, 40.0), (18.5, -33.2), (-3.0, 52.5)] {
+                assert!(near(back.place(there.place(p)), p, 1e-8), "{crs} {p:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn features_are_parts_wound_counterclockwise_then_lines() {
+        // clockwise with y going up, as drawn with y going down
+        let rings: Paths<Centi> = vec![vec![(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0)]].into();
+        let strokes = [Stroke { layer: "score", tool: Tool::default(), points: vec![(0.0, 5.0), (10.0, 5.0)] }];
+        let page = Page { strokes: &strokes, ..Page::plain((0.0, 0.0, 10.0, 10.0), 1.0) };
+        let json = geojson(&rings, &page);
+        let features = json["features"].as_array().unwrap();
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[0]["properties"]["part"], 1);
+        let outline: Vec<(f64, f64)> = features[0]["geometry"]["coordinates"][0]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| (p[0].as_f64().unwrap(), p[1].as_f64().unwrap()))
+            .collect();
+        assert_eq!(outline.len(), 5);
+        assert_eq!(outline.first(), outline.last());
+        assert!(area(&outline[..4]) > 0.0);
+        assert_eq!(features[1]["geometry"], json!({ "type": "LineString", "coordinates": [[0.0, 5.0], [10.0, 5.0]] }));
+        assert_eq!(features[1]["properties"]["layer"], "score");
+    }
+}
@@ -0,0 +1,81 @@
+use std::fmt::Write as _;
+
+use clipper2::*;
+
+use crate::export::{Page, rings_by_depth};
+
+/// RS-274X with every ring as a region, nesting levels drawn in turn with
+/// alternating polarity so holes clear and islands in them fill again;
+/// score and hatch lines are drawn on top.
+pub fn write<P: PointScaler>(rings: &Paths<P>, page: &Page) -> String {
+    let mut out = String::new();
+    out.push_str("G04 svg-combiner*\n%FSLAX46Y46*%\n%MOMM*%\nG01*\n");
+    let rings = rings_by_depth(rings);
+    let deepest = rings.iter().map(|(d, _)| *d).max().unwrap_or(0);
+    for depth in 0..=deepest {
+        let polarity = if depth % 2 == 0 { "D" } else { "C" };
+        let _ = writeln!(out, "%LP{polarity}*%");
+        for (_, ring) in rings.iter().filter(|(d, _)| *d == depth) {
+            out.push_str("G36*\n");
+            for (i, &p) in ring.iter().chain(ring.first()).enumerate() {
+                let (x, y) = page.up(p);
+                let op = if i == 0 { "D02" } else { "D01" };
+                let _ = writeln!(out, "X{}Y{}{op}*", fixed(x), fixed(y));
+            }
+            out.push_str("G37*\n");
+        }
+    }
+    // open lines drawn with a thin round aperture
+    if !page.strokes.is_empty() {
+        out.push_str("%LPD*%\n%ADD10C,0.100*%\nD10*\n");
+        for stroke in page.strokes {
+            for (i, &p) in stroke.points.iter().enumerate() {
+                let (x, y) = page.up(p);
+                let op = if i == 0 { "D02" } else { "D01" };
+                let _ = writeln!(out, "X{}Y{}{op}*", fixed(x), fixed(y));
+            }
+        }
+    }
+    out.push_str("M02*\n");
+    out
+}
+
+// millimetres in the 4.6 format: integer millionths
+fn fixed(mm: f64) -> i64 {
+    (mm * 1e6).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{Stroke, Tool};
+
+    fn square(x: f64, size: f64) -> Vec<(f64, f64)> {
+        vec![(x, x), (x + size, x), (x + size, x + size), (x, x + size)]
+    }
+
+    #[test]
+    fn a_ring_is_one_closed_dark_region_in_millionths() {
+        let rings: Paths<Centi> = vec![square(0.0, 10.0)].into();
+        let out = write(&rings, &Page::plain((0.0, 0.0, 10.0, 10.0), 1.5));
+        let body = "%LPD*%\nG36*\nX0Y15000000D02*\nX15000000Y15000000D01*\nX15000000Y0D01*\nX0Y0D01*\nX0Y15000000D01*\nG37*\n";
+        assert_eq!(out, format!("G04 svg-combiner*\n%FSLAX46Y46*%\n%MOMM*%\nG01*\n{body}M02*\n"));
+    }
+
+    #[test]
+    fn holes_clear_and_islands_in_them_fill_again() {
+        let rings: Paths<Centi> = vec![square(0.0, 10.0), square(2.0, 6.0), square(4.0, 2.0)].into();
+        let out = write(&rings, &Page::plain((0.0, 0.0, 10.0, 10.0), 1.0));
+        let polarities: Vec<&str> = out.lines().filter(|l| l.starts_with("%LP")).collect();
+        assert_eq!(polarities, ["%LPD*%", "%LPC*%", "%LPD*%"]);
+        assert_eq!(out.matches("G36*").count(), 3);
+    }
+
+    #[test]
+    fn lines_are_drawn_open_with_a_round_aperture() {
+        let strokes = [Stroke { layer: "score", tool: Tool::default(), points: vec![(0.0, 0.0), (1.0, 0.0)] }];
+        let page = Page { strokes: &strokes, ..Page::plain((0.0, 0.0, 1.0, 1.0), 1.0) };
+        let out = write(&Paths::<Centi>::new(vec![]), &page);
+        assert!(out.ends_with("%LPD*%\n%ADD10C,0.100*%\nD10*\nX0Y1000000D02*\nX1000000Y1000000D01*\nM02*\n"));
+    }
+}
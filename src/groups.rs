@@ -0,0 +1,70 @@
+use crate::FlatShape;
+use crate::config::Config;
+use crate::style::Style;
+
+/// What shapes are combined together by.
+#[derive(Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    /// The outermost `<g>` with an id the shape sits in.
+    Layer,
+    /// The shape's fill color, or its stroke if it is unfilled.
+    Color,
+    /// The shape's first class, or the nearest group's above it.
+    Class,
+}
+
+/// Shapes combined on their own, written with one style.
+pub struct Group {
+    pub name: Option<String>,
+    pub style: Style,
+    pub shapes: Vec<FlatShape>,
+}
+
+pub fn parse(spec: &str) -> Result<GroupBy, String> {
+    match spec {
+        "layer" => Ok(GroupBy::Layer),
+        "color" | "colour" => Ok(GroupBy::Color),
+        "class" => Ok(GroupBy::Class),
+        _ => Err(format!("unknown grouping {spec:?}, expected layer, color or class")),
+    }
+}
+
+/// Split shapes into groups, in order of first appearance. Without a
+/// grouping everything is one unstyled group, drawn black as before; a
+/// group otherwise takes the style of its first shape.
+pub fn group(shapes: Vec<FlatShape>, by: Option<GroupBy>) -> Vec<Group> {
+    let Some(by) = by else {
+        return vec![Group { name: None, style: Style::default(), shapes }];
+    };
+    let mut groups: Vec<Group> = Vec::new();
+    for shape in shapes {
+        let name = match by {
+            GroupBy::Layer => shape.layer.clone().unwrap_or_else(|| "root".into()),
+            GroupBy::Class => shape.class.clone().unwrap_or_else(|| "unclassed".into()),
+            // unfilled line art goes by its stroke
+            GroupBy::Color => match (&shape.style.fill, &shape.style.stroke) {
+                (Some(fill), Some(stroke)) if fill == "none" => stroke.clone(),
+                (fill, _) => fill.clone().unwrap_or_else(|| "black".into()),
+            },
+        };
+        match groups.iter_mut().find(|g| g.name.as_deref() == Some(name.as_str())) {
+            Some(group) => group.shapes.push(shape),
+            None => groups.push(Group { name: Some(name), style: shape.style.clone(), shapes: vec![shape] }),
+        }
+    }
+    groups
+}
+
+/// Give shapes the offset of the first config group matching their layer,
+/// fill color, class or id, in drawing units that are `scale` working units each.
+/// Returns how many shapes got one.
+pub fn apply_config(groups: &mut [Group], config: &Config, scale: f64) -> usize {
+    let mut changed = 0;
+    for shape in groups.iter_mut().flat_map(|g| &mut g.shapes) {
+        if let Some(offset) = config.group_for(shape.names()).and_then(|rule| rule.offset) {
+            shape.offset = offset * scale;
+            changed += 1;
+        }
+    }
+    changed
+}
@@ -0,0 +1,29 @@
+use std::fs;
+
+use crate::attrs::parse_length;
+use crate::primitive::{self, Primitive};
+
+/// Circles to punch out of the result, one per `x,y` or `x,y,diameter`
+/// line of a CSV file; lines that aren't coordinates, like a header, are
+/// skipped, and so are `#` comments. Holes without their own diameter are
+/// `diameter` across.
+pub fn read(path: &str, diameter: Option<f64>) -> Result<Vec<Primitive>, Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(path)?;
+    let mut holes = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let fields: Vec<&str> = line.split([',', ';', '\t']).map(str::trim).collect();
+        let (Some(x), Some(y)) = (fields.first(), fields.get(1)) else {
+            continue;
+        };
+        let (Ok(x), Ok(y)) = (parse_length(x), parse_length(y)) else {
+            continue;
+        };
+        let d = match fields.get(2).filter(|d| !d.is_empty()) {
+            Some(d) => parse_length(d).map_err(|e| format!("{path}:{}: {e}", n + 1))?,
+            None => diameter.ok_or_else(|| format!("{path}:{}: no diameter, give one or --hole-d", n + 1))?,
+        };
+        holes.push(primitive::circle(x, y, d / 2.0, format!("hole-{}", holes.len() + 1)));
+    }
+    Ok(holes)
+}
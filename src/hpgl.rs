@@ -0,0 +1,81 @@
+use std::fmt::Write as _;
+
+use clipper2::*;
+
+use crate::export::Page;
+
+// plotter units per millimetre
+const UNITS: f64 = 40.0;
+
+/// HPGL drawing every ring with its pen (`SP`), pens taken in turn, in
+/// calibrated plotter units with y going up; score and hatch lines are
+/// drawn first.
+pub fn write<P: PointScaler>(rings: &Paths<P>, page: &Page) -> String {
+    let mut out = String::from("IN;\n");
+    let mut pen = None;
+    let units = |p: (f64, f64)| {
+        let (x, y) = page.machine(p);
+        ((x * UNITS).round() as i64, (y * UNITS).round() as i64)
+    };
+    // score and hatch lines open, then every ring back to its start
+    let mut paths: Vec<(u32, Vec<(i64, i64)>)> =
+        page.strokes.iter().map(|s| (s.tool.number, s.points.iter().copied().map(units).collect())).collect();
+    paths.sort_by_key(|(number, _)| *number);
+    for (i, tool) in page.by_tool(rings.len()) {
+        let Some(ring) = rings.get(i) else {
+            continue;
+        };
+        let mut points: Vec<(i64, i64)> = ring.iter().map(|p| units((p.x(), p.y()))).collect();
+        points.extend(points.first().copied());
+        paths.push((tool.number, points));
+    }
+    for (number, points) in paths {
+        let Some(&(x, y)) = points.first() else {
+            continue;
+        };
+        if pen != Some(number) {
+            let _ = writeln!(out, "SP{number};");
+            pen = Some(number);
+        }
+        let _ = write!(out, "PU{x},{y};PD");
+        let rest: Vec<String> = points.iter().skip(1).map(|(x, y)| format!("{x},{y}")).collect();
+        let _ = writeln!(out, "{};", rest.join(","));
+    }
+    out.push_str("PU;SP0;\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::Tool;
+
+    fn square(x: f64) -> Vec<(f64, f64)> {
+        vec![(x, 0.0), (x + 1.0, 0.0), (x + 1.0, 1.0), (x, 1.0)]
+    }
+
+    #[test]
+    fn a_ring_is_drawn_back_to_its_start_in_plotter_units() {
+        let rings: Paths<Centi> = vec![square(0.0)].into();
+        let out = write(&rings, &Page::plain((0.0, 0.0, 1.0, 1.0), 1.0));
+        assert_eq!(out, "IN;\nSP1;\nPU0,40;PD40,40,40,0,0,0,0,40;\nPU;SP0;\n");
+    }
+
+    #[test]
+    fn pens_are_taken_in_turn_once_each() {
+        let rings: Paths<Centi> = vec![square(0.0), square(2.0), square(4.0)].into();
+        let pen = |number| Tool { number, ..Tool::default() };
+        let tools = [pen(2), pen(1), pen(2)];
+        let page = Page { tools: &tools, ..Page::plain((0.0, 0.0, 5.0, 1.0), 1.0) };
+        let out = write(&rings, &page);
+        let pens: Vec<&str> = out.lines().filter(|l| l.starts_with("SP")).collect();
+        assert_eq!(pens, ["SP1;", "SP2;"]);
+    }
+
+    #[test]
+    fn calibration_scales_each_axis() {
+        let rings: Paths<Centi> = vec![square(0.0)].into();
+        let page = Page { calibrate: (1.5, 0.5), ..Page::plain((0.0, 0.0, 1.0, 1.0), 1.0) };
+        assert!(write(&rings, &page).contains("PU0,20;PD60,20,60,0,0,0,0,20;"));
+    }
+}
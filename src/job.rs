@@ -0,0 +1,254 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// room between files as laid out for nesting, in user units
+const SPREAD: f64 = 10.0;
+
+/// One line of a job: a design, how many of it and what it is cut from.
+struct Entry {
+    file: PathBuf,
+    qty: usize,
+    material: String,
+    options: Vec<String>,
+}
+
+/// `svg-combiner job MANIFEST [--out-dir DIR] [--force] [-- OPTIONS...]`:
+/// a production run planned from a manifest of designs. Every file is
+/// combined on its own with its own options, then the designs of each
+/// material are nested together with OPTIONS, which need a `--nest`, as
+/// many of each as the manifest asks for, into `DIR/MATERIAL.EXT`
+/// (`job` by default). The manifest is a CSV of `file,qty,material,options`
+/// lines or a JSON list of objects with those keys; files are found next
+/// to the manifest, and only what is cut makes it into the nest. A
+/// material named in the `--config` among OPTIONS nests as `--material`.
+pub fn run(args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let (ours, options) = match args.iter().position(|a| a == "--") {
+        Some(i) => (&args[..i], &args[i + 1..]),
+        None => (&args[..], &[][..]),
+    };
+    let mut out_dir = PathBuf::from("job");
+    let mut force = false;
+    let mut manifest = None;
+    let mut ours = ours.iter();
+    while let Some(arg) = ours.next() {
+        match arg.as_str() {
+            "--out-dir" => out_dir = ours.next().ok_or("--out-dir needs a directory")?.into(),
+            "--force" => force = true,
+            other if other.starts_with('-') => {
+                return Err(format!("unknown job option {other}; options for the nesting go after --").into());
+            }
+            path if manifest.is_none() => manifest = Some(PathBuf::from(path)),
+            path => return Err(format!("one manifest per job, got {path} too").into()),
+        }
+    }
+    let manifest = manifest.ok_or("job needs a manifest")?;
+    if !options.iter().any(|a| a == "--nest") {
+        return Err("a job is nested; give --nest SHEET after --".into());
+    }
+    let spec = match options.iter().position(|a| a == "--format") {
+        Some(i) => options.get(i + 1).ok_or("--format needs a name")?.as_str(),
+        None => "svg",
+    };
+    let format = crate::export::parse(spec)?;
+    let config = match options.iter().position(|a| a == "--config") {
+        Some(i) => crate::config::read(options.get(i + 1).ok_or("--config needs a file")?)?,
+        None => crate::config::Config::default(),
+    };
+    let entries = read(&manifest)?;
+    if entries.is_empty() {
+        return Err(format!("nothing to make in {}", manifest.display()).into());
+    }
+
+    let work = out_dir.join(".parts");
+    fs::create_dir_all(&work)?;
+    let exe = std::env::current_exe()?;
+    let combine = |args: &[String], output: &Path| -> Result<(), String> {
+        let run = Command::new(&exe)
+            .args(args)
+            .args(["-o".to_string(), output.display().to_string()])
+            .args(["--quiet", "--force"])
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !run.status.success() {
+            return Err(String::from_utf8_lossy(&run.stderr).trim().to_string());
+        }
+        Ok(())
+    };
+
+    // materials in order of first appearance, with their designs combined
+    let mut materials: Vec<(String, Vec<(PathBuf, usize)>)> = Vec::new();
+    for (n, entry) in entries.iter().enumerate() {
+        let combined = work.join(format!("{}.svg", n + 1));
+        let mut args = vec![entry.file.display().to_string()];
+        args.extend(entry.options.iter().cloned());
+        combine(&args, &combined).map_err(|e| format!("{}: {e}", entry.file.display()))?;
+        tracing::info!("✓ Combined {} ×{} for {}", entry.file.display(), entry.qty, entry.material);
+        match materials.iter_mut().find(|(m, _)| *m == entry.material) {
+            Some((_, designs)) => designs.push((combined, entry.qty)),
+            None => materials.push((entry.material.clone(), vec![(combined, entry.qty)])),
+        }
+    }
+
+    for (material, designs) in &materials {
+        let sheet = work.join(format!("{material}.svg"));
+        fs::write(&sheet, layout(designs)?)?;
+        let output = out_dir.join(format!("{material}.{}", format.extension()));
+        if output.exists() && !force {
+            return Err(format!("{} exists; --force to overwrite", output.display()).into());
+        }
+        let mut args = vec![sheet.display().to_string()];
+        args.extend(options.iter().cloned());
+        args.extend(material_args(&config, options, material));
+        // named, since an extension like .json can stand for more than one
+        combine(&args, Path::new(&format!("{spec}:{}", output.display()))).map_err(|e| format!("{material}: {e}"))?;
+        let parts: usize = designs.iter().map(|(_, qty)| qty).sum();
+        tracing::info!("✓ {material}: {parts} design(s) nested into {}", output.display());
+    }
+    fs::remove_dir_all(&work)?;
+    tracing::info!("\n✅ Job done: {} material(s), {} design(s)", materials.len(), entries.len());
+    Ok(())
+}
+
+/// The manifest's lines, JSON if it says so by its extension, CSV
+/// otherwise, where a header or anything else without a file is skipped.
+fn read(path: &Path) -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(path)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    let entry = |file: &str, qty: Option<usize>, material: Option<&str>, options: Vec<String>| Entry {
+        file: base.join(file),
+        qty: qty.unwrap_or(1).max(1),
+        material: material.map(str::trim).filter(|m| !m.is_empty()).unwrap_or("default").to_string(),
+        options,
+    };
+    if path.extension().is_some_and(|e| e == "json") {
+        let list: Vec<serde_json::Value> = serde_json::from_str(&text)?;
+        return list
+            .iter()
+            .enumerate()
+            .map(|(n, item)| {
+                let file = item["file"].as_str().ok_or_else(|| format!("{}: entry {} has no file", path.display(), n + 1))?;
+                let options = match &item["options"] {
+                    serde_json::Value::Array(list) => list.iter().filter_map(|o| o.as_str()).map(str::to_string).collect(),
+                    serde_json::Value::String(line) => line.split_whitespace().map(str::to_string).collect(),
+                    _ => Vec::new(),
+                };
+                Ok(entry(file, item["qty"].as_u64().map(|q| q as usize), item["material"].as_str(), options))
+            })
+            .collect();
+    }
+    let mut entries = Vec::new();
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let fields: Vec<&str> = line.splitn(4, [',', ';', '\t']).map(str::trim).collect();
+        let Some(file) = fields.first().filter(|f| f.ends_with(".svg") || f.ends_with(".svgz")) else {
+            continue;
+        };
+        let qty = fields.get(1).and_then(|q| q.parse().ok());
+        let options = fields.get(3).map(|o| o.split_whitespace().map(str::to_string).collect()).unwrap_or_default();
+        entries.push(entry(file, qty, fields.get(2).copied(), options));
+    }
+    Ok(entries)
+}
+
+/// One SVG with every combined design side by side, kept apart so none
+/// join, each path asking for its design's quantity.
+fn layout(designs: &[(PathBuf, usize)]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut body = String::new();
+    let (mut x, mut height) = (0.0, 0.0_f64);
+    for (n, (path, qty)) in designs.iter().enumerate() {
+        let text = fs::read_to_string(path)?;
+        let doc = roxmltree::Document::parse(&text)?;
+        let root = doc.root_element();
+        let view: Vec<f64> = root.attribute("viewBox").unwrap_or("0 0 0 0").split([' ', ',']).filter_map(|v| v.parse().ok()).collect();
+        let &[vx, vy, w, h] = &view[..] else {
+            return Err(format!("{}: no viewBox", path.display()).into());
+        };
+        // what is cut, outlined or not, and not what is drawn as lines
+        let cut = doc.descendants().filter(|node| node.has_tag_name("path")).filter_map(|node| node.attribute("d")).filter(|d| closed(d));
+        for (k, d) in cut.enumerate() {
+            // 0.0 - vy, as -vy would print a viewBox at 0 as -0
+            writeln!(
+                body,
+                r#"<path id="design{}-{}" data-qty="{qty}" transform="translate({} {})" d="{d}"/>"#,
+                n + 1,
+                k + 1,
+                x - vx,
+                0.0 - vy
+            )?;
+        }
+        x += w + SPREAD;
+        height = height.max(h);
+    }
+    Ok(format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{x}\" height=\"{height}\" viewBox=\"0 0 {x} {height}\">\n{body}</svg>\n"))
+}
+
+/// `--material NAME` for the nest when the config knows the material and
+/// OPTIONS don't pick one already.
+fn material_args(config: &crate::config::Config, options: &[String], material: &str) -> Vec<String> {
+    if config.material(material).is_none() || options.iter().any(|a| a == "--material") {
+        return Vec::new();
+    }
+    vec!["--material".to_string(), material.to_string()]
+}
+
+/// Whether every subpath of path data `d` is closed, as outlines of parts
+/// are and engraved or scored lines are not.
+fn closed(d: &str) -> bool {
+    let mut runs = d.split(['M', 'm']).map(str::trim).filter(|run| !run.is_empty()).peekable();
+    runs.peek().is_some() && runs.all(|run| run.ends_with(['Z', 'z']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("svg-combiner-job-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn closed_paths_are_cut_and_open_ones_are_not() {
+        assert!(closed("M0 0L10 0L10 10Z"));
+        assert!(closed("M0 0L10 0L10 10Z M2 2L4 2L4 4z"));
+        assert!(!closed("M0 0L10 0"));
+        assert!(!closed("M0 0L10 0L10 10Z M2 2L4 2"));
+        assert!(!closed(""));
+    }
+
+    #[test]
+    fn outlined_designs_are_laid_out_but_engraved_lines_are_not() {
+        let dir = scratch("layout");
+        let design = dir.join("1.svg");
+        fs::write(
+            &design,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 20">
+<g fill="none" stroke="red"><path d="M0 0L20 0L20 20Z"/></g>
+<g id="engrave"><path fill="none" stroke="black" d="M1 1L5 5"/></g>
+</svg>"#,
+        )
+        .unwrap();
+        let sheet = layout(&[(design.clone(), 3), (design, 1)]).unwrap();
+        assert_eq!(sheet.matches("<path").count(), 2);
+        assert!(sheet.contains(r#"id="design1-1" data-qty="3" transform="translate(0 0)" d="M0 0L20 0L20 20Z""#));
+        assert!(sheet.contains(r#"id="design2-1" data-qty="1" transform="translate(30 0)""#));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn configured_materials_are_nested_as_such() {
+        let dir = scratch("material");
+        let file = dir.join("shop.toml");
+        fs::write(&file, "[material.\"birch-3mm\"]\nkerf = 0.2\n").unwrap();
+        let config = crate::config::read(&file.display().to_string()).unwrap();
+        let options = ["--nest".to_string(), "600x400".to_string()];
+        assert_eq!(material_args(&config, &options, "birch-3mm"), ["--material", "birch-3mm"]);
+        assert!(material_args(&config, &options, "default").is_empty());
+        let chosen = ["--material".to_string(), "birch-3mm".to_string()];
+        assert!(material_args(&config, &chosen, "birch-3mm").is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,79 @@
+use clipper2::*;
+
+use crate::FlatShape;
+
+/// Merge shapes whose outlines come within `gap` of each other into one
+/// shape each, closing the hairline between them (grow by half the gap,
+/// shrink back), so exported pieces meant to touch are combined as a whole
+/// instead of having a gap carved between them. A merged shape takes the
+/// place and settings of its topmost piece and the ids of all of them.
+pub fn join<P: PointScaler>(shapes: Vec<FlatShape>, gap: f64) -> Result<Vec<FlatShape>, ClipperError> {
+    let grown: Vec<Paths<P>> =
+        shapes.iter().map(|s| inflate(s.contours.clone(), gap / 2.0, JoinType::Miter, EndType::Polygon, 2.0)).collect();
+    let bounds: Vec<_> = grown.iter().map(|g| g.bounds()).collect();
+
+    // union-find over shapes whose grown outlines overlap
+    let mut root: Vec<usize> = (0..shapes.len()).collect();
+    fn find(root: &mut [usize], mut i: usize) -> usize {
+        while root[i] != i {
+            root[i] = root[root[i]];
+            i = root[i];
+        }
+        i
+    }
+    for i in 0..shapes.len() {
+        for j in i + 1..shapes.len() {
+            let (a, b) = (&bounds[i], &bounds[j]);
+            if a.max.x() < b.min.x() || b.max.x() < a.min.x() || a.max.y() < b.min.y() || b.max.y() < a.min.y() {
+                continue;
+            }
+            let (ri, rj) = (find(&mut root, i), find(&mut root, j));
+            if ri != rj && !intersect(grown[i].clone(), grown[j].clone(), FillRule::NonZero)?.is_empty() {
+                root[ri] = rj;
+            }
+        }
+    }
+
+    // every piece's index, gathered under its topmost piece
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); shapes.len()];
+    let mut top = vec![0; shapes.len()];
+    for i in 0..shapes.len() {
+        let r = find(&mut root, i);
+        members[r].push(i);
+        top[r] = i;
+    }
+    let mut placed: Vec<Option<Vec<usize>>> = vec![None; shapes.len()];
+    for (r, m) in members.into_iter().enumerate() {
+        if !m.is_empty() {
+            placed[top[r]] = Some(m);
+        }
+    }
+
+    let mut slots: Vec<Option<FlatShape>> = shapes.into_iter().map(Some).collect();
+    let mut out = Vec::new();
+    for (i, m) in placed.into_iter().enumerate() {
+        let Some(m) = m else {
+            continue;
+        };
+        if m.len() == 1 {
+            out.extend(slots[i].take());
+            continue;
+        }
+        let mut merged = Paths::new(vec![]);
+        for &k in &m {
+            merged.push(grown[k].clone());
+        }
+        let merged: Paths<P> = union(merged, Paths::new(vec![]), FillRule::NonZero)?;
+        let closed = inflate(merged, -gap / 2.0, JoinType::Miter, EndType::Polygon, 2.0);
+        let ids: Vec<String> = m.iter().filter_map(|&k| slots[k].as_ref().map(|s| s.id.clone())).collect();
+        let lines: Vec<_> = m.iter().filter_map(|&k| slots[k].as_ref()).flat_map(|s| s.lines.clone()).collect();
+        let Some(mut shape) = slots[i].take() else {
+            continue;
+        };
+        shape.id = ids.join(" ");
+        shape.lines = lines;
+        shape.contours = closed.iter().map(|p| p.iter().map(|pt| (pt.x(), pt.y())).collect()).collect();
+        out.push(shape);
+    }
+    Ok(out)
+}
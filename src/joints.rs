@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use clipper2::*;
+
+use crate::Contour;
+use crate::attrs::parse_length;
+
+// vertices of a puzzle tab's round head
+const ROUND: usize = 24;
+// parallel enough, and within a gap's reach, to be a shared edge
+const EPSILON: f64 = 1e-6;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    /// Square fingers.
+    Finger,
+    /// Round-headed tabs that lock in their sockets.
+    Puzzle,
+}
+
+/// `--joints "finger size=5 depth=4"`: straight edges of neighbouring parts
+/// that face each other across their gap made to interlock, with tabs
+/// `size` wide reaching `depth` into the neighbour, every other one going
+/// the other way. The gap is kept as clearance around every tab; `size`
+/// is four gaps unless given, and `depth` as much as `size`.
+#[derive(Clone, Copy)]
+pub struct Joints {
+    pub kind: Kind,
+    pub size: f64,
+    pub depth: f64,
+}
+
+pub fn parse(spec: &str, gap: f64) -> Result<Joints, String> {
+    let mut words = spec.split_whitespace();
+    let kind = match words.next() {
+        Some("finger") => Kind::Finger,
+        Some("puzzle") => Kind::Puzzle,
+        other => return Err(format!("unknown joint {other:?}, expected finger or puzzle")),
+    };
+    let mut keys = HashMap::new();
+    for word in words {
+        let (key, value) = word.split_once('=').ok_or_else(|| format!("expected KEY=VALUE, got {word:?}"))?;
+        if !matches!(key, "size" | "depth") {
+            return Err(format!("unknown joint setting {key:?}"));
+        }
+        keys.insert(key, parse_length(value)?);
+    }
+    let size = match keys.get("size") {
+        Some(&size) => size,
+        None if gap > 0.0 => 4.0 * gap,
+        None => return Err("without a --gap to size the tabs by, give their size=".into()),
+    };
+    let depth = keys.get("depth").copied().unwrap_or(size);
+    if size <= 0.0 || depth <= 0.0 {
+        return Err("size and depth need to be above 0".into());
+    }
+    Ok(Joints { kind, size, depth })
+}
+
+/// A straight stretch of one part's outline facing another's.
+struct Facing {
+    /// The owning parts, this one's outline going from `a` to `b`.
+    parts: (usize, usize),
+    a: (f64, f64),
+    /// Unit vectors along the edge and across it, towards the other part.
+    along: (f64, f64),
+    across: (f64, f64),
+    /// How far along the two overlap, and the gap between them.
+    span: (f64, f64),
+    gap: f64,
+}
+
+impl Joints {
+    /// The same joints with every length multiplied by `factor`.
+    pub fn scaled(self, factor: f64) -> Joints {
+        Joints { size: self.size * factor, depth: self.depth * factor, ..self }
+    }
+
+    /// One tab on `edge` between `u0` and `u1` along it, from the side it
+    /// starts on (`out` of the first part, or back from the second) into
+    /// the other part.
+    fn tab(&self, edge: &Facing, u0: f64, u1: f64, out: bool) -> Contour {
+        let at = |u: f64, v: f64| {
+            let v = if out { v } else { edge.gap - v };
+            (edge.a.0 + edge.along.0 * u + edge.across.0 * v, edge.a.1 + edge.along.1 * u + edge.across.1 * v)
+        };
+        let reach = edge.gap + self.depth;
+        let ring = match self.kind {
+            Kind::Finger => vec![at(u0, 0.0), at(u1, 0.0), at(u1, reach), at(u0, reach)],
+            Kind::Puzzle => {
+                // a neck half the tab wide, and a round head wider than it
+                let (mid, width) = ((u0 + u1) / 2.0, u1 - u0);
+                let r = (width * 0.4).min(self.depth * 0.4);
+                let (neck, centre) = (width * 0.25, reach - r);
+                let start = (neck / r).asin();
+                let mut ring = vec![at(mid - neck, 0.0), at(mid + neck, 0.0)];
+                for i in 0..=ROUND {
+                    let a = start + (2.0 * (PI - start)) * i as f64 / ROUND as f64;
+                    ring.push(at(mid + r * a.sin(), centre - r * a.cos()));
+                }
+                ring
+            }
+        };
+        // counterclockwise, so inflating grows it
+        if crate::clip::winding(std::slice::from_ref(&ring), at((u0 + u1) / 2.0, 1e-3)) < 0 {
+            ring.into_iter().rev().collect()
+        } else {
+            ring
+        }
+    }
+}
+
+/// `rings` with the facing straight edges of every pair of parts no more
+/// than `reach` apart made into joints, where their slots are wider than
+/// twice the gap; and how many edges were.
+pub fn join<P: PointScaler>(rings: &Paths<P>, joints: &Joints, reach: f64) -> Result<(Paths<P>, usize), ClipperError> {
+    let parts = crate::parts::parts(rings);
+    let outlines: Vec<Contour> = parts
+        .iter()
+        .map(|part| straight(&rings.get(part.outer).map(|r| r.iter().map(|p| (p.x(), p.y())).collect()).unwrap_or_default()))
+        .collect();
+    let mut facing = Vec::new();
+    for i in 0..outlines.len() {
+        for j in i + 1..outlines.len() {
+            facing.extend(faces(&outlines[i], &outlines[j], (i, j), reach, 3.0 * joints.size));
+        }
+    }
+
+    // what every part grows by and gives up
+    let mut grown: Vec<Vec<Contour>> = vec![Vec::new(); parts.len()];
+    let mut cut: Vec<Vec<Contour>> = vec![Vec::new(); parts.len()];
+    let join_type = if joints.kind == Kind::Puzzle { JoinType::Round } else { JoinType::Miter };
+    let mut jointed = 0;
+    for edge in &facing {
+        // an odd count of even slots along it, the two ends left straight
+        let length = edge.span.1 - edge.span.0;
+        let mut n = (length / joints.size).floor() as usize;
+        if n.is_multiple_of(2) {
+            n -= 1;
+        }
+        let width = length / n as f64;
+        // tabs narrower than the slots by the gap, to keep it between them
+        if width <= 2.0 * edge.gap {
+            continue;
+        }
+        jointed += 1;
+        for k in 1..n - 1 {
+            let (u0, u1) = (edge.span.0 + k as f64 * width + edge.gap / 2.0, edge.span.0 + (k + 1) as f64 * width - edge.gap / 2.0);
+            let out = k % 2 == 1;
+            let (owner, other) = if out { edge.parts } else { (edge.parts.1, edge.parts.0) };
+            let tab = joints.tab(edge, u0, u1, out);
+            let socket: Paths<P> = inflate(Paths::from(vec![tab.clone()]), edge.gap, join_type, EndType::Polygon, 2.0);
+            cut[other].extend(socket.iter().map(|r| r.iter().map(|p| (p.x(), p.y())).collect::<Contour>()));
+            grown[owner].push(tab);
+        }
+    }
+
+    let mut out: Vec<Contour> = Vec::new();
+    for (n, part) in parts.iter().enumerate() {
+        let mut own = part.paths(rings);
+        if !grown[n].is_empty() {
+            own = union(own, Paths::from(grown[n].clone()), FillRule::NonZero)?;
+        }
+        if !cut[n].is_empty() {
+            own = difference(own, Paths::from(cut[n].clone()), FillRule::NonZero)?;
+        }
+        out.extend(own.iter().map(|r| r.iter().map(|p| (p.x(), p.y())).collect::<Contour>()));
+    }
+    Ok((out.into(), jointed))
+}
+
+/// A ring without the points that lie on a straight line between their
+/// neighbours, so straight edges are one edge each.
+fn straight(ring: &Contour) -> Contour {
+    let n = ring.len();
+    (0..n)
+        .filter(|&i| {
+            let (p, q, r) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+            let cross = (q.0 - p.0) * (r.1 - q.1) - (q.1 - p.1) * (r.0 - q.0);
+            let lengths = ((q.0 - p.0).hypot(q.1 - p.1) * (r.0 - q.0).hypot(r.1 - q.1)).max(EPSILON);
+            (cross / lengths).abs() > 1e-4
+        })
+        .map(|i| ring[i])
+        .collect()
+}
+
+/// Edges of outline `a` facing, in the other direction, an edge of `b`
+/// within `reach`, and overlapping it for at least `least`.
+fn faces(a: &Contour, b: &Contour, parts: (usize, usize), reach: f64, least: f64) -> Vec<Facing> {
+    let edges = |ring: &Contour| (0..ring.len()).map(|i| (ring[i], ring[(i + 1) % ring.len()])).collect::<Vec<_>>();
+    let mut out = Vec::new();
+    for (p, q) in edges(a) {
+        let length = (q.0 - p.0).hypot(q.1 - p.1);
+        if length < least {
+            continue;
+        }
+        let along = ((q.0 - p.0) / length, (q.1 - p.1) / length);
+        for (r, s) in edges(b) {
+            let other = (s.0 - r.0, s.1 - r.1);
+            let other_length = other.0.hypot(other.1);
+            // the other way along the same line, near enough
+            let cross = (along.0 * other.1 - along.1 * other.0) / other_length.max(EPSILON);
+            if other_length < least || cross.abs() > 1e-4 || along.0 * other.0 + along.1 * other.1 >= 0.0 {
+                continue;
+            }
+            let side = (r.0 - p.0) * -along.1 + (r.1 - p.1) * along.0;
+            let gap = side.abs();
+            if gap > reach + EPSILON {
+                continue;
+            }
+            let across = if side >= 0.0 { (-along.1, along.0) } else { (along.1, -along.0) };
+            let u = |t: (f64, f64)| (t.0 - p.0) * along.0 + (t.1 - p.1) * along.1;
+            let span = (u(s).max(0.0), u(r).min(length));
+            if span.1 - span.0 < least {
+                continue;
+            }
+            // the other part is outside this one along the edge
+            let mid = (span.0 + span.1) / 2.0;
+            let probe = (p.0 + along.0 * mid + across.0 * gap / 2.0, p.1 + along.1 * mid + across.1 * gap / 2.0);
+            if gap > EPSILON && crate::clip::winding(std::slice::from_ref(a), probe) != 0 {
+                continue;
+            }
+            out.push(Facing { parts, a: p, along, across, span, gap });
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_default_to_four_gaps_and_depth_to_size() {
+        let joints = parse("puzzle", 0.5).unwrap();
+        assert!(joints.kind == Kind::Puzzle && joints.size == 2.0 && joints.depth == 2.0);
+        let joints = parse("finger size=6 depth=3", 0.5).unwrap();
+        assert!(joints.kind == Kind::Finger && joints.size == 6.0 && joints.depth == 3.0);
+    }
+
+    #[test]
+    fn bad_specs_are_refused() {
+        assert_eq!(parse("finger sise=5", 1.0).err().unwrap(), "unknown joint setting \"sise\"");
+        assert_eq!(parse("dovetail", 1.0).err().unwrap(), "unknown joint Some(\"dovetail\"), expected finger or puzzle");
+        assert_eq!(parse("finger", 0.0).err().unwrap(), "without a --gap to size the tabs by, give their size=");
+        assert_eq!(parse("finger size=5 depth=0", 1.0).err().unwrap(), "size and depth need to be above 0");
+        assert!(parse("finger size", 1.0).is_err());
+    }
+
+    fn square(x: f64) -> Contour {
+        vec![(x, 0.0), (x + 10.0, 0.0), (x + 10.0, 10.0), (x, 10.0)]
+    }
+
+    #[test]
+    fn facing_edges_interlock_keeping_the_gap_between_tabs_and_sockets() {
+        let rings: Paths<Centi> = vec![square(0.0), square(10.5)].into();
+        let joints = Joints { kind: Kind::Finger, size: 2.0, depth: 2.0 };
+        let (joined, edges) = join(&rings, &joints, 0.5 * 1.01).unwrap();
+        assert_eq!(edges, 1);
+        let parts = crate::parts::parts(&joined);
+        assert_eq!(parts.len(), 2);
+        // each reaches into the other, and they still don't touch
+        let pieces: Vec<Paths<Centi>> = parts.iter().map(|part| part.paths(&joined)).collect();
+        let reach = |n: usize| {
+            let xs = pieces[n].iter().flat_map(|r| r.iter().map(|p| p.x()).collect::<Vec<_>>());
+            xs.fold((f64::MAX, f64::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)))
+        };
+        let (first, second) = if reach(0).0 < reach(1).0 { (0, 1) } else { (1, 0) };
+        assert!(reach(first).1 > 10.5 && reach(second).0 < 10.0);
+        let both = union(pieces[0].clone(), pieces[1].clone(), FillRule::NonZero).unwrap();
+        let apart = pieces[0].signed_area() + pieces[1].signed_area();
+        assert!((both.signed_area() - apart).abs() < 1e-6);
+    }
+
+    #[test]
+    fn parts_further_apart_than_the_reach_are_left_alone() {
+        let rings: Paths<Centi> = vec![square(0.0), square(12.0)].into();
+        let joints = Joints { kind: Kind::Puzzle, size: 2.0, depth: 2.0 };
+        let (joined, edges) = join(&rings, &joints, 0.5).unwrap();
+        assert_eq!(edges, 0);
+        assert!((joined.signed_area() - rings.signed_area()).abs() < 1e-6);
+    }
+}
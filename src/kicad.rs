@@ -0,0 +1,76 @@
+use std::fmt::Write as _;
+
+use clipper2::*;
+
+use crate::export::Page;
+
+/// A KiCad footprint with the result on the page's layer. On Edge.Cuts every ring
+/// is an unfilled outline, which is what a board outline is; elsewhere
+/// every outline is a filled polygon with its holes cut in along a
+/// bridge, since footprint polygons can't have holes. Score and hatch lines
+/// are line segments on the same layer.
+pub fn write<P: PointScaler>(rings: &Paths<P>, page: &Page) -> String {
+    let layer = page.layer;
+    let mut out = String::new();
+    out.push_str("(footprint \"svg-combiner\" (version 20221018) (generator \"svg-combiner\")\n");
+    out.push_str("  (layer \"F.Cu\")\n  (attr board_only exclude_from_pos_files exclude_from_bom)\n");
+    let polygons: Vec<Vec<(f64, f64)>> =
+        if layer == "Edge.Cuts" { rings.iter().map(|r| r.iter().map(|p| (p.x(), p.y())).collect()).collect() } else { bridged(rings) };
+    let fill = if layer == "Edge.Cuts" { "none" } else { "solid" };
+    for polygon in polygons {
+        out.push_str("  (fp_poly (pts");
+        for (i, &p) in polygon.iter().enumerate() {
+            let (x, y) = page.down(p);
+            // KiCad keeps lines short, a few points to each
+            if i % 4 == 0 {
+                out.push_str("\n   ");
+            }
+            let _ = write!(out, " (xy {x:.6} {y:.6})");
+        }
+        let _ = writeln!(out, ")\n    (stroke (width 0.1) (type solid)) (fill {fill}) (layer \"{layer}\"))");
+    }
+    // score and hatch lines as segments
+    for stroke in page.strokes {
+        for w in stroke.points.windows(2) {
+            let (a, b) = (page.down(w[0]), page.down(w[1]));
+            let _ = writeln!(
+                out,
+                "  (fp_line (start {:.6} {:.6}) (end {:.6} {:.6}) (stroke (width 0.1) (type solid)) (layer \"{layer}\"))",
+                a.0, a.1, b.0, b.1
+            );
+        }
+    }
+    out.push_str(")\n");
+    out
+}
+
+/// Every outermost or island ring with the holes right inside it spliced
+/// in, each from its vertex closest to the ring around it and back.
+fn bridged<P: PointScaler>(rings: &Paths<P>) -> Vec<Vec<(f64, f64)>> {
+    let parents = crate::parts::containment(rings);
+    let depths = crate::parts::depths(&parents);
+    let points: Vec<Vec<(f64, f64)>> = rings.iter().map(|r| r.iter().map(|p| (p.x(), p.y())).collect()).collect();
+    let mut out = Vec::new();
+    for (i, ring) in points.iter().enumerate().filter(|(i, _)| depths[*i].is_multiple_of(2)) {
+        let mut polygon = ring.clone();
+        for hole in (0..points.len()).filter(|&h| parents[h] == Some(i)).map(|h| &points[h]) {
+            let mut best = (f64::INFINITY, 0, 0);
+            for (a, p) in polygon.iter().enumerate() {
+                for (b, q) in hole.iter().enumerate() {
+                    let d = (p.0 - q.0).powi(2) + (p.1 - q.1).powi(2);
+                    if d < best.0 {
+                        best = (d, a, b);
+                    }
+                }
+            }
+            let (_, a, b) = best;
+            let tail = polygon.split_off(a + 1);
+            polygon.extend_from_slice(&hole[b..]);
+            polygon.extend_from_slice(&hole[..=b]);
+            polygon.push(polygon[a]);
+            polygon.extend(tail);
+        }
+        out.push(polygon);
+    }
+    out
+}
@@ -0,0 +1,166 @@
+use clipper2::*;
+
+use crate::parts::Part;
+
+// Single-stroke digits on a 4 x 6 grid, y pointing down
+const DIGITS: [&[&[(f64, f64)]]; 10] = [
+    &[&[(0.0, 0.0), (4.0, 0.0), (4.0, 6.0), (0.0, 6.0), (0.0, 0.0)]],
+    &[&[(1.0, 1.0), (2.0, 0.0), (2.0, 6.0)], &[(1.0, 6.0), (3.0, 6.0)]],
+    &[&[(0.0, 0.0), (4.0, 0.0), (4.0, 3.0), (0.0, 3.0), (0.0, 6.0), (4.0, 6.0)]],
+    &[&[(0.0, 0.0), (4.0, 0.0), (4.0, 6.0), (0.0, 6.0)], &[(0.0, 3.0), (4.0, 3.0)]],
+    &[&[(0.0, 0.0), (0.0, 3.0), (4.0, 3.0)], &[(4.0, 0.0), (4.0, 6.0)]],
+    &[&[(4.0, 0.0), (0.0, 0.0), (0.0, 3.0), (4.0, 3.0), (4.0, 6.0), (0.0, 6.0)]],
+    &[&[(4.0, 0.0), (0.0, 0.0), (0.0, 6.0), (4.0, 6.0), (4.0, 3.0), (0.0, 3.0)]],
+    &[&[(0.0, 0.0), (4.0, 0.0), (4.0, 6.0)]],
+    &[&[(0.0, 0.0), (4.0, 0.0), (4.0, 6.0), (0.0, 6.0), (0.0, 0.0)], &[(0.0, 3.0), (4.0, 3.0)]],
+    &[&[(4.0, 3.0), (0.0, 3.0), (0.0, 0.0), (4.0, 0.0), (4.0, 6.0), (0.0, 6.0)]],
+];
+const ADVANCE: f64 = 6.0;
+const GRID_HEIGHT: f64 = 6.0;
+
+/// Outline of a number `height` tall centered on `at`, drawn with strokes
+/// one eighth of the height wide.
+pub fn number_outline<P: PointScaler>(n: usize, at: (f64, f64), height: f64) -> Paths<P> {
+    let text = n.to_string();
+    let scale = height / GRID_HEIGHT;
+    let width = (text.len() as f64 * ADVANCE - (ADVANCE - 4.0)) * scale;
+    let origin = (at.0 - width / 2.0, at.1 - height / 2.0);
+
+    let mut strokes: Vec<Vec<(f64, f64)>> = Vec::new();
+    for (k, digit) in text.bytes().map(|b| (b - b'0') as usize).enumerate() {
+        let x0 = origin.0 + k as f64 * ADVANCE * scale;
+        for stroke in DIGITS[digit] {
+            strokes.push(stroke.iter().map(|&(x, y)| (x0 + x * scale, origin.1 + y * scale)).collect());
+        }
+    }
+    inflate(strokes, height / 16.0, JoinType::Round, EndType::Round, 0.0)
+}
+
+/// A point well inside the part: the centroid when it falls inside,
+/// otherwise the sampled point farthest from any edge.
+pub fn anchor<P: PointScaler>(part: &Part, all: &Paths<P>) -> Option<(f64, f64)> {
+    let rings = part.paths(all);
+    let outer = all.get(part.outer)?;
+
+    let c = centroid(outer);
+    if contains(&rings, c) {
+        return Some(c);
+    }
+
+    const SAMPLES: usize = 24;
+    let b = outer.bounds();
+    let size = b.size();
+    let mut best = None;
+    let mut best_distance = 0.0;
+    for i in 0..SAMPLES {
+        for j in 0..SAMPLES {
+            let p = (b.min.x() + size.x() * (i as f64 + 0.5) / SAMPLES as f64, b.min.y() + size.y() * (j as f64 + 0.5) / SAMPLES as f64);
+            if !contains(&rings, p) {
+                continue;
+            }
+            let d = edge_distance(&rings, p);
+            if d > best_distance {
+                best_distance = d;
+                best = Some(p);
+            }
+        }
+    }
+    best
+}
+
+fn contains<P: PointScaler>(rings: &Paths<P>, p: (f64, f64)) -> bool {
+    let pt = Point::<P>::from(p);
+    let mut it = rings.iter();
+    it.next().is_some_and(|outer| outer.is_point_inside(pt) == PointInPolygonResult::IsInside)
+        && it.all(|hole| hole.is_point_inside(pt) == PointInPolygonResult::IsOutside)
+}
+
+fn centroid<P: PointScaler>(ring: &Path<P>) -> (f64, f64) {
+    let pts: Vec<(f64, f64)> = ring.iter().map(|p| (p.x(), p.y())).collect();
+    let (mut a, mut cx, mut cy) = (0.0, 0.0, 0.0);
+    for i in 0..pts.len() {
+        let (p, q) = (pts[i], pts[(i + 1) % pts.len()]);
+        let cross = p.0 * q.1 - q.0 * p.1;
+        a += cross;
+        cx += (p.0 + q.0) * cross;
+        cy += (p.1 + q.1) * cross;
+    }
+    if a == 0.0 {
+        return pts.first().copied().unwrap_or_default();
+    }
+    (cx / (3.0 * a), cy / (3.0 * a))
+}
+
+fn edge_distance<P: PointScaler>(rings: &Paths<P>, p: (f64, f64)) -> f64 {
+    let mut best = f64::INFINITY;
+    for ring in rings.iter() {
+        let pts: Vec<(f64, f64)> = ring.iter().map(|q| (q.x(), q.y())).collect();
+        for i in 0..pts.len() {
+            best = best.min(segment_distance(p, pts[i], pts[(i + 1) % pts.len()]));
+        }
+    }
+    best
+}
+
+pub fn segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let d = (b.0 - a.0, b.1 - a.1);
+    let len2 = d.0 * d.0 + d.1 * d.1;
+    let t = if len2 == 0.0 { 0.0 } else { (((p.0 - a.0) * d.0 + (p.1 - a.1) * d.1) / len2).clamp(0.0, 1.0) };
+    (p.0 - a.0 - d.0 * t).hypot(p.1 - a.1 - d.1 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Vec<(f64, f64)> {
+        vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)]
+    }
+
+    fn anchor_of(paths: &Paths<Centi>) -> (f64, f64) {
+        let parts = crate::parts::parts(paths);
+        assert_eq!(parts.len(), 1);
+        anchor(&parts[0], paths).unwrap()
+    }
+
+    #[test]
+    fn numbers_are_centred_on_their_anchor() {
+        let seven: Paths<Centi> = number_outline(7, (50.0, 20.0), 6.0);
+        let b = seven.bounds();
+        // the stroke half of an eighth of the height out on every side
+        assert!((b.min.x() - 47.625).abs() < 0.05 && (b.max.x() - 52.375).abs() < 0.05);
+        assert!((b.min.y() - 16.625).abs() < 0.05 && (b.max.y() - 23.375).abs() < 0.05);
+        let wide: Paths<Centi> = number_outline(42, (0.0, 0.0), 12.0);
+        let b = wide.bounds();
+        assert!((b.min.x() + 10.75).abs() < 0.05 && (b.max.x() - 10.75).abs() < 0.05);
+    }
+
+    #[test]
+    fn a_convex_part_is_labelled_at_its_centroid() {
+        let at = anchor_of(&vec![rect(0.0, 0.0, 40.0, 20.0)].into());
+        assert!((at.0 - 20.0).abs() < 1e-6 && (at.1 - 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn labels_stay_on_the_material() {
+        // a U, its centroid in the notch
+        let u: Paths<Centi> =
+            vec![vec![(0.0, 0.0), (30.0, 0.0), (30.0, 30.0), (20.0, 30.0), (20.0, 5.0), (10.0, 5.0), (10.0, 30.0), (0.0, 30.0)]].into();
+        let at = anchor_of(&u);
+        assert!(contains(&u, at));
+        // a frame, its centroid in the hole
+        let mut hole = rect(10.0, 10.0, 30.0, 30.0);
+        hole.reverse();
+        let frame: Paths<Centi> = vec![rect(0.0, 0.0, 40.0, 40.0), hole].into();
+        let at = anchor_of(&frame);
+        assert!(contains(&frame, at));
+        assert!(edge_distance(&frame, at) > 2.0);
+    }
+
+    #[test]
+    fn distances_are_to_the_nearest_point_of_a_segment() {
+        assert_eq!(segment_distance((5.0, 3.0), (0.0, 0.0), (10.0, 0.0)), 3.0);
+        assert_eq!(segment_distance((13.0, 4.0), (0.0, 0.0), (10.0, 0.0)), 5.0);
+        assert_eq!(segment_distance((3.0, 4.0), (0.0, 0.0), (0.0, 0.0)), 5.0);
+    }
+}
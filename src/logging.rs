@@ -0,0 +1,73 @@
+use std::time::Instant;
+
+use tracing::Subscriber;
+use tracing::level_filters::LevelFilter;
+use tracing::span::EnteredSpan;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::writer::MakeWriterExt;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Install the subscriber, showing records up to `level`. Text goes to
+/// stdout as plain lines, warnings and errors to stderr; `--log-format
+/// json` writes one JSON object per record to stdout instead, with the
+/// stage it happened in. Records of the libraries logging through `log`
+/// are taken in too.
+pub fn init(level: LevelFilter, json: bool) {
+    let builder = tracing_subscriber::fmt().with_max_level(level);
+    // a second init, as when a run starts others, keeps the first
+    let _ = if json {
+        builder.json().flatten_event(true).with_current_span(true).with_span_list(false).with_writer(std::io::stdout).try_init()
+    } else {
+        builder.event_format(Plain).with_writer(std::io::stderr.with_max_level(tracing::Level::WARN).or_else(std::io::stdout)).try_init()
+    };
+}
+
+/// One stage of the pipeline, entered as a span for as long as it lasts,
+/// so every record logged during it carries its name. How long it took is
+/// logged at debug level when it ends.
+pub struct Stage {
+    name: &'static str,
+    began: Instant,
+    span: Option<EnteredSpan>,
+}
+
+/// Begin the stage `name`.
+pub fn stage(name: &'static str) -> Stage {
+    Stage { name, began: Instant::now(), span: Some(tracing::info_span!("stage", stage = name).entered()) }
+}
+
+impl Stage {
+    /// End this stage and begin `name`, beside it rather than within it.
+    pub fn next(&mut self, name: &'static str) {
+        self.end();
+        *self = stage(name);
+    }
+
+    fn end(&mut self) {
+        if self.span.is_some() {
+            tracing::debug!(target: "stage", "{} took {:.1} ms", self.name, self.began.elapsed().as_secs_f64() * 1000.0);
+        }
+        self.span = None;
+    }
+}
+
+impl Drop for Stage {
+    fn drop(&mut self) {
+        self.end();
+    }
+}
+
+/// Just the message and any other fields, no time, level or span.
+struct Plain;
+
+impl<S, N> FormatEvent<S, N> for Plain
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &tracing::Event<'_>) -> std::fmt::Result {
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
@@ -1,275 +1,1718 @@
-use std::fs;
+use clipper2::*;
 use lyon::path::Path;
 use lyon::path::iterator::PathIterator;
-use clipper2::*;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
 use usvg::tiny_skia_path::PathSegment;
 
+mod attrs;
+mod batch;
+mod budget;
+mod buffer;
+mod cache;
+mod canonical;
+mod canvas;
+mod clip;
+mod config;
+mod corners;
+mod csv;
+mod curves;
+mod density;
+mod direction;
+mod dst;
+mod dxf;
+mod edges;
+mod engrave;
+mod eps;
+mod export;
+mod external;
+mod filter;
+mod fingers;
+mod frame;
+mod gcode;
+mod geo;
+mod gerber;
+mod groups;
+mod holes;
+mod hpgl;
+mod job;
+mod join;
+mod joints;
+mod kicad;
+mod labels;
+mod logging;
+mod mask;
+mod matrix;
+mod naming;
+mod nest;
+mod normalize;
+mod operation;
+mod options;
+mod order;
+mod output;
+mod overlap;
+mod paint;
+mod panel;
+mod parts;
+mod passthrough;
+mod pattern;
+mod perforate;
+mod precision;
+mod preview;
+mod primitive;
+mod progress;
+mod provenance;
+mod quantity;
+mod sanitize;
+mod select;
+mod stamp;
+mod start;
+mod stream;
+mod style;
+mod summary;
+mod svg;
+mod sweep;
+mod taper;
+mod tile;
+mod trace;
+mod transform;
+mod warnings;
+mod weed;
+
+use attrs::Attrs;
+use normalize::Normalization;
+use operation::Operation;
+use options::Options;
+use style::Style;
+use transform::Affine;
+
+// a flattened closed subpath
+type Contour = Vec<(f64, f64)>;
+
 const TOLERANCE: f32 = 0.1;
+// gap carved around each shape unless given `--gap` or its own `data-offset`
+const OFFSET: f64 = 10.0;
+// stroke width used for cut layers, thin enough for cutters to read as hairline
+const HAIRLINE: f64 = 0.25;
+// combined rings smaller than this (square working units) are slivers
+const MIN_AREA: f64 = 50.0;
+// square drawing units below which a dropped ring isn't worth a warning
+const SLIVER: f64 = 0.01;
+// share of its bounds a part covers to count as a rectangle when nesting
+// on common lines
+const RECTANGULAR: f64 = 0.99;
+// exit status of `--on-empty fail`, apart from errors' 1
+const EMPTY_EXIT: i32 = 3;
+
+/// The document as it was read, for what of it is written back as it was.
+struct Source<'a> {
+    text: &'a str,
+    /// The file before anything was done to it, `--in-place`.
+    original: Option<&'a str>,
+    /// What goes along with the result, `--passthrough`.
+    passed: passthrough::Passthrough,
+}
+
+/// The inputs as one document, what `create_init_svg_file` makes of them.
+struct Composed {
+    text: String,
+    /// The `data-*` attributes found, by element id.
+    attributes: HashMap<String, Attrs>,
+    /// Inputs left out for being unreadable, with why.
+    skipped: Vec<(String, String)>,
+}
+
+/// The result had no polygons and `--on-empty fail` was given.
+#[derive(Debug)]
+struct EmptyResult;
+
+impl std::fmt::Display for EmptyResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("the result is empty, which --on-empty fail doesn't allow")
+    }
+}
+
+impl std::error::Error for EmptyResult {}
+
+fn main() {
+    // errors as their messages, not as they are made
+    if let Err(e) = try_main() {
+        if e.is::<EmptyResult>() {
+            tracing::error!("{e}");
+            std::process::exit(EMPTY_EXIT);
+        }
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "preview") {
+        logging::init(tracing::level_filters::LevelFilter::INFO, false);
+        return preview::serve(args[1..].to_vec());
+    }
+    if args.first().is_some_and(|a| a == "batch") {
+        logging::init(tracing::level_filters::LevelFilter::INFO, false);
+        return batch::run(args[1..].to_vec());
+    }
+    if args.first().is_some_and(|a| a == "job") {
+        logging::init(tracing::level_filters::LevelFilter::INFO, false);
+        return job::run(args[1..].to_vec());
+    }
+    let options = Options::from_env()?;
+    logging::init(options.log_level, options.log_json);
+    let parse = logging::stage("parse");
+    let mut progress = progress::Progress::new(options.tui);
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Read and parse SVG
     //let svg = fs::read("./init.svg")?;
-    let svg = create_init_svg_file();
-    let opt = usvg::Options::default();
-    let tree = usvg::Tree::from_data(&(svg.as_bytes()), &opt)?;
-    
-    println!("✓ Parsed SVG with usvg");
-    println!("  Size: {}px x {}px", tree.size().width(), tree.size().height());
-    
+    let Composed { text: svg, attributes, skipped } = create_init_svg_file(&options.inputs, options.external.as_ref())?;
+    let mut found = warnings::in_source(&svg, options.external.is_some());
+    for (path, reason) in skipped {
+        found.add("skipped-input", path, format!("{reason}; left out, going on with the other inputs"));
+    }
+    let passed = if options.passthrough { passthrough::collect(&svg, options.trace.is_some()) } else { Default::default() };
+    // as it is on disk, for what is written back of it byte for byte
+    let original = if options.in_place { Some(read_svg(&options.inputs[0])?) } else { None };
+    let source = Source { text: &svg, original: original.as_deref(), passed };
+    let opt = match (&options.external, &options.inputs[..]) {
+        (Some(sandbox), [input]) => sandbox.options(input),
+        _ => usvg::Options::default(),
+    };
+    let tree = usvg::Tree::from_data(svg.as_bytes(), &opt).map_err(|e| match &options.inputs[..] {
+        [input] => format!("{input} is not valid SVG: {e}"),
+        _ => format!("the inputs laid out together are not valid SVG: {e}"),
+    })?;
+
+    tracing::info!("✓ Parsed SVG with usvg");
+    tracing::info!("  Size: {}px x {}px", tree.size().width(), tree.size().height());
+    progress.stage("parse", format!("{}px x {}px", tree.size().width(), tree.size().height()));
+
+    let bounds = normalize::bounds(&tree);
+    let norm = if options.normalize { Normalization::of(bounds) } else { Normalization::IDENTITY };
+    if !norm.is_identity() {
+        tracing::info!("  Normalized coordinates: moved to the origin, scaled by {}", norm.scale);
+    }
+    // clipper works on integers, so the scale decides the smallest detail
+    // kept; a normalized result has to stay exact in drawing units as well
+    let reach = norm.reach(bounds).max(Normalization::IDENTITY.reach(bounds));
+    let scale = match options.precision {
+        Some(precision::Precision::Fixed(scale)) => scale,
+        None if norm.is_identity() => 100,
+        _ => precision::auto(reach),
+    };
+    if scale != 100 {
+        tracing::info!("  Clipper precision: 1/{scale} unit");
+    }
+    drop(parse);
+    match scale {
+        1 => run::<One>(&options, &source, &tree, &attributes, found, &norm, &mut progress),
+        10 => run::<Deci>(&options, &source, &tree, &attributes, found, &norm, &mut progress),
+        100 => run::<Centi>(&options, &source, &tree, &attributes, found, &norm, &mut progress),
+        1000 => run::<Milli>(&options, &source, &tree, &attributes, found, &norm, &mut progress),
+        _ => run::<precision::Micro>(&options, &source, &tree, &attributes, found, &norm, &mut progress),
+    }
+}
+
+/// Everything after parsing `source`, with clipper coordinates scaled by
+/// `P`, in the working range `norm` maps the drawing into; `warnings` are those found
+/// so far.
+fn run<P: PointScaler>(
+    options: &Options,
+    source: &Source,
+    tree: &usvg::Tree,
+    attributes: &HashMap<String, Attrs>,
+    mut warnings: warnings::Warnings,
+    norm: &Normalization,
+    progress: &mut progress::Progress,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stage = logging::stage("prepare");
     // ---------------- SVG → lyon paths ----------------
-    let mut paths = Vec::<Path>::new();
-    
+    let mut paths = Vec::<Shape>::new();
+
     // Recursively extract paths from tree
-    fn extract_paths(node: &usvg::Node, paths: &mut Vec<Path>) {
+    // `layer` is the id of the outermost named group above the node
+    fn extract_paths<P: PointScaler>(
+        node: &usvg::Node,
+        layer: Option<&str>,
+        // what the groups above it multiply its opacity by
+        opacity: f64,
+        attributes: &HashMap<String, Attrs>,
+        options: &Options,
+        norm: &Normalization,
+        sink: &mut dyn FnMut(Shape),
+    ) {
         match node {
+            usvg::Node::Path(path)
+                if options
+                    .select
+                    .as_ref()
+                    .is_some_and(|s| !s.matches(path.id(), layer, attributes.get(path.id()), Some(&Style::of(path)))) => {}
+            usvg::Node::Path(path) if options.min_opacity.is_some_and(|min| opacity * style::opacity(path) < min) => {
+                tracing::info!("✓ {} left out, at opacity {:.2}", path.id(), opacity * style::opacity(path));
+            }
             usvg::Node::Path(path) => {
+                // where its transform puts it, then into the working range,
+                // in f64 before lyon's f32
+                let placed = Affine::of_usvg(path.abs_transform());
+                let to_working = placed.then(norm.forward);
+                let at = |p: usvg::tiny_skia_path::Point| {
+                    let (x, y) = to_working.apply((p.x as f64, p.y as f64));
+                    lyon::math::point(x as f32, y as f32)
+                };
                 // Lyon builder
                 let mut builder = Path::builder();
+                // lyon wants open subpaths ended explicitly
+                let mut open = false;
+                // broken segments never get as far as lyon
+                let (segments, repairs) = sanitize::sanitize(path.data().segments());
+                // the points segments end on, by subpath
+                let mut nodes: Vec<Vec<(f64, f64)>> = Vec::new();
+                let node = |nodes: &mut [Vec<(f64, f64)>], p: usvg::tiny_skia_path::Point| {
+                    if let Some(last) = nodes.last_mut() {
+                        last.push(placed.apply((p.x as f64, p.y as f64)));
+                    }
+                };
                 // May cycles over many subpaths
-                for segment in path.data().segments() {
+                for segment in segments {
                     match segment {
                         PathSegment::MoveTo(p) => {
-                            builder.begin((p.x, p.y).into());
+                            if open {
+                                builder.end(false);
+                            }
+                            builder.begin(at(p));
+                            open = true;
+                            nodes.push(vec![placed.apply((p.x as f64, p.y as f64))]);
                         }
                         PathSegment::LineTo(p) => {
-                            builder.line_to((p.x, p.y).into());
+                            builder.line_to(at(p));
+                            node(&mut nodes, p);
                         }
                         PathSegment::QuadTo(p1, p2) => {
-                            builder.quadratic_bezier_to(
-                                (p1.x, p1.y).into(),
-                                (p2.x, p2.y).into(),
-                            );
+                            builder.quadratic_bezier_to(at(p1), at(p2));
+                            node(&mut nodes, p2);
                         }
                         PathSegment::CubicTo(p1, p2, p3) => {
-                            builder.cubic_bezier_to(
-                                (p1.x, p1.y).into(),
-                                (p2.x, p2.y).into(),
-                                (p3.x, p3.y).into(),
-                            );
+                            builder.cubic_bezier_to(at(p1), at(p2), at(p3));
+                            node(&mut nodes, p3);
                         }
                         PathSegment::Close => {
                             builder.close();
+                            open = false;
+                        }
+                    }
+                }
+                if open {
+                    builder.end(false);
+                }
+                let mut built = builder.build();
+                let mut patterned = false;
+                // a patterned fill made of its tiles' shapes
+                if let Some(usvg::Paint::Pattern(pattern)) = path.fill().map(|f| f.paint()).filter(|_| options.pattern_fill) {
+                    match pattern::fill::<P>(path, pattern, to_working, TOLERANCE) {
+                        Some(rings) => {
+                            let mut tiles = Path::builder();
+                            for ring in rings.iter().filter(|r| r.len() >= 3) {
+                                tiles.begin(lyon::math::point(ring[0].0 as f32, ring[0].1 as f32));
+                                for &(x, y) in &ring[1..] {
+                                    tiles.line_to(lyon::math::point(x as f32, y as f32));
+                                }
+                                tiles.close();
+                            }
+                            tracing::debug!("  {}: pattern laid out as {} ring(s)", path.id(), rings.len());
+                            built = tiles.build();
+                            patterned = true;
+                        }
+                        None => {
+                            tracing::warn!(
+                                "⚠ {}: pattern {} has nothing to lay out or too many tiles, filled whole",
+                                path.id(),
+                                pattern.id()
+                            )
                         }
                     }
                 }
                 // exhausted navigating over the path with posible subpaths
-                paths.push(builder.build());
+                let attrs = attributes.get(path.id());
+                // gaps stay in drawing units
+                let offset = attrs::number(attrs, "offset").unwrap_or(options.gap) * norm.scale;
+                let taper = attrs
+                    .and_then(|a| attrs::parse_pair(a.get("taper")?))
+                    .or(options.taper)
+                    .map(|(start, end)| (start * norm.scale, end * norm.scale));
+                let buffer = attrs::number(attrs, "buffer").or(options.buffer).map(|width| buffer::Buffer {
+                    width: width * norm.scale,
+                    cap: attrs.and_then(|a| buffer::parse_cap(a.get("cap")?).ok()).unwrap_or(options.end_cap),
+                });
+                let widths = attrs.and_then(|a| a.get("widths")).map_or_else(Vec::new, |w| width_profiles(w, &nodes, norm.scale));
+                let seam = options.seam_allowance.map(|allowance| {
+                    attrs.and_then(|a| attrs::parse_length(a.get("seam-allowance")?).ok()).unwrap_or(allowance) * norm.scale
+                });
+                let mut style = Style::of(path);
+                if options.paint == paint::Strategy::Dominant
+                    && style.fill.is_none()
+                    && let Some(fill) = path.fill()
+                {
+                    style.fill = paint::dominant(fill.paint());
+                }
+                sink(Shape {
+                    taper,
+                    buffer,
+                    widths,
+                    style,
+                    strokes: options.engrave_lines.as_deref().is_some_and(|name| layer == Some(name) || attrs::has_class(attrs, name)),
+                    fold: options.config.fold.as_ref().is_some_and(|fold| {
+                        let stroke = Style::of(path).stroke;
+                        fold.matches(stroke.as_deref(), path.stroke().is_some_and(|s| s.dasharray().is_some()))
+                    }),
+                    layer: layer.map(str::to_string),
+                    class: attrs::class(attrs),
+                    seam,
+                    mark: [(&options.notches, Operation::Notch), (&options.grainlines, Operation::Grainline)]
+                        .into_iter()
+                        .find(|(marks, _)| marks.as_ref().is_some_and(|m| m.matches(path.id(), layer, attrs, Some(&Style::of(path)))))
+                        .map(|(_, mark)| mark),
+                    mate: options.finger_joints.and_then(|_| attrs?.get("mate").cloned()),
+                    repairs,
+                    // the same path data anywhere else is the same shape moved
+                    instance: (!patterned).then(|| (instance_key(path.data()), to_working)),
+                    ..Shape::new(path.id().to_string(), built, offset)
+                });
+            }
+            usvg::Node::Image(image)
+                if options.select.as_ref().is_some_and(|s| !s.matches(image.id(), layer, attributes.get(image.id()), None)) => {}
+            usvg::Node::Image(image) if options.trace.is_some() => {
+                let threshold = options.trace.unwrap_or(0.5);
+                let rings = match trace::trace(image, threshold) {
+                    Ok(rings) => rings,
+                    Err(e) => {
+                        tracing::warn!("skip image {}: {e}", image.id());
+                        return;
+                    }
+                };
+                // the image's place only exists as its transform
+                let m = image.abs_transform();
+                let at = |(x, y): (f64, f64)| {
+                    let placed = (m.sx as f64 * x + m.kx as f64 * y + m.tx as f64, m.ky as f64 * x + m.sy as f64 * y + m.ty as f64);
+                    let (x, y) = norm.forward.apply(placed);
+                    lyon::math::point(x as f32, y as f32)
+                };
+                let mut builder = Path::builder();
+                for ring in rings.iter().filter(|r| r.len() >= 3) {
+                    builder.begin(at(ring[0]));
+                    for &p in &ring[1..] {
+                        builder.line_to(at(p));
+                    }
+                    builder.close();
+                }
+                let attrs = attributes.get(image.id());
+                let offset = attrs::number(attrs, "offset").unwrap_or(options.gap) * norm.scale;
+                sink(Shape {
+                    layer: layer.map(str::to_string),
+                    class: attrs::class(attrs),
+                    ..Shape::new(image.id().to_string(), builder.build(), offset)
+                });
             }
             usvg::Node::Group(group) => {
-                for child in group.children() {
-                    extract_paths(child, paths);
+                let layer = layer.or(Some(group.id()).filter(|id| !id.is_empty()));
+                if !group.filters().is_empty() && options.filters == filter::Policy::Skip {
+                    return;
+                }
+                let opacity = opacity * group.opacity().get() as f64;
+                if options.min_opacity.is_some_and(|min| opacity < min) {
+                    let name = Some(group.id()).filter(|id| !id.is_empty()).unwrap_or("a group");
+                    tracing::info!("✓ {name} left out, at opacity {opacity:.2}");
+                    return;
+                }
+                // shadows under the content, each a copy of it thrown aside, made part of it
+                let thrown: Vec<lyon::math::Transform> = match options.filters {
+                    filter::Policy::Shadow => {
+                        let to_working = Affine::of_usvg(group.abs_transform()).then(norm.forward);
+                        let (x0, y0) = to_working.apply((0.0, 0.0));
+                        filter::shadows(group)
+                            .into_iter()
+                            .map(|by| {
+                                let (x, y) = to_working.apply(by);
+                                lyon::math::Transform::translation((x - x0) as f32, (y - y0) as f32)
+                            })
+                            .collect()
+                    }
+                    _ => Vec::new(),
+                };
+                let mut shadowed = |mut shape: Shape| {
+                    let copies: Vec<Path> = thrown.iter().map(|by| shape.path.clone().transformed(by)).collect();
+                    let mut builder = Path::builder();
+                    builder.extend_from_paths(&[shape.path.as_slice()]);
+                    builder.extend_from_paths(&copies.iter().map(|c| c.as_slice()).collect::<Vec<_>>());
+                    shape.path = builder.build();
+                    shape.instance = None;
+                    sink(shape);
+                };
+                let sink: &mut dyn FnMut(Shape) = if thrown.is_empty() { sink } else { &mut shadowed };
+                // what spills out of a nested viewport cut off
+                let viewport: Option<Paths<P>> = group.clip_path().and_then(mask::viewport).map(|rect| {
+                    let to_working = Affine::of_usvg(group.abs_transform()).then(norm.forward);
+                    vec![rect.into_iter().map(|p| to_working.apply(p)).collect::<Contour>()].into()
+                });
+                let mut cropped = |mut shape: Shape| {
+                    let Some(region) = &viewport else { return };
+                    match mask::clip::<P>(&shape.path, region, TOLERANCE) {
+                        Ok(path) => {
+                            shape.path = path;
+                            shape.instance = None;
+                            sink(shape);
+                        }
+                        Err(e) => tracing::warn!("⚠ {}: can't cut it to its viewport, {e:?}", shape.id),
+                    }
+                };
+                let sink: &mut dyn FnMut(Shape) = if viewport.is_none() { sink } else { &mut cropped };
+                // a mask as the area it lets through, the content cut down to it
+                let masked = options.mask_threshold.zip(group.mask()).map(|(threshold, mask)| {
+                    let to_working = Affine::of_usvg(group.abs_transform()).then(norm.forward);
+                    mask::region::<P>(mask, to_working, threshold, TOLERANCE)
+                });
+                match masked {
+                    Some(Ok(region)) => {
+                        let mut clip = |mut shape: Shape| match mask::clip::<P>(&shape.path, &region, TOLERANCE) {
+                            Ok(path) => {
+                                shape.path = path;
+                                shape.instance = None;
+                                sink(shape);
+                            }
+                            Err(e) => tracing::warn!("⚠ {}: can't mask it, {e:?}", shape.id),
+                        };
+                        for child in group.children() {
+                            extract_paths::<P>(child, layer, opacity, attributes, options, norm, &mut clip);
+                        }
+                    }
+                    Some(Err(e)) => tracing::warn!("⚠ {}: mask left out, {e:?}", group.id()),
+                    None => {
+                        for child in group.children() {
+                            extract_paths::<P>(child, layer, opacity, attributes, options, norm, sink);
+                        }
+                    }
                 }
             }
             _ => {}
         }
     }
-  
-    // start extracting paths
-    for node in tree.root().children() {
-      extract_paths(node, &mut paths);
-    }
-    
-    println!("\n✓ Extracted {} path(s) that may have subpaths", paths.len());
-    
-    // ---------------- Flatten → Clipper polygons ----------------
-    let mut contour_segments: Vec<Vec<(f64, f64)>> = Vec::new(); // by flattening a path is stored as a set of small segments (paths)
-    let mut contour_segments_paths: Vec<Vec<Vec<(f64, f64)>>> = Vec::new(); // a flattened path with all its flattened subpaths
+
+    let mut contour_segments_paths: Vec<FlatShape> = Vec::new(); // a flattened path with all its flattened subpaths
     let mut input_vertices: usize = 0;
-    
-    for (idx, path) in paths.iter().enumerate() {
-        let mut current_polygon = Vec::new();
-        
-        use lyon::path::Event::*;
-        for event in path.iter().flattened(TOLERANCE) {
-            match event {
-                Begin { at } => {
-                    current_polygon = Vec::new();
-                    current_polygon.push((
-                        at.x as f64,
-                        at.y as f64,
-                    ));
+    // fold annotations, as lines
+    let mut folds: Vec<Contour> = Vec::new();
+    // the outlines of sewing pattern pieces, before their allowances
+    let mut stitches: Vec<Contour> = Vec::new();
+    // notches and grainlines, as lines
+    let mut marks: Vec<(Operation, Contour)> = Vec::new();
+    // lines along box edges, by name
+    let mut mates: Vec<(String, Contour)> = Vec::new();
+    // the artwork as drawn, kept only for the engrave layer
+    let mut artwork: Vec<engrave::Artwork> = Vec::new();
+    // combined on the fly when streaming
+    let mut streamed = None;
+    // source curves, to put back into the result
+    let mut curves = curves::Curves::default();
+    // elements whose path data needed repairs
+    let mut repaired = Vec::new();
+    warnings.0.extend(warnings::unsupported(tree, options.trace.is_some(), options.mask_threshold.is_some(), options.filters).0);
+
+    // the stamp goes in the drawing's corner, on top of everything
+    let stamped: Vec<Shape> = match &options.stamp {
+        Some(stamp) => {
+            let bounds = normalize::bounds(tree).unwrap_or((0.0, 0.0, tree.size().width() as f64, tree.size().height() as f64));
+            let shapes: Vec<Shape> =
+                stamp.outlines(bounds, &norm.forward)?.into_iter().map(|path| Shape::new("stamp".into(), path, 0.0)).collect();
+            tracing::info!("✓ Stamped {:?} in {} outlined path(s)", stamp.text, shapes.len());
+            shapes
+        }
+        None => Vec::new(),
+    };
+    // engraved when there is an engrave layer, otherwise added to the
+    // result once it is cleaned up, which would drop small glyph pieces
+    let mut stamp_cut: Vec<Contour> = Vec::new();
+    for shape in &stamped {
+        if options.keep_curves {
+            curves.add(&shape.path);
+        }
+        let (flat, art) = flatten::<P>(shape, 0, options.engrave, false, TOLERANCE, false);
+        if options.engrave {
+            artwork.extend(art);
+        } else {
+            stamp_cut.extend(flat.into_iter().flat_map(|f| f.contours));
+        }
+    }
+
+    if let Some(budget) = options.stream {
+        stage.next("stream");
+        // every path is flattened and handed on as soon as it is found
+        tracing::info!("\n🔄 Streaming paths into Clipper2 in chunks of about {} bytes...", budget);
+        let mut combiner = stream::Combiner::new(budget);
+        let mut failed = None;
+        let mut idx = 0;
+        for node in tree.root().children() {
+            extract_paths::<P>(node, None, 1.0, attributes, options, norm, &mut |shape| {
+                idx += 1;
+                repaired.extend(repair_note(&shape, idx - 1));
+                warn_dropped(&shape, idx - 1, options, &mut warnings);
+                // there is no result yet to clip them to
+                if shape.fold {
+                    warnings.add("fold", shape.id.clone(), "fold lines aren't scored when streaming, left out");
+                    return;
+                }
+                if options.keep_curves {
+                    curves.add(&shape.path);
                 }
-                Line { to, .. } => {
-                    current_polygon.push((
-                        to.x as f64,
-                        to.y as f64,
-                    ));
+                if let (Some(flat), _) =
+                    flatten::<P>(&shape, idx - 1, false, false, tolerance_for(&shape, options, norm.scale), options.curvature)
+                    && let Err(e) = combiner.push(flat)
+                {
+                    failed.get_or_insert(e);
+                }
+            });
+        }
+        for (n, primitive) in options.add.iter().enumerate() {
+            let shape = primitive_shape(primitive, n, options.gap, norm);
+            if options.keep_curves {
+                curves.add(&shape.path);
+            }
+            if let (Some(flat), _) = flatten::<P>(&shape, idx + n, false, false, TOLERANCE, options.curvature) {
+                combiner.push(flat)?;
+            }
+        }
+        if let Some(e) = failed {
+            return Err(e.into());
+        }
+        streamed = Some(combiner.finish()?);
+        input_vertices = combiner.vertices;
+        tracing::info!("✓ Streamed {} path(s) as {} shape(s) in {} chunk(s)", idx, combiner.shapes, combiner.chunks);
+        progress.stage("stream", format!("{idx} path(s) in {} chunk(s)", combiner.chunks));
+    } else {
+        // start extracting paths
+        stage.next("extract");
+        for node in tree.root().children() {
+            extract_paths::<P>(node, None, 1.0, attributes, options, norm, &mut |shape| {
+                repaired.extend(repair_note(&shape, paths.len()));
+                warn_dropped(&shape, paths.len(), options, &mut warnings);
+                if options.keep_curves {
+                    curves.add(&shape.path);
                 }
-                End { close, .. } => {
-                    if close && current_polygon.len() >= 3 {
-                        contour_segments.push(current_polygon.clone());
+                paths.push(shape)
+            });
+        }
+
+        tracing::info!("\n✓ Extracted {} path(s) that may have subpaths", paths.len());
+        progress.stage("extract", format!("{} path(s)", paths.len()));
+        if !options.add.is_empty() {
+            for (n, primitive) in options.add.iter().enumerate() {
+                let shape = primitive_shape(primitive, n, options.gap, norm);
+                if options.keep_curves {
+                    curves.add(&shape.path);
+                }
+                paths.push(shape);
+            }
+            tracing::info!("✓ Added {} shape(s) given on the command line", options.add.len());
+        }
+
+        if let Some(sweep) = &options.sweep {
+            let view_box = (0.0, 0.0, tree.size().width() as f64, tree.size().height() as f64);
+            return sweep::run::<P>(&paths, sweep, view_box, options, norm);
+        }
+
+        // ---------------- Flatten → Clipper polygons ----------------
+        stage.next("flatten");
+        // the first flattening of every path data, and how to undo its transform
+        let mut instances: HashMap<u64, (Affine, Vec<Contour>, Vec<Contour>)> = HashMap::new();
+        let mut reused = 0;
+        for (idx, shape) in paths.iter().enumerate() {
+            // box edges are where their lines go, from end to end
+            if let Some(name) = &shape.mate {
+                if let (Some(flat), _) = flatten::<P>(shape, idx, false, true, tolerance_for(shape, options, norm.scale), false) {
+                    mates.extend(flat.lines.into_iter().chain(flat.contours).next().map(|line| (name.clone(), line)));
+                }
+                continue;
+            }
+            // pattern marks too, with every ring closed
+            if let Some(mark) = shape.mark {
+                if let (Some(flat), _) = flatten::<P>(shape, idx, false, true, tolerance_for(shape, options, norm.scale), false) {
+                    marks.extend(flat.lines.into_iter().map(|line| (mark, line)));
+                    marks.extend(flat.contours.into_iter().map(|mut ring| {
+                        ring.extend(ring.first().copied());
+                        (mark, ring)
+                    }));
+                }
+                continue;
+            }
+            // fold lines stay lines, all of their subpaths
+            if shape.fold {
+                if let (Some(flat), _) = flatten::<P>(shape, idx, false, true, tolerance_for(shape, options, norm.scale), false) {
+                    folds.extend(flat.lines);
+                    folds.extend(flat.contours.into_iter().map(|mut ring| {
+                        ring.extend(ring.first().copied());
+                        ring
+                    }));
+                }
+                continue;
+            }
+            let keep_artwork = options.engrave || shape.strokes;
+            // outlines and artwork are in working units, so only plain
+            // fills are copies of each other
+            let plain = !keep_artwork && shape.taper.is_none() && shape.buffer.is_none() && shape.widths.is_empty();
+            let copy = shape.instance.filter(|_| plain).and_then(|(key, placed)| {
+                let (undo, contours, lines) = instances.get(&key)?;
+                let step = undo.then(placed);
+                // a copy at another size would be flattened too finely or coarsely
+                ((step.determinant().abs() - 1.0).abs() < 1e-3).then(|| {
+                    let moved =
+                        |lines: &[Contour]| -> Vec<Contour> { lines.iter().map(|l| l.iter().map(|&p| step.apply(p)).collect()).collect() };
+                    FlatShape {
+                        id: if shape.id.is_empty() { format!("#{}", idx + 1) } else { shape.id.clone() },
+                        contours: moved(contours),
+                        offset: shape.offset,
+                        style: shape.style.clone(),
+                        layer: shape.layer.clone(),
+                        class: shape.class.clone(),
+                        lines: moved(lines),
                     }
+                })
+            });
+            let (flat, art) = match copy {
+                Some(flat) => {
+                    reused += 1;
+                    (Some(flat), None)
+                }
+                None => flatten::<P>(
+                    shape,
+                    idx,
+                    keep_artwork,
+                    options.config.scores(),
+                    tolerance_for(shape, options, norm.scale),
+                    options.curvature,
+                ),
+            };
+            if let (Some((key, placed)), Some(flat), true) = (shape.instance, &flat, plain)
+                && let Some(undo) = placed.inverse()
+            {
+                instances.entry(key).or_insert_with(|| (undo, flat.contours.clone(), flat.lines.clone()));
+            }
+            let contours = flat.as_ref().map_or(0, |f| f.contours.len());
+            tracing::debug!("  Path {}: {} polygon(s)", idx + 1, contours);
+            // a pattern piece is cut along its allowance and sewn along its outline
+            let flat = flat.map(|mut flat| {
+                if let Some(allowance) = shape.seam.filter(|_| !flat.contours.is_empty()) {
+                    let outlines: Paths<P> = flat.contours.clone().into();
+                    stitches.extend(operation::outlines(&outlines));
+                    let grown = outlines.inflate(allowance, JoinType::Miter, EndType::Polygon, 2.0);
+                    flat.contours = grown.iter().map(|ring| ring.iter().map(|p| (p.x(), p.y())).collect()).collect();
                 }
-                _ => {}
+                flat
+            });
+            if let Some(flat) = flat {
+                input_vertices += flat.contours.iter().map(|p| p.len()).sum::<usize>();
+                contour_segments_paths.push(flat);
             }
+            // without an engrave layer only the strokes are engraved
+            artwork.extend(art.map(|mut art| {
+                if !options.engrave {
+                    art.closed.clear();
+                }
+                art
+            }));
         }
-        // exhausted flattened (segmented) path with all subpaths 
-        if !contour_segments.is_empty() {
-            contour_segments_paths.push(contour_segments.clone().into());
-        }
-        println!("  Path {}: {} polygon(s)", idx + 1, &contour_segments.len());
-        input_vertices += contour_segments.iter().map(|p| p.len()).sum::<usize>();
-        contour_segments = Vec::new()
-    }
-    println!("\n✓ Created {} path group(s)", &contour_segments_paths.len()); 
-    
-    println!("\n🔄 Computing inflate with Clipper2...");
-    
-    let  mut combined =  Paths::new(vec![]);
-    for g in &contour_segments_paths {
-      let expanded: Paths<Centi> = inflate(g.clone(), 10.0, JoinType::Round, EndType::Polygon, 0.0);
-
-      combined = if combined.is_empty() {
-        g.clone().into()
-      } else {
-        combined = difference(combined, expanded, FillRule::NonZero)?;
-        union(combined, g.clone(), FillRule::NonZero)?
-      };
-    }
-
-    // Cleaning
-    combined = combined.simplify(0.2, true);
-    combined = filter_small(combined, 50.0);
-    combined = union(combined, Paths::new(vec![]), FillRule::NonZero)?;
-    combined = combined.simplify(0.4, true);
-
-    let output_polygons = combined.iter().collect::<Vec<_>>().len();
-    println!("✓ Union complete: {} polygon(s) in result", output_polygons);
-    // After the inflate/difference loop, group by original shape
-    // Simpler: combine all resulting polygons into one multi-subpath
-    let mut d = String::new();
-    for poly in combined.iter() {
-        if poly.is_empty() {
-            continue;
+        if reused > 0 {
+            tracing::info!("✓ Flattened {reused} instanced path(s) once, placed by their transforms");
         }
-        
-        let points: Vec<_> = poly.iter().collect();
-        if points.is_empty() {
-            continue;
+        tracing::info!("\n✓ Created {} path group(s)", &contour_segments_paths.len());
+        progress.stage("flatten", format!("{input_vertices} vertices"));
+    }
+    if let Some(path) = &options.intersections {
+        let found = matrix::matrix::<P>(&contour_segments_paths, options.intersections_by, options.intersections_of.as_deref())?;
+        // areas back in square drawing units
+        let found = found.scaled(1.0 / (norm.scale * norm.scale));
+        let text = if path.ends_with(".json") { serde_json::to_string_pretty(&found.json())? } else { found.csv() };
+        output::write_output(path, text, options, true)?;
+        tracing::info!("✓ Intersection matrix of {} item(s) saved to: {path}", found.names.len());
+    }
+    if !repaired.is_empty() {
+        tracing::warn!("⚠ Repaired path data in {} element(s)", repaired.len());
+    }
+    for note in &repaired {
+        progress.warn(format!("repaired path data in {}", note["element"].as_str().unwrap_or("?")));
+    }
+    // only the flattened shapes are needed from here on
+    drop(paths);
+
+    stage.next("group");
+    let mut groups = groups::group(contour_segments_paths, options.group_by);
+    if options.group_by.is_some() {
+        tracing::info!("✓ Sorted shapes into {} group(s)", groups.len());
+    }
+    if !options.config.groups.is_empty() {
+        let changed = groups::apply_config(&mut groups, &options.config, norm.scale);
+        tracing::info!("✓ Config: offsets set for {changed} shape(s)");
+    }
+    if let Some(order) = &options.order {
+        for group in &mut groups {
+            order::sort(&mut group.shapes, order, |id| attributes.get(id));
+        }
+    }
+    if let Some(gap) = options.join_gap {
+        let before: usize = groups.iter().map(|g| g.shapes.len()).sum();
+        for group in &mut groups {
+            group.shapes = join::join::<P>(std::mem::take(&mut group.shapes), gap * norm.scale)?;
+        }
+        let after: usize = groups.iter().map(|g| g.shapes.len()).sum();
+        tracing::info!("✓ Joined shapes within {gap} of each other: {before} shape(s) now {after}");
+    }
+
+    progress.stage("group", format!("{} group(s)", groups.len()));
+    stage.next("combine");
+    if streamed.is_none() {
+        tracing::info!("\n🔄 Computing inflate with Clipper2...");
+    }
+
+    // every group is combined on its own, then all of them are kept side by
+    // side as one set of rings; `group_rings` says which rings are whose
+    // streamed shapes are gone by now, so nothing to trace back to
+    let want_sources = options.stream.is_none()
+        && (options.labels
+            || options.separate
+            || options.split_parts.is_some()
+            || options.report.is_some()
+            || options.nest.is_some()
+            || !options.config.dimensions.is_empty());
+    // cut out of every group's result
+    let holes = match &options.punch_holes {
+        Some(path) => holes::read(path, options.hole_d)?,
+        None => Vec::new(),
+    };
+    if let Some(path) = &options.punch_holes {
+        tracing::info!("✓ Punching {} hole(s) listed in {path}", holes.len());
+    }
+    let mut cut_out: Vec<Contour> = Vec::new();
+    for (n, primitive) in options.subtract.iter().chain(&holes).enumerate() {
+        let shape = primitive_shape(primitive, n, options.gap, norm);
+        if options.keep_curves {
+            curves.add(&shape.path);
+        }
+        cut_out.extend(flatten::<P>(&shape, n, false, false, TOLERANCE, false).0.into_iter().flat_map(|f| f.contours));
+    }
+    let mut cache = options.cache.as_deref().map(|dir| cache::Cache::open(dir, options.cache_limits)).transpose()?;
+    let mut combined = Paths::new(vec![]);
+    let mut group_rings = Vec::with_capacity(groups.len());
+    let mut parts = Vec::new();
+    let mut part_group = Vec::new();
+    let mut part_sources = Vec::new();
+    let mut perforations = 0;
+    let mut jointed = 0;
+    // box edges paired up by name, and those cut
+    let fingers = options.finger_joints.map(|f| f.scaled(norm.scale));
+    let finger_edges = match fingers {
+        Some(fingers) => {
+            let (edges, unpaired) = fingers.edges(&mates);
+            for (name, n) in unpaired {
+                warnings.add("finger-joints", name, format!("{n} line(s) with this data-mate; finger joints pair two"));
+            }
+            edges
+        }
+        None => Vec::new(),
+    };
+    let mut fingered: Vec<usize> = Vec::new();
+    // lines of scored and engraved groups and folds: the group if any,
+    // what is done, the lines
+    let mut operated: Vec<(Option<usize>, Operation, Vec<Contour>)> = Vec::new();
+    for (g, group) in groups.iter().enumerate() {
+        let (mut result, dropped) = match streamed.take() {
+            Some(result) => result,
+            None => match (&mut cache, options.overlaps) {
+                (_, Some(which)) => (overlap::overlaps(&group.shapes, which)?, Paths::new(vec![])),
+                // gaps between pairs aren't part of what the cache knows
+                (_, None) if !options.config.gaps.is_empty() => clean(carve_pairs(&group.shapes, |upper, lower| {
+                    options.config.gap_between(upper.names(), lower.names()).map_or(upper.offset, |gap| gap * norm.scale)
+                })?)?,
+                (Some(cache), None) => cache.combine(&group.shapes)?,
+                (None, None) => combine(&group.shapes)?,
+            },
+        };
+        warn_small(&dropped, &group.shapes, "smaller than the minimum area", norm, &mut warnings)?;
+
+        if !cut_out.is_empty() {
+            result = difference(result, cut_out.clone(), FillRule::NonZero)?;
+        }
+
+        // ---------------- Post-processing ----------------
+        if let Some(r) = options.round_corners {
+            result = corners::round_corners(result, r * norm.scale);
+        }
+        if let Some(length) = options.chamfer {
+            result = corners::chamfer_corners(result, length * norm.scale);
+        }
+        if options.negative {
+            result = parts::negative(&result)?;
+        }
+        // same input, same bytes out
+        result = canonical::canonicalize(&result);
+        if options.silhouette {
+            result = parts::silhouette(&result);
+        }
+        if let Some(n) = options.keep_largest {
+            result = parts::keep_largest(&result, n);
+        }
+        if let Some(area) = options.min_area {
+            let dropped;
+            (result, dropped) = parts::drop_small(&result, area * norm.scale * norm.scale);
+            warn_small(&dropped, &group.shapes, &format!("smaller than --min-area {area}"), norm, &mut warnings)?;
+        }
+        if let Some(pattern) = options.perforate {
+            // parts overlapping one of the chosen elements, or all of them
+            let chosen: Vec<Paths<P>> = group
+                .shapes
+                .iter()
+                .filter(|s| options.perforate_only.iter().flatten().any(|id| s.id.split(' ').any(|piece| piece == id)))
+                .map(|s| s.contours.clone().into())
+                .collect();
+            let picked = |rings: &Paths<P>| {
+                options.perforate_only.is_none()
+                    || chosen.iter().any(|own| intersect(rings.clone(), own.clone(), FillRule::NonZero).is_ok_and(|c| !c.is_empty()))
+            };
+            let (punched, n) = perforate::perforate(&result, &pattern.scaled(norm.scale), picked)?;
+            result = canonical::canonicalize(&punched);
+            perforations += n;
+        }
+
+        if let Some(joints) = options.joints {
+            // neighbours are as far apart as the gap carved between them
+            let (joined, edges) = joints::join(&result, &joints.scaled(norm.scale), options.gap * norm.scale * 1.01)?;
+            result = canonical::canonicalize(&joined);
+            jointed += edges;
+        }
+        if let Some(fingers) = fingers {
+            let (cut, done) = fingers.cut(&result, &finger_edges)?;
+            result = canonical::canonicalize(&cut);
+            fingered.extend(done);
+        }
+
+        // scored and engraved groups are drawn as lines, leaving nothing to cut
+        let operation = group.name.as_deref().and_then(|name| options.config.operation_for(name));
+        match operation.map(|rule| (rule, rule.kind)) {
+            Some((rule, Operation::Cut)) if rule.kerf > 0.0 => {
+                result = canonical::canonicalize(&result.inflate(rule.kerf / 2.0 * norm.scale, JoinType::Miter, EndType::Polygon, 2.0));
+            }
+            Some((_, op @ (Operation::Score | Operation::Stitch | Operation::Notch | Operation::Grainline))) => {
+                let mut lines = operation::outlines(&result);
+                lines.extend(group.shapes.iter().flat_map(|s| s.lines.iter().cloned()));
+                operated.push((Some(g), op, lines));
+                result = Paths::new(vec![]);
+            }
+            Some((rule, Operation::Engrave)) => {
+                operated.push((Some(g), Operation::Engrave, operation::hatch(&result, rule.hatch * norm.scale, rule.angle)));
+                result = Paths::new(vec![]);
+            }
+            // the material's kerf, for cuts without their own
+            Some((_, Operation::Cut)) | None => {
+                if let Some(kerf) = options.material.as_ref().map(|m| m.kerf).filter(|&k| k > 0.0) {
+                    result = canonical::canonicalize(&result.inflate(kerf / 2.0 * norm.scale, JoinType::Miter, EndType::Polygon, 2.0));
+                }
+            }
+        }
+
+        // the stamp joins the last group
+        if g + 1 == groups.len() && !stamp_cut.is_empty() {
+            result = canonical::canonicalize(&union(result, stamp_cut.clone(), FillRule::NonZero)?);
+        }
+
+        // parts and what they came from, before moving anything around
+        // overlaps come from shapes as drawn, with nothing carved out
+        let owned = match (want_sources, options.overlaps) {
+            (false, _) => Vec::new(),
+            (true, Some(_)) => group.shapes.iter().map(|s| s.contours.clone().into()).collect(),
+            (true, None) => provenance::surviving(&group.shapes)?,
+        };
+        let start = combined.len();
+        for mut part in parts::parts(&result) {
+            let found = if want_sources { provenance::sources(&part.paths(&result), &owned)? } else { Vec::new() };
+            // a joined shape goes by the ids of all its pieces
+            part_sources.push(found.into_iter().flat_map(|i| group.shapes[i].id.split(' ').map(str::to_string)).collect::<Vec<_>>());
+            part.outer += start;
+            part.holes.iter_mut().for_each(|h| *h += start);
+            parts.push(part);
+            part_group.push(g);
+        }
+        combined.push(result);
+        group_rings.push(start..combined.len());
+    }
+
+    if !stitches.is_empty() {
+        tracing::info!("✓ Seam allowance around {} stitch line(s)", stitches.len());
+        operated.push((None, Operation::Stitch, stitches));
+    }
+    if !marks.is_empty() {
+        let count = |op| marks.iter().filter(|m| m.0 == op).count();
+        tracing::info!("✓ Pattern marks: {} notch line(s), {} grainline(s)", count(Operation::Notch), count(Operation::Grainline));
+        for op in [Operation::Notch, Operation::Grainline] {
+            let lines: Vec<Contour> = marks.iter().filter(|m| m.0 == op).map(|m| m.1.clone()).collect();
+            if !lines.is_empty() {
+                operated.push((None, op, lines));
+            }
         }
-        
-        let first = points[0];
-        d.push_str(&format!("M {} {} ", first.x(), first.y()));
-        for pt in points.iter().skip(1) {
-            d.push_str(&format!("L {} {} ", pt.x(), pt.y()));
-        }
-        d.push_str("Z ");
-    }
-
-    let output_svg = format!(
-        r#"<?xml version="1.0" encoding="UTF-8"?>
-    <svg xmlns="http://www.w3.org/2000/svg" 
-         viewBox="{} {} {} {}" 
-         width="{}px" 
-         height="{}px">
-        <path d="{}" fill="black" fill-rule="nonzero" stroke="none"/>
-    </svg>"#,
-        0.0, 0.0,
-        tree.size().width(), tree.size().height(),
-        tree.size().width(), tree.size().height(),
-        d.trim()
-    );    
-        fs::write("./output.svg", &output_svg)?;
-        
-        println!("\n✅ Success!");
-        println!("Output saved to: output.svg");
-        
-        // Statistics
-        let output_vertices: usize = combined.iter().map(|p| p.len()).sum();
-        
-        println!("\n📊 Statistics:");
-        println!("  Input vertices: {}", input_vertices);
-        println!("  Output polygons: {}", output_polygons);
-        println!("  Output vertices: {}", output_vertices);
-        
-        if output_vertices < input_vertices {
-            let reduction = 100.0 * (1.0 - output_vertices as f64 / input_vertices as f64);
-            println!(" Vertex reduction: {:.1}%", reduction);
-        }
-
-        Ok(())
-    }
-
-    fn filter_small(paths: Paths<Centi>, min_area: f64) -> Paths<Centi> {
-        Paths::new(
-            paths
+    }
+
+    // the panel is one piece, written with the last group
+    if let Some(panel) = options.panelize {
+        let panel = panel.scaled(norm.scale);
+        // lines go along with every copy
+        let placements = panel.placements(&combined);
+        let repeat = |lines: &[Contour]| -> Vec<Contour> {
+            placements.iter().flat_map(|t| lines.iter().map(move |l| l.iter().map(|&p| t.apply(p)).collect())).collect()
+        };
+        for (_, _, lines) in &mut operated {
+            *lines = repeat(lines);
+        }
+        folds = repeat(&folds);
+        let (rings, tabs) = panel.build(&combined)?;
+        combined = canonical::canonicalize(&rings);
+        parts = parts::parts(&combined);
+        part_group = vec![groups.len() - 1; parts.len()];
+        part_sources = vec![vec!["panel".to_string()]; parts.len()];
+        group_rings.iter_mut().for_each(|r| *r = 0..0);
+        if let Some(last) = group_rings.last_mut() {
+            *last = 0..combined.len();
+        }
+        tracing::info!("✓ Panelized into {}x{} copies held by {tabs} tab(s)", panel.columns, panel.rows);
+    }
+
+    // parts packed onto sheets, lines moving with the part they lie on
+    let mut sheets = None;
+    let mut part_sheet = Vec::new();
+    if let Some(nest) = options.nest {
+        let nest = nest.scaled(norm.scale);
+        let origin = norm.forward.apply((0.0, 0.0));
+        // every part as many times as asked for, copies next to each other
+        let listed = match &options.quantities {
+            Some(path) => quantity::read(path)?,
+            None => HashMap::new(),
+        };
+        let mut copies: Vec<usize> = Vec::new();
+        for (n, sources) in part_sources.iter().enumerate() {
+            copies.extend(std::iter::repeat_n(n, quantity::of(sources, &listed, attributes)?));
+        }
+        let part_rings: Vec<Vec<Contour>> =
+            parts.iter().map(|part| part.paths(&combined).iter().map(|r| r.iter().map(|p| (p.x(), p.y())).collect()).collect()).collect();
+        let boxes: Vec<(f64, f64, f64, f64)> = parts
+            .iter()
+            .map(|part| {
+                let b = part.paths(&combined).bounds();
+                (b.min.x(), b.min.y(), b.size().x(), b.size().y())
+            })
+            .collect();
+        // a part turns only as far as every element it came from allows
+        let turns: Vec<nest::Turns> = part_sources
+            .iter()
+            .map(|sources| {
+                sources
+                    .iter()
+                    .filter_map(|id| attributes.get(id)?.get("rotations"))
+                    .filter_map(|spec| nest::parse_rotations(spec).ok())
+                    .fold(nest.rotations, |turns, own| turns & own)
+                    .max(1)
+            })
+            .collect();
+        // parts filling their bounds can share their edges
+        let rectangular: Vec<bool> = parts
+            .iter()
+            .zip(&boxes)
+            .map(|(part, b)| {
+                let area = part.paths(&combined).signed_area().abs();
+                part.holes.is_empty() && area >= RECTANGULAR * b.2 * b.3
+            })
+            .collect();
+        for (n, b) in boxes.iter().enumerate().filter(|(n, b)| !nest.fits(**b, turns[*n])) {
+            warnings.add(
+                "nest",
+                format!("part-{}", n + 1),
+                format!("{:.2}x{:.2} doesn't fit on a sheet", b.2 / norm.scale, b.3 / norm.scale),
+            );
+        }
+        let (placements, count) = nest.place(
+            &copies.iter().map(|&n| boxes[n]).collect::<Vec<_>>(),
+            &copies.iter().map(|&n| turns[n]).collect::<Vec<_>>(),
+            &copies.iter().map(|&n| rectangular[n]).collect::<Vec<_>>(),
+            origin,
+        );
+        // the rings again, copy by copy, so every group's stay together
+        let mut rings: Vec<Contour> = Vec::new();
+        let mut placed = Vec::with_capacity(copies.len());
+        group_rings.iter_mut().for_each(|r| *r = 0..0);
+        for (&n, placement) in copies.iter().zip(&placements) {
+            let start = rings.len();
+            rings.extend(part_rings[n].iter().map(|r| r.iter().map(|&p| placement.transform.apply(p)).collect()));
+            placed.push(parts::Part { outer: start, holes: (start + 1..rings.len()).collect() });
+            let own = &mut group_rings[part_group[n]];
+            *own = if own.start == own.end { start..rings.len() } else { own.start..rings.len() };
+        }
+        // a line goes with every copy of the part it lies on
+        let follow = |lines: &mut Vec<Contour>| {
+            *lines = std::mem::take(lines)
                 .into_iter()
-                .filter(|p| p.signed_area().abs() >= min_area)
-                .collect(),
-        )
-    }
-
-    fn create_init_svg_file() -> String {
-      let opt = usvg::Options::default();
-      let entries: Vec<(usvg::Tree, f32, f32)> = Vec::new();
-      for i in 0..=5 {
-        let path = format!("{i}.svg");
-        match fs::read(&path) {
-          Ok(data) => match usvg::Tree::from_data(&data, &opt) {
-             Ok(tree) => {
-              let size = tree.size();
-              let (w, h) = (size.width(), size.height());
-              println!("loaded path {path} ({w}px x {h}px)");
-              entries.push((tree, w, h));
-             },
-             Err(e) => eprintln!("skip {path} - parse error {e}"),
-          },
-          Err(e) => eprintln!("skip {path}: {e}"),
-        }
-      }
-      if entries.is_empty() {
-        eprintln!("no SVG files found");
-      }
-
-      let canvas_h = entries.iter().map(|(_, _, h)| *h).fold(0.0_f32, f32::max);
-      let canvas_w = entries.iter().map(|(_, w, _)| *w).sum::<f32>();
-
-      let mut out = Vec::new();
-
-      writeln!(
+                .flat_map(|line| {
+                    // the first part any of it is on, for marks across an edge
+                    let on = line.iter().find_map(|&p| part_rings.iter().position(|rings| clip::winding(rings, p) != 0));
+                    match on {
+                        Some(n) => copies
+                            .iter()
+                            .zip(&placements)
+                            .filter(|(copy, _)| **copy == n)
+                            .map(|(_, placement)| line.iter().map(|&p| placement.transform.apply(p)).collect())
+                            .collect(),
+                        None => vec![line],
+                    }
+                })
+                .collect();
+        };
+        for (_, _, lines) in &mut operated {
+            follow(lines);
+        }
+        follow(&mut folds);
+        combined = rings.into();
+        if copies.len() > parts.len() {
+            tracing::info!("✓ Made {} part(s) into {} by quantity", parts.len(), copies.len());
+        }
+        part_group = copies.iter().map(|&n| part_group[n]).collect();
+        part_sources = copies.iter().map(|&n| part_sources[n].clone()).collect();
+        parts = placed;
+        tracing::info!(
+            "✓ Nested {} part(s) onto {count} sheet(s) of {}x{}",
+            parts.len(),
+            nest.sheet.0 / norm.scale,
+            nest.sheet.1 / norm.scale
+        );
+        part_sheet = placements.iter().map(|p| p.sheet).collect();
+        sheets = Some((nest, origin, count));
+    } else if attributes.values().any(|a| a.contains_key("qty")) {
+        tracing::warn!("⚠ data-qty copies are laid out by --nest; making one of each");
+    }
+
+    // a frame goes around everything, written with the last group
+    if let Some(frame) = options.frame {
+        let rings = canonical::canonicalize(&frame.scaled(norm.scale).around(&combined)?);
+        let start = combined.len();
+        for mut part in parts::parts(&rings) {
+            part.outer += start;
+            part.holes.iter_mut().for_each(|h| *h += start);
+            parts.push(part);
+            part_group.push(groups.len() - 1);
+            part_sources.push(vec!["frame".to_string()]);
+        }
+        combined.push(rings);
+        if let Some(last) = group_rings.last_mut() {
+            last.end = combined.len();
+        }
+        tracing::info!("✓ Framed the result, {} wide", frame.width);
+    }
+
+    let simplified = match (options.target_vertices, options.max_deviation) {
+        (Some(target), _) => Some(budget::to_target(&combined, target)?),
+        (None, Some(max)) => Some(budget::within(&combined, max * norm.scale)),
+        (None, None) => None,
+    };
+    // the deviation simplifying left, for the summary
+    let mut deviation = None;
+    if let Some(simplified) = simplified.filter(|s| s.epsilon > 0.0) {
+        combined = simplified.rings;
+        deviation = Some(simplified.error / norm.scale);
+        tracing::info!(
+            "✓ Simplified to {} vertices: tolerance {:.4}, max deviation {:.4}",
+            combined.iter().map(|p| p.len()).sum::<usize>(),
+            simplified.epsilon / norm.scale,
+            simplified.error / norm.scale
+        );
+    }
+
+    if let Some(start) = options.start_point {
+        combined = start::place(&combined, start);
+    }
+    if let Some(direction) = options.direction {
+        combined = direction::orient(&combined, direction);
+    }
+
+    if let Some(cache) = &cache {
+        tracing::info!("✓ Cache: {} result(s) reused, {} carved anew", cache.hits, cache.misses);
+        match cache.evict() {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("✓ Cache: {n} result(s) past its limits removed"),
+            Err(e) => tracing::warn!("cache: {e}"),
+        }
+    }
+    let output_polygons = combined.len();
+    progress.stage("combine", format!("{output_polygons} polygon(s)"));
+    tracing::info!("✓ Union complete: {} polygon(s) in result", output_polygons);
+    if let Some(r) = options.round_corners {
+        tracing::info!("✓ Rounded corners with radius {r}");
+    }
+    if let Some(length) = options.chamfer {
+        tracing::info!("✓ Chamfered corners by {length}");
+    }
+    match options.overlaps {
+        Some(overlap::Overlap::AtLeast(k)) => tracing::info!("✓ Overlaps: where {k} or more shapes cover each other"),
+        Some(overlap::Overlap::Exactly(k)) => tracing::info!("✓ Overlaps: where exactly {k} shape(s) cover each other"),
+        None => {}
+    }
+    if options.negative {
+        tracing::info!("✓ Negative space: holes and gaps within the bounding box");
+    }
+    if options.silhouette {
+        tracing::info!("✓ Silhouette: holes filled in");
+    }
+    if let Some(n) = options.keep_largest {
+        tracing::info!("✓ Kept the {n} largest part(s) of each group");
+    }
+    if options.perforate.is_some() {
+        tracing::info!("✓ Perforated with {perforations} hole(s)");
+    }
+    if options.joints.is_some() {
+        tracing::info!("✓ Joints along {jointed} edge(s) between neighbouring parts");
+    }
+    if options.finger_joints.is_some() {
+        for (_, edge) in finger_edges.iter().enumerate().filter(|(n, _)| !fingered.contains(n)) {
+            warnings.add("finger-joints", edge.name.clone(), "a data-mate line isn't along the edge of any part");
+        }
+        tracing::info!("✓ Finger joints cut along {} edge(s)", fingered.len());
+    }
+
+    if !folds.is_empty() {
+        // scored only where there is something to fold
+        let silhouette = union(combined.clone(), Paths::new(vec![]), FillRule::NonZero)?;
+        let lines: Vec<Contour> = folds.iter().flat_map(|line| clip::clip_polyline(line, &silhouette, true)).collect();
+        tracing::info!("✓ Folds: {} line(s) marked, {} piece(s) within the result", folds.len(), lines.len());
+        operated.push((None, Operation::Score, lines));
+    }
+    if !operated.is_empty() {
+        let count = |op| operated.iter().filter(|o| o.1 == op).map(|o| o.2.len()).sum::<usize>();
+        tracing::info!(
+            "✓ Operations: {} score line(s), {} hatch line(s), {} stitch line(s)",
+            count(Operation::Score),
+            count(Operation::Engrave),
+            count(Operation::Stitch)
+        );
+    }
+
+    // writing is a stage of its own, not one within this
+    drop(stage);
+    output::write(
+        output::Combined {
+            rings: combined,
+            groups,
+            group_rings,
+            parts,
+            part_group,
+            part_sources,
+            part_sheet,
+            operated,
+            artwork,
+            curves,
+            sheets,
+            repaired,
+            input_vertices,
+            deviation,
+        },
+        options,
+        source,
+        tree,
+        norm,
+        warnings,
+        progress,
+    )
+}
+
+/// A source path with the settings picked up from its element attributes.
+struct Shape {
+    id: String,
+    path: Path,
+    offset: f64,
+    // offsets at the start and end of open subpaths
+    taper: Option<(f64, f64)>,
+    // width and ends open subpaths are outlined with, unless tapered
+    buffer: Option<buffer::Buffer>,
+    // per subpath, widths at fractions of its length, from `data-widths`
+    widths: Vec<Vec<(f64, f64)>>,
+    style: Style,
+    layer: Option<String>,
+    // its own class, or the nearest group's above it
+    class: Option<String>,
+    // what sanitizing had to fix in its path data
+    repairs: sanitize::Repairs,
+    // open subpaths kept as engraving strokes
+    strokes: bool,
+    // a fold annotation, scored where it crosses the result
+    fold: bool,
+    // how far a sewing pattern piece is grown past its stitch line
+    seam: Option<f64>,
+    // a notch or grainline, drawn on the piece it lies on
+    mark: Option<Operation>,
+    // the name of the box edge it marks, for finger joints
+    mate: Option<String>,
+    // its path data as a key, and what takes that data to where the
+    // shape is, so `<use>` copies are flattened once
+    instance: Option<(u64, Affine)>,
+}
+
+impl Shape {
+    /// A plain shape: `path` with a gap of `offset` around it, no style,
+    /// layer or class, and none of the settings elements can ask for.
+    fn new(id: String, path: Path, offset: f64) -> Shape {
+        Shape {
+            id,
+            path,
+            offset,
+            taper: None,
+            buffer: None,
+            widths: Vec::new(),
+            style: Style::default(),
+            layer: None,
+            class: None,
+            repairs: sanitize::Repairs::default(),
+            strokes: false,
+            fold: false,
+            seam: None,
+            mark: None,
+            mate: None,
+            instance: None,
+        }
+    }
+}
+
+/// A source shape after flattening: its closed subpaths and settings.
+struct FlatShape {
+    id: String,
+    contours: Vec<Contour>,
+    offset: f64,
+    style: Style,
+    layer: Option<String>,
+    class: Option<String>,
+    // open subpaths as drawn, kept for score lines
+    lines: Vec<Contour>,
+}
+
+impl FlatShape {
+    /// What config patterns match a shape by: layer, fill color, class and id.
+    fn names(&self) -> impl Iterator<Item = &str> + Clone {
+        [self.layer.as_deref(), self.style.fill.as_deref(), self.class.as_deref(), Some(self.id.as_str())].into_iter().flatten()
+    }
+}
+
+/// Carve the gap around every shape out of what came before it, then
+/// clean up slivers and redundant vertices; and the rings dropped as
+/// smaller than `MIN_AREA`.
+fn combine<P: PointScaler>(shapes: &[FlatShape]) -> Result<(Paths<P>, Paths<P>), ClipperError> {
+    clean(carve(shapes)?)
+}
+
+/// Every shape in turn: carve its gap out of what's there, then add it.
+fn carve<P: PointScaler>(shapes: &[FlatShape]) -> Result<Paths<P>, ClipperError> {
+    carve_onto(Paths::new(vec![]), shapes)
+}
+
+/// Carve shapes into an already combined result.
+fn carve_onto<P: PointScaler>(mut combined: Paths<P>, shapes: &[FlatShape]) -> Result<Paths<P>, ClipperError> {
+    for shape in shapes {
+        let g = &shape.contours;
+        let expanded: Paths<P> = inflate(g.clone(), shape.offset, JoinType::Round, EndType::Polygon, 0.0);
+
+        combined = if combined.is_empty() {
+            g.clone().into()
+        } else {
+            combined = difference(combined, expanded, FillRule::NonZero)?;
+            union(combined, g.clone(), FillRule::NonZero)?
+        };
+    }
+
+    Ok(combined)
+}
+
+/// Like `carve`, but with the gap carved around every shape out of each
+/// earlier one given by `gap(shape, earlier)`.
+fn carve_pairs<P: PointScaler>(shapes: &[FlatShape], gap: impl Fn(&FlatShape, &FlatShape) -> f64) -> Result<Paths<P>, ClipperError> {
+    // what each shape so far still covers
+    let mut owned: Vec<Paths<P>> = Vec::with_capacity(shapes.len());
+    for (i, shape) in shapes.iter().enumerate() {
+        for (j, own) in owned.iter_mut().enumerate() {
+            if own.is_empty() {
+                continue;
+            }
+            let expanded: Paths<P> = inflate(shape.contours.clone(), gap(shape, &shapes[j]), JoinType::Round, EndType::Polygon, 0.0);
+            *own = difference(std::mem::replace(own, Paths::new(vec![])), expanded, FillRule::NonZero)?;
+        }
+        owned.push(shapes[i].contours.clone().into());
+    }
+    let all: Paths<P> = owned.into_iter().flatten().collect::<Vec<_>>().into();
+    union(all, Paths::new(vec![]), FillRule::NonZero)
+}
+
+/// `combined` simplified, without rings smaller than `MIN_AREA`; and
+/// those rings.
+fn clean<P: PointScaler>(combined: Paths<P>) -> Result<(Paths<P>, Paths<P>), ClipperError> {
+    let (kept, dropped) = filter_small(combined.simplify(0.2, true), MIN_AREA);
+    let combined = union(kept, Paths::new(vec![]), FillRule::NonZero)?;
+    Ok((combined.simplify(0.4, true), dropped))
+}
+
+/// A warning for every shape of `shapes` that lost pieces, or had holes
+/// filled, to an area filter, in `dropped`, with how many and their area;
+/// those within no shape are told by where they were.
+fn warn_small<P: PointScaler>(
+    dropped: &Paths<P>,
+    shapes: &[FlatShape],
+    why: &str,
+    norm: &Normalization,
+    warnings: &mut warnings::Warnings,
+) -> Result<(), ClipperError> {
+    if dropped.is_empty() {
+        return Ok(());
+    }
+    let owned = provenance::surviving(shapes)?;
+    // every ring of a shape filled, so its holes lie within it
+    let filled = |ring: &Contour| if order::signed_area(ring) < 0.0 { ring.iter().rev().copied().collect() } else { ring.clone() };
+    let whole: Vec<Paths<P>> = shapes.iter().map(|s| s.contours.iter().map(filled).collect::<Vec<Contour>>().into()).collect();
+    // pieces, holes and area by source, in the order first dropped
+    let mut lost: Vec<(String, usize, usize, f64)> = Vec::new();
+    for ring in dropped.iter() {
+        let region: Paths<P> = Paths::new(vec![ring.clone()]);
+        let area = region.signed_area() / (norm.scale * norm.scale);
+        // slivers of carving, too small to have come from anything
+        if area.abs() < SLIVER {
+            continue;
+        }
+        // a hole is owned by no one, but lies within the shape it was in
+        let mut found = provenance::sources(&region, &owned)?;
+        if found.is_empty() {
+            found = provenance::sources(&region, &whole)?;
+        }
+        let ids: Vec<String> = if found.is_empty() {
+            let (x, y) = norm.inverse.apply((region.bounds().min.x(), region.bounds().min.y()));
+            vec![format!("ring at {x:.1},{y:.1}")]
+        } else {
+            found.into_iter().map(|i| shapes[i].id.clone()).collect()
+        };
+        let (pieces, holes) = if area > 0.0 { (1, 0) } else { (0, 1) };
+        for id in ids {
+            match lost.iter_mut().find(|(known, ..)| *known == id) {
+                Some((_, p, h, total)) => (*p, *h, *total) = (*p + pieces, *h + holes, *total + area.abs()),
+                None => lost.push((id, pieces, holes, area.abs())),
+            }
+        }
+    }
+    for (id, pieces, holes, area) in lost {
+        let what = match (pieces, holes) {
+            (p, 0) => format!("{p} piece(s) dropped"),
+            (0, h) => format!("{h} hole(s) filled"),
+            (p, h) => format!("{p} piece(s) dropped and {h} hole(s) filled"),
+        };
+        warnings.add("area-filter", id, format!("{what}, {area:.2} square unit(s) in all: {why}"));
+    }
+    Ok(())
+}
+
+/// Read an SVG file, unpacking it first when it is gzipped (`.svgz`).
+fn read_svg(path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let data = fs::read(path)?;
+    let data = if data.starts_with(&[0x1f, 0x8b]) { usvg::decompress_svgz(&data)? } else { data };
+    Ok(String::from_utf8(data)?)
+}
+
+/// A shape given with `--add` or `--subtract`, as if it had been drawn,
+/// with a gap of `gap` unless it gives its own;
+/// unnamed ones are known as `add-N`.
+fn primitive_shape(primitive: &primitive::Primitive, n: usize, gap: f64, norm: &Normalization) -> Shape {
+    Shape::new(
+        primitive.id.clone().unwrap_or_else(|| format!("add-{}", n + 1)),
+        primitive.path(&norm.forward),
+        primitive.offset.unwrap_or(gap) * norm.scale,
+    )
+}
+
+/// A hash of path data as drawn, before any transform.
+fn instance_key(data: &usvg::tiny_skia_path::Path) -> u64 {
+    let mut bytes: Vec<u8> = data.verbs().iter().map(|&v| v as u8).collect();
+    for p in data.points() {
+        bytes.extend(p.x.to_le_bytes());
+        bytes.extend(p.y.to_le_bytes());
+    }
+    cache::hash(&bytes)
+}
+
+/// Warn about the segments sanitizing dropped or changed in a shape, for
+/// the report too.
+fn repair_note(shape: &Shape, idx: usize) -> Option<serde_json::Value> {
+    let r = shape.repairs;
+    if !r.any() {
+        return None;
+    }
+    let id = if shape.id.is_empty() { format!("#{}", idx + 1) } else { shape.id.clone() };
+    tracing::warn!(
+        "  ⚠ {id}: dropped {} non-finite and {} zero-length segment(s), straightened {} curve(s)",
+        r.non_finite,
+        r.zero_length,
+        r.straightened
+    );
+    Some(serde_json::json!({
+        "element": id,
+        "non_finite": r.non_finite,
+        "zero_length": r.zero_length,
+        "straightened": r.straightened,
+    }))
+}
+
+/// `data-widths`, one width per point the path data's segments end on,
+/// given out in order over `nodes` (those points by subpath; widths
+/// running short repeat the last one) and placed at each point's
+/// fraction of its subpath's length.
+fn width_profiles(widths: &str, nodes: &[Vec<(f64, f64)>], scale: f64) -> Vec<Vec<(f64, f64)>> {
+    let widths: Vec<f64> = widths.split(|c: char| c == ',' || c.is_whitespace()).filter_map(|w| w.parse().ok()).collect();
+    let Some(&last) = widths.last() else {
+        return Vec::new();
+    };
+    let mut given = widths.into_iter().chain(std::iter::repeat(last));
+    nodes
+        .iter()
+        .map(|points| {
+            let mut lengths = vec![0.0];
+            for w in points.windows(2) {
+                lengths.push(lengths[lengths.len() - 1] + (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1));
+            }
+            let total = lengths[lengths.len() - 1];
+            lengths.iter().zip(given.by_ref()).map(|(l, w)| (if total > 0.0 { l / total } else { 0.0 }, w * scale)).collect()
+        })
+        .collect()
+}
+
+/// Every warning, in the log and on the progress panel.
+fn show_warnings(warnings: &warnings::Warnings, progress: &mut progress::Progress) {
+    if warnings.is_empty() {
+        return;
+    }
+    tracing::warn!("\n⚠ {} warning(s):", warnings.0.len());
+    for w in &warnings.0 {
+        tracing::warn!("  {}: {}", w.element, w.message);
+        progress.warn(format!("{}: {}", w.element, w.message));
+    }
+}
+
+/// Warn when a shape has open subpaths nothing outlines or keeps.
+fn warn_dropped(shape: &Shape, idx: usize, options: &Options, warnings: &mut warnings::Warnings) {
+    if shape.taper.is_some()
+        || shape.buffer.is_some()
+        || !shape.widths.is_empty()
+        || shape.strokes
+        || shape.fold
+        || options.engrave
+        || options.config.scores()
+    {
+        return;
+    }
+    let open = shape.path.iter().filter(|e| matches!(e, lyon::path::Event::End { close: false, .. })).count();
+    if open > 0 {
+        let id = if shape.id.is_empty() { format!("#{}", idx + 1) } else { shape.id.clone() };
+        warnings.add("open-path", id, format!("{open} open subpath(s) dropped; --buffer or data-taper outlines them"));
+    }
+}
+
+/// Flattening tolerance for a shape: the fixed one, or scaled to the
+/// shape's size when adaptive, given tolerances being in drawing units
+/// that are `scale` working units each.
+fn tolerance_for(shape: &Shape, options: &Options, scale: f64) -> f32 {
+    let Some(a) = options.adaptive else {
+        return options.tolerance.map_or(TOLERANCE, |t| (t * scale) as f32);
+    };
+    let b = lyon::algorithms::aabb::fast_bounding_box(shape.path.iter());
+    let diagonal = (b.width() as f64).hypot(b.height() as f64);
+    (diagonal * a.factor).clamp(a.min * scale, a.max * scale) as f32
+}
+
+/// Flatten a source path into closed contours, outlining its open
+/// subpaths when it has widths, is tapered or buffered. Also returns the subpaths as drawn when
+/// `keep_artwork` is set, and keeps open subpaths as they are with
+/// `keep_lines`. With `curvature` every curve is flattened with a
+/// tolerance of its own, by `density::flatten`.
+fn flatten<P: PointScaler>(
+    shape: &Shape,
+    idx: usize,
+    keep_artwork: bool,
+    keep_lines: bool,
+    tolerance: f32,
+    curvature: bool,
+) -> (Option<FlatShape>, Option<engrave::Artwork>) {
+    let mut contour_segments: Vec<Contour> = Vec::new(); // by flattening a path is stored as a set of small segments (paths)
+    let mut current_polygon = Vec::new();
+    // which subpath this is, for its width profile
+    let mut subpath = 0;
+    let mut art = engrave::Artwork { style: shape.style.clone(), closed: Vec::new(), open: Vec::new() };
+    let mut lines = Vec::new();
+
+    let adapted = curvature.then(|| density::flatten(&shape.path, tolerance));
+    use lyon::path::Event::*;
+    for event in adapted.as_ref().unwrap_or(&shape.path).iter().flattened(tolerance) {
+        match event {
+            Begin { at } => {
+                subpath += 1;
+                current_polygon = Vec::new();
+                current_polygon.push((at.x as f64, at.y as f64));
+            }
+            Line { to, .. } => {
+                current_polygon.push((to.x as f64, to.y as f64));
+            }
+            End { close, .. } if close && current_polygon.len() >= 3 => {
+                contour_segments.push(current_polygon.clone());
+                if keep_artwork {
+                    art.closed.push(current_polygon.clone());
+                }
+            }
+            // open subpaths only take part when they are outlined
+            End { close: false, .. } if current_polygon.len() >= 2 => {
+                if keep_artwork {
+                    art.open.push(current_polygon.clone());
+                }
+                if keep_lines {
+                    lines.push(current_polygon.clone());
+                }
+                if let Some(profile) = shape.widths.get(subpath - 1).filter(|p| !p.is_empty()) {
+                    contour_segments.extend(taper::profiled_outline::<P>(&current_polygon, profile, tolerance as f64));
+                } else if let Some((start, end)) = shape.taper {
+                    contour_segments.extend(taper::tapered_outline::<P>(&current_polygon, start, end, tolerance as f64));
+                } else if let Some(buffer) = shape.buffer {
+                    contour_segments.extend(buffer::outline::<P>(&current_polygon, buffer));
+                }
+            }
+            _ => {}
+        }
+    }
+    // exhausted flattened (segmented) path with all subpaths
+    let flat = (!contour_segments.is_empty() || !lines.is_empty()).then(|| FlatShape {
+        // elements without an id are known by their position
+        id: if shape.id.is_empty() { format!("#{}", idx + 1) } else { shape.id.clone() },
+        contours: contour_segments,
+        offset: shape.offset,
+        style: shape.style.clone(),
+        layer: shape.layer.clone(),
+        class: shape.class.clone(),
+        lines,
+    });
+    (flat, keep_artwork.then_some(art))
+}
+
+/// Seconds since the epoch as `YYYY-MM-DDTHH:MM:SSZ`.
+fn utc_timestamp(seconds: u64) -> String {
+    let (days, rest) = ((seconds / 86400) as i64, seconds % 86400);
+    // civil date from day count, after Howard Hinnant's days_from_civil
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z", rest / 3600, rest / 60 % 60, rest % 60)
+}
+
+/// The rings of `paths` at least `min_area` in area, and the others.
+fn filter_small<P: PointScaler>(paths: Paths<P>, min_area: f64) -> (Paths<P>, Paths<P>) {
+    let (kept, dropped) = paths.into_iter().partition(|p| p.signed_area().abs() >= min_area);
+    (Paths::new(kept), Paths::new(dropped))
+}
+
+/// A single input is used as is; several inputs are laid out side by side
+/// on one canvas, with what they link to in place when `external` allows.
+/// Unreadable inputs are left out while others remain, and an error when
+/// none does.
+fn create_init_svg_file(inputs: &[String], external: Option<&external::Sandbox>) -> Result<Composed, String> {
+    let mut entries: Vec<(usvg::Tree, f32, f32)> = Vec::new();
+    let mut attributes = HashMap::new();
+    let mut skipped = Vec::new();
+    for (i, path) in inputs.iter().enumerate() {
+        let text = match read_svg(path) {
+            Ok(text) => text,
+            Err(e) => {
+                skipped.push((path.clone(), format!("cannot be read: {e}")));
+                continue;
+            }
+        };
+        let text = match external {
+            Some(sandbox) => sandbox.inline(&text, path),
+            None => text,
+        };
+        let (text, found) = match attrs::annotate(&text) {
+            Ok(annotated) => annotated,
+            Err(e) => {
+                skipped.push((path.clone(), format!("is not valid SVG: {e}")));
+                continue;
+            }
+        };
+        if inputs.len() == 1 {
+            return Ok(Composed { text, attributes: found, skipped });
+        }
+        let opt = external.map_or_else(usvg::Options::default, |sandbox| sandbox.options(path));
+        match usvg::Tree::from_data(text.as_bytes(), &opt) {
+            Ok(tree) => {
+                let size = tree.size();
+                let (w, h) = (size.width(), size.height());
+                tracing::info!("loaded path {path} ({w}px x {h}px)");
+                // ids are prefixed per file when writing, so keep the keys in step
+                attributes.extend(found.into_iter().map(|(id, a)| (format!("f{i}-{id}"), a)));
+                entries.push((tree, w, h));
+            }
+            Err(e) => skipped.push((path.clone(), format!("is not valid SVG: {e}"))),
+        }
+    }
+    // a file is only left out while others remain to go on with
+    if entries.is_empty() {
+        return Err(skipped.iter().map(|(path, why)| format!("{path} {why}")).collect::<Vec<_>>().join("; "));
+    }
+
+    let canvas_h = entries.iter().map(|(_, _, h)| *h).fold(0.0_f32, f32::max);
+    let canvas_w = entries.iter().map(|(_, w, _)| *w).sum::<f32>();
+
+    let mut out = String::new();
+
+    writeln!(
         out,
-        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{canvas_w}" height="{canvas_h}" viewBox="0 0 {canvas_w} {canvas_h}">"#,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="{canvas_w}" height="{canvas_h}" viewBox="0 0 {canvas_w} {canvas_h}">"#,
       ).unwrap();
 
-      let mut x = 0.0_f32;
-      let mut y = 0.0_f32;
-      let opt = usvg::Options::default();
-      for (_, (tree, w, h)) in entries.iter().enumerate() {
-        y = canvas_h - h;
-        writeln!(
-          out,
-          r#" <svg x={x} y={y} width={w} height={h} overflow="visible">"#,
-        );
-        x += w/2.0f32;
+    let mut x = 0.0_f32;
+    for (i, (tree, w, h)) in entries.iter().enumerate() {
+        let y = canvas_h - h;
+        writeln!(out, r#" <svg x="{x}" y="{y}" width="{w}" height="{h}" overflow="visible">"#,).unwrap();
+        x += w / 2.0f32;
 
+        let opt = usvg::WriteOptions { id_prefix: Some(format!("f{i}-")), ..Default::default() };
         let inner_doc = tree.to_string(&opt);
         if let Some(children) = svg_children(&inner_doc) {
             out.push_str(children);
             out.push('\n');
         }
-      }
-      writeln!(out, "  </svg>").unwrap();
-
-      out.join("\n")
+        writeln!(out, " </svg>").unwrap();
     }
+    writeln!(out, "</svg>").unwrap();
 
-    /// Return the XML content between the outer `<svg …>` and `</svg>` tags.
-    fn svg_children(doc: &str) -> Option<&str> {
-        // Skip optional XML declaration
-        let body = if doc.starts_with("<?xml") {
-            doc.splitn(2, "?>").nth(1)?.trim_start()
-        } else {
-            doc
-        };
+    Ok(Composed { text: out, attributes, skipped })
+}
 
-        // Locate the end of the opening <svg …> tag
-        let open_end = body.find('>')?;
-        if body[..open_end].ends_with('/') {
-            return Some(""); // self-closing tag, no children
-        }
-        let after_open = &body[open_end + 1..];
+/// Return the XML content between the outer `<svg …>` and `</svg>` tags.
+fn svg_children(doc: &str) -> Option<&str> {
+    // Skip optional XML declaration
+    let body = if doc.starts_with("<?xml") { doc.split_once("?>")?.1.trim_start() } else { doc };
 
-        // Strip the trailing </svg>
-        let close = after_open.rfind("</svg>")?;
-        Some(&after_open[..close])
+    // Locate the end of the opening <svg …> tag
+    let open_end = body.find('>')?;
+    if body[..open_end].ends_with('/') {
+        return Some(""); // self-closing tag, no children
     }
+    let after_open = &body[open_end + 1..];
+
+    // Strip the trailing </svg>
+    let close = after_open.rfind("</svg>")?;
+    Some(&after_open[..close])
+}
@@ -0,0 +1,101 @@
+use clipper2::*;
+use lyon::path::Path;
+use lyon::path::iterator::PathIterator;
+
+use crate::Contour;
+use crate::transform::Affine;
+
+/// `--mask-threshold T`: where a mask lets its content through, as an
+/// area. Every filled shape of the mask, in drawing order, adds to it when
+/// it is at least `threshold` bright (or opaque, for alpha masks) and cuts
+/// it away otherwise, all within the mask's rectangle. `to_working` takes
+/// the masked group's space into working units.
+pub fn region<P: PointScaler>(mask: &usvg::Mask, to_working: Affine, threshold: f64, tolerance: f32) -> Result<Paths<P>, ClipperError> {
+    let mut keep: Paths<P> = Paths::new(vec![]);
+    let mut shapes = Vec::new();
+    walk(mask.root(), Affine::IDENTITY, 1.0, &mut shapes);
+    for (data, within, color, opacity) in shapes {
+        let value = match mask.kind() {
+            // relative luminance, as SVG masks weigh it
+            usvg::MaskType::Luminance => {
+                let (r, g, b) = (color.red as f64 / 255.0, color.green as f64 / 255.0, color.blue as f64 / 255.0);
+                (0.2125 * r + 0.7154 * g + 0.0721 * b) * opacity
+            }
+            usvg::MaskType::Alpha => opacity,
+        };
+        let rings: Paths<P> = crate::pattern::rings(data, within.then(to_working), tolerance).into();
+        keep = if value >= threshold { union(keep, rings, FillRule::NonZero)? } else { difference(keep, rings, FillRule::NonZero)? };
+    }
+    let r = mask.rect();
+    let corners = [(r.left(), r.top()), (r.right(), r.top()), (r.right(), r.bottom()), (r.left(), r.bottom())];
+    let rect: Contour = corners.iter().map(|&(x, y)| to_working.apply((x as f64, y as f64))).collect();
+    intersect(keep, Paths::<P>::from(vec![rect]), FillRule::NonZero)
+}
+
+/// `path` cut down to `region`, its subpaths all taken as closed.
+pub fn clip<P: PointScaler>(path: &Path, region: &Paths<P>, tolerance: f32) -> Result<Path, ClipperError> {
+    let mut rings: Vec<Contour> = Vec::new();
+    for event in path.iter().flattened(tolerance) {
+        match event {
+            lyon::path::Event::Begin { at } => rings.push(vec![(at.x as f64, at.y as f64)]),
+            lyon::path::Event::Line { to, .. } => rings.last_mut().into_iter().for_each(|r| r.push((to.x as f64, to.y as f64))),
+            _ => {}
+        }
+    }
+    rings.retain(|r| r.len() >= 3);
+    let kept = intersect(Paths::<P>::from(rings), region.clone(), FillRule::NonZero)?;
+    let mut builder = Path::builder();
+    for ring in kept.iter() {
+        let mut points = ring.iter().map(|p| lyon::math::point(p.x() as f32, p.y() as f32));
+        let Some(first) = points.next() else { continue };
+        builder.begin(first);
+        for p in points {
+            builder.line_to(p);
+        }
+        builder.close();
+    }
+    Ok(builder.build())
+}
+
+type Painted<'a> = (&'a usvg::tiny_skia_path::Path, Affine, usvg::Color, f64);
+
+/// Filled shapes of a mask with a flat color, with the transform and
+/// opacity the groups they sit in give them.
+fn walk<'a>(group: &'a usvg::Group, within: Affine, opacity: f64, out: &mut Vec<Painted<'a>>) {
+    let within = Affine::of_usvg(group.transform()).then(within);
+    let opacity = opacity * group.opacity().get() as f64;
+    for node in group.children() {
+        match node {
+            usvg::Node::Path(path) => {
+                let Some(fill) = path.fill() else { continue };
+                let color = match fill.paint() {
+                    usvg::Paint::Color(c) => *c,
+                    // gradients and patterns by nothing better than their look as a whole
+                    paint => crate::paint::dominant(paint)
+                        .and_then(|hex| u32::from_str_radix(hex.trim_start_matches('#'), 16).ok())
+                        .map_or(usvg::Color::black(), |v| usvg::Color::new_rgb((v >> 16) as u8, (v >> 8) as u8, v as u8)),
+                };
+                out.push((path.data(), within, color, opacity * fill.opacity().get() as f64));
+            }
+            usvg::Node::Group(group) => walk(group, within, opacity, out),
+            _ => {}
+        }
+    }
+}
+
+/// The rectangle a clip path is when it is nothing more, in the clipped
+/// group's units: what a nested `<svg>` or a `<symbol>` becomes, its
+/// viewport, so content spilling out of it is cut off.
+pub fn viewport(clip: &usvg::ClipPath) -> Option<Contour> {
+    let [usvg::Node::Path(path)] = clip.root().children() else { return None };
+    if clip.clip_path().is_some() || !clip.transform().is_identity() || !path.abs_transform().is_identity() {
+        return None;
+    }
+    let b = path.data().bounds();
+    let corner = |x: f32, y: f32| (x == b.left() || x == b.right()) && (y == b.top() || y == b.bottom());
+    if path.data().len() > 6 || !path.data().points().iter().all(|p| corner(p.x, p.y)) {
+        return None;
+    }
+    let corners = [(b.left(), b.top()), (b.right(), b.top()), (b.right(), b.bottom()), (b.left(), b.bottom())];
+    Some(corners.iter().map(|&(x, y)| (x as f64, y as f64)).collect())
+}
@@ -0,0 +1,99 @@
+use std::fmt::Write as _;
+
+use clipper2::*;
+
+use crate::FlatShape;
+
+/// What the rows and columns of the matrix are.
+#[derive(Clone, Copy, PartialEq)]
+pub enum By {
+    Element,
+    Layer,
+}
+
+pub fn parse_by(spec: &str) -> Result<By, String> {
+    match spec {
+        "element" => Ok(By::Element),
+        "layer" => Ok(By::Layer),
+        _ => Err(format!("unknown grouping {spec:?}, expected element or layer")),
+    }
+}
+
+/// Area every pair of items covers in common, shapes taken as drawn; the
+/// diagonal holds each item's own area.
+pub struct Matrix {
+    pub names: Vec<String>,
+    pub areas: Vec<Vec<f64>>,
+}
+
+/// The matrix for every element or layer in drawing order, or for those
+/// `only` names, in that order.
+pub fn matrix<P: PointScaler>(shapes: &[FlatShape], by: By, only: Option<&[String]>) -> Result<Matrix, ClipperError> {
+    let mut names: Vec<String> = Vec::new();
+    let mut cover: Vec<Paths<P>> = Vec::new();
+    for shape in shapes {
+        let name = match by {
+            By::Element => shape.id.clone(),
+            By::Layer => shape.layer.clone().unwrap_or_else(|| "root".into()),
+        };
+        let own: Paths<P> = shape.contours.clone().into();
+        match names.iter().position(|n| *n == name) {
+            Some(i) => cover[i] = union(cover[i].clone(), own, FillRule::NonZero)?,
+            None => {
+                names.push(name);
+                cover.push(union(own, Paths::new(vec![]), FillRule::NonZero)?);
+            }
+        }
+    }
+    if let Some(only) = only {
+        let picked: Vec<usize> = only.iter().filter_map(|n| names.iter().position(|m| m == n)).collect();
+        names = picked.iter().map(|&i| names[i].clone()).collect();
+        cover = picked.iter().map(|&i| cover[i].clone()).collect();
+    }
+
+    let n = names.len();
+    let mut areas = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        areas[i][i] = cover[i].signed_area().abs();
+        for j in i + 1..n {
+            let common = intersect(cover[i].clone(), cover[j].clone(), FillRule::NonZero)?;
+            areas[i][j] = common.signed_area().abs();
+            areas[j][i] = areas[i][j];
+        }
+    }
+    Ok(Matrix { names, areas })
+}
+
+impl Matrix {
+    /// Areas scaled by `factor`, to go back to drawing units.
+    pub fn scaled(mut self, factor: f64) -> Matrix {
+        self.areas.iter_mut().flatten().for_each(|a| *a *= factor);
+        self
+    }
+
+    pub fn csv(&self) -> String {
+        let mut out = String::new();
+        for name in &self.names {
+            out.push(',');
+            out.push_str(&quote(name));
+        }
+        out.push('\n');
+        for (name, row) in self.names.iter().zip(&self.areas) {
+            out.push_str(&quote(name));
+            for area in row {
+                let _ = write!(out, ",{area:.4}");
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::json!({ "names": self.names, "areas": self.areas })
+    }
+}
+
+// names with commas or quotes quoted as CSV wants
+fn quote(name: &str) -> String {
+    if name.contains([',', '"', '\n']) { format!("\"{}\"", name.replace('"', "\"\"")) } else { name.to_string() }
+}
@@ -0,0 +1,41 @@
+/// A file name from `template` with every `{name}` in it replaced by what
+/// `value` gives for it; names it doesn't know are an error, so a typo
+/// doesn't quietly end up in every file name.
+pub fn expand(template: &str, value: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let close = rest[open..].find('}').ok_or_else(|| format!("unclosed {{ in {template:?}"))? + open;
+        let name = &rest[open + 1..close];
+        out.push_str(&value(name).ok_or_else(|| format!("unknown name {{{name}}} in {template:?}"))?);
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(name: &str) -> Option<String> {
+        match name {
+            "stem" => Some("plate".into()),
+            "part" => Some("3".into()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn names_are_replaced_and_the_rest_kept() {
+        assert_eq!(expand("{stem}-part{part}.svg", value).unwrap(), "plate-part3.svg");
+        assert_eq!(expand("fixed.svg", value).unwrap(), "fixed.svg");
+    }
+
+    #[test]
+    fn unknown_and_unclosed_names_are_errors() {
+        assert_eq!(expand("{stme}.svg", value).unwrap_err(), "unknown name {stme} in \"{stme}.svg\"");
+        assert_eq!(expand("{stem.svg", value).unwrap_err(), "unclosed { in \"{stem.svg\"");
+    }
+}
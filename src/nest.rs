@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+
+use clipper2::*;
+
+use crate::Contour;
+use crate::attrs::parse_length;
+use crate::transform::Affine;
+
+// room between sheets as laid out in the output, in sheet widths
+const SHEET_GAP: f64 = 0.05;
+
+/// `--nest "600x400 spacing=3 margin=5"`: every part packed onto sheets of
+/// that size, at least `spacing` apart and `margin` in from the edges.
+/// Parts go in shelves, tallest first, left to right, with a new sheet
+/// when one is full; sheets are laid out side by side. With
+/// `rotations=0,90` parts may be turned to lie flatter; a part's own
+/// `data-rotations` narrows that down, as wood grain or fabric direction
+/// does. With `common-line` rectangular parts go first and butt up against
+/// each other with no spacing, guillotine fashion, so touching edges are
+/// cut once.
+#[derive(Clone, Copy)]
+pub struct Nest {
+    pub sheet: (f64, f64),
+    pub spacing: f64,
+    pub margin: f64,
+    pub rotations: Turns,
+    pub common_line: bool,
+}
+
+/// Quarter turns a part may be given, bit `k` for `k` × 90°.
+pub type Turns = u8;
+
+/// Where a part goes: its sheet and how it is turned and moved there.
+#[derive(Clone, Copy)]
+pub struct Placement {
+    pub sheet: usize,
+    pub transform: Affine,
+}
+
+/// Allowed rotations such as `0,180`, in quarter turns.
+pub fn parse_rotations(spec: &str) -> Result<Turns, String> {
+    let mut turns = 0;
+    for item in spec.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+        turns |= match item {
+            "0" | "360" => 1,
+            "90" | "-270" => 2,
+            "180" | "-180" => 4,
+            "270" | "-90" => 8,
+            _ => return Err(format!("rotations are quarter turns, 0, 90, 180 or 270; got {item:?}")),
+        };
+    }
+    if turns == 0 {
+        return Err("no rotations given".into());
+    }
+    Ok(turns)
+}
+
+pub fn parse(spec: &str) -> Result<Nest, String> {
+    let mut words = spec.split_whitespace();
+    let size = words.next().ok_or("nesting needs a sheet size, like 600x400")?;
+    let sheet = crate::transform::parse_size(size)?;
+    let mut keys = HashMap::new();
+    let mut rotations = 1;
+    let mut common_line = false;
+    for word in words {
+        if word == "common-line" {
+            common_line = true;
+            continue;
+        }
+        let (key, value) = word.split_once('=').ok_or_else(|| format!("expected KEY=VALUE, got {word:?}"))?;
+        match key {
+            "spacing" | "margin" => {
+                keys.insert(key, parse_length(value)?);
+            }
+            "rotations" => rotations = parse_rotations(value)?,
+            _ => return Err(format!("unknown nesting setting {key:?}")),
+        }
+    }
+    Ok(Nest {
+        sheet,
+        spacing: keys.get("spacing").copied().unwrap_or(2.0),
+        margin: keys.get("margin").copied().unwrap_or(0.0),
+        rotations,
+        common_line,
+    })
+}
+
+impl Nest {
+    /// The same nesting with every length multiplied by `factor`.
+    pub fn scaled(self, factor: f64) -> Nest {
+        Nest { sheet: (self.sheet.0 * factor, self.sheet.1 * factor), spacing: self.spacing * factor, margin: self.margin * factor, ..self }
+    }
+
+    /// Min x, min y, max x and max y of sheet `n`, the first one's corner
+    /// at `origin`.
+    pub fn sheet_bounds(&self, n: usize, origin: (f64, f64)) -> (f64, f64, f64, f64) {
+        let x = origin.0 + n as f64 * self.sheet.0 * (1.0 + SHEET_GAP);
+        (x, origin.1, x + self.sheet.0, origin.1 + self.sheet.1)
+    }
+
+    /// Whether a part with bounds `b` fits on a sheet turned one of the
+    /// ways `turns` allows.
+    pub fn fits(&self, b: (f64, f64, f64, f64), turns: Turns) -> bool {
+        let room = (self.sheet.0 - 2.0 * self.margin, self.sheet.1 - 2.0 * self.margin);
+        (0..4)
+            .filter(|k| turns & (1 << k) != 0)
+            .map(|k| Affine::rotate(90.0 * k as f64).apply_rect(b))
+            .any(|r| r.2 <= room.0 && r.3 <= room.1)
+    }
+
+    /// Where each part with bounds `boxes` (min x, min y, width, height)
+    /// goes, turned by one of its allowed `turns`, and how many sheets that
+    /// takes. A part too big for a sheet gets one to itself, in its corner.
+    /// `rectangular` says which parts fill their bounds, for common lines.
+    pub fn place(
+        &self,
+        boxes: &[(f64, f64, f64, f64)],
+        turns: &[Turns],
+        rectangular: &[bool],
+        origin: (f64, f64),
+    ) -> (Vec<Placement>, usize) {
+        let room = (self.sheet.0 - 2.0 * self.margin, self.sheet.1 - 2.0 * self.margin);
+        // every part turned the flattest way that fits, or as it is
+        let turned: Vec<(Affine, (f64, f64, f64, f64))> = boxes
+            .iter()
+            .zip(turns)
+            .map(|(&b, &allowed)| {
+                let options: Vec<(Affine, (f64, f64, f64, f64))> = (0..4)
+                    .filter(|k| allowed & (1 << k) != 0)
+                    .map(|k| {
+                        let t = Affine::rotate(90.0 * k as f64);
+                        (t, t.apply_rect(b))
+                    })
+                    .collect();
+                let fits = |r: &(f64, f64, f64, f64)| r.2 <= room.0 && r.3 <= room.1;
+                options
+                    .iter()
+                    .filter(|(_, r)| fits(r))
+                    .min_by(|a, b| a.1.3.total_cmp(&b.1.3))
+                    .or(options.first())
+                    .copied()
+                    .unwrap_or((Affine::IDENTITY, b))
+            })
+            .collect();
+        let tight = |i: usize| self.common_line && rectangular.get(i).copied().unwrap_or(false);
+        let mut order: Vec<usize> = (0..boxes.len()).collect();
+        order.sort_by(|&a, &b| tight(b).cmp(&tight(a)).then(turned[b].1.3.total_cmp(&turned[a].1.3)));
+        let mut placements = vec![Placement { sheet: 0, transform: Affine::IDENTITY }; boxes.len()];
+        // the sheet being filled, the shelf's top, the next free x and the
+        // shelf's height, all within the margins
+        let (mut sheet, mut top, mut x, mut shelf) = (0, 0.0, 0.0, 0.0);
+        let mut used = false;
+        // whether the last part placed, and every part of the shelf, were
+        // rectangles butting up to each other, and whether the shelf sits
+        // right on the one before
+        let (mut last_tight, mut shelf_tight, mut on_shelf) = (false, true, false);
+        for i in order {
+            let (turn, (bx, by, w, h)) = turned[i];
+            let to = |sheet: usize, x: f64, y: f64| {
+                let corner = self.sheet_bounds(sheet, origin);
+                turn.then(Affine::translate(corner.0 + self.margin + x - bx, corner.1 + self.margin + y - by))
+            };
+            if w > room.0 || h > room.1 {
+                if used {
+                    sheet += 1;
+                }
+                placements[i] = Placement { sheet, transform: to(sheet, 0.0, 0.0) };
+                (sheet, top, x, shelf, used) = (sheet + 1, 0.0, 0.0, 0.0, false);
+                (last_tight, shelf_tight, on_shelf) = (false, true, false);
+                continue;
+            }
+            let gap = if tight(i) && last_tight { 0.0 } else { self.spacing };
+            // a part with spacing of its own can't sit right on the shelf before
+            if used && (x + gap + w > room.0 || (on_shelf && !tight(i))) {
+                let gap = if tight(i) && shelf_tight { 0.0 } else { self.spacing };
+                (top, x, shelf, on_shelf, shelf_tight) = (top + shelf + gap, 0.0, 0.0, gap == 0.0, true);
+            } else if used {
+                x += gap;
+            }
+            if used && top + h > room.1 {
+                (sheet, top, x, shelf, on_shelf, shelf_tight) = (sheet + 1, 0.0, 0.0, 0.0, false, true);
+            }
+            placements[i] = Placement { sheet, transform: to(sheet, x, top) };
+            x += w;
+            shelf = f64::max(shelf, h);
+            used = true;
+            last_tight = tight(i);
+            shelf_tight &= tight(i);
+        }
+        (placements, if used { sheet + 1 } else { sheet })
+    }
+
+    /// What is left of each of `sheets` once `parts` are cut from it with
+    /// `spacing` around them, less slivers too narrow to use again.
+    pub fn offcuts<P: PointScaler>(&self, sheets: usize, origin: (f64, f64), parts: &Paths<P>) -> Result<Vec<Paths<P>>, ClipperError> {
+        let taken = parts.inflate(self.spacing, JoinType::Miter, EndType::Polygon, 2.0);
+        let narrow = self.spacing.max(self.margin);
+        (0..sheets)
+            .map(|n| {
+                let (x0, y0, x1, y1) = self.sheet_bounds(n, origin);
+                let sheet: Contour = vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+                let left = difference(Paths::<P>::from(vec![sheet]), taken.clone(), FillRule::NonZero)?;
+                Ok(left.inflate(-narrow, JoinType::Miter, EndType::Polygon, 2.0).inflate(narrow, JoinType::Miter, EndType::Polygon, 2.0))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // where a part with bounds `b` ends up, as min x, min y, width, height
+    fn placed(b: (f64, f64, f64, f64), p: &Placement) -> (f64, f64, f64, f64) {
+        p.transform.apply_rect(b)
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn rotations_are_quarter_turns() {
+        assert_eq!(parse_rotations("0,90"), Ok(3));
+        assert_eq!(parse_rotations("180, -90"), Ok(12));
+        assert_eq!(parse_rotations("360,270"), Ok(9));
+        assert!(parse_rotations("45").is_err());
+        assert!(parse_rotations(" , ").is_err());
+    }
+
+    #[test]
+    fn nestings_space_parts_unless_told() {
+        let nest = parse("600x400").unwrap();
+        assert_eq!((nest.sheet, nest.spacing, nest.margin, nest.rotations, nest.common_line), ((600.0, 400.0), 2.0, 0.0, 1, false));
+        let nest = parse("600x400 spacing=3 margin=5 rotations=0,180 common-line").unwrap();
+        assert_eq!((nest.spacing, nest.margin, nest.rotations, nest.common_line), (3.0, 5.0, 5, true));
+        assert!(parse("").is_err());
+        assert!(parse("600x400 spacing").is_err());
+        assert_eq!(parse("600x400 gap=3").err().unwrap(), "unknown nesting setting \"gap\"");
+    }
+
+    #[test]
+    fn parts_stay_inside_the_margins_and_apart() {
+        let nest = parse("100x100 spacing=3 margin=5").unwrap();
+        let boxes = vec![(10.0, 10.0, 40.0, 30.0), (-5.0, 0.0, 20.0, 20.0), (0.0, 0.0, 30.0, 25.0), (50.0, 50.0, 45.0, 10.0)];
+        let (placements, sheets) = nest.place(&boxes, &[1; 4], &[false; 4], (0.0, 0.0));
+        assert_eq!(sheets, 1);
+        let rects: Vec<_> = boxes.iter().zip(&placements).map(|(&b, p)| placed(b, p)).collect();
+        for (i, &(x, y, w, h)) in rects.iter().enumerate() {
+            assert_eq!(placements[i].sheet, 0);
+            assert!(x >= 5.0 - 1e-9 && y >= 5.0 - 1e-9 && x + w <= 95.0 + 1e-9 && y + h <= 95.0 + 1e-9, "{i}: {:?}", rects[i]);
+            for &(ox, oy, ow, oh) in &rects[i + 1..] {
+                let apart = x + w + 3.0 <= ox + 1e-9 || ox + ow + 3.0 <= x + 1e-9 || y + h + 3.0 <= oy + 1e-9 || oy + oh + 3.0 <= y + 1e-9;
+                assert!(apart, "{:?} and {:?}", (x, y, w, h), (ox, oy, ow, oh));
+            }
+        }
+    }
+
+    #[test]
+    fn parts_that_fill_a_sheet_go_on_to_the_next() {
+        let nest = parse("100x100").unwrap();
+        let boxes = vec![(0.0, 0.0, 60.0, 60.0); 3];
+        let (placements, sheets) = nest.place(&boxes, &[1; 3], &[false; 3], (0.0, 0.0));
+        assert_eq!(sheets, 3);
+        for (n, p) in placements.iter().enumerate() {
+            assert_eq!(p.sheet, n);
+            let (x, y, _, _) = placed(boxes[n], p);
+            let (x0, y0, _, _) = nest.sheet_bounds(n, (0.0, 0.0));
+            assert!(close(x, x0) && close(y, y0));
+        }
+    }
+
+    #[test]
+    fn common_lines_butt_rectangles_together() {
+        let nest = parse("100x100 spacing=5 common-line").unwrap();
+        let boxes = vec![(0.0, 0.0, 30.0, 20.0); 2];
+        let (placements, _) = nest.place(&boxes, &[1; 2], &[true; 2], (0.0, 0.0));
+        let (a, b) = (placed(boxes[0], &placements[0]), placed(boxes[1], &placements[1]));
+        assert!(close(a.0 + a.2, b.0) || close(b.0 + b.2, a.0));
+        // without common lines they keep their spacing
+        let nest = parse("100x100 spacing=5").unwrap();
+        let (placements, _) = nest.place(&boxes, &[1; 2], &[true; 2], (0.0, 0.0));
+        let (a, b) = (placed(boxes[0], &placements[0]), placed(boxes[1], &placements[1]));
+        assert!(close((b.0 - a.0).abs(), 35.0));
+    }
+
+    #[test]
+    fn a_part_too_big_for_a_sheet_gets_one_to_itself() {
+        let nest = parse("100x100 margin=5").unwrap();
+        let boxes = vec![(0.0, 0.0, 20.0, 20.0), (0.0, 0.0, 200.0, 50.0), (0.0, 0.0, 20.0, 10.0)];
+        assert!(!nest.fits(boxes[1], 1));
+        let (placements, sheets) = nest.place(&boxes, &[1; 3], &[false; 3], (0.0, 0.0));
+        assert_eq!(sheets, 2);
+        // tallest first: the oversized part, then both small ones together
+        assert_eq!((placements[1].sheet, placements[0].sheet, placements[2].sheet), (0, 1, 1));
+        let (x, y, _, _) = placed(boxes[1], &placements[1]);
+        assert!(close(x, 5.0) && close(y, 5.0));
+    }
+
+    #[test]
+    fn parts_are_turned_to_fit_when_allowed() {
+        let nest = parse("50x100").unwrap();
+        let b = (0.0, 0.0, 80.0, 20.0);
+        assert!(!nest.fits(b, 1));
+        assert!(nest.fits(b, 3));
+        let (placements, sheets) = nest.place(&[b], &[3], &[false], (0.0, 0.0));
+        assert_eq!(sheets, 1);
+        let (x, y, w, h) = placed(b, &placements[0]);
+        assert!(close(x, 0.0) && close(y, 0.0) && close(w, 20.0) && close(h, 80.0));
+    }
+
+    #[test]
+    fn offcuts_are_the_sheet_less_the_parts_and_their_spacing() {
+        let nest = parse("100x100 spacing=2").unwrap();
+        let part: Paths<Centi> = vec![vec![(0.0, 0.0), (50.0, 0.0), (50.0, 100.0), (0.0, 100.0)]].into();
+        let left = nest.offcuts(2, (0.0, 0.0), &part).unwrap();
+        assert_eq!(left.len(), 2);
+        let area = |p: &Paths<Centi>| p.iter().map(|r| r.signed_area()).sum::<f64>().abs();
+        assert!((area(&left[0]) - 48.0 * 100.0).abs() < 1.0, "{}", area(&left[0]));
+        assert!((area(&left[1]) - 100.0 * 100.0).abs() < 1.0);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::transform::Affine;
+
+// coordinates are left alone while they stay within this range and the
+// drawing is at least one unit across
+const FAR: f64 = 1e5;
+const SMALL: f64 = 1.0;
+// size a drawing is brought to otherwise, give or take a power of ten
+const WORKING: f64 = 1000.0;
+
+/// The map from drawing coordinates into the range everything is combined
+/// in, and back. Drawings in micrometres or metres come out of CAD and GIS
+/// tools with numbers that lose precision in lyon's f32 points or overflow
+/// clipper's grid once scaled.
+#[derive(Clone, Copy)]
+pub struct Normalization {
+    /// Working units per drawing unit.
+    pub scale: f64,
+    pub forward: Affine,
+    pub inverse: Affine,
+}
+
+impl Normalization {
+    pub const IDENTITY: Normalization = Normalization { scale: 1.0, forward: Affine::IDENTITY, inverse: Affine::IDENTITY };
+
+    /// Move the drawing to the origin and scale it by a power of ten when its
+    /// coordinates are out of range; left as is otherwise.
+    pub fn of(bounds: Option<(f64, f64, f64, f64)>) -> Normalization {
+        let Some((x0, y0, x1, y1)) = bounds else {
+            return Self::IDENTITY;
+        };
+        let reach = [x0, y0, x1, y1].iter().fold(0.0f64, |m, v| m.max(v.abs()));
+        let extent = (x1 - x0).max(y1 - y0);
+        if reach <= FAR && (extent >= SMALL || extent <= 0.0) {
+            return Self::IDENTITY;
+        }
+        let scale = if extent > 0.0 { 10f64.powi((WORKING / extent).log10().round() as i32) } else { 1.0 };
+        let forward = Affine::translate(-x0, -y0).then(Affine::scale(scale, scale));
+        let inverse = Affine::scale(1.0 / scale, 1.0 / scale).then(Affine::translate(x0, y0));
+        Normalization { scale, forward, inverse }
+    }
+
+    /// The largest coordinate of `bounds` once normalized.
+    pub fn reach(&self, bounds: Option<(f64, f64, f64, f64)>) -> f64 {
+        bounds.map_or(0.0, |(x0, y0, x1, y1)| {
+            let (a, b) = self.forward.apply((x0, y0));
+            let (c, d) = self.forward.apply((x1, y1));
+            [a, b, c, d].iter().fold(0.0, |m, v| m.max(v.abs()))
+        })
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.scale == 1.0 && self.forward.e == 0.0 && self.forward.f == 0.0
+    }
+}
+
+/// Bounds `(min x, min y, max x, max y)` of the path data the combiner
+/// reads, which is every path's data where its transform puts it.
+pub fn bounds(tree: &usvg::Tree) -> Option<(f64, f64, f64, f64)> {
+    let mut out: Option<(f64, f64, f64, f64)> = None;
+    let mut stack: Vec<&usvg::Node> = tree.root().children().iter().collect();
+    while let Some(node) = stack.pop() {
+        match node {
+            usvg::Node::Path(path) => {
+                let b = path.data().bounds();
+                let (x, y, w, h) = crate::transform::Affine::of_usvg(path.abs_transform()).apply_rect((
+                    b.left() as f64,
+                    b.top() as f64,
+                    b.width() as f64,
+                    b.height() as f64,
+                ));
+                let (l, t, r, btm) = (x, y, x + w, y + h);
+                out = Some(match out {
+                    Some((x0, y0, x1, y1)) => (x0.min(l), y0.min(t), x1.max(r), y1.max(btm)),
+                    None => (l, t, r, btm),
+                });
+            }
+            usvg::Node::Group(group) => stack.extend(group.children()),
+            _ => {}
+        }
+    }
+    out
+}
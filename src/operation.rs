@@ -0,0 +1,94 @@
+use clipper2::*;
+
+use crate::Contour;
+
+/// What the machine does with a group, from the config's `[operation]`s.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Operation {
+    /// Cut through along closed outlines, the default.
+    Cut,
+    /// Marked along open lines, for folding.
+    Score,
+    /// Areas filled with hatching.
+    Engrave,
+    /// Sewn along, inside a seam allowance: drawn, not cut.
+    Stitch,
+    /// Marks on a sewing pattern piece where it meets another.
+    Notch,
+    /// The arrow a sewing pattern piece is laid along the fabric's grain by.
+    Grainline,
+}
+
+pub fn parse(spec: &str) -> Result<Operation, String> {
+    match spec {
+        "cut" => Ok(Operation::Cut),
+        "score" | "fold" => Ok(Operation::Score),
+        "engrave" | "hatch" => Ok(Operation::Engrave),
+        "stitch" => Ok(Operation::Stitch),
+        "notch" => Ok(Operation::Notch),
+        "grainline" | "grain" => Ok(Operation::Grainline),
+        _ => Err(format!("unknown operation {spec:?}, expected cut, score, engrave, stitch, notch or grainline")),
+    }
+}
+
+impl Operation {
+    /// The layer the operation is written to.
+    pub fn name(self) -> &'static str {
+        match self {
+            Operation::Cut => "cut",
+            Operation::Score => "score",
+            Operation::Engrave => "engrave",
+            Operation::Stitch => "stitch",
+            Operation::Notch => "notch",
+            Operation::Grainline => "grainline",
+        }
+    }
+}
+
+/// Rings as polylines going all the way round, their start repeated at
+/// the end.
+pub fn outlines<P: PointScaler>(rings: &Paths<P>) -> Vec<Contour> {
+    rings
+        .iter()
+        .map(|ring| {
+            let mut line: Contour = ring.iter().map(|p| (p.x(), p.y())).collect();
+            line.extend(line.first().copied());
+            line
+        })
+        .collect()
+}
+
+/// Straight lines `spacing` apart at `angle` degrees across the inside of
+/// `rings`, every other one reversed so a machine goes back and forth.
+pub fn hatch<P: PointScaler>(rings: &Paths<P>, spacing: f64, angle: f64) -> Vec<Contour> {
+    if rings.is_empty() || spacing <= 0.0 {
+        return Vec::new();
+    }
+    let (sin, cos) = angle.to_radians().sin_cos();
+    // along the lines and across them
+    let along = |p: (f64, f64)| p.0 * cos + p.1 * sin;
+    let across = |p: (f64, f64)| -p.0 * sin + p.1 * cos;
+    let (mut a0, mut a1, mut c0, mut c1) = (f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::NEG_INFINITY);
+    for p in rings.iter().flat_map(|r| r.iter()) {
+        let p = (p.x(), p.y());
+        (a0, a1) = (a0.min(along(p)), a1.max(along(p)));
+        (c0, c1) = (c0.min(across(p)), c1.max(across(p)));
+    }
+    let (a0, a1) = (a0 - spacing, a1 + spacing);
+    let point = |a: f64, c: f64| (a * cos - c * sin, a * sin + c * cos);
+
+    let mut lines = Vec::new();
+    let mut c = c0 + spacing / 2.0;
+    let mut forward = true;
+    while c < c1 {
+        let mut pieces = crate::clip::clip_polyline(&[point(a0, c), point(a1, c)], rings, true);
+        if !forward {
+            pieces.reverse();
+            pieces.iter_mut().for_each(|piece| piece.reverse());
+        }
+        lines.extend(pieces);
+        forward = !forward;
+        c += spacing;
+    }
+    lines
+}
@@ -0,0 +1,600 @@
+/// Command line switches. Anything not given keeps the built-in defaults.
+pub struct Options {
+    /// SVG files to combine; several are laid out side by side first.
+    pub inputs: Vec<String>,
+    /// Emit outlines as cut layers with edges shared by touching parts cut
+    /// once. Touching shapes of one group are one part by then, so only
+    /// parts of different groups share edges.
+    pub shared_edges: bool,
+    /// Gap carved around every shape that sets no `data-offset`, out of the
+    /// shapes drawn before it.
+    pub gap: f64,
+    /// Start and end offset for outlining open paths that set no `data-taper`.
+    pub taper: Option<(f64, f64)>,
+    /// Width open paths that set no `data-buffer` are outlined with.
+    pub buffer: Option<f64>,
+    /// Closed shapes grown by this as sewing pattern pieces, unless they
+    /// set `data-seam-allowance`, their outlines kept as stitch lines.
+    pub seam_allowance: Option<f64>,
+    /// Shapes kept as notch and grainline marks of the pieces they lie on,
+    /// picked like `--select` picks, by layer, class or color.
+    pub notches: Option<crate::select::Selection>,
+    pub grainlines: Option<crate::select::Selection>,
+    /// How buffered open paths end, unless they set `data-cap`.
+    pub end_cap: clipper2::EndType,
+    /// Fillet radius applied to every corner of the result.
+    pub round_corners: Option<f64>,
+    /// Length of the straight cut replacing every convex corner of the result.
+    pub chamfer: Option<f64>,
+    /// How far flattened curves may stray from the drawn ones.
+    pub tolerance: Option<f64>,
+    /// Parts and holes of the result smaller than this area are dropped.
+    pub min_area: Option<f64>,
+    /// Flatten each path with a tolerance scaled to its size.
+    pub adaptive: Option<Adaptive>,
+    /// Flatten every curve with a tolerance of its own, finer where it bends
+    /// tightly, and thin out straight runs of lines, never straying further
+    /// than the tolerance.
+    pub curvature: bool,
+    /// Write parts of the result that still follow a source curve whole as
+    /// that curve rather than as flattened lines.
+    pub keep_curves: bool,
+    /// Mirror/rotate/scale steps applied to the result, e.g. `"mirror-x, rotate 90"`.
+    pub transform: Option<crate::transform::Affine>,
+    /// Target canvas the result is aligned (and optionally fitted) on.
+    pub placement: Option<crate::transform::Placement>,
+    /// Write every part as its own path carrying `data-source` ids.
+    pub separate: bool,
+    /// Number every part with an outlined label placed inside it.
+    pub labels: bool,
+    /// Height of part labels.
+    pub label_size: f64,
+    /// Add a weed box and weeding lines through the waste.
+    pub weed: bool,
+    /// Distance from the result to the weed box.
+    pub weed_margin: f64,
+    /// Extra weeding lines on a grid with this pitch.
+    pub weed_spacing: Option<f64>,
+    /// Directory getting one SVG file per part.
+    pub split_parts: Option<String>,
+    /// How part files are named, `--name-template`, with `{stem}`,
+    /// `{layer}`, `{label}`, `{index}`, `{date}` and `{ext}` filled in.
+    pub name_template: String,
+    /// Record version, arguments, input hashes and time in a `<metadata>`
+    /// block; off by default so output stays byte for byte reproducible.
+    pub metadata: bool,
+    /// How much gets logged: warnings only with `--quiet`, more with `-v`
+    /// and `-vv`.
+    pub log_level: tracing::level_filters::LevelFilter,
+    /// Log JSON lines (`--log-format json`) instead of text.
+    pub log_json: bool,
+    /// Fail instead of writing a result when there are warnings.
+    pub strict: bool,
+    /// Simplify the result as little as it takes to stay within this
+    /// many vertices.
+    pub target_vertices: Option<usize>,
+    /// Simplify the result as far as it goes without anything moving
+    /// further than this, measured.
+    pub max_deviation: Option<f64>,
+    /// Where every ring of the result starts.
+    pub start_point: Option<crate::start::Start>,
+    /// Which way outlines and holes of the result go round.
+    pub direction: Option<crate::direction::Direction>,
+    /// Fail rather than write a result with more vertices than this.
+    pub max_vertices: Option<usize>,
+    /// Fail rather than write a result with more parts than this.
+    pub max_parts: Option<usize>,
+    /// What to do when nothing is left to write, `--on-empty`.
+    pub on_empty: OnEmpty,
+    /// Show progress as a live panel on stderr.
+    pub tui: bool,
+    /// Indent the output and break long path data into lines.
+    pub pretty: bool,
+    /// What the result is written as.
+    pub format: crate::export::Format,
+    /// Millimetres per drawing unit, for formats with real units.
+    pub unit_mm: f64,
+    /// The layer KiCad output goes on.
+    pub kicad_layer: String,
+    /// Longest stitch of embroidery output, in millimetres.
+    pub stitch_length: f64,
+    /// Write gzip-compressed SVG (`.svgz`).
+    pub compress: bool,
+    /// Files the result is written to, `-o` once for each; output.svg
+    /// (or output.EXT for `--format`) without any.
+    pub outputs: Vec<crate::export::Output>,
+    /// SVG output written as tiles of this size instead, `--tile-output`.
+    pub tile_output: Option<(f64, f64)>,
+    /// Overwrite files named with `-o` that already exist.
+    pub force: bool,
+    /// Move a file named with `-o` that already exists to NAME.bak first.
+    pub backup: bool,
+    /// Scale corrections along x and y for G-code, HPGL and DXF output.
+    pub calibrate: (f64, f64),
+    /// Where GeoJSON output puts the drawing on the earth.
+    pub geo: Option<crate::geo::Reference>,
+    /// How G-code enters and leaves every ring.
+    pub leads: crate::export::Leads,
+    /// Feed rate of G-code output, in millimetres per minute.
+    pub feed: f64,
+    /// Only where shapes as drawn cover each other, to check for double
+    /// coverage.
+    pub overlaps: Option<crate::overlap::Overlap>,
+    /// The uncovered space within the result's bounding box instead of the
+    /// result itself.
+    pub negative: bool,
+    /// Only the outer boundary of the result, holes filled in.
+    pub silhouette: bool,
+    /// Keep only this many of the largest parts of every group, dropping
+    /// debris; 1 leaves just the main silhouette.
+    pub keep_largest: Option<usize>,
+    /// Shapes from the command line combined along with the input.
+    pub add: Vec<crate::primitive::Primitive>,
+    /// Shapes from the command line cut out of the result.
+    pub subtract: Vec<crate::primitive::Primitive>,
+    /// CSV file of `x,y[,diameter]` positions of holes punched out of the
+    /// result.
+    pub punch_holes: Option<String>,
+    /// Diameter of punched holes that don't give their own.
+    pub hole_d: Option<f64>,
+    /// Hole pattern punched into the result's parts.
+    pub perforate: Option<crate::perforate::Pattern>,
+    /// Only perforate parts made from these elements.
+    pub perforate_only: Option<Vec<String>>,
+    /// Facing straight edges of neighbouring parts made to interlock.
+    pub joints: Option<crate::joints::Joints>,
+    /// Box edges, marked by `data-mate` lines, cut into finger joints.
+    pub finger_joints: Option<crate::fingers::Fingers>,
+    /// Border generated around the whole result.
+    pub frame: Option<crate::frame::Frame>,
+    /// The result repeated into a tabbed production panel.
+    pub panelize: Option<crate::panel::Panel>,
+    /// Parts packed onto sheets.
+    pub nest: Option<crate::nest::Nest>,
+    /// Where what is left of the sheets is written.
+    pub offcuts: Option<crate::export::Output>,
+    /// CSV file of `id,qty` copies of parts to nest, over their own
+    /// `data-qty`.
+    pub quantities: Option<String>,
+    /// Text outlined and combined with the result, or put on the engrave
+    /// layer when engraving.
+    pub stamp: Option<crate::stamp::Stamp>,
+    /// Trace embedded PNG images into shapes, pixels darker than this (0
+    /// to 1) being ink; images are dropped otherwise.
+    pub trace: Option<f64>,
+    /// Merge shapes coming within this distance of each other before
+    /// combining, closing the hairline between them.
+    pub join_gap: Option<f64>,
+    /// Combine paths as they are read, carving them into the result in
+    /// chunks of about this many bytes of flattened shapes, `--chunk-size`.
+    /// Only those copies are bounded: the parsed document and the result
+    /// so far are whole in memory all along.
+    pub stream: Option<usize>,
+    /// Directory keeping combined results between runs.
+    pub cache: Option<String>,
+    /// How much of it is kept when a run is done.
+    pub cache_limits: crate::cache::Limits,
+    /// Run once per value of a setting and compare the results instead.
+    pub sweep: Option<crate::sweep::Sweep>,
+    /// Where to write the matrix of areas shapes have in common, as CSV or,
+    /// for `.json` files, JSON.
+    pub intersections: Option<String>,
+    /// Rows and columns of that matrix, elements or layers.
+    pub intersections_by: crate::matrix::By,
+    /// Only these elements or layers in the matrix.
+    pub intersections_of: Option<Vec<String>>,
+    /// Where to write the JSON report.
+    pub report: Option<String>,
+    /// Order shapes are combined in, and so which win their gaps.
+    pub order: Option<crate::order::Order>,
+    /// Shapes filled with a `<pattern>` made of its tiles' shapes, clipped
+    /// to them, rather than filled whole.
+    pub pattern_fill: bool,
+    /// Masked content cut down to where its mask is at least this bright.
+    pub mask_threshold: Option<f64>,
+    /// Shapes fainter than this, their groups' opacity included, left out.
+    pub min_opacity: Option<f64>,
+    /// The only elements combined, `--select`.
+    pub select: Option<crate::select::Selection>,
+    /// The result written into the source where the selected elements
+    /// were, `--in-place`.
+    pub in_place: bool,
+    /// What isn't combined written along with the result, `--passthrough`.
+    pub passthrough: bool,
+    /// Linked files loaded as part of the drawing, `--allow-external`.
+    pub external: Option<crate::external::Sandbox>,
+    /// What content under a filter effect becomes, `--filters`.
+    pub filters: crate::filter::Policy,
+    /// What gradient and pattern fills become, `--paint`.
+    pub paint: crate::paint::Strategy,
+    /// Settings from `--config FILE`.
+    pub config: crate::config::Config,
+    /// The config's `[material]` picked with `--material NAME`.
+    pub material: Option<crate::config::Material>,
+    /// Combine shapes per layer or per fill color instead of all together.
+    pub group_by: Option<crate::groups::GroupBy>,
+    /// Put the result on a "cut" layer and the source artwork, clipped to
+    /// it, on an "engrave" layer.
+    pub engrave: bool,
+    /// Class or layer whose open subpaths are kept as engraving strokes,
+    /// clipped to the result, rather than dropped.
+    pub engrave_lines: Option<String>,
+    /// Write the engrave layer to this file instead of next to the cut layer.
+    pub engrave_file: Option<String>,
+    /// Fill, stroke and stroke width forced on every output path; `--outline`
+    /// turns the fill off and strokes every contour instead.
+    pub style: crate::style::Style,
+    /// Clipper grid steps per SVG unit, or picked from the drawing's size;
+    /// 100 unless given, or picked when coordinates get normalized.
+    pub precision: Option<crate::precision::Precision>,
+    /// Bring drawings with far out or tiny coordinates into a safe range
+    /// while combining; `--no-normalize` turns it off.
+    pub normalize: bool,
+}
+
+/// Tolerance as a fraction of a path's bounding box diagonal, kept within
+/// `min..=max`.
+#[derive(Clone, Copy)]
+pub struct Adaptive {
+    pub factor: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// What happens to a result with no polygons left, from everything
+/// being filtered out, an empty input or shapes cancelling out.
+#[derive(Clone, Copy, PartialEq)]
+pub enum OnEmpty {
+    /// Write a blank document, with a warning.
+    Write,
+    /// Write nothing.
+    Skip,
+    /// Write nothing and exit with `EMPTY_EXIT`.
+    Fail,
+}
+
+impl Options {
+    pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        let mut args = pico_args::Arguments::from_env();
+
+        // the material's feed unless given here
+        let feed: Option<f64> = args.opt_value_from_str("--feed")?;
+        let mut opts = Options {
+            inputs: Vec::new(),
+            shared_edges: args.contains("--shared-edges"),
+            gap: args.opt_value_from_fn("--gap", crate::attrs::parse_length)?.unwrap_or(crate::OFFSET),
+            taper: args.opt_value_from_fn("--taper", |s| crate::attrs::parse_pair(s).ok_or("expected START,END"))?,
+            buffer: args.opt_value_from_fn("--buffer", crate::attrs::parse_length)?,
+            seam_allowance: args.opt_value_from_fn("--seam-allowance", crate::attrs::parse_length)?,
+            notches: args.opt_value_from_fn("--notches", crate::select::parse)?,
+            grainlines: args.opt_value_from_fn("--grainlines", crate::select::parse)?,
+            end_cap: args.opt_value_from_fn("--end-cap", crate::buffer::parse_cap)?.unwrap_or(clipper2::EndType::Round),
+            round_corners: args.opt_value_from_str("--round-corners")?,
+            chamfer: args.opt_value_from_str("--chamfer")?,
+            tolerance: args.opt_value_from_str("--tolerance")?,
+            min_area: args.opt_value_from_str("--min-area")?,
+            adaptive: match args.opt_value_from_str::<_, f64>("--adaptive-tolerance")? {
+                Some(factor) => Some(Adaptive {
+                    factor,
+                    min: args.opt_value_from_str("--min-tolerance")?.unwrap_or(0.01),
+                    max: args.opt_value_from_str("--max-tolerance")?.unwrap_or(1.0),
+                }),
+                None => None,
+            },
+            curvature: args.contains("--curvature"),
+            keep_curves: args.contains("--keep-curves"),
+            transform: args.opt_value_from_fn("--transform", crate::transform::parse)?,
+            placement: None,
+            separate: args.contains("--separate"),
+            labels: args.contains("--labels"),
+            label_size: args.opt_value_from_str("--label-size")?.unwrap_or(6.0),
+            weed: args.contains("--weed"),
+            weed_margin: args.opt_value_from_str("--weed-margin")?.unwrap_or(5.0),
+            weed_spacing: args.opt_value_from_str("--weed-spacing")?,
+            split_parts: args.opt_value_from_str("--split-parts")?,
+            name_template: args.opt_value_from_str("--name-template")?.unwrap_or_else(|| "{label}.{ext}".into()),
+            metadata: args.contains("--metadata"),
+            log_level: if args.contains("--quiet") {
+                tracing::level_filters::LevelFilter::WARN
+            } else if args.contains("-vv") {
+                tracing::level_filters::LevelFilter::TRACE
+            } else if args.contains("-v") {
+                tracing::level_filters::LevelFilter::DEBUG
+            } else {
+                tracing::level_filters::LevelFilter::INFO
+            },
+            log_json: match args.opt_value_from_str::<_, String>("--log-format")?.as_deref() {
+                None | Some("text") => false,
+                Some("json") => true,
+                Some(other) => return Err(format!("unknown log format {other:?}, expected text or json").into()),
+            },
+            strict: args.contains("--strict"),
+            target_vertices: args.opt_value_from_str("--target-vertices")?,
+            max_deviation: args.opt_value_from_fn("--max-deviation", crate::attrs::parse_length)?,
+            start_point: args.opt_value_from_fn("--start-point", crate::start::parse)?,
+            direction: args.opt_value_from_fn("--direction", crate::direction::parse)?,
+            max_vertices: args.opt_value_from_str("--max-output-vertices")?,
+            max_parts: args.opt_value_from_str("--max-parts")?,
+            on_empty: match args.opt_value_from_str::<_, String>("--on-empty")?.as_deref() {
+                None | Some("write") => OnEmpty::Write,
+                Some("skip") => OnEmpty::Skip,
+                Some("fail") => OnEmpty::Fail,
+                Some(other) => return Err(format!("unknown --on-empty {other:?}, expected write, skip or fail").into()),
+            },
+            tui: args.contains("--tui"),
+            pretty: args.contains("--pretty"),
+            compress: args.contains("--compress"),
+            format: args.opt_value_from_fn("--format", crate::export::parse)?.unwrap_or(crate::export::Format::Svg),
+            unit_mm: args.opt_value_from_fn("--units", crate::export::parse_unit)?.unwrap_or(25.4 / 96.0),
+            kicad_layer: args.opt_value_from_str("--kicad-layer")?.unwrap_or_else(|| "F.SilkS".into()),
+            stitch_length: args.opt_value_from_str("--stitch-length")?.unwrap_or(2.5),
+            outputs: args.values_from_fn(["-o", "--output"], crate::export::parse_output)?,
+            tile_output: args.opt_value_from_fn("--tile-output", crate::transform::parse_size)?,
+            feed: feed.unwrap_or(1000.0),
+            leads: crate::export::Leads {
+                lead_in: args.opt_value_from_str("--lead-in")?.unwrap_or(0.0),
+                lead_out: args.opt_value_from_str("--lead-out")?.unwrap_or(0.0),
+                arc: args.contains("--lead-arc"),
+                dwell: args.opt_value_from_str("--pierce-dwell")?.unwrap_or(0.0),
+            },
+            calibrate: args.opt_value_from_fn("--calibrate", crate::export::parse_calibration)?.unwrap_or((1.0, 1.0)),
+            geo: None,
+            force: args.contains("--force"),
+            backup: args.contains("--backup"),
+            overlaps: match args.opt_value_from_str("--overlaps-exactly")? {
+                Some(k) => Some(crate::overlap::Overlap::Exactly(k)),
+                None => args.contains("--overlaps").then_some(crate::overlap::Overlap::AtLeast(2)),
+            },
+            negative: args.contains("--negative"),
+            silhouette: args.contains("--silhouette"),
+            keep_largest: args.opt_value_from_str("--keep-largest")?,
+            add: args.values_from_fn("--add", crate::primitive::parse)?,
+            subtract: args.values_from_fn("--subtract", crate::primitive::parse)?,
+            punch_holes: args.opt_value_from_str("--punch-holes")?,
+            hole_d: args.opt_value_from_fn("--hole-d", crate::attrs::parse_length)?,
+            perforate: args.opt_value_from_fn("--perforate", crate::perforate::parse)?,
+            joints: None,
+            finger_joints: args.opt_value_from_fn("--finger-joints", crate::fingers::parse)?,
+            perforate_only: args.opt_value_from_fn("--perforate-only", |s| {
+                Ok::<_, String>(s.split(',').map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).collect())
+            })?,
+            frame: args.opt_value_from_fn("--frame", crate::frame::parse)?,
+            panelize: args.opt_value_from_fn("--panelize", crate::panel::parse)?,
+            nest: args.opt_value_from_fn("--nest", crate::nest::parse)?,
+            offcuts: args.opt_value_from_fn("--offcuts", crate::export::parse_output)?,
+            quantities: args.opt_value_from_str("--quantities")?,
+            stamp: None,
+            trace: match args.opt_value_from_str("--trace-threshold")? {
+                Some(threshold) => Some(threshold),
+                None => args.contains("--trace").then_some(0.5),
+            },
+            join_gap: args.opt_value_from_str("--join-gap")?,
+            stream: None,
+            sweep: args.opt_value_from_fn("--sweep", crate::sweep::parse)?,
+            cache: args.opt_value_from_str("--cache")?,
+            cache_limits: crate::cache::Limits::default(),
+            intersections: args.opt_value_from_str("--intersections")?,
+            intersections_by: args.opt_value_from_fn("--intersections-by", crate::matrix::parse_by)?.unwrap_or(crate::matrix::By::Element),
+            intersections_of: args.opt_value_from_fn("--intersections-of", |s| {
+                Ok::<_, String>(s.split(',').map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).collect())
+            })?,
+            report: args.opt_value_from_str("--report")?,
+            order: args.opt_value_from_fn("--order", crate::order::parse)?,
+            config: args.opt_value_from_fn("--config", crate::config::read)?.unwrap_or_default(),
+            material: None,
+            pattern_fill: args.contains("--pattern-fill"),
+            mask_threshold: args.opt_value_from_str("--mask-threshold")?,
+            min_opacity: args.opt_value_from_str("--min-opacity")?,
+            select: args.opt_value_from_fn("--select", crate::select::parse)?,
+            in_place: args.contains("--in-place"),
+            passthrough: args.contains("--passthrough"),
+            external: args.contains("--allow-external").then_some(crate::external::Sandbox { root: None }),
+            filters: args.opt_value_from_fn("--filters", crate::filter::parse)?.unwrap_or_default(),
+            paint: args.opt_value_from_fn("--paint", crate::paint::parse)?.unwrap_or_default(),
+            group_by: args.opt_value_from_fn("--group-by", crate::groups::parse)?,
+            engrave: false,
+            engrave_lines: args.opt_value_from_str("--engrave-lines")?,
+            engrave_file: args.opt_value_from_str("--engrave-file")?,
+            style: crate::style::Style {
+                fill: args.opt_value_from_str("--fill")?,
+                stroke: args.opt_value_from_str("--stroke")?,
+                stroke_width: args.opt_value_from_str("--stroke-width")?,
+            },
+            precision: args.opt_value_from_fn("--precision-scale", crate::precision::parse)?,
+            normalize: !args.contains("--no-normalize"),
+        };
+
+        let canvas = args.opt_value_from_fn("--canvas", crate::transform::parse_size)?;
+        let anchor = args.opt_value_from_fn("--align", crate::transform::parse_align)?;
+        let offset = args.opt_value_from_fn("--canvas-offset", |s| crate::attrs::parse_pair(s).ok_or("expected X,Y"))?;
+        let fit = args.contains("--fit");
+        if let Some(text) = args.opt_value_from_str("--stamp")? {
+            opts.stamp = Some(crate::stamp::Stamp {
+                text,
+                size: args.opt_value_from_fn("--stamp-size", crate::attrs::parse_length)?.unwrap_or(10.0),
+                anchor: args.opt_value_from_fn("--stamp-pos", crate::transform::parse_align)?.unwrap_or((0.0, 1.0)),
+                font: args.opt_value_from_str("--stamp-font")?.unwrap_or_else(|| "sans-serif".into()),
+            });
+        }
+        opts.engrave = args.contains("--engrave") || opts.engrave_file.is_some();
+        // outlines only: stroked, unfilled, hairline unless told otherwise;
+        // a cut layer is outlines too unless it was given a fill
+        let outline = args.contains("--outline");
+        if outline || (opts.engrave && opts.style.fill.is_none()) {
+            if outline && opts.style.fill.is_some() {
+                return Err("--outline and --fill can't be combined".into());
+            }
+            opts.style.fill = Some("none".into());
+            opts.style.stroke.get_or_insert_with(|| "black".into());
+            opts.style.stroke_width.get_or_insert(crate::HAIRLINE);
+        }
+        match canvas {
+            Some(canvas) => {
+                opts.placement = Some(crate::transform::Placement {
+                    canvas,
+                    anchor: anchor.unwrap_or((0.5, 0.5)),
+                    offset: offset.unwrap_or((0.0, 0.0)),
+                    fit,
+                })
+            }
+            None if anchor.is_some() || offset.is_some() || fit => {
+                return Err("--align, --canvas-offset and --fit need --canvas".into());
+            }
+            None => {}
+        }
+        // megabytes and days
+        if let Some(size) = args.opt_value_from_str::<_, f64>("--cache-size")? {
+            opts.cache_limits.bytes = (size * 1024.0 * 1024.0) as u64;
+        }
+        if let Some(days) = args.opt_value_from_str::<_, f64>("--cache-days")? {
+            opts.cache_limits.age =
+                std::time::Duration::try_from_secs_f64(days * 24.0 * 3600.0).map_err(|_| "--cache-days needs a number of days")?;
+        }
+        // chunks in megabytes, 64 unless given
+        let budget: Option<f64> = args.opt_value_from_str("--chunk-size")?;
+        if args.contains("--stream") || budget.is_some() {
+            opts.stream = Some((budget.unwrap_or(64.0) * 1024.0 * 1024.0) as usize);
+            if !opts.config.groups.is_empty() || !opts.config.gaps.is_empty() {
+                return Err("--config group settings apply while grouping, which --stream skips".into());
+            }
+            if opts.group_by.is_some()
+                || opts.order.is_some()
+                || opts.engrave
+                || opts.engrave_lines.is_some()
+                || opts.sweep.is_some()
+                || opts.join_gap.is_some()
+                || opts.overlaps.is_some()
+                || opts.intersections.is_some()
+                || opts.seam_allowance.is_some()
+            {
+                return Err("--group-by, --order, --engrave, --engrave-lines, --sweep, --join-gap, --overlaps, --intersections and --seam-allowance need every shape kept, which --stream doesn't".into());
+            }
+        }
+        if opts.offcuts.is_some() && opts.nest.is_none() {
+            return Err("--offcuts are what --nest leaves of the sheets; nest first".into());
+        }
+        // tabs sized by the gap unless given
+        if let Some(spec) = args.opt_value_from_str::<_, String>("--joints")? {
+            opts.joints = Some(crate::joints::parse(&spec, opts.gap).map_err(|e| format!("--joints {spec:?}: {e}"))?);
+        }
+        if let Some(name) = args.opt_value_from_str::<_, String>("--material")? {
+            let material = opts.config.material(&name).ok_or_else(|| format!("no [material.\"{name}\"] in the --config"))?.clone();
+            if let (Some(max), Some(nest)) = (material.sheet, opts.nest)
+                && (nest.sheet.0 > max.0 || nest.sheet.1 > max.1)
+                && (nest.sheet.1 > max.0 || nest.sheet.0 > max.1)
+            {
+                return Err(format!("{name} comes in sheets up to {}x{}, smaller than --nest's", max.0, max.1).into());
+            }
+            if let (None, Some(f)) = (feed, material.feed) {
+                opts.feed = f;
+            }
+            opts.material = Some(material);
+        }
+        if opts.mask_threshold.is_some_and(|t| !(0.0..=1.0).contains(&t)) {
+            return Err("--mask-threshold is a brightness from 0 to 1".into());
+        }
+        if opts.tile_output.is_some_and(|(w, h)| !(w > 0.0 && h > 0.0)) {
+            return Err("--tile-output tiles need a size".into());
+        }
+        if opts.min_opacity.is_some_and(|o| !(0.0..=1.0).contains(&o)) {
+            return Err("--min-opacity is an opacity from 0 to 1".into());
+        }
+        let from = args.opt_value_from_fn("--crs", crate::geo::parse_crs)?;
+        let to = args.opt_value_from_fn("--to-crs", crate::geo::parse_crs)?;
+        match args.opt_value_from_fn("--georef", crate::geo::parse_georef)? {
+            Some(georef) => {
+                let (from, to) = (from.unwrap_or_else(crate::geo::wgs84), to.unwrap_or_else(crate::geo::wgs84));
+                opts.geo = Some(crate::geo::Reference { georef, from, to });
+            }
+            None if from.is_some() || to.is_some() => {
+                return Err("--crs and --to-crs reproject what --georef places; georeference the drawing first".into());
+            }
+            None => {}
+        }
+        if let Some(root) = args.opt_value_from_str::<_, std::path::PathBuf>("--external-root")? {
+            let sandbox = opts.external.as_mut().ok_or("--external-root bounds what --allow-external loads; allow it first")?;
+            sandbox.root = Some(root);
+        }
+        if opts.quantities.is_some() && opts.nest.is_none() {
+            return Err("--quantities are copies laid out by --nest; nest first".into());
+        }
+        if let Some(a) = opts.adaptive
+            && !(a.factor > 0.0 && a.min > 0.0 && a.min <= a.max)
+        {
+            return Err("--adaptive-tolerance needs a positive factor and 0 < --min-tolerance <= --max-tolerance".into());
+        }
+        if let Some(crate::overlap::Overlap::Exactly(0)) = opts.overlaps {
+            return Err("--overlaps-exactly needs a count of at least 1".into());
+        }
+        if opts.negative && opts.silhouette {
+            return Err("--negative and --silhouette can't be combined".into());
+        }
+        if opts.keep_largest == Some(0) {
+            return Err("--keep-largest needs at least 1 part".into());
+        }
+        if opts.join_gap.is_some_and(|gap| gap <= 0.0) {
+            return Err("--join-gap needs a distance above 0".into());
+        }
+        if opts.tolerance.is_some_and(|t| t <= 0.0) {
+            return Err("--tolerance needs to be above 0".into());
+        }
+        if opts.gap < 0.0 {
+            return Err("--gap can't be negative".into());
+        }
+        if opts.seam_allowance.is_some_and(|allowance| allowance < 0.0) {
+            return Err("--seam-allowance grows pieces outward; it can't be below 0".into());
+        }
+        if opts.buffer.is_some_and(|width| width <= 0.0) {
+            return Err("--buffer needs a width above 0".into());
+        }
+        if opts.max_deviation.is_some_and(|d| d <= 0.0) {
+            return Err("--max-deviation needs a distance above 0".into());
+        }
+        if opts.max_deviation.is_some() && opts.target_vertices.is_some() {
+            return Err("--max-deviation and --target-vertices can't be combined".into());
+        }
+        if opts.round_corners.is_some() && opts.chamfer.is_some() {
+            return Err("--round-corners and --chamfer can't be combined".into());
+        }
+
+        // before any work, so a mistyped path fails at once
+        let named = opts.outputs.iter().filter(|o| o.named).chain(&opts.offcuts).map(|o| o.path.as_str());
+        for path in named.chain(opts.report.as_deref()).chain(opts.engrave_file.as_deref()).chain(opts.intersections.as_deref()) {
+            crate::output::check_free(path, &opts, true)?;
+        }
+        if opts.outputs.is_empty() {
+            let path = match opts.format {
+                crate::export::Format::Svg if opts.compress => "output.svgz".to_string(),
+                format => format!("output.{}", format.extension()),
+            };
+            opts.outputs.push(crate::export::Output { path, format: opts.format, compress: opts.compress, named: false });
+        }
+        if opts.geo.is_none() && opts.outputs.iter().any(|o| o.format == crate::export::Format::GeoJson) {
+            return Err("GeoJSON is in real-world coordinates; --georef the drawing".into());
+        }
+
+        for arg in args.finish() {
+            let arg = arg.into_string().map_err(|a| format!("invalid argument {a:?}"))?;
+            if arg.starts_with('-') {
+                return Err(format!("unknown option {arg}").into());
+            }
+            opts.inputs.push(arg);
+        }
+        if opts.inputs.is_empty() {
+            opts.inputs = (0..=5).map(|i| format!("{i}.svg")).collect();
+        }
+        if opts.in_place {
+            if opts.select.is_none() {
+                return Err("--in-place puts the result where the selected elements were; --select them".into());
+            }
+            if opts.inputs.len() != 1 || opts.outputs.iter().any(|o| o.format != crate::export::Format::Svg) || opts.tile_output.is_some() {
+                return Err("--in-place writes one input back as SVG, whole".into());
+            }
+            // linked files would be written back into it
+            if opts.external.is_some() {
+                return Err("--in-place leaves what the source links to as links, which --allow-external doesn't".into());
+            }
+        }
+        Ok(opts)
+    }
+}
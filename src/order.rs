@@ -0,0 +1,57 @@
+use crate::FlatShape;
+use crate::attrs::Attrs;
+
+/// Order shapes are combined in. Each shape carves its gap out of the ones
+/// before it, so later shapes win.
+#[derive(Clone, PartialEq)]
+pub enum Order {
+    /// As they appear in the document.
+    Document,
+    /// Last in the document first.
+    Reverse,
+    /// Largest first, so smaller shapes on top keep their gaps.
+    AreaDesc,
+    /// By a numeric `data-*` attribute, lowest first; shapes without one
+    /// count as 0.
+    Attr(String),
+}
+
+pub fn parse(spec: &str) -> Result<Order, String> {
+    match spec {
+        "document" => Ok(Order::Document),
+        "reverse" => Ok(Order::Reverse),
+        "area-desc" => Ok(Order::AreaDesc),
+        _ => match spec.strip_prefix("attr:") {
+            Some(name) if !name.is_empty() => Ok(Order::Attr(name.strip_prefix("data-").unwrap_or(name).to_string())),
+            _ => Err(format!("unknown order {spec:?}, expected attr:NAME, area-desc, document or reverse")),
+        },
+    }
+}
+
+/// Put `shapes` in `order`; ties keep document order. `attrs` looks up an
+/// element's attributes by id.
+pub fn sort<'a>(shapes: &mut [FlatShape], order: &Order, attrs: impl Fn(&str) -> Option<&'a Attrs>) {
+    match order {
+        Order::Document => {}
+        Order::Reverse => shapes.reverse(),
+        Order::AreaDesc => {
+            let area = |s: &FlatShape| -> f64 { s.contours.iter().map(|c| signed_area(c)).sum::<f64>().abs() };
+            shapes.sort_by(|a, b| area(b).total_cmp(&area(a)));
+        }
+        Order::Attr(name) => {
+            let key = |s: &FlatShape| crate::attrs::number(attrs(&s.id), name).unwrap_or(0.0);
+            shapes.sort_by(|a, b| key(a).total_cmp(&key(b)));
+        }
+    }
+}
+
+pub fn signed_area(ring: &[(f64, f64)]) -> f64 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % n]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum::<f64>()
+        / 2.0
+}
@@ -0,0 +1,747 @@
+use std::fs;
+use std::io::Write as _;
+use std::ops::Range;
+use std::path::Path;
+
+use clipper2::*;
+
+use crate::groups::Group;
+use crate::normalize::Normalization;
+use crate::operation::Operation;
+use crate::options::{self, Options};
+use crate::style::Style;
+use crate::{
+    Contour, EmptyResult, HAIRLINE, Source, cache, config, curves, edges, engrave, export, labels, logging, naming, nest, paint, parts,
+    progress, show_warnings, summary, svg, tile, utc_timestamp, warnings, weed,
+};
+
+/// What the pipeline made of the drawing, in working units, for `write`.
+pub struct Combined<P: PointScaler> {
+    /// Every group's rings side by side, `group_rings` saying whose are whose.
+    pub rings: Paths<P>,
+    pub groups: Vec<Group>,
+    pub group_rings: Vec<Range<usize>>,
+    /// The parts, their groups, the elements they came from and the
+    /// sheets they were nested on.
+    pub parts: Vec<parts::Part>,
+    pub part_group: Vec<usize>,
+    pub part_sources: Vec<Vec<String>>,
+    pub part_sheet: Vec<usize>,
+    /// Lines of scored and engraved groups and folds: the group if any,
+    /// what is done, the lines.
+    pub operated: Vec<(Option<usize>, Operation, Vec<Contour>)>,
+    pub artwork: Vec<engrave::Artwork>,
+    pub curves: curves::Curves,
+    /// The nest, its origin and how many sheets it filled.
+    pub sheets: Option<(nest::Nest, (f64, f64), usize)>,
+    pub repaired: Vec<serde_json::Value>,
+    /// For the summary: vertices read and the deviation simplifying left.
+    pub input_vertices: usize,
+    pub deviation: Option<f64>,
+}
+
+/// The output stage: checks on the result, then every file it is written
+/// to, the report and the summary.
+pub fn write<P: PointScaler>(
+    made: Combined<P>,
+    options: &Options,
+    source: &Source,
+    tree: &usvg::Tree,
+    norm: &Normalization,
+    mut warnings: warnings::Warnings,
+    progress: &mut progress::Progress,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Combined {
+        rings: mut combined,
+        groups,
+        group_rings,
+        parts,
+        part_group,
+        part_sources,
+        part_sheet,
+        mut operated,
+        artwork,
+        mut curves,
+        sheets,
+        repaired,
+        input_vertices,
+        deviation,
+    } = made;
+    let mut stage = logging::stage("write");
+    if combined.is_empty() && operated.is_empty() {
+        match options.on_empty {
+            options::OnEmpty::Write => warnings.add("empty-result", "result", "no polygons left; writing a blank document"),
+            options::OnEmpty::Skip => {
+                show_warnings(&warnings, progress);
+                tracing::warn!("⚠️ No polygons left, nothing written");
+                return Ok(());
+            }
+            options::OnEmpty::Fail => {
+                show_warnings(&warnings, progress);
+                return Err(EmptyResult.into());
+            }
+        }
+    }
+    // cutters and embedded renderers have hard limits
+    let output_vertices: usize = combined.iter().map(|p| p.len()).sum();
+    if let Some(max) = options.max_vertices.filter(|&max| output_vertices > max) {
+        return Err(format!("the result has {output_vertices} vertices, over --max-output-vertices {max}").into());
+    }
+    if let Some(max) = options.max_parts.filter(|&max| parts.len() > max) {
+        return Err(format!("the result has {} parts, over --max-parts {max}", parts.len()).into());
+    }
+    if !options.config.dimensions.is_empty() {
+        check_dimensions(&combined, &parts, &part_sources, options, norm, &mut warnings)?;
+    }
+    if let Some(material) = &options.material {
+        check_material(&combined, &parts, material, sheets.is_none(), norm, &mut warnings);
+    }
+    if options.strict && !warnings.is_empty() {
+        show_warnings(&warnings, progress);
+        return Err(format!("{} warning(s), which --strict doesn't allow", warnings.0.len()).into());
+    }
+    let mut report = serde_json::Map::new();
+    if !repaired.is_empty() {
+        report.insert("repairs".into(), repaired.into());
+    }
+    if !warnings.is_empty() {
+        report.insert("warnings".into(), warnings.json());
+    }
+    report.insert(
+        "parts".into(),
+        parts
+            .iter()
+            .zip(&part_sources)
+            .zip(&part_group)
+            .enumerate()
+            .map(|(n, ((part, sources), g))| {
+                let mut entry = serde_json::json!({
+                    "part": n + 1,
+                    "outer": part.outer,
+                    "holes": part.holes,
+                    "sources": sources,
+                });
+                if let Some(name) = &groups[*g].name {
+                    entry["group"] = name.as_str().into();
+                }
+                if let Some(sheet) = part_sheet.get(n) {
+                    entry["sheet"] = (sheet + 1).into();
+                }
+                entry
+            })
+            .collect::<Vec<_>>()
+            .into(),
+    );
+
+    // what is left of every sheet, for reuse
+    let mut offcuts: Paths<P> = Paths::new(vec![]);
+    let mut sheet_outlines: Vec<Contour> = Vec::new();
+    if let Some((nest, origin, count)) = sheets {
+        let left = nest.offcuts(count, origin, &combined)?;
+        let mut entries = Vec::new();
+        for (n, rings) in left.into_iter().enumerate() {
+            let area = rings.signed_area().abs() / (norm.scale * norm.scale);
+            tracing::info!("✓ Offcuts: sheet {} has {area:.2} left in {} piece(s)", n + 1, rings.len());
+            entries.push(serde_json::json!({ "sheet": n + 1, "area": area, "pieces": rings.len() }));
+            offcuts.push(rings);
+            let (x0, y0, x1, y1) = nest.sheet_bounds(n, origin);
+            sheet_outlines.push(vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1), (x0, y0)]);
+        }
+        report.insert("offcuts".into(), entries.into());
+    }
+
+    let mut engraving = if !artwork.is_empty() {
+        let silhouette = union(combined.clone(), Paths::new(vec![]), FillRule::NonZero)?;
+        engrave::engrave(&artwork, &silhouette)?
+    } else {
+        Vec::new()
+    };
+
+    // x, y, width, height of the output document
+    let mut view_box = (0.0, 0.0, tree.size().width() as f64, tree.size().height() as f64);
+    // sheets and panels are bigger than the drawing they hold
+    if let Some((nest, origin, count)) = sheets.filter(|s| s.2 > 0) {
+        let (x0, y0, ..) = nest.sheet_bounds(0, origin);
+        let (.., x1, y1) = nest.sheet_bounds(count - 1, origin);
+        let (a, b) = (norm.inverse.apply((x0, y0)), norm.inverse.apply((x1, y1)));
+        view_box = (a.0, a.1, b.0 - a.0, b.1 - a.1);
+    } else if options.panelize.is_some() && !combined.is_empty() {
+        let b = norm.inverse.apply_paths(&combined).bounds();
+        view_box = (b.min.x(), b.min.y(), b.size().x(), b.size().y());
+    }
+    // everything drawn moves along with the result, back out of the
+    // working range first
+    let mut moved = norm.inverse;
+    if let Some(t) = options.transform {
+        moved = moved.then(t);
+        view_box = t.apply_rect(view_box);
+        tracing::info!("✓ Transformed result, viewBox now {} {} {} {}", view_box.0, view_box.1, view_box.2, view_box.3);
+    }
+    if let Some(placement) = options.placement {
+        if !combined.is_empty() {
+            moved = moved.then(placement.affine(moved.apply_paths(&combined).bounds()));
+        }
+        view_box = (0.0, 0.0, placement.canvas.0, placement.canvas.1);
+        tracing::info!("✓ Placed result on {}x{} canvas", placement.canvas.0, placement.canvas.1);
+    }
+    if !norm.is_identity() || options.transform.is_some() || options.placement.is_some() {
+        combined = moved.apply_paths(&combined);
+        engraving = engraving.into_iter().map(|p| p.transformed(&moved)).collect();
+        offcuts = moved.apply_paths(&offcuts);
+        sheet_outlines.iter_mut().flat_map(|l| l.iter_mut()).for_each(|p| *p = moved.apply(*p));
+        for (_, _, lines) in &mut operated {
+            lines.iter_mut().flat_map(|l| l.iter_mut()).for_each(|p| *p = moved.apply(*p));
+        }
+        curves = curves.transformed(&moved);
+    }
+    if options.keep_curves {
+        tracing::info!("✓ Looking for {} source curve(s) in the result", curves.len());
+    }
+    // result rings as path data, nested for even-odd filling, with surviving
+    // curves put back when asked
+    let ring_data = |rings: &Paths<P>| {
+        let rings = parts::nested(rings);
+        if options.keep_curves { curves.polygon_data(&rings) } else { svg::polygon_data(&rings) }
+    };
+
+    // common lines are only worth having cut once
+    let shared_edges = options.shared_edges || options.nest.is_some_and(|n| n.common_line);
+    let cuts = shared_edges.then(|| edges::dedup_shared_edges(&combined));
+    // shapes touching in one group were unioned into one part in the carve,
+    // leaving no edge between them to share
+    if cuts.as_ref().is_some_and(|c| c.shared.is_empty()) && groups.len() < 2 && options.nest.is_none() {
+        let why = "shapes touching in one group are combined into one part, so no edge is shared; split them with --group-by";
+        warnings.add("shared-edges", "result", why);
+    }
+    let mut layers: Vec<svg::Element> = if let Some(cuts) = &cuts {
+        tracing::info!("✓ Shared edges: {} run(s), {:.2} units cut once", cuts.shared.len(), cuts.shared_length);
+        let mut layers = vec![stroked_layer("cuts", "black").child(svg::Element::new("path").attr("d", svg::polyline_data(&cuts.cuts)))];
+        if !cuts.shared.is_empty() {
+            layers.push(stroked_layer("shared-cuts", "red").child(svg::Element::new("path").attr("d", svg::polyline_data(&cuts.shared))));
+        }
+        layers
+    } else if options.separate {
+        // one path per part, tagged with the elements it came from
+        parts
+            .iter()
+            .zip(&part_sources)
+            .zip(&part_group)
+            .map(|((part, sources), g)| {
+                svg::Element::new("path")
+                    .attr("d", ring_data(&part.paths(&combined)))
+                    .attr("data-source", sources.join(" "))
+                    .attrs(groups[*g].style.with(&options.style).attributes())
+            })
+            .collect()
+    } else {
+        // one multi-subpath per group, in the group's own style; scored and
+        // engraved groups are drawn as lines further on
+        groups
+            .iter()
+            .zip(&group_rings)
+            .enumerate()
+            .filter(|(g, _)| !operated.iter().any(|o| o.0 == Some(*g)))
+            .map(|(_, (group, rings))| {
+                let mut path =
+                    svg::Element::new("path").attr("d", ring_data(&rings.clone().filter_map(|i| combined.get(i).cloned()).collect()));
+                if let Some(name) = &group.name {
+                    path = path.attr("data-group", name);
+                }
+                path.attrs(group.style.with(&options.style).attributes())
+            })
+            .collect()
+    };
+
+    // gradient fills as drawn, showing through the combined shape
+    if options.paint == paint::Strategy::Clip && !combined.is_empty() {
+        let (gradients, painted) = paint::painted(tree);
+        if !painted.is_empty() {
+            tracing::info!("✓ Painted {} gradient-filled path(s) through the result", painted.len());
+            let clip = svg::Element::new("clipPath")
+                .attr("id", "combined-paint")
+                .child(svg::Element::new("path").attr("d", ring_data(&combined)).attr("clip-rule", "evenodd"));
+            layers.push(svg::Element::new("defs").children(gradients).child(clip));
+            // drawn where the source was, moved along with the result
+            let drawn = svg::Element::new("g").attr("transform", paint::matrix(norm.forward.then(moved))).children(painted);
+            layers.push(svg::Element::new("g").attr("id", "paint").attr("clip-path", "url(#combined-paint)").child(drawn));
+        }
+    }
+
+    // what isn't combined, as it was, moved along with the result
+    if source.passed.count > 0 {
+        tracing::info!("✓ Passed {} element(s) through as they were", source.passed.count);
+        if !source.passed.defs.is_empty() {
+            layers.push(svg::Element::new("defs").raw(&source.passed.defs));
+        }
+        let at = paint::matrix(norm.forward.then(moved));
+        layers.push(svg::Element::new("g").attr("id", "passthrough").attr("transform", at).raw(&source.passed.content));
+    }
+
+    if options.labels {
+        let mut outlines: Paths<P> = Paths::new(vec![]);
+        let mut legend = Vec::new();
+        for (n, (part, sources)) in parts.iter().zip(&part_sources).enumerate() {
+            let Some(at) = labels::anchor(part, &combined) else {
+                continue;
+            };
+            outlines.push(labels::number_outline(n + 1, at, options.label_size));
+            legend.push(serde_json::json!({
+                "label": n + 1,
+                "x": at.0,
+                "y": at.1,
+                "sources": sources,
+            }));
+        }
+        tracing::info!("✓ Labeled {} part(s)", legend.len());
+        layers.push(
+            svg::Element::new("g")
+                .attr("id", "labels")
+                .attr("fill", "blue")
+                .attr("stroke", "none")
+                .child(svg::Element::new("path").attr("d", svg::polygon_data(&outlines))),
+        );
+        report.insert("labels".into(), legend.into());
+    }
+
+    if options.weed && !combined.is_empty() {
+        let weeding = weed::weed(&combined, &parts, options.weed_margin, options.weed_spacing);
+        tracing::info!("✓ Weed box with {} weeding line(s)", weeding.lines.len());
+        let mut lines = weeding.lines;
+        let mut weed_box = weeding.weed_box;
+        weed_box.push(weed_box[0]);
+        lines.insert(0, weed_box);
+        layers.push(stroked_layer("weed", "green").child(svg::Element::new("path").attr("d", svg::polyline_data(&lines))));
+    }
+
+    if options.engrave {
+        let layer = engrave_layer("engrave", &engraving);
+        tracing::info!("✓ Engrave layer with {} piece(s) of artwork", engraving.len());
+        layers = vec![svg::Element::new("g").attr("id", "cut").children(layers)];
+        match &options.engrave_file {
+            Some(path) => {
+                write_output(path, svg_bytes(&svg::document(view_box, [layer], options.pretty), options.compress)?, options, true)?;
+                tracing::info!("Engrave layer saved to: {path}");
+            }
+            None => layers.push(layer),
+        }
+    } else if !engraving.is_empty() {
+        tracing::info!("✓ Engraving strokes: {} line(s) kept within the result", engraving.iter().map(|p| p.lines.len()).sum::<usize>());
+        layers.push(engrave_layer("engrave-lines", &engraving));
+    }
+
+    if !operated.is_empty() {
+        // what is cut sits in a layer of its own, next to the lines
+        if !options.engrave {
+            layers = vec![svg::Element::new("g").attr("id", "cut").children(layers)];
+        }
+        for (op, color) in [
+            (Operation::Score, "blue"),
+            (Operation::Engrave, "black"),
+            (Operation::Stitch, "red"),
+            (Operation::Notch, "orange"),
+            (Operation::Grainline, "purple"),
+        ] {
+            let id = if op == Operation::Engrave && options.engrave { "hatch" } else { op.name() };
+            let mut layer = stroked_layer(id, color);
+            for (g, _, lines) in operated.iter().filter(|o| o.1 == op) {
+                let mut path = svg::Element::new("path").attr("d", svg::polyline_data(lines));
+                if let Some(name) = g.and_then(|g| groups[g].name.as_ref()) {
+                    path = path.attr("data-group", name);
+                }
+                layer = layer.child(path);
+            }
+            if operated.iter().any(|o| o.1 == op) {
+                layers.push(layer);
+            }
+        }
+    }
+
+    if !sheet_outlines.is_empty() {
+        layers.insert(0, stroked_layer("sheets", "gray").child(svg::Element::new("path").attr("d", svg::polyline_data(&sheet_outlines))));
+    }
+
+    if options.metadata {
+        layers.insert(0, svg::Element::new("metadata").attr("id", "svg-combiner").text(metadata(options).to_string()));
+    }
+
+    // every ring drawn with the tool its group's name picks
+    // with the material's power where the tool has none of its own
+    let power = options.material.as_ref().and_then(|m| m.power);
+    let tool_of = |rule: Option<&config::ToolRule>| {
+        rule.map_or(export::Tool { speed: power, ..export::Tool::default() }, |rule| export::Tool {
+            number: rule.number,
+            feed: rule.feed,
+            speed: rule.speed.or(power),
+        })
+    };
+    let mut tools = vec![tool_of(None); combined.len()];
+    for (group, rings) in groups.iter().zip(&group_rings) {
+        let rule = group.name.as_deref().and_then(|name| options.config.tool_for(name));
+        if rule.is_some() {
+            let tool = tool_of(rule);
+            tools.iter_mut().skip(rings.start).take(rings.len()).for_each(|t| *t = tool);
+        }
+    }
+    // scored and engraved lines, with their groups' tools too
+    let strokes: Vec<export::Stroke> = operated
+        .iter()
+        .flat_map(|(g, op, lines)| {
+            let rule = g.and_then(|g| groups[g].name.as_deref()).and_then(|name| options.config.tool_for(name));
+            let tool = tool_of(rule);
+            lines.iter().map(move |line| export::Stroke { layer: op.name(), tool, points: line.clone() })
+        })
+        .collect();
+    let page = export::Page {
+        view_box,
+        mm: options.unit_mm,
+        layer: &options.kicad_layer,
+        stitch: options.stitch_length,
+        feed: options.feed,
+        calibrate: options.calibrate,
+        leads: options.leads,
+        tools: &tools,
+        strokes: &strokes,
+        geo: options.geo.as_ref(),
+    };
+    let document = options
+        .outputs
+        .iter()
+        .any(|o| o.format == export::Format::Svg)
+        .then(|| match &options.select {
+            // the source as it was, but for what was combined
+            Some(selection) if options.in_place => {
+                selection.replace(source.original.unwrap_or(source.text), source.text, tree, &svg::markup(layers, options.pretty))
+            }
+            _ => Ok(svg::document(view_box, layers, options.pretty)),
+        })
+        .transpose()?;
+    // the combined shapes and their lines cut into tiles, empty ones left out
+    let mut tiles: Vec<(tile::Tile, String)> = Vec::new();
+    if let Some(size) = options.tile_output.filter(|_| document.is_some()) {
+        for tile in tile::grid(view_box, size) {
+            let mut cut = Vec::new();
+            for (g, (group, rings)) in groups.iter().zip(&group_rings).enumerate() {
+                let kept = tile.rings(&rings.clone().filter_map(|i| combined.get(i).cloned()).collect())?;
+                if kept.is_empty() || operated.iter().any(|o| o.0 == Some(g)) {
+                    continue;
+                }
+                let mut path = svg::Element::new("path").attr("d", svg::polygon_data(&parts::nested(&kept)));
+                if let Some(name) = &group.name {
+                    path = path.attr("data-group", name);
+                }
+                cut.push(path.attrs(group.style.with(&options.style).attributes()));
+            }
+            let empty = cut.is_empty();
+            let mut layers = vec![svg::Element::new("g").attr("id", "cut").children(cut)];
+            for (op, color) in [
+                (Operation::Score, "blue"),
+                (Operation::Engrave, "black"),
+                (Operation::Stitch, "red"),
+                (Operation::Notch, "orange"),
+                (Operation::Grainline, "purple"),
+            ] {
+                let lines: Vec<Contour> = operated.iter().filter(|o| o.1 == op).flat_map(|o| tile.lines::<P>(&o.2)).collect();
+                if !lines.is_empty() {
+                    layers.push(stroked_layer(op.name(), color).child(svg::Element::new("path").attr("d", svg::polyline_data(&lines))));
+                }
+            }
+            if !empty || layers.len() > 1 {
+                let doc = svg::document(tile.view_box, layers, options.pretty);
+                tiles.push((tile, doc));
+            }
+        }
+        tracing::info!("✓ Tiled into {} piece(s) of {}x{}", tiles.len(), size.0, size.1);
+    }
+    // tiles are only named now; none is written while any is in the way
+    for output in options.outputs.iter().filter(|o| o.format == export::Format::Svg && options.tile_output.is_some()) {
+        for (tile, _) in &tiles {
+            check_free(tile.file(&output.path), options, output.named)?;
+        }
+    }
+    for output in &options.outputs {
+        match (&document, &cuts) {
+            // the tiles in place of the whole
+            (Some(_), _) if output.format == export::Format::Svg && options.tile_output.is_some() => {
+                for (tile, doc) in &tiles {
+                    write_output(tile.file(&output.path), svg_bytes(doc, output.compress)?, options, output.named)?;
+                }
+            }
+            (Some(doc), _) if output.format == export::Format::Svg => {
+                write_output(&output.path, svg_bytes(doc, output.compress)?, options, output.named)?
+            }
+            // machines following lines cut every shared edge once
+            (_, Some(cuts)) if output.format.traces() => {
+                let mut lines = strokes.clone();
+                lines.extend(cuts.cuts.iter().chain(&cuts.shared).map(|line| export::Stroke {
+                    layer: "cut",
+                    tool: export::Tool::default(),
+                    points: line.clone(),
+                }));
+                let bytes = export::write(output.format, &Paths::<P>::new(vec![]), &export::Page { strokes: &lines, ..page });
+                write_output(&output.path, bytes, options, output.named)?
+            }
+            _ => write_output(&output.path, export::write(output.format, &combined, &page), options, output.named)?,
+        }
+    }
+
+    if let Some(output) = &options.offcuts {
+        if output.format == export::Format::Svg {
+            let path = svg::Element::new("path")
+                .attr("d", svg::polygon_data(&parts::nested(&offcuts)))
+                .attr("fill", "green")
+                .attr("fill-rule", "evenodd");
+            write_output(&output.path, svg_bytes(&svg::document(view_box, [path], options.pretty), output.compress)?, options, true)?;
+        } else {
+            let bytes = export::write(output.format, &offcuts, &export::Page { tools: &[], strokes: &[], ..page });
+            write_output(&output.path, bytes, options, true)?;
+        }
+        tracing::info!("Offcuts saved to: {}", output.path);
+    }
+
+    if let Some(dir) = &options.split_parts {
+        fs::create_dir_all(dir)?;
+        let mut taken = std::collections::HashSet::new();
+        let stem = options
+            .inputs
+            .first()
+            .map_or("output".into(), |input| file_name(&std::path::Path::new(input).file_stem().unwrap_or_default().to_string_lossy()));
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let date = utc_timestamp(now)[..10].to_string();
+        let mut files = Vec::new();
+        for (n, ((part, sources), g)) in parts.iter().zip(&part_sources).zip(&part_group).enumerate() {
+            let rings = part.paths(&combined);
+            if rings.is_empty() {
+                continue;
+            }
+            // labeled parts go by their number, others by what they came from
+            let label = match sources.first() {
+                Some(id) if !options.labels => file_name(id),
+                _ => format!("part-{}", n + 1),
+            };
+            let extension = if options.compress { "svgz" } else { "svg" };
+            let name = naming::expand(&options.name_template, |name| match name {
+                "stem" => Some(stem.clone()),
+                "layer" | "group" => Some(groups[*g].name.as_deref().map_or("none".into(), file_name)),
+                "label" => Some(label.clone()),
+                "index" => Some((n + 1).to_string()),
+                "date" => Some(date.clone()),
+                "ext" => Some(extension.into()),
+                _ => None,
+            })?;
+            let mut file = std::path::Path::new(dir).join(&name);
+            if !taken.insert(file.clone()) {
+                let stem = file.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+                file.set_file_name(match file.extension() {
+                    Some(ext) => format!("{stem}-{}.{}", n + 1, ext.to_string_lossy()),
+                    None => format!("{stem}-{}", n + 1),
+                });
+                taken.insert(file.clone());
+            }
+            let b = rings.bounds();
+            let size = b.size();
+            let path = svg::Element::new("path")
+                .attr("d", ring_data(&rings))
+                .attr("data-source", sources.join(" "))
+                .attrs(groups[*g].style.with(&options.style).attributes());
+            let doc = svg::document((b.min.x(), b.min.y(), size.x(), size.y()), [path], options.pretty);
+            files.push((file, doc));
+        }
+        // none is written while any is in the way
+        for (file, _) in &files {
+            check_free(file, options, true)?;
+        }
+        for (file, doc) in &files {
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            write_output(file, svg_bytes(doc, options.compress)?, options, true)?;
+        }
+        tracing::info!("Parts saved to: {dir} ({} file(s))", taken.len());
+    }
+    if let Some(path) = &options.report {
+        write_output(path, serde_json::to_string_pretty(&report)?, options, true)?;
+        tracing::info!("Report saved to: {path}");
+    }
+
+    progress.stage("write", format!("{} vertices", combined.iter().map(|p| p.len()).sum::<usize>()));
+    stage.next("done");
+    tracing::info!("\n✅ Success!");
+    for output in &options.outputs {
+        tracing::info!("Output saved to: {}", output.path);
+    }
+
+    let rows: Vec<summary::Row> = groups
+        .iter()
+        .zip(&group_rings)
+        .enumerate()
+        .map(|(g, (group, rings))| summary::Row {
+            group: group.name.clone().unwrap_or_else(|| "all".into()),
+            parts: part_group.iter().filter(|&&p| p == g).count(),
+            polygons: rings.len(),
+            vertices: combined.iter().skip(rings.start).take(rings.len()).map(|p| p.len()).sum(),
+        })
+        .collect();
+    summary::show(&rows, input_vertices, deviation, options.log_json);
+
+    show_warnings(&warnings, progress);
+
+    Ok(())
+}
+
+/// Compare the size of every part a `[dimension]` of the config names
+/// with what it should be, failing if any is off by more than allowed.
+fn check_dimensions<P: PointScaler>(
+    combined: &Paths<P>,
+    parts: &[parts::Part],
+    part_sources: &[Vec<String>],
+    options: &Options,
+    norm: &Normalization,
+    warnings: &mut warnings::Warnings,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut off = Vec::new();
+    let mut used = vec![false; options.config.dimensions.len()];
+    for (n, part) in parts.iter().enumerate() {
+        let label = format!("part-{}", n + 1);
+        let sources = part_sources.get(n).map_or(&[][..], |s| &s[..]);
+        let names = sources.iter().map(String::as_str).chain([label.as_str()]);
+        let Some(rule) = options.config.dimension_for(names) else {
+            continue;
+        };
+        if let Some(i) = options.config.dimensions.iter().position(|r| std::ptr::eq(r, rule)) {
+            used[i] = true;
+        }
+        let size = part.paths(combined).bounds().size();
+        let name = sources.first().unwrap_or(&label);
+        for (what, want, got) in [("width", rule.width, size.x() / norm.scale), ("height", rule.height, size.y() / norm.scale)] {
+            if let Some(want) = want.filter(|want| (got - want).abs() > rule.tolerance) {
+                off.push(format!("{name}: {what} {got:.3}, expected {want} ± {}", rule.tolerance));
+            }
+        }
+    }
+    for (rule, _) in options.config.dimensions.iter().zip(used).filter(|(_, u)| !u) {
+        warnings.add("dimension", rule.pattern.clone(), "no part to check the size of");
+    }
+    if !off.is_empty() {
+        for line in &off {
+            tracing::error!("  {line}");
+        }
+        return Err(format!("{} dimension(s) out of tolerance", off.len()).into());
+    }
+    tracing::info!("✓ Dimensions: every checked part within tolerance");
+    Ok(())
+}
+
+/// Parts too thin for the material to hold, and with `whole`, a result
+/// bigger than the sheets it comes in.
+fn check_material<P: PointScaler>(
+    combined: &Paths<P>,
+    parts: &[parts::Part],
+    material: &config::Material,
+    whole: bool,
+    norm: &Normalization,
+    warnings: &mut warnings::Warnings,
+) {
+    if let Some(feature) = material.min_feature.filter(|&f| f > 0.0) {
+        let half = feature / 2.0 * norm.scale;
+        for (n, part) in parts.iter().enumerate() {
+            // nothing survives taking half the feature off every side
+            if part.paths(combined).inflate(-half, JoinType::Miter, EndType::Polygon, 2.0).is_empty() {
+                warnings.add("material", format!("part-{}", n + 1), format!("thinner than {}'s min feature of {feature}", material.name));
+            }
+        }
+    }
+    if let Some((w, h)) = material.sheet.filter(|_| whole && !combined.is_empty()) {
+        let size = combined.bounds().size();
+        let (x, y) = (size.x() / norm.scale, size.y() / norm.scale);
+        if (x > w || y > h) && (y > w || x > h) {
+            warnings.add("material", material.name.clone(), format!("{x:.2}x{y:.2} doesn't fit on a {w}x{h} sheet"));
+        }
+    }
+}
+
+/// An SVG document as written, gzipped when `compress` is set.
+fn svg_bytes(doc: &str, compress: bool) -> std::io::Result<Vec<u8>> {
+    if !compress {
+        return Ok(doc.as_bytes().to_vec());
+    }
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(doc.as_bytes())?;
+    encoder.finish()
+}
+
+/// Whether `path` may be written: nothing is there, `--force` or `--backup`
+/// was given, or it isn't `named` by the user but a default every run
+/// rewrites, like output.svg.
+pub fn check_free(path: impl AsRef<Path>, options: &Options, named: bool) -> Result<(), String> {
+    let path = path.as_ref();
+    if named && !options.force && !options.backup && path.exists() {
+        return Err(format!("{} already exists; --force overwrites it, --backup keeps it as .bak", path.display()));
+    }
+    Ok(())
+}
+
+/// Write a file the run makes, as `check_free` allows, moving what was
+/// there to NAME.bak first with `--backup`. Every file goes through here.
+pub fn write_output(
+    path: impl AsRef<Path>,
+    bytes: impl AsRef<[u8]>,
+    options: &Options,
+    named: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    check_free(path, options, named)?;
+    if named && options.backup && path.exists() {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".bak");
+        fs::rename(path, backup)?;
+    }
+    fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// `id` with anything that isn't safe in a file name replaced by `_`.
+fn file_name(id: &str) -> String {
+    id.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect()
+}
+
+/// How this file was produced: program version, the arguments given,
+/// a hash of every input and when it ran.
+fn metadata(options: &Options) -> serde_json::Value {
+    let inputs: Vec<_> = options
+        .inputs
+        .iter()
+        .filter_map(|path| {
+            let data = fs::read(path).ok()?;
+            Some(serde_json::json!({ "file": path, "fnv1a": format!("{:016x}", cache::hash(&data)) }))
+        })
+        .collect();
+    let seconds = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    serde_json::json!({
+        "generator": concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION")),
+        "arguments": std::env::args().skip(1).collect::<Vec<_>>(),
+        "inputs": inputs,
+        "created": utc_timestamp(seconds),
+    })
+}
+
+/// A group of engraved artwork, fills and lines in their own styles.
+fn engrave_layer<P: PointScaler>(id: &str, engraving: &[engrave::Piece<P>]) -> svg::Element {
+    let mut layer = svg::Element::new("g").attr("id", id);
+    for piece in engraving {
+        if !piece.fill.is_empty() {
+            layer = layer.child(svg::Element::new("path").attr("d", svg::polygon_data(&piece.fill)).attrs(piece.style.attributes()));
+        }
+        if !piece.lines.is_empty() {
+            // open lines only ever show as strokes
+            let line_style =
+                Style { fill: Some("none".into()), stroke: piece.style.stroke.clone().or(Some("black".into())), ..piece.style.clone() };
+            layer = layer.child(svg::Element::new("path").attr("d", svg::polyline_data(&piece.lines)).attrs(line_style.attributes()));
+        }
+    }
+    layer
+}
+
+/// An empty group for hairline cut lines in `color`.
+fn stroked_layer(id: &str, color: &str) -> svg::Element {
+    svg::Element::new("g").attr("id", id).attr("fill", "none").attr("stroke", color).attr("stroke-width", HAIRLINE)
+}
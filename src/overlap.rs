@@ -0,0 +1,39 @@
+use clipper2::*;
+
+use crate::FlatShape;
+
+/// Which overlaps `--overlaps` keeps.
+#[derive(Clone, Copy)]
+pub enum Overlap {
+    /// Covered by this many shapes or more.
+    AtLeast(usize),
+    /// Covered by exactly this many shapes.
+    Exactly(usize),
+}
+
+/// Where the shapes as drawn, before any gaps, cover each other: `levels[j]`
+/// holds what at least `j + 1` of them cover, built up one shape at a time.
+pub fn overlaps<P: PointScaler>(shapes: &[FlatShape], which: Overlap) -> Result<Paths<P>, ClipperError> {
+    let (k, exact) = match which {
+        Overlap::AtLeast(k) => (k, false),
+        Overlap::Exactly(k) => (k, true),
+    };
+    let depth = if exact { k + 1 } else { k };
+    let mut levels: Vec<Paths<P>> = vec![Paths::new(vec![]); depth];
+    for shape in shapes {
+        // a shape overlapping itself still counts once
+        let own: Paths<P> = union(shape.contours.clone(), Paths::new(vec![]), FillRule::NonZero)?;
+        for j in (1..depth).rev() {
+            if levels[j - 1].is_empty() {
+                continue;
+            }
+            let more = intersect(levels[j - 1].clone(), own.clone(), FillRule::NonZero)?;
+            if !more.is_empty() {
+                levels[j] = union(levels[j].clone(), more, FillRule::NonZero)?;
+            }
+        }
+        levels[0] = union(levels[0].clone(), own, FillRule::NonZero)?;
+    }
+    let at_least = levels[k - 1].clone();
+    if exact && !levels[k].is_empty() { difference(at_least, levels[k].clone(), FillRule::NonZero) } else { Ok(at_least) }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+use usvg::Paint;
+use usvg::tiny_skia_path::PathSegment;
+
+use crate::svg::Element;
+use crate::transform::Affine;
+
+/// `--paint`: what becomes of gradient and pattern fills, which a combined
+/// shape can't keep: plain black as before (`discard`), the color covering
+/// most of them (`dominant`), or the painting as drawn showing through the
+/// combined shape used as a clip path (`clip`).
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Strategy {
+    #[default]
+    Discard,
+    Dominant,
+    Clip,
+}
+
+pub fn parse(spec: &str) -> Result<Strategy, String> {
+    match spec {
+        "discard" => Ok(Strategy::Discard),
+        "dominant" => Ok(Strategy::Dominant),
+        "clip" => Ok(Strategy::Clip),
+        _ => Err(format!("unknown paint strategy {spec:?}, expected discard, dominant or clip")),
+    }
+}
+
+/// The color taking up most of a gradient, each stop owning the run up to
+/// halfway to its neighbours, or the one filling most of a pattern's tile.
+pub fn dominant(paint: &Paint) -> Option<String> {
+    let hex = |c: usvg::Color| format!("#{:02x}{:02x}{:02x}", c.red, c.green, c.blue);
+    let stops = match paint {
+        Paint::Color(c) => return Some(hex(*c)),
+        Paint::LinearGradient(g) => g.stops(),
+        Paint::RadialGradient(g) => g.stops(),
+        Paint::Pattern(pattern) => {
+            let mut areas: Vec<(String, f64)> = Vec::new();
+            let mut stack: Vec<&usvg::Node> = pattern.root().children().iter().collect();
+            while let Some(node) = stack.pop() {
+                match node {
+                    usvg::Node::Path(path) => {
+                        let Some(Paint::Color(c)) = path.fill().map(|f| f.paint()) else { continue };
+                        let b = path.data().bounds();
+                        let area = (b.width() * b.height()) as f64;
+                        match areas.iter_mut().find(|(color, _)| *color == hex(*c)) {
+                            Some((_, total)) => *total += area,
+                            None => areas.push((hex(*c), area)),
+                        }
+                    }
+                    usvg::Node::Group(group) => stack.extend(group.children()),
+                    _ => {}
+                }
+            }
+            return areas.into_iter().max_by(|a, b| a.1.total_cmp(&b.1)).map(|(color, _)| color);
+        }
+    };
+    let offset = |i: usize| stops[i].offset().get() as f64;
+    (0..stops.len())
+        .max_by(|&a, &b| {
+            let run = |i: usize| {
+                let start = if i == 0 { 0.0 } else { (offset(i - 1) + offset(i)) / 2.0 };
+                let end = if i + 1 == stops.len() { 1.0 } else { (offset(i) + offset(i + 1)) / 2.0 };
+                end - start
+            };
+            run(a).total_cmp(&run(b))
+        })
+        .map(|i| hex(stops[i].color()))
+}
+
+/// Every gradient-filled path of `tree` as it was drawn, in the drawing's
+/// own units: the gradients, then the paths using them.
+pub fn painted(tree: &usvg::Tree) -> (Vec<Element>, Vec<Element>) {
+    let (mut defs, mut paths) = (Vec::new(), Vec::new());
+    let mut seen = HashSet::new();
+    let mut stack: Vec<&usvg::Node> = tree.root().children().iter().rev().collect();
+    while let Some(node) = stack.pop() {
+        match node {
+            usvg::Node::Path(path) => {
+                let Some(fill) = path.fill() else { continue };
+                let (gradient, id) = match fill.paint() {
+                    Paint::LinearGradient(g) => {
+                        (Element::new("linearGradient").attr("x1", g.x1()).attr("y1", g.y1()).attr("x2", g.x2()).attr("y2", g.y2()), g.id())
+                    }
+                    Paint::RadialGradient(g) => (
+                        Element::new("radialGradient")
+                            .attr("cx", g.cx())
+                            .attr("cy", g.cy())
+                            .attr("r", g.r().get())
+                            .attr("fx", g.fx())
+                            .attr("fy", g.fy()),
+                        g.id(),
+                    ),
+                    _ => continue,
+                };
+                let id = format!("paint-{id}");
+                if seen.insert(id.clone()) {
+                    let base: &usvg::BaseGradient = match fill.paint() {
+                        Paint::LinearGradient(g) => g,
+                        Paint::RadialGradient(g) => g,
+                        _ => continue,
+                    };
+                    let spread = match base.spread_method() {
+                        usvg::SpreadMethod::Pad => "pad",
+                        usvg::SpreadMethod::Reflect => "reflect",
+                        usvg::SpreadMethod::Repeat => "repeat",
+                    };
+                    defs.push(
+                        gradient
+                            .attr("id", &id)
+                            .attr("gradientUnits", "userSpaceOnUse")
+                            .attr("gradientTransform", matrix(Affine::of_usvg(base.transform())))
+                            .attr("spreadMethod", spread)
+                            .children(base.stops().iter().map(|stop| {
+                                let c = stop.color();
+                                Element::new("stop")
+                                    .attr("offset", stop.offset().get())
+                                    .attr("stop-color", format!("#{:02x}{:02x}{:02x}", c.red, c.green, c.blue))
+                                    .attr("stop-opacity", stop.opacity().get())
+                            })),
+                    );
+                }
+                let rule = if fill.rule() == usvg::FillRule::EvenOdd { "evenodd" } else { "nonzero" };
+                paths.push(
+                    Element::new("path")
+                        .attr("d", data(path.data()))
+                        .attr("transform", matrix(Affine::of_usvg(path.abs_transform())))
+                        .attr("fill", format!("url(#{id})"))
+                        .attr("fill-rule", rule)
+                        .attr("fill-opacity", fill.opacity().get()),
+                );
+            }
+            usvg::Node::Group(group) => stack.extend(group.children().iter().rev()),
+            _ => {}
+        }
+    }
+    (defs, paths)
+}
+
+/// SVG's `matrix()` of `t`.
+pub fn matrix(t: Affine) -> String {
+    // and no -0 from a flipped sign
+    format!("matrix({} {} {} {} {} {})", t.a + 0.0, t.b + 0.0, t.c + 0.0, t.d + 0.0, t.e + 0.0, t.f + 0.0)
+}
+
+/// Path data as written in SVG.
+fn data(path: &usvg::tiny_skia_path::Path) -> String {
+    let mut d = String::new();
+    for segment in path.segments() {
+        let _ = match segment {
+            PathSegment::MoveTo(p) => write!(d, "M{} {}", p.x, p.y),
+            PathSegment::LineTo(p) => write!(d, "L{} {}", p.x, p.y),
+            PathSegment::QuadTo(a, p) => write!(d, "Q{} {} {} {}", a.x, a.y, p.x, p.y),
+            PathSegment::CubicTo(a, b, p) => write!(d, "C{} {} {} {} {} {}", a.x, a.y, b.x, b.y, p.x, p.y),
+            PathSegment::Close => write!(d, "Z"),
+        };
+    }
+    d
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use clipper2::*;
+
+use crate::Contour;
+use crate::attrs::parse_length;
+use crate::transform::Affine;
+
+/// `--panelize "3x2 gap=4 rail=5 tab=3"`: the result repeated in a grid of
+/// columns and rows `gap` apart, inside a frame `rail` wide and `gap` away,
+/// every copy held to its neighbours and the frame by tabs `tab` wide.
+/// Tabs are mouse bites unless `solid`: a row of holes `hole` across and
+/// `pitch` apart where the tab meets a copy, so it snaps off clean.
+#[derive(Clone, Copy)]
+pub struct Panel {
+    pub columns: usize,
+    pub rows: usize,
+    pub gap: f64,
+    pub rail: f64,
+    pub tab: f64,
+    pub solid: bool,
+    pub hole: f64,
+    pub pitch: f64,
+}
+
+pub fn parse(spec: &str) -> Result<Panel, String> {
+    let mut words = spec.split_whitespace();
+    let grid = words.next().ok_or("a panel needs COLUMNSxROWS, like 3x2")?;
+    let (columns, rows) = grid
+        .split_once('x')
+        .and_then(|(c, r)| Some((c.parse::<usize>().ok()?, r.parse::<usize>().ok()?)))
+        .filter(|&(c, r)| c > 0 && r > 0)
+        .ok_or_else(|| format!("expected COLUMNSxROWS, like 3x2, got {grid:?}"))?;
+    let mut solid = false;
+    let mut keys = HashMap::new();
+    for word in words {
+        match word {
+            "solid" => solid = true,
+            "mouse-bites" => solid = false,
+            _ => {
+                let (key, value) = word.split_once('=').ok_or_else(|| format!("expected KEY=VALUE, got {word:?}"))?;
+                if !matches!(key, "gap" | "rail" | "tab" | "hole" | "pitch") {
+                    return Err(format!("unknown panel setting {key:?}"));
+                }
+                keys.insert(key, parse_length(value)?);
+            }
+        }
+    }
+    let panel = Panel {
+        columns,
+        rows,
+        gap: keys.get("gap").copied().unwrap_or(3.0),
+        rail: keys.get("rail").copied().unwrap_or(5.0),
+        tab: keys.get("tab").copied().unwrap_or(3.0),
+        solid,
+        hole: keys.get("hole").copied().unwrap_or(0.5),
+        pitch: keys.get("pitch").copied().unwrap_or(0.8),
+    };
+    if panel.gap <= 0.0 || panel.rail <= 0.0 || panel.tab <= 0.0 || panel.hole <= 0.0 || panel.pitch < panel.hole {
+        return Err("panel gap, rail, tab and hole have to be above 0, and pitch at least the hole".into());
+    }
+    Ok(panel)
+}
+
+impl Panel {
+    /// The same panel with every length multiplied by `factor`.
+    pub fn scaled(self, factor: f64) -> Panel {
+        Panel {
+            gap: self.gap * factor,
+            rail: self.rail * factor,
+            tab: self.tab * factor,
+            hole: self.hole * factor,
+            pitch: self.pitch * factor,
+            ..self
+        }
+    }
+
+    /// Where every copy of `paths` goes, row by row.
+    pub fn placements<P: PointScaler>(&self, paths: &Paths<P>) -> Vec<Affine> {
+        if paths.is_empty() {
+            return Vec::new();
+        }
+        let size = paths.bounds().size();
+        let step = (size.x() + self.gap, size.y() + self.gap);
+        (0..self.rows)
+            .flat_map(|row| (0..self.columns).map(move |column| Affine::translate(column as f64 * step.0, row as f64 * step.1)))
+            .collect()
+    }
+
+    /// The panel made of copies of `paths`, with how many tabs hold them.
+    pub fn build<P: PointScaler>(&self, paths: &Paths<P>) -> Result<(Paths<P>, usize), ClipperError> {
+        if paths.is_empty() {
+            return Ok((Paths::new(vec![]), 0));
+        }
+        let b = paths.bounds();
+        let (x0, y0) = (b.min.x(), b.min.y());
+        let step = (b.size().x() + self.gap, b.size().y() + self.gap);
+        let (x1, y1) = (x0 + step.0 * self.columns as f64 - self.gap, y0 + step.1 * self.rows as f64 - self.gap);
+
+        let mut copies: Paths<P> = Paths::new(vec![]);
+        for t in self.placements(paths) {
+            copies.push(t.apply_paths(paths));
+        }
+        // the frame's inside, with the copies `gap` in from it
+        let inner = (x0 - self.gap, y0 - self.gap, x1 + self.gap, y1 + self.gap);
+        let rail = self.rail;
+        let frame: Paths<P> = vec![
+            rect(inner.0 - rail, inner.1 - rail, inner.2 + rail, inner.3 + rail),
+            rect(inner.0, inner.1, inner.2, inner.3).into_iter().rev().collect(),
+        ]
+        .into();
+
+        // tabs run through the middle of every copy to the next one or the
+        // frame, and keep what lies outside the copies
+        let solid = crate::parts::silhouette(&union(copies.clone(), Paths::new(vec![]), FillRule::NonZero)?);
+        let half = self.tab / 2.0;
+        let mut strips = Vec::new();
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let (cx, cy) = (x0 + (column as f64 + 0.5) * step.0 - self.gap / 2.0, y0 + (row as f64 + 0.5) * step.1 - self.gap / 2.0);
+                let right = if column + 1 < self.columns { cx + step.0 } else { inner.2 };
+                let down = if row + 1 < self.rows { cy + step.1 } else { inner.3 };
+                strips.push((true, rect(cx, cy - half, right, cy + half)));
+                strips.push((false, rect(cx - half, cy, cx + half, down)));
+                if column == 0 {
+                    strips.push((true, rect(inner.0, cy - half, cx, cy + half)));
+                }
+                if row == 0 {
+                    strips.push((false, rect(cx - half, inner.1, cx + half, cy)));
+                }
+            }
+        }
+        let mut tabs: Paths<P> = Paths::new(vec![]);
+        let mut bites: Vec<Contour> = Vec::new();
+        let mut count = 0;
+        for (across, strip) in strips {
+            let pieces = difference(Paths::<P>::from(vec![strip]), solid.clone(), FillRule::NonZero)?;
+            for piece in pieces.iter() {
+                count += 1;
+                if self.solid {
+                    continue;
+                }
+                // holes where the tab meets a copy, not the frame
+                let pb = piece.bounds();
+                let (ends, span) = if across {
+                    ([pb.min.x(), pb.max.x()], (pb.min.y(), pb.max.y()))
+                } else {
+                    ([pb.min.y(), pb.max.y()], (pb.min.x(), pb.max.x()))
+                };
+                let frame_edges = if across { [inner.0, inner.2] } else { [inner.1, inner.3] };
+                for end in ends.into_iter().filter(|e| frame_edges.iter().all(|f| (e - f).abs() > 1e-9 * self.tab.max(1.0))) {
+                    let n = ((span.1 - span.0) / self.pitch).floor() as usize + 1;
+                    let start = (span.0 + span.1) / 2.0 - (n - 1) as f64 * self.pitch / 2.0;
+                    for k in 0..n {
+                        let along = start + k as f64 * self.pitch;
+                        let (x, y) = if across { (end, along) } else { (along, end) };
+                        bites.push(crate::perforate::ring(x, y, self.hole / 2.0, 16, 0.0));
+                    }
+                }
+            }
+            tabs.push(pieces);
+        }
+        let mut panel = union(copies, frame, FillRule::NonZero)?;
+        panel = union(panel, tabs, FillRule::NonZero)?;
+        if !bites.is_empty() {
+            panel = difference(panel, Paths::<P>::from(bites), FillRule::NonZero)?;
+        }
+        Ok((panel, count))
+    }
+}
+
+// a rectangle between two corners
+fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Contour {
+    vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(size: f64) -> Paths<Centi> {
+        vec![rect(0.0, 0.0, size, size)].into()
+    }
+
+    fn area(paths: &Paths<Centi>) -> f64 {
+        paths.iter().map(|r| r.signed_area()).sum::<f64>().abs()
+    }
+
+    #[test]
+    fn panels_have_mouse_bites_unless_solid() {
+        let p = parse("3x2").unwrap();
+        assert_eq!((p.columns, p.rows, p.gap, p.rail, p.tab, p.solid, p.hole, p.pitch), (3, 2, 3.0, 5.0, 3.0, false, 0.5, 0.8));
+        let p = parse("1x4 gap=2 rail=6 tab=4 solid").unwrap();
+        assert_eq!((p.columns, p.rows, p.gap, p.rail, p.tab, p.solid), (1, 4, 2.0, 6.0, 4.0, true));
+    }
+
+    #[test]
+    fn bad_panels_are_refused() {
+        assert!(parse("").is_err());
+        assert!(parse("3").is_err());
+        assert!(parse("0x2").is_err());
+        assert!(parse("3x2 gap").is_err());
+        assert!(parse("3x2 gap=0").is_err());
+        assert!(parse("3x2 hole=1 pitch=0.5").is_err());
+        assert_eq!(parse("3x2 margin=2").err().unwrap(), "unknown panel setting \"margin\"");
+    }
+
+    #[test]
+    fn copies_go_row_by_row_a_gap_apart() {
+        let p = parse("2x2 gap=5").unwrap();
+        let moved: Vec<_> = p.placements(&square(20.0)).iter().map(|t| t.apply((0.0, 0.0))).collect();
+        assert_eq!(moved, [(0.0, 0.0), (25.0, 0.0), (0.0, 25.0), (25.0, 25.0)]);
+        assert!(p.placements(&Paths::<Centi>::new(vec![])).is_empty());
+    }
+
+    #[test]
+    fn tabs_hold_every_copy_to_its_neighbours_and_the_frame() {
+        let p = parse("2x1 gap=4 rail=5 tab=3 solid").unwrap();
+        let (panel, tabs) = p.build(&square(20.0)).unwrap();
+        // left, up, down and across to the next copy, then up, down and right
+        assert_eq!(tabs, 7);
+        let b = panel.bounds();
+        assert!((b.min.x() + 9.0).abs() < 1e-2 && (b.min.y() + 9.0).abs() < 1e-2);
+        assert!((b.max.x() - 53.0).abs() < 1e-2 && (b.max.y() - 29.0).abs() < 1e-2);
+        let frame = 62.0 * 38.0 - 52.0 * 28.0;
+        assert!((area(&panel) - (frame + 2.0 * 400.0 + 7.0 * 4.0 * 3.0)).abs() < 1.0, "{}", area(&panel));
+    }
+
+    #[test]
+    fn mouse_bites_take_from_the_tabs_where_they_meet_copies() {
+        let solid = parse("2x1 gap=4 tab=3 solid").unwrap().build(&square(20.0)).unwrap();
+        let bitten = parse("2x1 gap=4 tab=3").unwrap().build(&square(20.0)).unwrap();
+        assert_eq!(solid.1, bitten.1);
+        assert!(area(&bitten.0) < area(&solid.0));
+        assert!(bitten.0.len() > solid.0.len());
+    }
+}
@@ -0,0 +1,206 @@
+use clipper2::*;
+
+/// One connected piece of the result: an outer ring and the holes directly
+/// inside it, as indices into the result paths.
+pub struct Part {
+    pub outer: usize,
+    pub holes: Vec<usize>,
+}
+
+/// Parent ring of every ring: the smallest ring containing it, if any.
+pub fn containment<P: PointScaler>(paths: &Paths<P>) -> Vec<Option<usize>> {
+    let areas: Vec<f64> = paths.iter().map(|p| p.signed_area().abs()).collect();
+    let mut parents = vec![None; paths.len()];
+    for (i, ring) in paths.iter().enumerate() {
+        let mut best: Option<usize> = None;
+        for (j, other) in paths.iter().enumerate() {
+            if i == j || areas[j] <= areas[i] {
+                continue;
+            }
+            if best.is_some_and(|b| areas[b] <= areas[j]) {
+                continue;
+            }
+            if inside(ring, other) {
+                best = Some(j);
+            }
+        }
+        parents[i] = best;
+    }
+    parents
+}
+
+/// Nesting depth of every ring, 0 for outermost rings.
+pub fn depths(parents: &[Option<usize>]) -> Vec<usize> {
+    (0..parents.len())
+        .map(|mut i| {
+            let mut depth = 0;
+            while let Some(p) = parents[i] {
+                depth += 1;
+                i = p;
+            }
+            depth
+        })
+        .collect()
+}
+
+/// Group rings into parts: rings at even depth are outlines, rings at odd
+/// depth are holes of their parent, so islands inside holes are parts too.
+pub fn parts<P: PointScaler>(paths: &Paths<P>) -> Vec<Part> {
+    let parents = containment(paths);
+    let depths = depths(&parents);
+    let mut parts: Vec<Part> = Vec::new();
+    let mut part_of = vec![usize::MAX; paths.len()];
+    for i in 0..paths.len() {
+        if depths[i].is_multiple_of(2) {
+            part_of[i] = parts.len();
+            parts.push(Part { outer: i, holes: Vec::new() });
+        }
+    }
+    for i in 0..paths.len() {
+        if let (1, Some(p)) = (depths[i] % 2, parents[i]) {
+            parts[part_of[p]].holes.push(i);
+        }
+    }
+    parts
+}
+
+/// Rings in containment order, every outline followed by its holes and
+/// every hole by the islands inside it, turned so orientation alternates
+/// with depth. Written that way with `fill-rule="evenodd"` any nesting,
+/// like a letter "B" standing in a cutout of a plate, fills as it should.
+pub fn nested<P: PointScaler>(paths: &Paths<P>) -> Paths<P> {
+    let parents = containment(paths);
+    let depths = depths(&parents);
+    let mut children = vec![Vec::new(); paths.len()];
+    let mut stack = Vec::new();
+    for (i, parent) in parents.iter().enumerate() {
+        match parent {
+            Some(p) => children[*p].push(i),
+            None => stack.push(i),
+        }
+    }
+    // depth first, keeping the rings' own order among siblings
+    stack.reverse();
+    let mut out = Paths::new(vec![]);
+    while let Some(i) = stack.pop() {
+        stack.extend(children[i].iter().rev());
+        let Some(ring) = paths.get(i) else {
+            continue;
+        };
+        if (ring.signed_area() > 0.0) == depths[i].is_multiple_of(2) {
+            out.push(ring.clone());
+        } else {
+            out.push(Path::<P>::from(ring.iter().rev().map(|p| (p.x(), p.y())).collect::<Vec<_>>()));
+        }
+    }
+    out
+}
+
+/// Outermost rings only: every hole, and whatever sits inside it, filled.
+pub fn silhouette<P: PointScaler>(paths: &Paths<P>) -> Paths<P> {
+    let parents = containment(paths);
+    paths.iter().zip(parents).filter(|(_, p)| p.is_none()).map(|(r, _)| r.clone()).collect()
+}
+
+/// What the result leaves uncovered in its bounding box: its holes and the
+/// space between its parts, for stencil backgrounds and inlay pockets.
+pub fn negative<P: PointScaler>(paths: &Paths<P>) -> Result<Paths<P>, ClipperError> {
+    if paths.is_empty() {
+        return Ok(Paths::new(vec![]));
+    }
+    let b = paths.bounds();
+    let (x0, y0, x1, y1) = (b.min.x(), b.min.y(), b.max.x(), b.max.y());
+    let frame: Paths<P> = vec![vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)]].into();
+    difference(frame, paths.clone(), FillRule::NonZero)
+}
+
+/// Only the `n` largest parts by area (outline less holes), rings kept in
+/// their order; islands count as parts of their own.
+pub fn keep_largest<P: PointScaler>(paths: &Paths<P>, n: usize) -> Paths<P> {
+    let mut parts = parts(paths);
+    let area = |part: &Part| part.paths(paths).signed_area().abs();
+    // largest first, earlier first among equals
+    parts.sort_by(|a, b| area(b).total_cmp(&area(a)));
+    let mut keep = vec![false; paths.len()];
+    for part in parts.iter().take(n) {
+        keep[part.outer] = true;
+        part.holes.iter().for_each(|&h| keep[h] = true);
+    }
+    paths.iter().zip(keep).filter(|(_, k)| *k).map(|(p, _)| p.clone()).collect()
+}
+
+/// Without parts, or holes, smaller in area than `min_area`; holes are
+/// filled in and a part's area doesn't count its holes. The parts dropped
+/// and the holes filled come second.
+pub fn drop_small<P: PointScaler>(paths: &Paths<P>, min_area: f64) -> (Paths<P>, Paths<P>) {
+    let mut keep = vec![false; paths.len()];
+    let mut dropped = Vec::new();
+    for part in parts(paths) {
+        if part.paths(paths).signed_area().abs() < min_area {
+            dropped.extend(part.paths(paths).iter().cloned());
+            continue;
+        }
+        keep[part.outer] = true;
+        for &h in &part.holes {
+            let Some(ring) = paths.get(h) else { continue };
+            keep[h] = ring.signed_area().abs() >= min_area;
+            if !keep[h] {
+                dropped.push(ring.clone());
+            }
+        }
+    }
+    (paths.iter().zip(keep).filter(|(_, k)| *k).map(|(p, _)| p.clone()).collect(), Paths::new(dropped))
+}
+
+impl Part {
+    /// Outline and holes as one set of paths.
+    pub fn paths<P: PointScaler>(&self, all: &Paths<P>) -> Paths<P> {
+        std::iter::once(self.outer).chain(self.holes.iter().copied()).filter_map(|i| all.get(i).cloned()).collect()
+    }
+}
+
+/// Whether `ring` lies inside `other`; vertices on the border don't decide.
+fn inside<P: PointScaler>(ring: &Path<P>, other: &Path<P>) -> bool {
+    for pt in ring.iter() {
+        match other.is_point_inside(*pt) {
+            PointInPolygonResult::IsInside => return true,
+            PointInPolygonResult::IsOutside => return false,
+            PointInPolygonResult::IsOn => {}
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // counterclockwise for an outline, clockwise for a hole
+    fn square(x: f64, y: f64, size: f64, hole: bool) -> Vec<(f64, f64)> {
+        let ring = vec![(x, y), (x + size, y), (x + size, y + size), (x, y + size)];
+        if hole { ring.into_iter().rev().collect() } else { ring }
+    }
+
+    #[test]
+    fn small_parts_are_dropped_and_small_holes_filled() {
+        let rings: Paths<Centi> =
+            vec![square(0.0, 0.0, 20.0, false), square(2.0, 2.0, 1.0, true), square(10.0, 10.0, 5.0, true), square(30.0, 0.0, 2.0, false)]
+                .into();
+        let (kept, dropped) = drop_small(&rings, 4.5);
+        assert_eq!(kept.len(), 2);
+        assert!((kept.signed_area() - (400.0 - 25.0)).abs() < 1e-6);
+        // the hole filled in is handed back along with the part dropped
+        let mut areas: Vec<f64> = dropped.iter().map(|r| r.signed_area()).collect();
+        areas.sort_by(f64::total_cmp);
+        assert_eq!(areas, [-1.0, 4.0]);
+    }
+
+    #[test]
+    fn a_part_counts_without_its_holes() {
+        // 36 around, 25 of it hole
+        let rings: Paths<Centi> = vec![square(0.0, 0.0, 6.0, false), square(0.5, 0.5, 5.0, true)].into();
+        let (kept, dropped) = drop_small(&rings, 20.0);
+        assert!(kept.is_empty());
+        assert_eq!(dropped.len(), 2);
+    }
+}
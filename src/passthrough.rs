@@ -0,0 +1,168 @@
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+// elements never combined, carried over as they are
+const PASSED: [&str; 3] = ["text", "image", "foreignObject"];
+// where elements are only definitions, drawn by reference if at all
+const DEFINING: [&str; 6] = ["defs", "clipPath", "mask", "pattern", "marker", "symbol"];
+
+/// `--passthrough`: what the drawing has that isn't combined, to be written
+/// along with the result as it was: every definition it may draw with,
+/// its `<style>` sheets included, and the content itself, each element in
+/// the groups it sat in, attributes and transforms kept, ids and classes
+/// as they were.
+#[derive(Default)]
+pub struct Passthrough {
+    pub defs: String,
+    pub content: String,
+    pub count: usize,
+}
+
+/// What of `xml` is passed through; images only when they aren't
+/// `traced` into shapes.
+pub fn collect(xml: &str, traced: bool) -> Passthrough {
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let Ok(doc) = roxmltree::Document::parse_with_options(xml, options) else {
+        return Passthrough::default();
+    };
+    let namespaces: String =
+        doc.root_element().namespaces().filter_map(|ns| Some(format!(r#" xmlns:{}="{}""#, ns.name()?, ns.uri()))).collect();
+    let markup = |node: roxmltree::Node| &xml[node.range()];
+    let mut out = Passthrough::default();
+
+    let passed = doc.descendants().filter(|n| {
+        PASSED.contains(&n.tag_name().name())
+            && !(traced && n.has_tag_name("image"))
+            && n.ancestors().skip(1).all(|a| !DEFINING.contains(&a.tag_name().name()) && !PASSED.contains(&a.tag_name().name()))
+    });
+    for node in passed {
+        // the element inside the groups above it, the root aside
+        let above: Vec<_> = node.ancestors().skip(1).filter(|a| a.is_element() && a.parent().is_some_and(|p| p.is_element())).collect();
+        let mut wrapped = String::new();
+        for a in above.iter().rev() {
+            let tag = if a.has_tag_name("svg") { "svg" } else { "g" };
+            wrapped.push('<');
+            wrapped.push_str(tag);
+            for attr in a.attributes().filter(|attr| attr.name() != "id" && attr.namespace().is_none()) {
+                let _ = write!(wrapped, r#" {}="{}""#, attr.name(), crate::svg::escape(attr.value()));
+            }
+            wrapped.push('>');
+        }
+        wrapped.push_str(markup(node));
+        for a in &above {
+            wrapped.push_str(if a.has_tag_name("svg") { "</svg>" } else { "</g>" });
+        }
+        let _ = write!(out.content, "<g{namespaces}>{wrapped}</g>");
+        out.count += 1;
+    }
+    if out.count == 0 {
+        return out;
+    }
+
+    // definitions, and what the content refers to wherever it is
+    let mut copied: HashSet<roxmltree::NodeId> = HashSet::new();
+    for node in doc.descendants().filter(|n| n.has_tag_name("defs") || n.has_tag_name("style")) {
+        if node.ancestors().skip(1).any(|a| copied.contains(&a.id())) {
+            continue;
+        }
+        copied.insert(node.id());
+        let inner = if node.has_tag_name("defs") { children(xml, node) } else { markup(node) };
+        out.defs.push_str(inner);
+    }
+    let mut pending = referenced(&out.content);
+    let mut seen = HashSet::new();
+    while let Some(id) = pending.pop() {
+        if !seen.insert(id.clone()) {
+            continue;
+        }
+        let Some(target) = doc.descendants().find(|n| n.attribute("id") == Some(id.as_str())) else { continue };
+        if target.ancestors().any(|a| copied.contains(&a.id())) {
+            continue;
+        }
+        copied.insert(target.id());
+        pending.extend(referenced(markup(target)));
+        out.defs.push_str(markup(target));
+    }
+    if !out.defs.is_empty() {
+        out.defs = format!("<g{namespaces}>{}</g>", out.defs);
+    }
+    out
+}
+
+/// The markup between an element's tags.
+fn children<'a>(xml: &'a str, node: roxmltree::Node) -> &'a str {
+    match (node.first_child(), node.last_child()) {
+        (Some(first), Some(last)) => &xml[first.range().start..last.range().end],
+        _ => "",
+    }
+}
+
+/// Ids some markup points to, with `url(#id)` or a `#id` link.
+fn referenced(markup: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    for (start, _) in markup.match_indices('#') {
+        let before = &markup[..start];
+        if !(before.ends_with("url(") || before.ends_with("href=\"") || before.ends_with("href='")) {
+            continue;
+        }
+        let id: String = markup[start + 1..].chars().take_while(|c| !matches!(c, ')' | '"' | '\'' | ' ')).collect();
+        if !id.is_empty() {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" viewBox="0 0 100 100">
+<style>.t { font-size: 4px }</style>
+<defs><linearGradient id="base"><stop offset="0"/></linearGradient><clipPath id="c"><text>not content</text></clipPath></defs>
+<linearGradient id="ink" xlink:href="#base"/>
+<g id="layer" transform="translate(5 5)"><rect width="10" height="10"/><text class="t" fill="url(#ink)">A<tspan>b</tspan></text></g>
+<image href="photo.png" width="10" height="10"/>
+</svg>"##;
+
+    #[test]
+    fn content_keeps_its_groups_without_their_ids() {
+        let passed = collect(SVG, false);
+        assert_eq!(passed.count, 2);
+        assert!(passed.content.contains(r#"<g transform="translate(5 5)"><text class="t" fill="url(#ink)">A<tspan>b</tspan></text></g>"#));
+        assert!(passed.content.contains(r#"<image href="photo.png" width="10" height="10"/>"#));
+        assert!(passed.content.starts_with(r#"<g xmlns:xlink="http://www.w3.org/1999/xlink">"#));
+        assert!(!passed.content.contains("rect") && !passed.content.contains("not content"));
+    }
+
+    #[test]
+    fn traced_images_are_not_passed() {
+        let passed = collect(SVG, true);
+        assert_eq!(passed.count, 1);
+        assert!(!passed.content.contains("image"));
+    }
+
+    #[test]
+    fn definitions_come_along_with_what_they_refer_to() {
+        let defs = collect(SVG, false).defs;
+        for (markup, copies) in
+            [(".t { font-size: 4px }", 1), (r#"<linearGradient id="base">"#, 1), (r##"<linearGradient id="ink" xlink:href="#base"/>"##, 1)]
+        {
+            assert_eq!(defs.matches(markup).count(), copies, "{markup}");
+        }
+    }
+
+    #[test]
+    fn nothing_to_pass_brings_no_definitions() {
+        let passed =
+            collect(r#"<svg xmlns="http://www.w3.org/2000/svg"><defs><g id="a"/></defs><rect width="1" height="1"/></svg>"#, false);
+        assert_eq!((passed.count, passed.content.as_str(), passed.defs.as_str()), (0, "", ""));
+        assert_eq!(collect("not svg", false).count, 0);
+    }
+
+    #[test]
+    fn references_are_urls_and_links() {
+        let ids = referenced(r##"<a fill="url(#one)" href="#two" xlink:href='#three' stroke="#fff" style="mask: url(#four) "/>"##);
+        assert_eq!(ids, ["one", "two", "three", "four"]);
+    }
+}
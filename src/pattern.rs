@@ -0,0 +1,115 @@
+use clipper2::*;
+use lyon::path::Path;
+use lyon::path::iterator::PathIterator;
+use usvg::tiny_skia_path::PathSegment;
+
+use crate::Contour;
+use crate::transform::Affine;
+
+// tiles a single fill may be made of before it is left as it is
+const MAX_TILES: usize = 10_000;
+
+/// `--pattern-fill`: the pattern's tiles laid across `path`'s area, each
+/// tile's shapes clipped to it, in working units where `to_working` puts
+/// the path. None when there is nothing in the tiles or too many of them.
+pub fn fill<P: PointScaler>(path: &usvg::Path, pattern: &usvg::Pattern, to_working: Affine, tolerance: f32) -> Option<Vec<Contour>> {
+    let fill_rule = match path.fill().map(|f| f.rule()) {
+        Some(usvg::FillRule::EvenOdd) => FillRule::EvenOdd,
+        _ => FillRule::NonZero,
+    };
+    // its holes as the fill rule makes them
+    let area = union(Paths::<P>::from(rings(path.data(), to_working, tolerance)), Paths::<P>::new(vec![]), fill_rule).ok()?;
+
+    // the path's bounds in the pattern's own space
+    let into_pattern = Affine::of_usvg(pattern.transform()).inverse()?;
+    let b = path.data().bounds();
+    let (x0, y0, w, h) = into_pattern.apply_rect((b.left() as f64, b.top() as f64, b.width() as f64, b.height() as f64));
+    let tile = pattern.rect();
+    let (tx, ty, tw, th) = (tile.x() as f64, tile.y() as f64, tile.width() as f64, tile.height() as f64);
+    let columns = ((x0 - tx) / tw).floor() as i64..((x0 + w - tx) / tw).ceil() as i64;
+    let rows = ((y0 - ty) / th).floor() as i64..((y0 + h - ty) / th).ceil() as i64;
+    if columns.clone().count() * rows.clone().count() > MAX_TILES {
+        return None;
+    }
+
+    let mut shapes = Vec::new();
+    collect(pattern.root(), &mut shapes);
+    let mut pieces: Vec<Contour> = Vec::new();
+    for i in columns {
+        for j in rows.clone() {
+            let at = Affine::translate(tx + i as f64 * tw, ty + j as f64 * th).then(Affine::of_usvg(pattern.transform())).then(to_working);
+            for (data, own) in &shapes {
+                pieces.extend(rings(data, own.then(at), tolerance));
+            }
+        }
+    }
+    if pieces.is_empty() {
+        return None;
+    }
+    let kept = intersect(Paths::<P>::from(pieces), area, FillRule::NonZero).ok()?;
+    Some(kept.iter().map(|ring| ring.iter().map(|p| (p.x(), p.y())).collect()).collect())
+}
+
+/// Filled paths drawn in a pattern's tile, with the transform within it:
+/// the groups' own, as a tile has no place of its own in the drawing.
+fn collect<'a>(group: &'a usvg::Group, out: &mut Vec<(&'a usvg::tiny_skia_path::Path, Affine)>) {
+    fn walk<'a>(group: &'a usvg::Group, within: Affine, out: &mut Vec<(&'a usvg::tiny_skia_path::Path, Affine)>) {
+        let within = Affine::of_usvg(group.transform()).then(within);
+        for node in group.children() {
+            match node {
+                usvg::Node::Path(path) if path.fill().is_some() => out.push((path.data(), within)),
+                usvg::Node::Group(group) => walk(group, within, out),
+                _ => {}
+            }
+        }
+    }
+    walk(group, Affine::IDENTITY, out);
+}
+
+/// Every subpath of `data` moved by `t` and flattened, as closed rings.
+pub fn rings(data: &usvg::tiny_skia_path::Path, t: Affine, tolerance: f32) -> Vec<Contour> {
+    let at = |p: usvg::tiny_skia_path::Point| {
+        let (x, y) = t.apply((p.x as f64, p.y as f64));
+        lyon::math::point(x as f32, y as f32)
+    };
+    let mut builder = Path::builder();
+    let mut open = false;
+    for segment in data.segments() {
+        match segment {
+            PathSegment::MoveTo(p) => {
+                if open {
+                    builder.close();
+                }
+                builder.begin(at(p));
+                open = true;
+            }
+            PathSegment::LineTo(p) => {
+                builder.line_to(at(p));
+            }
+            PathSegment::QuadTo(p1, p2) => {
+                builder.quadratic_bezier_to(at(p1), at(p2));
+            }
+            PathSegment::CubicTo(p1, p2, p3) => {
+                builder.cubic_bezier_to(at(p1), at(p2), at(p3));
+            }
+            PathSegment::Close => {
+                builder.close();
+                open = false;
+            }
+        }
+    }
+    if open {
+        builder.close();
+    }
+    let mut out = Vec::new();
+    let mut ring: Contour = Vec::new();
+    for event in builder.build().iter().flattened(tolerance) {
+        match event {
+            lyon::path::Event::Begin { at } => ring = vec![(at.x as f64, at.y as f64)],
+            lyon::path::Event::Line { to, .. } => ring.push((to.x as f64, to.y as f64)),
+            lyon::path::Event::End { .. } if ring.len() >= 3 => out.push(std::mem::take(&mut ring)),
+            _ => {}
+        }
+    }
+    out
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use clipper2::*;
+
+use crate::Contour;
+use crate::attrs::parse_length;
+
+// vertices of a round hole
+const ROUND: usize = 32;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Hole {
+    Circle,
+    Hexagon,
+    /// A stadium, `length` long.
+    Slot,
+}
+
+/// `--perforate "hex size=4 pitch=6 margin=5"`: holes `size` across, at
+/// `pitch` between centres on a staggered grid, kept `margin` clear of the
+/// part's edges.
+#[derive(Clone, Copy)]
+pub struct Pattern {
+    pub hole: Hole,
+    pub size: f64,
+    pub pitch: f64,
+    pub margin: f64,
+    pub length: f64,
+}
+
+pub fn parse(spec: &str) -> Result<Pattern, String> {
+    let mut words = spec.split_whitespace();
+    let hole = match words.next() {
+        Some("circle") => Hole::Circle,
+        Some("hex") | Some("hexagon") => Hole::Hexagon,
+        Some("slot") => Hole::Slot,
+        other => return Err(format!("unknown hole {other:?}, expected circle, hex or slot")),
+    };
+    let mut keys = HashMap::new();
+    for word in words {
+        let (key, value) = word.split_once('=').ok_or_else(|| format!("expected KEY=VALUE, got {word:?}"))?;
+        if !matches!(key, "size" | "pitch" | "margin" | "length") {
+            return Err(format!("unknown perforation setting {key:?}"));
+        }
+        keys.insert(key, parse_length(value)?);
+    }
+    let size = *keys.get("size").ok_or("a pattern needs size=")?;
+    let pitch = keys.get("pitch").copied().unwrap_or(size * 1.5);
+    let length = keys.get("length").copied().unwrap_or(size * 3.0);
+    if size <= 0.0 || pitch <= 0.0 {
+        return Err("size and pitch need to be above 0".into());
+    }
+    Ok(Pattern { hole, size, pitch, margin: keys.get("margin").copied().unwrap_or(pitch), length })
+}
+
+impl Pattern {
+    /// The same pattern with every length multiplied by `factor`.
+    pub fn scaled(self, factor: f64) -> Pattern {
+        Pattern { size: self.size * factor, pitch: self.pitch * factor, margin: self.margin * factor, length: self.length * factor, ..self }
+    }
+
+    /// Outline of one hole centred on `(x, y)`.
+    fn hole_at(&self, x: f64, y: f64) -> Contour {
+        let r = self.size / 2.0;
+        match self.hole {
+            Hole::Circle => ring(x, y, r, ROUND, 0.0),
+            Hole::Hexagon => ring(x, y, r, 6, PI / 6.0),
+            Hole::Slot => {
+                // two half circles joined by straight sides
+                let half = (self.length / 2.0 - r).max(0.0);
+                let mut out = Vec::with_capacity(ROUND + 2);
+                for i in 0..=ROUND / 2 {
+                    let a = -PI / 2.0 + PI * i as f64 / (ROUND / 2) as f64;
+                    out.push((x + half + r * a.cos(), y + r * a.sin()));
+                }
+                for i in 0..=ROUND / 2 {
+                    let a = PI / 2.0 + PI * i as f64 / (ROUND / 2) as f64;
+                    out.push((x - half + r * a.cos(), y + r * a.sin()));
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Punch the pattern into every part of `paths` that `selected` picks, only
+/// whole holes lying inside the part shrunk by the margin.
+pub fn perforate<P: PointScaler>(
+    paths: &Paths<P>,
+    pattern: &Pattern,
+    selected: impl Fn(&Paths<P>) -> bool,
+) -> Result<(Paths<P>, usize), ClipperError> {
+    let mut holes: Vec<Contour> = Vec::new();
+    for part in crate::parts::parts(paths) {
+        let rings = part.paths(paths);
+        if !selected(&rings) {
+            continue;
+        }
+        let room: Paths<P> = inflate(rings.clone(), -pattern.margin, JoinType::Miter, EndType::Polygon, 2.0);
+        if room.is_empty() {
+            continue;
+        }
+        let b = room.bounds();
+        // staggered rows; slots line up in columns instead
+        let (dx, dy, shift) = match pattern.hole {
+            Hole::Slot => (pattern.length + pattern.pitch - pattern.size, pattern.pitch, 0.0),
+            _ => (pattern.pitch, pattern.pitch * 3f64.sqrt() / 2.0, pattern.pitch / 2.0),
+        };
+        let mut y = b.min.y();
+        let mut row = 0;
+        while y <= b.max.y() {
+            let mut x = b.min.x() + if row % 2 == 1 { shift } else { 0.0 };
+            while x <= b.max.x() {
+                let hole = pattern.hole_at(x, y);
+                if hole.iter().all(|&pt| inside(&room, pt)) {
+                    holes.push(hole);
+                }
+                x += dx;
+            }
+            y += dy;
+            row += 1;
+        }
+    }
+    let punched = holes.len();
+    if holes.is_empty() {
+        return Ok((paths.clone(), 0));
+    }
+    Ok((difference(paths.clone(), holes, FillRule::NonZero)?, punched))
+}
+
+/// A regular polygon of `sides` around `(x, y)`, `r` to its corners,
+/// the first corner `turn` radians round.
+pub fn ring(x: f64, y: f64, r: f64, sides: usize, turn: f64) -> Contour {
+    (0..sides)
+        .map(|i| {
+            let a = turn + 2.0 * PI * i as f64 / sides as f64;
+            (x + r * a.cos(), y + r * a.sin())
+        })
+        .collect()
+}
+
+// inside under the nonzero rule, so inside an outline and out of its holes
+fn inside<P: PointScaler>(paths: &Paths<P>, (x, y): (f64, f64)) -> bool {
+    let pt = Point::<P>::new(x, y);
+    let mut winding = 0;
+    for ring in paths.iter() {
+        if ring.is_point_inside(pt) == PointInPolygonResult::IsInside {
+            winding += if ring.signed_area() > 0.0 { 1 } else { -1 };
+        }
+    }
+    winding != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(size: f64) -> Paths<Centi> {
+        vec![vec![(0.0, 0.0), (size, 0.0), (size, size), (0.0, size)]].into()
+    }
+
+    #[test]
+    fn pitch_margin_and_length_follow_the_size() {
+        let p = parse("hex size=4").unwrap();
+        assert!(p.hole == Hole::Hexagon);
+        assert_eq!((p.size, p.pitch, p.margin, p.length), (4.0, 6.0, 6.0, 12.0));
+        let p = parse("slot size=2 pitch=5 margin=1 length=10").unwrap();
+        assert!(p.hole == Hole::Slot);
+        assert_eq!((p.size, p.pitch, p.margin, p.length), (2.0, 5.0, 1.0, 10.0));
+    }
+
+    #[test]
+    fn bad_patterns_are_refused() {
+        assert!(parse("star size=4").is_err());
+        assert!(parse("circle").is_err());
+        assert!(parse("circle size=0").is_err());
+        assert!(parse("circle size").is_err());
+        assert_eq!(parse("circle size=4 spacing=6").err().unwrap(), "unknown perforation setting \"spacing\"");
+    }
+
+    #[test]
+    fn holes_keep_the_margin_clear() {
+        let pattern = parse("circle size=4 pitch=10 margin=5").unwrap();
+        let (punched, holes) = perforate(&square(100.0), &pattern, |_| true).unwrap();
+        assert!(holes > 0);
+        assert_eq!(punched.len(), holes + 1);
+        for ring in punched.iter().filter(|r| r.signed_area() < 0.0) {
+            let b = ring.bounds();
+            assert!(b.min.x() >= 5.0 - 1e-2 && b.min.y() >= 5.0 - 1e-2);
+            assert!(b.max.x() <= 95.0 + 1e-2 && b.max.y() <= 95.0 + 1e-2);
+        }
+    }
+
+    #[test]
+    fn unselected_parts_are_left_whole() {
+        let pattern = parse("circle size=4").unwrap();
+        let (punched, holes) = perforate(&square(100.0), &pattern, |_| false).unwrap();
+        assert_eq!(holes, 0);
+        assert_eq!(punched.len(), 1);
+    }
+}
@@ -0,0 +1,39 @@
+use clipper2::PointScaler;
+
+// largest integer coordinate wanted, well inside clipper's ±4.6e18 so
+// that products of coordinates in its intersection math stay exact enough
+const LIMIT: f64 = 1e10;
+const SCALES: [u32; 5] = [1, 10, 100, 1000, 1_000_000];
+
+/// Micrometre grid, for tiny artwork clipper's own scalers lose detail on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Micro;
+
+impl PointScaler for Micro {
+    const MULTIPLIER: f64 = 1_000_000.0;
+}
+
+/// How many clipper grid steps make one SVG unit.
+#[derive(Clone, Copy)]
+pub enum Precision {
+    Fixed(u32),
+    /// The finest scale the input's coordinates fit in.
+    Auto,
+}
+
+pub fn parse(s: &str) -> Result<Precision, String> {
+    if s == "auto" {
+        return Ok(Precision::Auto);
+    }
+    match s.parse() {
+        Ok(scale) if SCALES.contains(&scale) => Ok(Precision::Fixed(scale)),
+        _ => Err(format!("expected auto or one of {SCALES:?}, got {s:?}")),
+    }
+}
+
+/// The finest scale keeping coordinates up to `reach`, with room for the
+/// default gap, within clipper's comfortable range.
+pub fn auto(reach: f64) -> u32 {
+    let reach = reach + crate::OFFSET;
+    SCALES.iter().rev().copied().find(|&s| reach * s as f64 <= LIMIT).unwrap_or(1)
+}
@@ -0,0 +1,149 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// settings the page has sliders for, each named after its option:
+// name, min, max, step, start
+const SLIDERS: [(&str, f64, f64, f64, f64); 4] = [
+    ("tolerance", 0.01, 2.0, 0.01, 0.1),
+    ("gap", 0.0, 50.0, 0.5, crate::OFFSET),
+    ("min-area", 0.0, 2000.0, 10.0, 0.0),
+    ("join-gap", 0.0, 20.0, 0.1, 0.0),
+];
+
+/// `svg-combiner preview input.svg [--port N]`: a local page with sliders
+/// for the main settings, showing the input next to the result, which is
+/// combined again whenever a slider moves. Further arguments are passed on
+/// to every run.
+pub fn serve(mut args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut port = 8080;
+    if let Some(i) = args.iter().position(|a| a == "--port") {
+        port = args.get(i + 1).and_then(|p| p.parse().ok()).ok_or("--port needs a number")?;
+        args.drain(i..i + 2);
+    }
+    let input = args.iter().position(|a| !a.starts_with('-')).ok_or("preview needs an input SVG")?;
+    let input = std::fs::canonicalize(args.remove(input))?;
+    let work = std::env::temp_dir().join(format!("svg-combiner-preview-{}", std::process::id()));
+    std::fs::create_dir_all(&work)?;
+
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    tracing::info!("👀 Previewing {} at http://127.0.0.1:{port}/ (Ctrl+C to stop)", input.display());
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::warn!("preview: {e}");
+                continue;
+            }
+        };
+        if let Err(e) = answer(stream, &input, &work, &args) {
+            tracing::warn!("preview: {e}");
+        }
+    }
+    Ok(())
+}
+
+fn answer(mut stream: TcpStream, input: &Path, work: &Path, extra: &[String]) -> std::io::Result<()> {
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+    let target = request.split_whitespace().nth(1).unwrap_or("/");
+    let (route, query) = target.split_once('?').unwrap_or((target, ""));
+    let (status, kind, body) = match route {
+        "/" => ("200 OK", "text/html; charset=utf-8", page().into_bytes()),
+        "/input.svg" => ("200 OK", "image/svg+xml", std::fs::read(input)?),
+        "/output.svg" => match combine(input, work, query, extra) {
+            Ok(svg) => ("200 OK", "image/svg+xml", svg),
+            Err(e) => ("500 Internal Server Error", "text/plain; charset=utf-8", e.into_bytes()),
+        },
+        _ => ("404 Not Found", "text/plain", b"not found".to_vec()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {kind}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(&body)
+}
+
+/// Run this program on the input with the settings in `query`, in `work`
+/// so its output.svg doesn't land next to the input.
+fn combine(input: &Path, work: &Path, query: &str, extra: &[String]) -> Result<Vec<u8>, String> {
+    let mut args: Vec<String> = vec![input.display().to_string()];
+    for pair in query.split('&') {
+        let Some((key, value)) = pair.split_once('=') else {
+            continue;
+        };
+        if !SLIDERS.iter().any(|s| s.0 == key) {
+            continue;
+        }
+        let value: f64 = value.parse().map_err(|_| format!("bad {key} {value:?}"))?;
+        // nothing to filter or join at 0
+        if value > 0.0 || key == "gap" {
+            args.extend([format!("--{key}"), value.to_string()]);
+        }
+    }
+    args.extend(extra.iter().cloned());
+    // a run that fails, or writes elsewhere, mustn't leave the last result showing
+    let output = work.join("output.svg");
+    match std::fs::remove_file(&output) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(format!("{}: {e}", output.display())),
+        _ => {}
+    }
+    let exe: PathBuf = std::env::current_exe().map_err(|e| e.to_string())?;
+    let run = Command::new(exe).args(&args).current_dir(work).output().map_err(|e| e.to_string())?;
+    if !run.status.success() {
+        return Err(String::from_utf8_lossy(&run.stderr).into_owned());
+    }
+    std::fs::read(&output).map_err(|e| format!("the run wrote no output.svg: {e}"))
+}
+
+fn page() -> String {
+    let sliders: String = SLIDERS
+        .iter()
+        .map(|(name, min, max, step, start)| {
+            format!(
+                r#"<label>{name} <input type="range" name="{name}" min="{min}" max="{max}" step="{step}" value="{start}"> <output>{start}</output></label>"#
+            )
+        })
+        .collect();
+    format!(
+        r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>svg-combiner preview</title>
+<style>
+body {{ font-family: sans-serif; margin: 1em; }}
+label {{ display: inline-block; margin-right: 2em; }}
+.panes {{ display: flex; gap: 1em; margin-top: 1em; }}
+.panes figure {{ flex: 1; margin: 0; border: 1px solid #ccc; }}
+.panes img {{ width: 100%; }}
+#error {{ color: #b00; white-space: pre-wrap; }}
+</style></head>
+<body>
+<form>{sliders}</form>
+<div id="error"></div>
+<div class="panes">
+<figure><figcaption>input</figcaption><img src="/input.svg"></figure>
+<figure><figcaption>output</figcaption><img id="output"></figure>
+</div>
+<script>
+const form = document.querySelector('form');
+const output = document.getElementById('output');
+const error = document.getElementById('error');
+function update() {{
+  const query = new URLSearchParams(new FormData(form)).toString();
+  // a failed run shows its error in place of an out of date result
+  const failed = text => {{ error.textContent = text; output.removeAttribute('src'); }};
+  fetch('/output.svg?' + query).then(async r => {{
+    if (!r.ok) {{ failed(await r.text()); return; }}
+    error.textContent = '';
+    output.src = URL.createObjectURL(await r.blob());
+  }}).catch(e => failed(String(e)));
+}}
+form.addEventListener('input', e => e.target.nextElementSibling.value = e.target.value);
+form.addEventListener('change', update);
+update();
+</script>
+</body></html>
+"#
+    )
+}
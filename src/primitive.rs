@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use lyon::math::{Box2D, point, vector};
+use lyon::path::builder::BorderRadii;
+use lyon::path::{Path, Winding};
+
+use crate::transform::Affine;
+
+/// A shape given on the command line, like `"circle cx=50 cy=50 r=20"` or
+/// `"rect x=0 y=0 w=100 h=10 rx=3"`, with an optional `id=` and `offset=`.
+pub struct Primitive {
+    pub id: Option<String>,
+    pub offset: Option<f64>,
+    path: Path,
+}
+
+pub fn parse(spec: &str) -> Result<Primitive, String> {
+    let mut words = spec.split_whitespace();
+    let kind = words.next().ok_or("expected a shape such as \"circle cx=50 cy=50 r=20\"")?;
+    let mut id = None;
+    let mut keys = HashMap::new();
+    for word in words {
+        let (key, value) = word.split_once('=').ok_or_else(|| format!("expected KEY=VALUE, got {word:?}"))?;
+        if key == "id" {
+            id = Some(value.to_string());
+            continue;
+        }
+        let value: f64 = value.parse().map_err(|_| format!("bad number {value:?} for {key}"))?;
+        keys.insert(key, value);
+    }
+    let get = |names: &[&str]| names.iter().find_map(|n| keys.get(n).copied());
+    let need = |names: &[&str]| get(names).ok_or_else(|| format!("{kind} needs {}", names[0]));
+
+    let mut builder = Path::builder();
+    match kind {
+        "circle" => {
+            let center = point(need(&["cx"])? as f32, need(&["cy"])? as f32);
+            builder.add_circle(center, need(&["r"])? as f32, Winding::Positive);
+        }
+        "ellipse" => {
+            let center = point(need(&["cx"])? as f32, need(&["cy"])? as f32);
+            let radii = vector(need(&["rx"])? as f32, need(&["ry"])? as f32);
+            builder.add_ellipse(center, radii, lyon::math::Angle::zero(), Winding::Positive);
+        }
+        "rect" => {
+            let (x, y) = (get(&["x"]).unwrap_or(0.0), get(&["y"]).unwrap_or(0.0));
+            let (w, h) = (need(&["w", "width"])?, need(&["h", "height"])?);
+            let rect = Box2D::new(point(x as f32, y as f32), point((x + w) as f32, (y + h) as f32));
+            // corners clamped like SVG's, to half the shorter side
+            let r = get(&["rx", "r"]).unwrap_or(0.0).min(w.min(h) / 2.0);
+            builder.add_rounded_rectangle(&rect, &BorderRadii::new(r as f32), Winding::Positive);
+        }
+        _ => return Err(format!("unknown shape {kind:?}, expected circle, ellipse or rect")),
+    }
+    Ok(Primitive { id, offset: get(&["offset"]), path: builder.build() })
+}
+
+/// A circle named `id`, with no gap of its own.
+pub fn circle(cx: f64, cy: f64, r: f64, id: String) -> Primitive {
+    let mut builder = Path::builder();
+    builder.add_circle(point(cx as f32, cy as f32), r as f32, Winding::Positive);
+    Primitive { id: Some(id), offset: None, path: builder.build() }
+}
+
+impl Primitive {
+    /// The outline mapped by `t`.
+    pub fn path(&self, t: &Affine) -> Path {
+        let m = lyon::math::Transform::new(t.a as f32, t.b as f32, t.c as f32, t.d as f32, t.e as f32, t.f as f32);
+        self.path.clone().transformed(&m)
+    }
+}
@@ -0,0 +1,144 @@
+use std::io::{IsTerminal, Stderr};
+use std::time::Instant;
+
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Gauge, List, Row, Table};
+
+/// `--tui`: a panel on stderr, redrawn as the pipeline moves on, with the
+/// time every stage took, what it produced and any warnings, and for a
+/// batch how many files are made and failed so far. The usual log still
+/// goes to stdout, so it can be sent elsewhere while watching.
+pub struct Progress {
+    terminal: Option<Terminal<CrosstermBackend<Stderr>>>,
+    started: Instant,
+    last: Instant,
+    stages: Vec<(String, f64, String)>,
+    warnings: Vec<String>,
+    batch: Option<Batch>,
+}
+
+/// Files of a batch: how many there are to make, made and failed, and
+/// the last few finished.
+struct Batch {
+    total: usize,
+    made: usize,
+    failed: usize,
+    recent: Vec<(String, bool)>,
+}
+
+// finished files of a batch listed at a time
+const RECENT: usize = 8;
+
+impl Progress {
+    /// Only drawn when `live` and stderr is a terminal.
+    pub fn new(live: bool) -> Progress {
+        let now = Instant::now();
+        let terminal =
+            (live && std::io::stderr().is_terminal()).then(|| Terminal::new(CrosstermBackend::new(std::io::stderr())).ok()).flatten();
+        Progress { terminal, started: now, last: now, stages: Vec::new(), warnings: Vec::new(), batch: None }
+    }
+
+    /// A stage finished just now, having produced `detail`.
+    pub fn stage(&mut self, name: &str, detail: impl Into<String>) {
+        let now = Instant::now();
+        let ms = now.duration_since(self.last).as_secs_f64() * 1000.0;
+        self.last = now;
+        self.stages.push((name.to_string(), ms, detail.into()));
+        self.draw();
+    }
+
+    pub fn warn(&mut self, warning: impl Into<String>) {
+        self.warnings.push(warning.into());
+        self.draw();
+    }
+
+    /// A batch of `total` files to make begins.
+    pub fn batch(&mut self, total: usize) {
+        self.batch = Some(Batch { total, made: 0, failed: 0, recent: Vec::new() });
+        self.draw();
+    }
+
+    /// A file of the batch is done with, made or failed.
+    pub fn file(&mut self, name: impl Into<String>, made: bool) {
+        if let Some(batch) = &mut self.batch {
+            if made {
+                batch.made += 1;
+            } else {
+                batch.failed += 1;
+            }
+            batch.recent.push((name.into(), made));
+            if batch.recent.len() > RECENT {
+                batch.recent.remove(0);
+            }
+        }
+        self.last = Instant::now();
+        self.draw();
+    }
+
+    fn draw(&mut self) {
+        let Some(terminal) = &mut self.terminal else {
+            return;
+        };
+        let total = self.last.duration_since(self.started).as_secs_f64() * 1000.0;
+        let (stages, warnings, batch) = (&self.stages, &self.warnings, &self.batch);
+        // the log on stdout may have scrolled it, so all of it is drawn again
+        let _ = terminal.clear();
+        let _ = terminal.draw(|frame| {
+            let batch_height = batch.as_ref().map_or(0, |b| 3 + b.recent.len() as u16);
+            let [top, middle, bottom] = Layout::vertical([
+                Constraint::Length(batch_height),
+                Constraint::Length(if stages.is_empty() { 0 } else { stages.len() as u16 + 2 }),
+                Constraint::Min(0),
+            ])
+            .areas(frame.area());
+
+            if let Some(batch) = batch {
+                let [gauge, list] = Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(top);
+                let done = batch.made + batch.failed;
+                let label = format!("{done} of {} · {} made · {} failed", batch.total, batch.made, batch.failed);
+                let colour = if batch.failed > 0 { Color::Yellow } else { Color::Green };
+                frame.render_widget(
+                    Gauge::default()
+                        .block(Block::bordered().title(" batch "))
+                        .gauge_style(Style::new().fg(colour))
+                        .ratio(if batch.total == 0 { 1.0 } else { done as f64 / batch.total as f64 })
+                        .label(label),
+                    gauge,
+                );
+                let files = batch.recent.iter().map(|(name, made)| match made {
+                    true => Line::from(format!("  ✓ {name}")).green(),
+                    false => Line::from(format!("  ✗ {name}")).red(),
+                });
+                frame.render_widget(List::new(files), list);
+            }
+
+            // a batch has no stages of its own, only files
+            if !stages.is_empty() {
+                let rows = stages.iter().map(|(name, ms, detail)| Row::new([format!("✓ {name}"), format!("{ms:>9.1} ms"), detail.clone()]));
+                let widths = [Constraint::Length(14), Constraint::Length(12), Constraint::Min(0)];
+                let table = Table::new(rows, widths)
+                    .block(Block::bordered().title(" svg-combiner ").title_bottom(format!(" total {total:.1} ms ")));
+                frame.render_widget(table, middle);
+            }
+
+            if !warnings.is_empty() {
+                let lines = warnings.iter().map(|w| Line::from(format!("⚠ {w}")));
+                let title = format!(" {} warning(s) ", warnings.len());
+                frame.render_widget(List::new(lines).block(Block::bordered().title(title)).yellow(), bottom);
+            }
+        });
+    }
+}
+
+impl Drop for Progress {
+    // the panel is left on screen, but not the hidden cursor
+    fn drop(&mut self) {
+        if let Some(terminal) = &mut self.terminal {
+            let _ = terminal.show_cursor();
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use clipper2::*;
+
+use crate::FlatShape;
+
+// overlaps smaller than this (square user units) are rounding noise
+const MIN_OVERLAP: f64 = 0.01;
+
+/// What each shape still owns in the result: its own area minus the gaps
+/// carved by every shape combined after it.
+pub fn surviving<P: PointScaler>(shapes: &[FlatShape]) -> Result<Vec<Paths<P>>, ClipperError> {
+    let mut cover: Paths<P> = Paths::new(vec![]);
+    let mut owned = vec![Paths::new(vec![]); shapes.len()];
+    for (i, shape) in shapes.iter().enumerate().rev() {
+        let own: Paths<P> = shape.contours.clone().into();
+        owned[i] = if cover.is_empty() { own.clone() } else { difference(own.clone(), cover.clone(), FillRule::NonZero)? };
+        let expanded = inflate(own, shape.offset, JoinType::Round, EndType::Polygon, 0.0);
+        cover = union(cover, expanded, FillRule::NonZero)?;
+    }
+    Ok(owned)
+}
+
+/// Indices of the shapes whose surviving area overlaps `region`.
+pub fn sources<P: PointScaler>(region: &Paths<P>, owned: &[Paths<P>]) -> Result<Vec<usize>, ClipperError> {
+    let mut found = Vec::new();
+    if region.is_empty() {
+        return Ok(found);
+    }
+    let b = region.bounds();
+    for (i, own) in owned.iter().enumerate() {
+        if own.is_empty() || !overlaps(&b, &own.bounds()) {
+            continue;
+        }
+        let common = intersect(region.clone(), own.clone(), FillRule::NonZero)?;
+        if common.signed_area().abs() > MIN_OVERLAP {
+            found.push(i);
+        }
+    }
+    Ok(found)
+}
+
+fn overlaps<P: PointScaler>(a: &Bounds<P>, b: &Bounds<P>) -> bool {
+    a.min.x() <= b.max.x() && b.min.x() <= a.max.x() && a.min.y() <= b.max.y() && b.min.y() <= a.max.y()
+}
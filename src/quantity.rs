@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::attrs::Attrs;
+
+/// How many of each element to make, one `id,qty` line of a CSV file
+/// each; lines that aren't that, like a header, are skipped, and so are
+/// `#` comments, but a quantity that is a number and not a whole one is
+/// refused.
+pub fn read(path: &str) -> Result<HashMap<String, usize>, Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(path)?;
+    let mut quantities = HashMap::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let fields: Vec<&str> = line.split([',', ';', '\t']).map(str::trim).collect();
+        let (Some(id), Some(qty)) = (fields.first(), fields.get(1)) else {
+            continue;
+        };
+        match qty.parse::<usize>() {
+            Ok(qty) => quantities.insert(id.to_string(), qty),
+            Err(_) if qty.parse::<f64>().is_ok() => return Err(format!("{path}:{}: {qty} is not a whole number of copies", n + 1).into()),
+            Err(_) => continue,
+        };
+    }
+    Ok(quantities)
+}
+
+/// Copies wanted of a part made from the elements `sources`: the most any
+/// of them asks for, in `listed` or its own `data-qty`, and at least one.
+/// A `data-qty` that isn't a whole number is an error.
+pub fn of(sources: &[String], listed: &HashMap<String, usize>, attributes: &HashMap<String, Attrs>) -> Result<usize, String> {
+    let mut most = 1;
+    for id in sources {
+        let qty = match listed.get(id) {
+            Some(&qty) => qty,
+            None => match attributes.get(id).and_then(|a| a.get("qty")).map(|q| q.trim()) {
+                Some(q) => q.parse().map_err(|_| format!("{id}: data-qty {q:?} is not a whole number of copies"))?,
+                None => continue,
+            },
+        };
+        most = most.max(qty);
+    }
+    Ok(most)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    fn qty(id: &str, value: &str) -> (String, Attrs) {
+        (id.to_string(), HashMap::from([("qty".to_string(), value.to_string())]))
+    }
+
+    #[test]
+    fn lists_skip_headers_and_comments() {
+        let path = std::env::temp_dir().join(format!("svg-combiner-qty-{}.csv", std::process::id()));
+        fs::write(&path, "id,qty\nleg,4 # one per corner\ntop;1\n\n# shelf,2\nback\t2\n").unwrap();
+        let listed = read(&path.display().to_string()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(listed.len(), 3);
+        assert_eq!((listed["leg"], listed["top"], listed["back"]), (4, 1, 2));
+    }
+
+    #[test]
+    fn fractions_of_copies_are_refused_in_lists() {
+        let path = std::env::temp_dir().join(format!("svg-combiner-qty-frac-{}.csv", std::process::id()));
+        fs::write(&path, "leg,2.7\n").unwrap();
+        let err = read(&path.display().to_string()).err().unwrap().to_string();
+        fs::remove_file(&path).unwrap();
+        assert!(err.ends_with(":1: 2.7 is not a whole number of copies"), "{err}");
+    }
+
+    #[test]
+    fn a_part_gets_the_most_any_of_its_sources_asks_for() {
+        let listed = HashMap::from([("leg".to_string(), 4)]);
+        let attributes = HashMap::from([qty("top", "2"), qty("leg", "9")]);
+        // the list wins over the element's own data-qty
+        assert_eq!(of(&ids(&["leg"]), &listed, &attributes), Ok(4));
+        assert_eq!(of(&ids(&["top"]), &listed, &attributes), Ok(2));
+        assert_eq!(of(&ids(&["top", "leg"]), &listed, &attributes), Ok(4));
+        assert_eq!(of(&ids(&["other"]), &listed, &attributes), Ok(1));
+        assert_eq!(of(&ids(&["top"]), &listed, &HashMap::from([qty("top", "0")])), Ok(1));
+    }
+
+    #[test]
+    fn fractions_of_copies_are_refused_in_attributes() {
+        let attributes = HashMap::from([qty("leg", "2.7")]);
+        assert_eq!(
+            of(&ids(&["leg"]), &HashMap::new(), &attributes),
+            Err("leg: data-qty \"2.7\" is not a whole number of copies".to_string())
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use usvg::tiny_skia_path::{PathSegment, Point};
+
+/// What sanitizing took out of, or changed in, one element's path data.
+#[derive(Clone, Copy, Default)]
+pub struct Repairs {
+    /// Segments dropped for NaN or infinite coordinates, a subpath starting
+    /// at such a point being dropped whole.
+    pub non_finite: usize,
+    /// Lines and curves going nowhere.
+    pub zero_length: usize,
+    /// Curves with their controls on their ends, written as lines.
+    pub straightened: usize,
+}
+
+impl Repairs {
+    pub fn any(&self) -> bool {
+        self.non_finite + self.zero_length + self.straightened > 0
+    }
+}
+
+/// Path segments with the ones broken exporters leave behind dropped or
+/// repaired, so that only finite, non-degenerate geometry reaches lyon and
+/// clipper.
+pub fn sanitize(segments: impl Iterator<Item = PathSegment>) -> (Vec<PathSegment>, Repairs) {
+    let mut out = Vec::new();
+    let mut repairs = Repairs::default();
+    let (mut start, mut current) = (Point::zero(), Point::zero());
+    // inside a subpath that started at a non-finite point
+    let mut skipping = false;
+    for segment in segments {
+        if let PathSegment::MoveTo(p) = segment {
+            skipping = !finite(&[p]);
+            if skipping {
+                repairs.non_finite += 1;
+            } else {
+                out.push(segment);
+                (start, current) = (p, p);
+            }
+            continue;
+        }
+        if skipping {
+            repairs.non_finite += 1;
+            continue;
+        }
+        if segment == PathSegment::Close {
+            out.push(segment);
+            current = start;
+            continue;
+        }
+        let points = controls(segment);
+        let end = points[points.len() - 1];
+        if !finite(&points) {
+            repairs.non_finite += 1;
+        } else if points.iter().all(|&q| q == current) {
+            repairs.zero_length += 1;
+        } else if points.len() > 1 && points.iter().all(|&q| q == current || q == end) {
+            repairs.straightened += 1;
+            out.push(PathSegment::LineTo(end));
+            current = end;
+        } else {
+            out.push(segment);
+            current = end;
+        }
+    }
+    (out, repairs)
+}
+
+// every point a segment names, its end last
+fn controls(segment: PathSegment) -> Vec<Point> {
+    match segment {
+        PathSegment::MoveTo(p) | PathSegment::LineTo(p) => vec![p],
+        PathSegment::QuadTo(c, p) => vec![c, p],
+        PathSegment::CubicTo(c1, c2, p) => vec![c1, c2, p],
+        PathSegment::Close => vec![],
+    }
+}
+
+fn finite(points: &[Point]) -> bool {
+    points.iter().all(|p| p.x.is_finite() && p.y.is_finite())
+}
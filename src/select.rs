@@ -0,0 +1,187 @@
+use crate::attrs::{self, Attrs};
+use crate::style::Style;
+use crate::transform::Affine;
+
+/// `--select a,b,.name,fill=#rrggbb`: the only elements combined, each
+/// named by its id, the id of the layer it is in, `.` and a class it or a
+/// group above it has, or the `fill` or `stroke` color it is painted with
+/// in the end, however its style sheets, classes and attributes set it.
+#[derive(Clone, Debug)]
+pub struct Selection(Vec<String>);
+
+pub fn parse(spec: &str) -> Result<Selection, String> {
+    let names: Vec<String> = spec.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect();
+    if names.is_empty() {
+        return Err("a selection needs ids, layers, .classes or fill=/stroke= colors".into());
+    }
+    let names = names
+        .into_iter()
+        .map(|name| match name.split_once('=') {
+            Some((property @ ("fill" | "stroke"), value)) => Ok(format!("{property}={}", hex(value)?)),
+            Some(_) => Err(format!("{name:?}: only fill= and stroke= colors can be selected by")),
+            None => Ok(name),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Selection(names))
+}
+
+/// A color as `#rrggbb`, the way styles are compared.
+fn hex(value: &str) -> Result<String, String> {
+    let digits = value.trim().trim_start_matches('#').to_ascii_lowercase();
+    let digits = match digits.len() {
+        3 => digits.chars().flat_map(|c| [c, c]).collect(),
+        _ => digits,
+    };
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("color {value:?} isn't #rrggbb or #rgb"));
+    }
+    Ok(format!("#{digits}"))
+}
+
+impl Selection {
+    /// Whether an element is selected; `style` is how it is painted, when
+    /// it is a path.
+    pub fn matches(&self, id: &str, layer: Option<&str>, attrs: Option<&Attrs>, style: Option<&Style>) -> bool {
+        self.0.iter().any(|name| {
+            if let Some(class) = name.strip_prefix('.') {
+                return attrs::has_class(attrs, class);
+            }
+            match name.split_once('=') {
+                Some(("fill", color)) => style.is_some_and(|s| s.fill.as_deref() == Some(color)),
+                Some((_, color)) => style.is_some_and(|s| s.stroke.as_deref() == Some(color)),
+                None => name == id || Some(name.as_str()) == layer,
+            }
+        })
+    }
+
+    /// `original`, the source file as it was, with the selected shapes
+    /// taken out and `result` where the first of them that can be placed
+    /// was: one with an id, or in a group with one, whose place `tree`
+    /// knows. Shapes are matched up in `annotated`, the source as it was
+    /// parsed, which only has ids added. Everything else is left byte for
+    /// byte as it was. `result` is in the drawing's units, and undoes the
+    /// transforms it finds itself under there.
+    pub fn replace(&self, original: &str, annotated: &str, tree: &usvg::Tree, result: &str) -> Result<String, String> {
+        let parse = |xml| {
+            let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+            roxmltree::Document::parse_with_options(xml, options).map_err(|e| e.to_string())
+        };
+        let (parsed, source) = (parse(annotated)?, parse(original)?);
+        let is_shape = |n: &roxmltree::Node| attrs::SHAPE_TAGS.contains(&n.tag_name().name());
+        let parsed_shapes: Vec<_> = parsed.descendants().filter(is_shape).collect();
+        let shapes: Vec<_> = source.descendants().filter(is_shape).collect();
+        if parsed_shapes.len() != shapes.len() {
+            return Err("the source has changed since it was read".into());
+        }
+        let selected: Vec<usize> = (0..shapes.len())
+            .filter(|&i| {
+                let n = parsed_shapes[i];
+                // the outermost named group, as usvg has it
+                let layer =
+                    n.ancestors().skip(1).filter(|a| a.parent().is_some_and(|p| p.is_element())).filter_map(|a| a.attribute("id")).last();
+                let classes: Vec<&str> = n.ancestors().filter_map(|a| a.attribute("class")).flat_map(str::split_whitespace).collect();
+                let attrs = Attrs::from([("class".to_string(), classes.join(" "))]);
+                let id = n.attribute("id").unwrap_or("");
+                let style = match tree.node_by_id(id) {
+                    Some(usvg::Node::Path(path)) => Some(Style::of(path)),
+                    _ => None,
+                };
+                self.matches(id, layer, Some(&attrs), style.as_ref())
+            })
+            .collect();
+        if selected.is_empty() {
+            return Err("nothing selected to replace".into());
+        }
+
+        // the first shape a place can be found for, and the transform there
+        let placed = selected.iter().find_map(|&i| {
+            let n = parsed_shapes[i];
+            let own = |id| tree.node_by_id(id).map(|node| Affine::of_usvg(node.abs_transform()));
+            if let Some(at) = n.attribute("id").and_then(own).and_then(|t| t.inverse()) {
+                return Some((i, format!("{} {}", n.attribute("transform").unwrap_or(""), crate::paint::matrix(at))));
+            }
+            let at = n.parent().and_then(|p| p.attribute("id")).and_then(own)?.inverse()?;
+            Some((i, crate::paint::matrix(at)))
+        });
+        let Some((anchor, transform)) = placed else {
+            return Err("no selected shape can be placed; give one of them, or its group, an id".into());
+        };
+
+        let mut out = original.to_string();
+        for &i in selected.iter().rev() {
+            let range = shapes[i].range();
+            if i == anchor {
+                out.replace_range(range, &format!(r#"<g id="combined" transform="{}">{result}</g>"#, transform.trim()));
+                continue;
+            }
+            // the line it was on too, when it had it to itself
+            let line = original[..range.start].trim_end_matches([' ', '\t']);
+            let end = ["\r\n", "\n"].iter().find(|eol| original[range.end..].starts_with(**eol)).map(|eol| eol.len());
+            match end {
+                Some(eol) if line.is_empty() || line.ends_with('\n') => out.replace_range(line.len()..range.end + eol, ""),
+                _ => out.replace_range(range, ""),
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100">
+  <g id="cuts" transform="translate(10 0)">
+    <rect id="a" width="10" height="10" fill="#F00"/>
+    <rect id="b" x="20" width="10" height="10" fill="#00f"/>
+  </g>
+  <circle id="c" cx="50" cy="50" r="5"/>
+</svg>"##;
+
+    fn red() -> Style {
+        Style { fill: Some("#ff0000".into()), ..Default::default() }
+    }
+
+    #[test]
+    fn selections_are_names_and_colors() {
+        assert_eq!(parse(" a, .cut ,fill=#F00,stroke=00ff00").unwrap().0, ["a", ".cut", "fill=#ff0000", "stroke=#00ff00"]);
+        assert!(parse(" , ").is_err());
+        assert!(parse("opacity=1").is_err());
+        assert!(parse("fill=red").is_err());
+        assert!(parse("fill=#ff00").is_err());
+    }
+
+    #[test]
+    fn elements_match_by_id_layer_class_or_paint() {
+        let selection = parse("a,cuts,.etch,fill=#f00").unwrap();
+        let etched = Attrs::from([("class".to_string(), "outline etch".to_string())]);
+        assert!(selection.matches("a", None, None, None));
+        assert!(selection.matches("x", Some("cuts"), None, None));
+        assert!(selection.matches("x", None, Some(&etched), None));
+        assert!(selection.matches("x", None, None, Some(&red())));
+        assert!(!selection.matches("x", Some("other"), None, Some(&Style::default())));
+        let stroked = parse("stroke=#f00").unwrap();
+        assert!(!stroked.matches("x", None, None, Some(&red())));
+    }
+
+    #[test]
+    fn the_result_takes_the_first_selected_shape_s_place() {
+        let tree = usvg::Tree::from_str(SVG, &usvg::Options::default()).unwrap();
+        let out = parse("cuts").unwrap().replace(SVG, SVG, &tree, "<path d=\"M0 0\"/>").unwrap();
+        // the group's transform undone, the other shape and its line gone
+        assert!(out.contains(r#"<g id="combined" transform="matrix(1 0 0 1 -10 0)"><path d="M0 0"/></g>"#), "{out}");
+        assert!(!out.contains(r#"id="b""#));
+        assert_eq!(out.lines().count(), SVG.lines().count() - 1);
+        assert!(out.contains(r#"<circle id="c" cx="50" cy="50" r="5"/>"#));
+    }
+
+    #[test]
+    fn shapes_are_selected_by_how_they_end_up_painted() {
+        let tree = usvg::Tree::from_str(SVG, &usvg::Options::default()).unwrap();
+        let out = parse("fill=#00f").unwrap().replace(SVG, SVG, &tree, "").unwrap();
+        assert!(out.contains(r#"<rect id="a""#) && !out.contains(r#"<rect id="b""#));
+        assert_eq!(parse("missing").unwrap().replace(SVG, SVG, &tree, "").err().unwrap(), "nothing selected to replace");
+        let changed = SVG.replace(r#"<circle id="c" cx="50" cy="50" r="5"/>"#, "");
+        assert!(parse("a").unwrap().replace(&changed, SVG, &tree, "").is_err());
+    }
+}
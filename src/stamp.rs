@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use lyon::path::Path;
+use usvg::tiny_skia_path::PathSegment;
+
+use crate::transform::Affine;
+
+/// `--stamp "QTY 4 — acrylic 3mm"`: a line of text turned into outlines
+/// and placed in a corner of the drawing, for labeling production files.
+pub struct Stamp {
+    pub text: String,
+    /// Font size in drawing units.
+    pub size: f64,
+    /// Where in the drawing's bounds, as for `--align`.
+    pub anchor: (f64, f64),
+    pub font: String,
+}
+
+impl Stamp {
+    /// The text's glyph outlines placed within `bounds` (min x, min y, max
+    /// x, max y), half the font size in from its edges, then mapped by `t`.
+    pub fn outlines(&self, bounds: (f64, f64, f64, f64), t: &Affine) -> Result<Vec<Path>, Box<dyn std::error::Error>> {
+        let doc = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="1" height="1"><text font-family="{}" font-size="{}">{}</text></svg>"#,
+            crate::svg::escape(&self.font),
+            self.size,
+            crate::svg::escape(&self.text)
+        );
+        let mut opt = usvg::Options::default();
+        let mut fonts = usvg::fontdb::Database::new();
+        fonts.load_system_fonts();
+        // generic families map to fonts that may not be installed, like Arial
+        let sans = [usvg::fontdb::Family::SansSerif];
+        if fonts.query(&usvg::fontdb::Query { families: &sans, ..Default::default() }).is_none() {
+            let installed = fonts
+                .faces()
+                .find(|f| f.families.iter().any(|(name, _)| name.contains("Sans")))
+                .or_else(|| fonts.faces().next())
+                .and_then(|f| f.families.first())
+                .map(|(name, _)| name.clone());
+            if let Some(name) = installed {
+                fonts.set_sans_serif_family(name);
+            }
+        }
+        opt.fontdb = Arc::new(fonts);
+        let tree = usvg::Tree::from_data(doc.as_bytes(), &opt)?;
+
+        // glyph outlines with their transforms applied
+        let mut glyphs = Vec::new();
+        let mut stack: Vec<&usvg::Node> = tree.root().children().iter().collect();
+        while let Some(node) = stack.pop() {
+            match node {
+                usvg::Node::Path(path) => glyphs.extend(path.data().clone().transform(path.abs_transform())),
+                usvg::Node::Group(group) => stack.extend(group.children()),
+                usvg::Node::Text(text) => stack.extend(text.flattened().children()),
+                _ => {}
+            }
+        }
+        if glyphs.is_empty() {
+            return Err(format!("no font found to draw {:?} with", self.font).into());
+        }
+
+        // the text's own box, moved into the corner asked for
+        let (mut x0, mut y0, mut x1, mut y1) = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for b in glyphs.iter().map(|g| g.bounds()) {
+            (x0, y0) = (x0.min(b.left() as f64), y0.min(b.top() as f64));
+            (x1, y1) = (x1.max(b.right() as f64), y1.max(b.bottom() as f64));
+        }
+        let margin = self.size / 2.0;
+        let room = (bounds.2 - bounds.0 - 2.0 * margin - (x1 - x0), bounds.3 - bounds.1 - 2.0 * margin - (y1 - y0));
+        let place =
+            Affine::translate(bounds.0 + margin + room.0 * self.anchor.0 - x0, bounds.1 + margin + room.1 * self.anchor.1 - y0).then(*t);
+        let pt = |p: usvg::tiny_skia_path::Point| {
+            let (x, y) = place.apply((p.x as f64, p.y as f64));
+            lyon::math::point(x as f32, y as f32)
+        };
+
+        let mut out = Vec::new();
+        for glyph in &glyphs {
+            let mut builder = Path::builder();
+            let mut open = false;
+            for segment in glyph.segments() {
+                match segment {
+                    PathSegment::MoveTo(p) => {
+                        if open {
+                            builder.end(true);
+                        }
+                        builder.begin(pt(p));
+                        open = true;
+                    }
+                    PathSegment::LineTo(p) => {
+                        builder.line_to(pt(p));
+                    }
+                    PathSegment::QuadTo(a, p) => {
+                        builder.quadratic_bezier_to(pt(a), pt(p));
+                    }
+                    PathSegment::CubicTo(a, b, p) => {
+                        builder.cubic_bezier_to(pt(a), pt(b), pt(p));
+                    }
+                    PathSegment::Close => {
+                        builder.close();
+                        open = false;
+                    }
+                }
+            }
+            // glyph outlines are always filled
+            if open {
+                builder.end(true);
+            }
+            out.push(builder.build());
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,82 @@
+use clipper2::*;
+
+/// Where every closed ring of the result starts, `--start-point`, which
+/// decides where a cutter pierces or a plotter pen lands.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Start {
+    /// The sharpest corner, where a mark shows least.
+    Corner,
+    /// The middle of the longest edge, away from any corner.
+    LongestEdge,
+    /// The vertex nearest to where the ring before it ends, the first
+    /// ring's nearest to the page's origin.
+    Nearest,
+    /// The vertex farthest in a direction, in degrees counterclockwise
+    /// from the page's right (90 is up).
+    Heading(f64),
+}
+
+pub fn parse(spec: &str) -> Result<Start, String> {
+    match spec {
+        "corner" => Ok(Start::Corner),
+        "longest-edge" => Ok(Start::LongestEdge),
+        "nearest" => Ok(Start::Nearest),
+        _ => match spec.strip_prefix("heading:").map(str::parse) {
+            Some(Ok(degrees)) => Ok(Start::Heading(degrees)),
+            _ => Err(format!("unknown start point {spec:?}, expected corner, longest-edge, nearest or heading:DEGREES")),
+        },
+    }
+}
+
+/// `rings` with each one turned to begin where `start` says; the shapes
+/// stay the same, apart from the vertex `LongestEdge` adds.
+pub fn place<P: PointScaler>(rings: &Paths<P>, start: Start) -> Paths<P> {
+    let mut at = (0.0, 0.0);
+    let out: Vec<Vec<(f64, f64)>> = rings
+        .iter()
+        .map(|ring| {
+            let mut points: Vec<(f64, f64)> = ring.iter().map(|p| (p.x(), p.y())).collect();
+            let n = points.len();
+            if n < 3 {
+                return points;
+            }
+            let first = match start {
+                Start::Corner => (0..n).max_by(|&a, &b| turn(&points, a).total_cmp(&turn(&points, b))).unwrap_or(0),
+                Start::LongestEdge => {
+                    let length = |i: usize| {
+                        let (a, b) = (points[i], points[(i + 1) % n]);
+                        (b.0 - a.0).hypot(b.1 - a.1)
+                    };
+                    let i = (0..n).max_by(|&a, &b| length(a).total_cmp(&length(b))).unwrap_or(0);
+                    let (a, b) = (points[i], points[(i + 1) % n]);
+                    points.insert(i + 1, ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0));
+                    i + 1
+                }
+                Start::Nearest => {
+                    let d = |p: (f64, f64)| (p.0 - at.0).hypot(p.1 - at.1);
+                    (0..n).min_by(|&a, &b| d(points[a]).total_cmp(&d(points[b]))).unwrap_or(0)
+                }
+                Start::Heading(degrees) => {
+                    // page y goes down
+                    let (dx, dy) = (degrees.to_radians().cos(), -degrees.to_radians().sin());
+                    let reach = |p: (f64, f64)| p.0 * dx + p.1 * dy;
+                    (0..n).max_by(|&a, &b| reach(points[a]).total_cmp(&reach(points[b]))).unwrap_or(0)
+                }
+            };
+            points.rotate_left(first);
+            // a closed ring ends where it starts
+            at = points[0];
+            points
+        })
+        .collect();
+    out.into()
+}
+
+// how sharply the ring turns at vertex `i`, 0 going straight on to π
+// doubling back
+fn turn(points: &[(f64, f64)], i: usize) -> f64 {
+    let n = points.len();
+    let (a, b, c) = (points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+    let (u, v) = ((b.0 - a.0, b.1 - a.1), (c.0 - b.0, c.1 - b.1));
+    (u.0 * v.1 - u.1 * v.0).atan2(u.0 * v.0 + u.1 * v.1).abs()
+}
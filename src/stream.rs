@@ -0,0 +1,115 @@
+use clipper2::*;
+
+use crate::FlatShape;
+
+/// Combines shapes as they arrive, a chunk at a time, so the flattened
+/// shapes are never all held at once: at most one chunk of them, about
+/// `budget` bytes and one shape more, waits to be carved into the result.
+/// That is all that is bounded. The result so far grows with the drawing,
+/// and the usvg tree the shapes are walked from is parsed whole before
+/// the first one arrives, so memory still grows with the input. Shapes are
+/// carved in one by one in the order they came, as `crate::combine` does,
+/// so the result is the same to the byte.
+pub struct Combiner<P: PointScaler> {
+    budget: usize,
+    result: Paths<P>,
+    chunk: Vec<FlatShape>,
+    chunk_bytes: usize,
+    /// Chunks merged so far.
+    pub chunks: usize,
+    pub shapes: usize,
+    pub vertices: usize,
+}
+
+impl<P: PointScaler> Combiner<P> {
+    pub fn new(budget: usize) -> Self {
+        Combiner { budget, result: Paths::new(vec![]), chunk: Vec::new(), chunk_bytes: 0, chunks: 0, shapes: 0, vertices: 0 }
+    }
+
+    pub fn push(&mut self, shape: FlatShape) -> Result<(), ClipperError> {
+        let vertices: usize = shape.contours.iter().map(|c| c.len()).sum();
+        self.vertices += vertices;
+        self.shapes += 1;
+        self.chunk_bytes += vertices * std::mem::size_of::<(f64, f64)>();
+        self.chunk.push(shape);
+        if self.chunk_bytes >= self.budget {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Merge the last chunk and clean up the result; and the rings dropped
+    /// as smaller than the minimum area.
+    pub fn finish(&mut self) -> Result<(Paths<P>, Paths<P>), ClipperError> {
+        self.flush()?;
+        crate::clean(std::mem::take(&mut self.result))
+    }
+
+    fn flush(&mut self) -> Result<(), ClipperError> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::take(&mut self.chunk);
+        self.chunk_bytes = 0;
+        self.result = crate::carve_onto(std::mem::take(&mut self.result), &chunk)?;
+        self.chunks += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a ring of 24 points, off the clipper grid everywhere
+    fn disc(id: usize, x: f64, y: f64, r: f64) -> FlatShape {
+        let ring = (0..24).map(|k| (k as f64 * std::f64::consts::TAU / 24.0).sin_cos()).map(|(s, c)| (x + r * c, y + r * s)).collect();
+        FlatShape {
+            id: format!("d{id}"),
+            contours: vec![ring],
+            offset: 1.5,
+            style: crate::style::Style::default(),
+            layer: None,
+            class: None,
+            lines: Vec::new(),
+        }
+    }
+
+    fn points<P: PointScaler>(paths: &Paths<P>) -> Vec<Vec<(f64, f64)>> {
+        paths.iter().map(|p| p.iter().map(|q| (q.x(), q.y())).collect()).collect()
+    }
+
+    #[test]
+    fn chunks_give_the_result_of_combining_everything_at_once() {
+        // overlapping discs, each carving into those before it
+        let shapes = || (0..60).map(|i| disc(i, i as f64 * 5.3, (i % 7) as f64 * 6.1, 9.0 + (i % 4) as f64 * 1.7));
+        let (whole, whole_dropped) = crate::combine::<Centi>(&shapes().collect::<Vec<_>>()).unwrap();
+        // one shape per chunk, a few, and all in one
+        for budget in [1, 500, usize::MAX] {
+            let mut combiner = Combiner::<Centi>::new(budget);
+            for shape in shapes() {
+                combiner.push(shape).unwrap();
+            }
+            let (streamed, dropped) = combiner.finish().unwrap();
+            assert_eq!(points(&streamed), points(&whole), "budget {budget}");
+            assert_eq!(points(&dropped), points(&whole_dropped), "budget {budget}");
+        }
+    }
+
+    #[test]
+    fn no_more_than_a_chunk_waits_to_be_carved() {
+        // 24 points of 16 bytes each per disc, so three to a chunk
+        let budget = 3 * 24 * 16;
+        let mut combiner = Combiner::<Centi>::new(budget);
+        for i in 0..10 {
+            combiner.push(disc(i, i as f64 * 50.0, 0.0, 20.0)).unwrap();
+            assert!(combiner.chunk_bytes < budget);
+            assert!(combiner.chunk.len() < 3);
+        }
+        assert_eq!(combiner.chunks, 3);
+        let (result, _) = combiner.finish().unwrap();
+        assert_eq!(combiner.chunks, 4);
+        assert!(combiner.chunk.is_empty() && combiner.result.is_empty());
+        assert_eq!(result.len(), 10);
+    }
+}
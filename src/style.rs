@@ -0,0 +1,66 @@
+use usvg::Paint;
+
+/// Paint attributes of an output path; unset values fall back to a plain
+/// black fill with no stroke.
+#[derive(Clone, Default)]
+pub struct Style {
+    pub fill: Option<String>,
+    pub stroke: Option<String>,
+    pub stroke_width: Option<f64>,
+}
+
+impl Style {
+    /// How the source element was painted.
+    pub fn of(path: &usvg::Path) -> Self {
+        Style {
+            fill: match path.fill() {
+                Some(fill) => color(fill.paint()),
+                None => Some("none".into()),
+            },
+            stroke: path.stroke().and_then(|s| color(s.paint())),
+            stroke_width: path.stroke().map(|s| s.width().get() as f64),
+        }
+    }
+
+    /// This style with every value set in `overrides` replaced.
+    pub fn with(&self, overrides: &Style) -> Style {
+        Style {
+            fill: overrides.fill.clone().or_else(|| self.fill.clone()),
+            stroke: overrides.stroke.clone().or_else(|| self.stroke.clone()),
+            stroke_width: overrides.stroke_width.or(self.stroke_width),
+        }
+    }
+
+    /// `fill`, `stroke` and `stroke-width` attributes.
+    pub fn attributes(&self) -> Vec<(&'static str, String)> {
+        let mut out = vec![
+            ("fill", self.fill.clone().unwrap_or_else(|| "black".into())),
+            ("fill-rule", "evenodd".into()),
+            ("stroke", self.stroke.clone().unwrap_or_else(|| "none".into())),
+        ];
+        if let (Some(_), Some(w)) = (&self.stroke, self.stroke_width) {
+            out.push(("stroke-width", w.to_string()));
+        }
+        out
+    }
+}
+
+/// How opaque a path's paint is on its own: its fill's or stroke's
+/// opacity, whichever shows more.
+pub fn opacity(path: &usvg::Path) -> f64 {
+    let fill = path.fill().map(|f| f.opacity().get());
+    let stroke = path.stroke().map(|s| s.opacity().get());
+    match (fill, stroke) {
+        (Some(fill), Some(stroke)) => fill.max(stroke) as f64,
+        (Some(only), None) | (None, Some(only)) => only as f64,
+        (None, None) => 1.0,
+    }
+}
+
+/// Flat colors as `#rrggbb`; gradients and patterns don't survive combining.
+fn color(paint: &Paint) -> Option<String> {
+    match paint {
+        Paint::Color(c) => Some(format!("#{:02x}{:02x}{:02x}", c.red, c.green, c.blue)),
+        _ => None,
+    }
+}
@@ -0,0 +1,55 @@
+use std::io::IsTerminal as _;
+
+/// One line of the table: a group and what it came out as.
+pub struct Row {
+    pub group: String,
+    pub parts: usize,
+    pub polygons: usize,
+    pub vertices: usize,
+}
+
+/// The closing summary: polygon counts per group as a table, then the
+/// totals and how far simplifying moved anything, bold and colored on a terminal unless `NO_COLOR` is set, plain
+/// for JSON logs and pipes.
+pub fn show(rows: &[Row], input_vertices: usize, deviation: Option<f64>, json: bool) {
+    let color = !json && std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty());
+    let paint = |code: &str, text: &str| if color { format!("\x1b[{code}m{text}\x1b[0m") } else { text.to_string() };
+
+    let (parts, polygons, vertices) = rows.iter().fold((0, 0, 0), |t, r| (t.0 + r.parts, t.1 + r.polygons, t.2 + r.vertices));
+    let width = rows.iter().map(|r| r.group.chars().count()).chain([5]).max().unwrap_or(5);
+    tracing::info!("\n{}", paint("1", "📊 Result"));
+    tracing::info!("{}", paint("2", &format!("  {:<width$}  {:>8}  {:>8}  {:>9}", "group", "parts", "polygons", "vertices")));
+    // a single group is its own total
+    if rows.len() > 1 {
+        for r in rows {
+            tracing::info!("  {:<width$}  {:>8}  {:>8}  {:>9}", r.group, count(r.parts), count(r.polygons), count(r.vertices));
+        }
+    }
+    let total = format!("  {:<width$}  {:>8}  {:>8}  {:>9}", "total", count(parts), count(polygons), count(vertices));
+    tracing::info!("{}", paint("1", &total));
+
+    let change = if input_vertices == 0 || vertices == input_vertices {
+        String::new()
+    } else if vertices < input_vertices {
+        paint("32", &format!("  {:.1}% fewer", 100.0 * (1.0 - vertices as f64 / input_vertices as f64)))
+    } else {
+        paint("33", &format!("  {:.1}% more", 100.0 * (vertices as f64 / input_vertices as f64 - 1.0)))
+    };
+    tracing::info!("  {} {} → {}{change}", paint("2", "vertices"), count(input_vertices), count(vertices));
+    if let Some(d) = deviation {
+        tracing::info!("  {} {d:.4}", paint("2", "max deviation"));
+    }
+}
+
+// 12345 as "12,345"
+fn count(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,208 @@
+use std::fmt::Display;
+
+use clipper2::*;
+
+// pretty output wraps lines longer than this
+const WIDTH: usize = 100;
+const INDENT: &str = "  ";
+
+/// An SVG element with its attributes and children, written out either
+/// minified (no whitespace at all) or pretty (indented, long path data
+/// broken into lines).
+pub struct Element {
+    name: &'static str,
+    attrs: Vec<(&'static str, String)>,
+    children: Vec<Element>,
+    text: Option<String>,
+    raw: Option<String>,
+}
+
+impl Element {
+    pub fn new(name: &'static str) -> Self {
+        Element { name, attrs: Vec::new(), children: Vec::new(), text: None, raw: None }
+    }
+
+    /// Character data written before any children.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Markup written as it is after any text, like content copied from
+    /// the source.
+    pub fn raw(mut self, markup: impl Into<String>) -> Self {
+        self.raw = Some(markup.into());
+        self
+    }
+
+    pub fn attr(mut self, name: &'static str, value: impl Display) -> Self {
+        self.attrs.push((name, value.to_string()));
+        self
+    }
+
+    pub fn attrs(mut self, attrs: impl IntoIterator<Item = (&'static str, String)>) -> Self {
+        self.attrs.extend(attrs);
+        self
+    }
+
+    pub fn child(mut self, child: Element) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    pub fn children(mut self, children: impl IntoIterator<Item = Element>) -> Self {
+        self.children.extend(children);
+        self
+    }
+
+    fn write(&self, out: &mut String, pretty: bool, depth: usize) {
+        let indent = INDENT.repeat(depth);
+        if pretty {
+            out.push_str(&indent);
+        }
+        out.push('<');
+        out.push_str(self.name);
+
+        let one_line: usize = self.attrs.iter().map(|(k, v)| k.len() + v.len() + 4).sum();
+        let wrap = pretty && indent.len() + self.name.len() + one_line > WIDTH;
+        for (name, value) in &self.attrs {
+            if wrap {
+                out.push('\n');
+                out.push_str(&indent);
+                out.push_str(INDENT);
+            } else {
+                out.push(' ');
+            }
+            out.push_str(name);
+            out.push_str("=\"");
+            if wrap && *name == "d" {
+                out.push_str(&wrap_data(&escape(value), &format!("{indent}{INDENT}{INDENT}")));
+            } else {
+                out.push_str(&escape(value));
+            }
+            out.push('"');
+        }
+
+        if self.children.is_empty() && self.text.is_none() && self.raw.is_none() {
+            out.push_str("/>");
+        } else {
+            out.push('>');
+            if let Some(text) = &self.text {
+                if pretty {
+                    out.push('\n');
+                    out.push_str(&indent);
+                    out.push_str(INDENT);
+                }
+                out.push_str(&text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;"));
+            }
+            if let Some(markup) = &self.raw {
+                if pretty {
+                    out.push('\n');
+                    out.push_str(&indent);
+                    out.push_str(INDENT);
+                }
+                out.push_str(markup);
+            }
+            for child in &self.children {
+                if pretty {
+                    out.push('\n');
+                }
+                child.write(out, pretty, depth + 1);
+            }
+            if pretty {
+                out.push('\n');
+                out.push_str(&indent);
+            }
+            out.push_str("</");
+            out.push_str(self.name);
+            out.push('>');
+        }
+    }
+}
+
+/// A whole SVG file showing `view_box` (x, y, width, height).
+pub fn document(view_box: (f64, f64, f64, f64), children: impl IntoIterator<Item = Element>, pretty: bool) -> String {
+    let root = Element::new("svg")
+        .attr("xmlns", "http://www.w3.org/2000/svg")
+        .attr("viewBox", format!("{} {} {} {}", view_box.0, view_box.1, view_box.2, view_box.3))
+        .attr("width", format!("{}px", view_box.2))
+        .attr("height", format!("{}px", view_box.3))
+        .children(children);
+    let mut out = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    out.push('\n');
+    root.write(&mut out, pretty, 0);
+    out.push('\n');
+    out
+}
+
+/// Elements written one after another, as they would be in a document.
+pub fn markup(elements: impl IntoIterator<Item = Element>, pretty: bool) -> String {
+    let mut out = String::new();
+    for element in elements {
+        element.write(&mut out, pretty, 0);
+        if pretty {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Escape text for use inside a double-quoted attribute.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Closed polygons as path data, one `M…L…Z` run per ring.
+pub fn polygon_data<P: PointScaler>(paths: &Paths<P>) -> String {
+    let mut d = String::new();
+    for poly in paths.iter() {
+        let points: Vec<_> = poly.iter().map(|p| (p.x(), p.y())).collect();
+        run(&mut d, &points);
+        if !points.is_empty() {
+            d.push('Z');
+        }
+    }
+    d
+}
+
+/// Open polylines as path data, one `M…L…` run per line.
+pub fn polyline_data(lines: &[Vec<(f64, f64)>]) -> String {
+    let mut d = String::new();
+    for line in lines {
+        run(&mut d, line);
+    }
+    d
+}
+
+fn run(d: &mut String, points: &[(f64, f64)]) {
+    let Some((first, rest)) = points.split_first() else {
+        return;
+    };
+    d.push_str(&format!("M{} {}", first.0, first.1));
+    for pt in rest {
+        d.push_str(&format!("L{} {}", pt.0, pt.1));
+    }
+}
+
+/// Path data with every subpath on its own line, long ones broken before a
+/// command.
+fn wrap_data(d: &str, indent: &str) -> String {
+    let mut out = String::new();
+    let mut line = String::new();
+    for (i, c) in d.char_indices() {
+        let command = c.is_ascii_alphabetic() && c != 'e';
+        if command && !line.is_empty() && (c == 'M' || indent.len() + line.len() > WIDTH) {
+            out.push('\n');
+            out.push_str(indent);
+            out.push_str(line.trim_end());
+            line.clear();
+        }
+        line.push(c);
+        if i + c.len_utf8() == d.len() {
+            out.push('\n');
+            out.push_str(indent);
+            out.push_str(&line);
+        }
+    }
+    out
+}
@@ -0,0 +1,138 @@
+use std::fmt::Write as _;
+use std::time::Instant;
+
+use clipper2::PointScaler;
+
+use crate::Shape;
+use crate::normalize::Normalization;
+use crate::options::Options;
+use crate::svg;
+
+/// The setting varied by a sweep.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Param {
+    /// Curve flattening tolerance.
+    Tolerance,
+    /// Gap around every shape, `data-offset` included.
+    Offset,
+}
+
+/// `--sweep name=FROM..TO:STEPS`: evenly spaced values from FROM to TO.
+pub struct Sweep {
+    pub param: Param,
+    pub values: Vec<f64>,
+}
+
+pub fn parse(spec: &str) -> Result<Sweep, String> {
+    let (name, range) = spec.split_once('=').ok_or("expected NAME=FROM..TO:STEPS")?;
+    let param = match name.trim() {
+        "tolerance" => Param::Tolerance,
+        "offset" => Param::Offset,
+        other => return Err(format!("can't sweep {other:?}, expected tolerance or offset")),
+    };
+    let (range, steps) = range.split_once(':').unwrap_or((range, "5"));
+    let (from, to) = range.split_once("..").ok_or("expected FROM..TO")?;
+    let number = |s: &str| s.trim().parse::<f64>().map_err(|_| format!("bad number {s:?}"));
+    let (from, to) = (number(from)?, number(to)?);
+    let steps: usize = steps.trim().parse().map_err(|_| format!("bad step count {steps:?}"))?;
+    if steps == 0 || from <= 0.0 && param == Param::Tolerance {
+        return Err("a sweep needs at least one step and tolerances above 0".into());
+    }
+    let values = (0..steps).map(|i| if steps == 1 { from } else { from + (to - from) * i as f64 / (steps - 1) as f64 }).collect();
+    Ok(Sweep { param, values })
+}
+
+/// Combine `paths` once per value, writing `STEM-sweep-NAME-VALUE.svg` for
+/// each and a comparison against the first run to `STEM-sweep.csv`, beside
+/// the first output. Values are in drawing units, `paths` already in
+/// `norm`'s working range.
+pub fn run<P: PointScaler>(
+    paths: &[Shape],
+    sweep: &Sweep,
+    view_box: (f64, f64, f64, f64),
+    options: &Options,
+    norm: &Normalization,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let name = match sweep.param {
+        Param::Tolerance => "tolerance",
+        Param::Offset => "offset",
+    };
+    let output = options.outputs.first().ok_or("nowhere to write the sweep")?;
+    let svg_file = |value: f64| beside(&output.path, &format!("sweep-{name}-{value}.svg"));
+    let csv_file = beside(&output.path, "sweep.csv");
+    // none written if any is in the way
+    for file in sweep.values.iter().map(|&v| svg_file(v)).chain([csv_file.clone()]) {
+        crate::output::check_free(&file, options, output.named)?;
+    }
+    tracing::info!("\n🔄 Sweeping {name} over {} value(s)...", sweep.values.len());
+    tracing::info!("\n  {name:>10} {:>10} {:>12} {:>9} {:>9}", "vertices", "area", "Δarea %", "ms");
+
+    let mut csv = format!("{name},vertices,area,area_delta_percent,milliseconds\n");
+    let mut first_area = None;
+    for &value in &sweep.values {
+        let started = Instant::now();
+        let tolerance = match sweep.param {
+            Param::Tolerance => (value * norm.scale) as f32,
+            Param::Offset => options.tolerance.map_or(crate::TOLERANCE, |t| (t * norm.scale) as f32),
+        };
+        let mut shapes = Vec::new();
+        for (idx, shape) in paths.iter().enumerate() {
+            if let (Some(mut flat), _) = crate::flatten::<P>(shape, idx, false, false, tolerance, options.curvature) {
+                if sweep.param == Param::Offset {
+                    flat.offset = value * norm.scale;
+                }
+                shapes.push(flat);
+            }
+        }
+        let result = norm.inverse.apply_paths(&crate::combine::<P>(&shapes)?.0);
+        let ms = started.elapsed().as_secs_f64() * 1000.0;
+
+        let vertices: usize = result.iter().map(|p| p.len()).sum();
+        let area = result.signed_area().abs();
+        let base = *first_area.get_or_insert(area);
+        let delta = if base > 0.0 { 100.0 * (area - base) / base } else { 0.0 };
+        tracing::info!("  {value:>10.4} {vertices:>10} {area:>12.2} {delta:>9.3} {ms:>9.1}");
+        writeln!(csv, "{value},{vertices},{area:.4},{delta:.4},{ms:.3}")?;
+
+        let path = svg::Element::new("path").attr("d", svg::polygon_data(&result)).attrs(crate::Style::default().attributes());
+        crate::output::write_output(svg_file(value), svg::document(view_box, [path], options.pretty), options, output.named)?;
+    }
+    crate::output::write_output(&csv_file, csv, options, output.named)?;
+    tracing::info!("\n✅ Sweep saved to: {}, {csv_file}", beside(&output.path, &format!("sweep-{name}-*.svg")));
+    Ok(())
+}
+
+/// `file` in the directory of `output`, after its stem and a dash.
+fn beside(output: &str, file: &str) -> String {
+    let path = std::path::Path::new(output);
+    let stem = path.file_stem().map_or("output".into(), |s| s.to_string_lossy().into_owned());
+    path.with_file_name(format!("{stem}-{file}")).display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_evenly_spaced_from_first_to_last() {
+        let sweep = parse("offset=1..2:5").unwrap();
+        assert!(sweep.param == Param::Offset);
+        assert_eq!(sweep.values, [1.0, 1.25, 1.5, 1.75, 2.0]);
+        assert_eq!(parse("tolerance=0.1..0.5:1").unwrap().values, [0.1]);
+        assert_eq!(parse("tolerance=0.1..0.5").unwrap().values.len(), 5);
+    }
+
+    #[test]
+    fn bad_sweeps_are_refused() {
+        assert_eq!(parse("gap=1..2").err().unwrap(), "can't sweep \"gap\", expected tolerance or offset");
+        assert!(parse("tolerance=0..1:3").is_err());
+        assert!(parse("offset=1..2:0").is_err());
+        assert!(parse("offset=1:3").is_err());
+    }
+
+    #[test]
+    fn files_go_beside_the_output() {
+        assert_eq!(beside("out/plate.svg", "sweep.csv"), "out/plate-sweep.csv");
+        assert_eq!(beside("output.svg", "sweep-offset-1.5.svg"), "output-sweep-offset-1.5.svg");
+    }
+}
@@ -0,0 +1,136 @@
+use std::f64::consts::PI;
+
+use clipper2::*;
+
+// turns sharper than this get a round join, gentler ones share a bisector
+const JOIN_ANGLE: f64 = 30.0 * PI / 180.0;
+
+/// Outline around an open polyline whose offset on each side changes
+/// linearly from `start` at the first point to `end` at the last one.
+pub fn tapered_outline<P: PointScaler>(line: &[(f64, f64)], start: f64, end: f64, tolerance: f64) -> Vec<Vec<(f64, f64)>> {
+    outline::<P>(line, |t| start + (end - start) * t, tolerance)
+}
+
+/// Outline around an open polyline drawn with a width that varies along it,
+/// `profile` giving widths at fractions of its length, in order.
+pub fn profiled_outline<P: PointScaler>(line: &[(f64, f64)], profile: &[(f64, f64)], tolerance: f64) -> Vec<Vec<(f64, f64)>> {
+    let width = |t: f64| {
+        let after = profile.iter().position(|&(at, _)| at >= t).unwrap_or(profile.len() - 1);
+        match after.checked_sub(1).map(|before| (profile[before], profile[after])) {
+            Some(((t0, w0), (t1, w1))) if t1 > t0 => w0 + (w1 - w0) * (t - t0) / (t1 - t0),
+            _ => profile[after].1,
+        }
+    };
+    if profile.is_empty() {
+        return Vec::new();
+    }
+    outline::<P>(line, |t| width(t) / 2.0, tolerance)
+}
+
+/// Outline around an open polyline with `radius(t)` on each side at
+/// fraction `t` of its length.
+fn outline<P: PointScaler>(line: &[(f64, f64)], radius: impl Fn(f64) -> f64, tolerance: f64) -> Vec<Vec<(f64, f64)>> {
+    let mut lengths = vec![0.0];
+    for w in line.windows(2) {
+        let last = *lengths.last().unwrap();
+        lengths.push(last + distance(w[0], w[1]));
+    }
+    let total = *lengths.last().unwrap();
+    if total == 0.0 {
+        return Vec::new();
+    }
+    let radius = |i: usize| radius(lengths[i] / total).max(0.0);
+
+    // segment normals, then per-vertex normals shared by neighbouring quads
+    // so consecutive pieces meet without slivers
+    let seg_normals: Vec<Option<(f64, f64)>> = line
+        .windows(2)
+        .map(|w| {
+            let len = distance(w[0], w[1]);
+            (len > 0.0).then(|| ((w[0].1 - w[1].1) / len, (w[1].0 - w[0].0) / len))
+        })
+        .collect();
+    let vertex_normal = |i: usize| {
+        let before = i.checked_sub(1).and_then(|k| seg_normals[k]);
+        let after = seg_normals.get(i).copied().flatten();
+        match (before, after) {
+            (Some(a), Some(b)) => {
+                let m = (a.0 + b.0, a.1 + b.1);
+                let len = (m.0 * m.0 + m.1 * m.1).sqrt();
+                if len < 1e-9 { Some(b) } else { Some((m.0 / len, m.1 / len)) }
+            }
+            (a, b) => a.or(b),
+        }
+    };
+
+    let mut pieces: Vec<Vec<(f64, f64)>> = Vec::new();
+    for i in 0..line.len() - 1 {
+        if seg_normals[i].is_none() {
+            continue;
+        }
+        let (a, b) = (line[i], line[i + 1]);
+        let (Some(na), Some(nb)) = (vertex_normal(i), vertex_normal(i + 1)) else {
+            continue;
+        };
+        let (ra, rb) = (radius(i), radius(i + 1));
+        pieces.push(vec![
+            (a.0 + na.0 * ra, a.1 + na.1 * ra),
+            (b.0 + nb.0 * rb, b.1 + nb.1 * rb),
+            (b.0 - nb.0 * rb, b.1 - nb.1 * rb),
+            (a.0 - na.0 * ra, a.1 - na.1 * ra),
+        ]);
+    }
+
+    // round caps at both ends and round joins where the line turns sharply
+    for i in 0..line.len() {
+        let is_end = i == 0 || i == line.len() - 1;
+        if (is_end || turn(line[i - 1], line[i], line[i + 1]) > JOIN_ANGLE)
+            && let Some(disc) = disc(line[i], radius(i), tolerance)
+        {
+            pieces.push(disc);
+        }
+    }
+
+    // same winding for all pieces, or overlaps would cancel out under NonZero
+    for piece in pieces.iter_mut() {
+        if Path::<P>::from(piece.clone()).signed_area() < 0.0 {
+            piece.reverse();
+        }
+    }
+
+    match union::<P>(pieces, Paths::new(vec![]), FillRule::NonZero) {
+        Ok(outline) => outline.into(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Polygon approximating a circle within `tolerance`.
+pub fn disc(center: (f64, f64), r: f64, tolerance: f64) -> Option<Vec<(f64, f64)>> {
+    if r <= tolerance {
+        return None;
+    }
+    let step = 2.0 * (1.0 - tolerance / r).acos();
+    let n = ((2.0 * PI / step).ceil() as usize).max(8);
+    Some(
+        (0..n)
+            .map(|k| {
+                let a = 2.0 * PI * k as f64 / n as f64;
+                (center.0 + r * a.cos(), center.1 + r * a.sin())
+            })
+            .collect(),
+    )
+}
+
+fn turn(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    let d1 = (b.1 - a.1).atan2(b.0 - a.0);
+    let d2 = (c.1 - b.1).atan2(c.0 - b.0);
+    let mut t = (d2 - d1).abs();
+    if t > PI {
+        t = 2.0 * PI - t;
+    }
+    t
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+}
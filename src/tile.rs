@@ -0,0 +1,99 @@
+use clipper2::*;
+
+use crate::Contour;
+
+/// One piece of `--tile-output WxH`: a cell of a grid laid over the
+/// result's viewBox from its top-left corner, rows and columns from 1.
+pub struct Tile {
+    pub row: usize,
+    pub column: usize,
+    /// x, y, width, height, as for the SVG viewBox; the last row and
+    /// column are cut down to the viewBox.
+    pub view_box: (f64, f64, f64, f64),
+}
+
+/// The tiles covering `view_box`, row by row.
+pub fn grid(view_box: (f64, f64, f64, f64), (width, height): (f64, f64)) -> Vec<Tile> {
+    let (x, y, w, h) = view_box;
+    let (columns, rows) = ((w / width).ceil().max(1.0) as usize, (h / height).ceil().max(1.0) as usize);
+    let mut tiles = Vec::with_capacity(rows * columns);
+    for row in 0..rows {
+        for column in 0..columns {
+            let (x0, y0) = (x + column as f64 * width, y + row as f64 * height);
+            let view_box = (x0, y0, width.min(x + w - x0), height.min(y + h - y0));
+            tiles.push(Tile { row: row + 1, column: column + 1, view_box });
+        }
+    }
+    tiles
+}
+
+impl Tile {
+    fn rect<P: PointScaler>(&self) -> Paths<P> {
+        let (x, y, w, h) = self.view_box;
+        vec![vec![(x, y), (x + w, y), (x + w, y + h), (x, y + h)]].into()
+    }
+
+    /// What of `rings`, filled nonzero as they were combined, falls within
+    /// the tile.
+    pub fn rings<P: PointScaler>(&self, rings: &Paths<P>) -> Result<Paths<P>, ClipperError> {
+        if rings.is_empty() {
+            return Ok(Paths::new(vec![]));
+        }
+        intersect(rings.clone(), self.rect::<P>(), FillRule::NonZero)
+    }
+
+    /// The pieces of open `lines` within the tile.
+    pub fn lines<P: PointScaler>(&self, lines: &[Contour]) -> Vec<Contour> {
+        let rect = self.rect::<P>();
+        lines.iter().flat_map(|line| crate::clip::clip_polyline(line, &rect, true)).collect()
+    }
+
+    /// `path` with `-ROW-COLUMN` before its extension.
+    pub fn file(&self, path: &str) -> String {
+        let path = std::path::Path::new(path);
+        let stem = path.file_stem().map_or("output".into(), |s| s.to_string_lossy().into_owned());
+        let name = match path.extension() {
+            Some(ext) => format!("{stem}-{}-{}.{}", self.row, self.column, ext.to_string_lossy()),
+            None => format!("{stem}-{}-{}", self.row, self.column),
+        };
+        path.with_file_name(name).display().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_grid_covers_the_view_box_cutting_down_the_last_row_and_column() {
+        let tiles = grid((10.0, 20.0, 25.0, 10.0), (10.0, 6.0));
+        let cells: Vec<_> = tiles.iter().map(|t| (t.row, t.column, t.view_box)).collect();
+        assert_eq!(cells.len(), 6);
+        assert_eq!(cells[0], (1, 1, (10.0, 20.0, 10.0, 6.0)));
+        assert_eq!(cells[2], (1, 3, (30.0, 20.0, 5.0, 6.0)));
+        assert_eq!(cells[5], (2, 3, (30.0, 26.0, 5.0, 4.0)));
+    }
+
+    #[test]
+    fn a_tile_larger_than_the_view_box_is_the_one_tile() {
+        let tiles = grid((0.0, 0.0, 5.0, 5.0), (10.0, 10.0));
+        assert_eq!(tiles.len(), 1);
+        assert_eq!(tiles[0].view_box, (0.0, 0.0, 5.0, 5.0));
+    }
+
+    #[test]
+    fn files_are_numbered_before_the_extension() {
+        let tile = Tile { row: 2, column: 3, view_box: (0.0, 0.0, 1.0, 1.0) };
+        assert_eq!(tile.file("out/plate.svg"), "out/plate-2-3.svg");
+        assert_eq!(tile.file("plate"), "plate-2-3");
+    }
+
+    #[test]
+    fn rings_are_cut_to_the_tile() {
+        let tile = Tile { row: 1, column: 1, view_box: (0.0, 0.0, 10.0, 10.0) };
+        let rings: Paths<Centi> = vec![vec![(5.0, 5.0), (15.0, 5.0), (15.0, 15.0), (5.0, 15.0)]].into();
+        let cut = tile.rings(&rings).unwrap();
+        assert_eq!(cut.len(), 1);
+        assert!((cut.signed_area().abs() - 25.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use crate::Contour;
+
+// how far, in pixels, a smoothed outline may stray to drop vertices
+const STRAY: f64 = 0.25;
+
+/// A bitmap thresholded to which pixels are ink.
+struct Mask {
+    width: usize,
+    height: usize,
+    ink: Vec<bool>,
+}
+
+impl Mask {
+    fn at(&self, x: i64, y: i64) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height && self.ink[y as usize * self.width + x as usize]
+    }
+}
+
+/// Outlines of an embedded PNG's dark, opaque pixels (luminance below
+/// `threshold`, 0 to 1) in the image's own coordinates, where it is
+/// `size` across. Ink is kept on the same side of every outline, so holes
+/// come out turned the other way from what surrounds them. Other bitmap
+/// formats aren't traced.
+pub fn trace(image: &usvg::Image, threshold: f64) -> Result<Vec<Contour>, String> {
+    let usvg::ImageKind::PNG(data) = image.kind() else {
+        return Err("only PNG images can be traced".into());
+    };
+    let mask = decode(data, threshold).map_err(|e| format!("can't decode image: {e}"))?;
+    let (sx, sy) = (image.size().width() as f64 / mask.width as f64, image.size().height() as f64 / mask.height as f64);
+    Ok(outlines(&mask)
+        .into_iter()
+        .map(|ring| {
+            // through the middle of every pixel edge, so staircases become slopes
+            let n = ring.len();
+            let ring: Contour = (0..n)
+                .map(|i| {
+                    let (a, b) = (ring[i], ring[(i + 1) % n]);
+                    ((a.0 + b.0) as f64 / 2.0, (a.1 + b.1) as f64 / 2.0)
+                })
+                .collect();
+            let ring = crate::density::reduce(&ring, STRAY, true);
+            ring.into_iter().map(|(x, y)| (x * sx, y * sy)).collect()
+        })
+        .collect())
+}
+
+fn decode(data: &[u8], threshold: f64) -> Result<Mask, png::DecodingError> {
+    let mut decoder = png::Decoder::new(Cursor::new(data));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size().unwrap_or(0)];
+    let info = reader.next_frame(&mut buf)?;
+    let channels = info.color_type.samples();
+    let ink = buf[..info.buffer_size()]
+        .chunks(info.line_size)
+        .take(info.height as usize)
+        .flat_map(|row| row.chunks(channels).take(info.width as usize))
+        .map(|px| {
+            let (luma, alpha) = match px {
+                [g] => (*g as f64, 255.0),
+                [g, a] => (*g as f64, *a as f64),
+                [r, g, b] => (0.299 * *r as f64 + 0.587 * *g as f64 + 0.114 * *b as f64, 255.0),
+                [r, g, b, a, ..] => (0.299 * *r as f64 + 0.587 * *g as f64 + 0.114 * *b as f64, *a as f64),
+                _ => (255.0, 0.0),
+            };
+            alpha >= 128.0 && luma / 255.0 < threshold
+        })
+        .collect();
+    Ok(Mask { width: info.width as usize, height: info.height as usize, ink })
+}
+
+/// Pixel-edge outlines of the ink, one point per pixel corner.
+fn outlines(mask: &Mask) -> Vec<Vec<(i64, i64)>> {
+    // every edge between ink and background, ink on its right (screen y
+    // down), from one pixel corner to the next
+    let mut next: HashMap<(i64, i64), Vec<(i64, i64)>> = HashMap::new();
+    for y in 0..mask.height as i64 {
+        for x in 0..mask.width as i64 {
+            if !mask.at(x, y) {
+                continue;
+            }
+            let mut edge = |a: (i64, i64), b: (i64, i64)| next.entry(a).or_default().push(b);
+            if !mask.at(x, y - 1) {
+                edge((x, y), (x + 1, y));
+            }
+            if !mask.at(x + 1, y) {
+                edge((x + 1, y), (x + 1, y + 1));
+            }
+            if !mask.at(x, y + 1) {
+                edge((x + 1, y + 1), (x, y + 1));
+            }
+            if !mask.at(x - 1, y) {
+                edge((x, y + 1), (x, y));
+            }
+        }
+    }
+
+    let mut starts: Vec<(i64, i64)> = next.keys().copied().collect();
+    starts.sort();
+    let mut rings = Vec::new();
+    for start in starts {
+        while next.get(&start).is_some_and(|v| !v.is_empty()) {
+            let mut ring = vec![start];
+            let mut at = start;
+            let mut dir = (0, 0);
+            while let Some(outs) = next.get_mut(&at) {
+                // where two pixels touch only at a corner, turn right so
+                // they stay apart
+                let pick = (0..outs.len())
+                    .max_by_key(|&i| {
+                        let d = (outs[i].0 - at.0, outs[i].1 - at.1);
+                        dir.0 * d.1 - dir.1 * d.0
+                    })
+                    .unwrap_or(0);
+                let to = outs.swap_remove(pick);
+                dir = (to.0 - at.0, to.1 - at.1);
+                at = to;
+                if at == start {
+                    break;
+                }
+                ring.push(at);
+            }
+            rings.push(ring);
+        }
+    }
+    rings
+}
@@ -0,0 +1,237 @@
+use clipper2::*;
+
+/// Affine map `x' = a*x + c*y + e`, `y' = b*x + d*y + f`, as in SVG's `matrix()`.
+#[derive(Clone, Copy, Debug)]
+pub struct Affine {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Affine {
+    pub const IDENTITY: Affine = Affine { a: 1.0, b: 0.0, c: 0.0, d: 1.0, e: 0.0, f: 0.0 };
+
+    pub fn scale(sx: f64, sy: f64) -> Self {
+        Affine { a: sx, d: sy, ..Self::IDENTITY }
+    }
+
+    pub fn translate(tx: f64, ty: f64) -> Self {
+        Affine { e: tx, f: ty, ..Self::IDENTITY }
+    }
+
+    /// Clockwise on screen, like SVG's `rotate()`.
+    pub fn rotate(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        // keep quarter turns exact
+        let snap = |v: f64| if v.abs() < 1e-12 { 0.0 } else { v };
+        let (sin, cos) = (snap(sin), snap(cos));
+        Affine { a: cos, b: sin, c: -sin, d: cos, ..Self::IDENTITY }
+    }
+
+    /// Apply `self` first, then `next`.
+    pub fn then(self, next: Affine) -> Self {
+        Affine {
+            a: next.a * self.a + next.c * self.b,
+            b: next.b * self.a + next.d * self.b,
+            c: next.a * self.c + next.c * self.d,
+            d: next.b * self.c + next.d * self.d,
+            e: next.a * self.e + next.c * self.f + next.e,
+            f: next.b * self.e + next.d * self.f + next.f,
+        }
+    }
+
+    /// A transform as usvg gives it.
+    pub fn of_usvg(t: usvg::Transform) -> Self {
+        Affine { a: t.sx as f64, b: t.ky as f64, c: t.kx as f64, d: t.sy as f64, e: t.tx as f64, f: t.ty as f64 }
+    }
+
+    /// How much areas grow, negative when mirrored.
+    pub fn determinant(&self) -> f64 {
+        self.a * self.d - self.b * self.c
+    }
+
+    /// The map undoing `self`, unless it squashes everything flat.
+    pub fn inverse(&self) -> Option<Affine> {
+        let det = self.determinant();
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let (a, b, c, d) = (self.d / det, -self.b / det, -self.c / det, self.a / det);
+        Some(Affine { a, b, c, d, e: -(a * self.e + c * self.f), f: -(b * self.e + d * self.f) })
+    }
+
+    pub fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (self.a * x + self.c * y + self.e, self.b * x + self.d * y + self.f)
+    }
+
+    pub fn apply_paths<P: PointScaler>(&self, paths: &Paths<P>) -> Paths<P> {
+        paths.iter().map(|p| p.iter().map(|pt| self.apply((pt.x(), pt.y()))).collect::<Vec<_>>().into()).collect()
+    }
+
+    /// Bounding box `(x, y, w, h)` of a rectangle after mapping its corners.
+    pub fn apply_rect(&self, (x, y, w, h): (f64, f64, f64, f64)) -> (f64, f64, f64, f64) {
+        let corners = [(x, y), (x + w, y), (x, y + h), (x + w, y + h)].map(|p| self.apply(p));
+        let min_x = corners.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+        let min_y = corners.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        let max_x = corners.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+        let max_y = corners.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+        (min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+}
+
+/// Parse a comma separated list such as `"mirror-x, rotate 90, scale 2"`.
+/// Steps apply left to right around the origin; `scale` and `translate`
+/// take one or two numbers.
+pub fn parse(spec: &str) -> Result<Affine, String> {
+    let mut t = Affine::IDENTITY;
+    for step in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let mut words = step.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let args = words.map(|w| w.parse::<f64>().map_err(|_| format!("bad number {w:?} in {step:?}"))).collect::<Result<Vec<_>, _>>()?;
+        let next = match (name, args.as_slice()) {
+            ("mirror-x", []) => Affine::scale(-1.0, 1.0),
+            ("mirror-y", []) => Affine::scale(1.0, -1.0),
+            ("rotate", [deg]) => Affine::rotate(*deg),
+            ("scale", [s]) => Affine::scale(*s, *s),
+            ("scale", [sx, sy]) => Affine::scale(*sx, *sy),
+            ("translate", [tx]) => Affine::translate(*tx, 0.0),
+            ("translate", [tx, ty]) => Affine::translate(*tx, *ty),
+            _ => return Err(format!("unknown transform step {step:?}")),
+        };
+        t = t.then(next);
+    }
+    Ok(t)
+}
+
+/// Where the result sits on a target canvas.
+#[derive(Clone, Copy, Debug)]
+pub struct Placement {
+    pub canvas: (f64, f64),
+    /// Horizontal and vertical anchor, 0 = left/top, 0.5 = center, 1 = right/bottom.
+    pub anchor: (f64, f64),
+    /// Extra shift applied after aligning.
+    pub offset: (f64, f64),
+    /// Scale proportionally so the result fills the canvas.
+    pub fit: bool,
+}
+
+/// Parse `center`, `top-left`, `bottom`, … into an anchor.
+pub fn parse_align(s: &str) -> Result<(f64, f64), String> {
+    let anchor = match s {
+        "center" => (0.5, 0.5),
+        "top-left" => (0.0, 0.0),
+        "top" => (0.5, 0.0),
+        "top-right" => (1.0, 0.0),
+        "left" => (0.0, 0.5),
+        "right" => (1.0, 0.5),
+        "bottom-left" => (0.0, 1.0),
+        "bottom" => (0.5, 1.0),
+        "bottom-right" => (1.0, 1.0),
+        _ => return Err(format!("unknown alignment {s:?}")),
+    };
+    Ok(anchor)
+}
+
+/// Parse a size such as `600x400`.
+pub fn parse_size(s: &str) -> Result<(f64, f64), String> {
+    let (w, h) = s.split_once('x').ok_or("expected WIDTHxHEIGHT")?;
+    let size = (w.trim().parse().map_err(|_| format!("bad width {w:?}"))?, h.trim().parse().map_err(|_| format!("bad height {h:?}"))?);
+    Ok(size)
+}
+
+impl Placement {
+    /// Map taking the result's bounds to its place on the canvas.
+    pub fn affine<P: PointScaler>(&self, bounds: Bounds<P>) -> Affine {
+        let size = bounds.size();
+        let (w, h) = (size.x(), size.y());
+        let s = if self.fit && w > 0.0 && h > 0.0 { (self.canvas.0 / w).min(self.canvas.1 / h) } else { 1.0 };
+        let x = (self.canvas.0 - w * s) * self.anchor.0 + self.offset.0;
+        let y = (self.canvas.1 - h * s) * self.anchor.1 + self.offset.1;
+        Affine::translate(-bounds.min.x(), -bounds.min.y()).then(Affine::scale(s, s)).then(Affine::translate(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(p: (f64, f64), q: (f64, f64)) -> bool {
+        (p.0 - q.0).abs() < 1e-9 && (p.1 - q.1).abs() < 1e-9
+    }
+
+    #[test]
+    fn steps_apply_left_to_right() {
+        // scaled, then moved: the move isn't scaled
+        let t = parse("scale 2, translate 10 5").unwrap();
+        assert!(close(t.apply((1.0, 1.0)), (12.0, 7.0)));
+        let t = parse("translate 10 5, scale 2").unwrap();
+        assert!(close(t.apply((1.0, 1.0)), (22.0, 12.0)));
+    }
+
+    #[test]
+    fn each_step_does_what_it_says() {
+        let at = |spec: &str, p| parse(spec).unwrap().apply(p);
+        assert!(close(at("mirror-x", (3.0, 4.0)), (-3.0, 4.0)));
+        assert!(close(at("mirror-y", (3.0, 4.0)), (3.0, -4.0)));
+        // clockwise on screen, where y points down
+        assert_eq!(at("rotate 90", (1.0, 0.0)), (0.0, 1.0));
+        assert!(close(at("scale 2 3", (1.0, 1.0)), (2.0, 3.0)));
+        assert!(close(at("translate 7", (1.0, 1.0)), (8.0, 1.0)));
+        assert!(close(at(" , ", (1.0, 1.0)), (1.0, 1.0)));
+    }
+
+    #[test]
+    fn bad_steps_are_named() {
+        assert_eq!(parse("rotate").err().unwrap(), "unknown transform step \"rotate\"");
+        assert_eq!(parse("scale 1 2 3").err().unwrap(), "unknown transform step \"scale 1 2 3\"");
+        assert_eq!(parse("scale two").err().unwrap(), "bad number \"two\" in \"scale two\"");
+        assert_eq!(parse("skew 10").err().unwrap(), "unknown transform step \"skew 10\"");
+    }
+
+    #[test]
+    fn inverse_undoes_and_flat_maps_have_none() {
+        let t = parse("rotate 30, scale 2 0.5, translate 3 -4, mirror-x").unwrap();
+        let back = t.inverse().unwrap();
+        assert!(close(back.apply(t.apply((5.0, 7.0))), (5.0, 7.0)));
+        assert!(t.determinant() < 0.0);
+        assert!(Affine::scale(1.0, 0.0).inverse().is_none());
+    }
+
+    #[test]
+    fn apply_rect_bounds_the_mapped_corners() {
+        let (x, y, w, h) = Affine::rotate(90.0).apply_rect((0.0, 0.0, 4.0, 2.0));
+        assert!(close((x, y), (-2.0, 0.0)) && close((w, h), (2.0, 4.0)));
+    }
+
+    #[test]
+    fn alignments_name_their_anchor() {
+        assert_eq!(parse_align("center"), Ok((0.5, 0.5)));
+        assert_eq!(parse_align("top-right"), Ok((1.0, 0.0)));
+        assert_eq!(parse_align("bottom"), Ok((0.5, 1.0)));
+        assert_eq!(parse_align("middle"), Err("unknown alignment \"middle\"".into()));
+    }
+
+    #[test]
+    fn sizes_are_width_by_height() {
+        assert_eq!(parse_size("600x400"), Ok((600.0, 400.0)));
+        assert_eq!(parse_size(" 2.5 x 1e2 "), Ok((2.5, 100.0)));
+        assert_eq!(parse_size("600"), Err("expected WIDTHxHEIGHT".into()));
+        assert_eq!(parse_size("ax400"), Err("bad width \"a\"".into()));
+        assert_eq!(parse_size("600x"), Err("bad height \"\"".into()));
+    }
+
+    #[test]
+    fn placement_aligns_and_fits_on_the_canvas() {
+        let bounds = Paths::<Centi>::from(vec![vec![(10.0, 10.0), (30.0, 10.0), (30.0, 20.0), (10.0, 20.0)]]).bounds();
+        let at = |anchor, offset, fit| Placement { canvas: (100.0, 50.0), anchor, offset, fit }.affine(bounds);
+        // 20 by 10 in the middle of 100 by 50
+        assert!(close(at((0.5, 0.5), (0.0, 0.0), false).apply((10.0, 10.0)), (40.0, 20.0)));
+        assert!(close(at((1.0, 1.0), (-5.0, 0.0), false).apply((30.0, 20.0)), (95.0, 50.0)));
+        // scaled by 5, as wide as the canvas, and centred down it
+        let fit = at((0.5, 0.5), (0.0, 0.0), true);
+        assert!(close(fit.apply((10.0, 10.0)), (0.0, 0.0)) && close(fit.apply((30.0, 20.0)), (100.0, 50.0)));
+    }
+}
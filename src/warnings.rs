@@ -0,0 +1,103 @@
+use crate::filter::Policy;
+
+/// Something the result leaves out or may get wrong, traced back to the
+/// element it is about.
+pub struct Warning {
+    pub kind: &'static str,
+    /// Element id, or its kind and place in the document when it has none.
+    pub element: String,
+    pub message: String,
+}
+
+/// Warnings collected along the pipeline, reported together at the end.
+#[derive(Default)]
+pub struct Warnings(pub Vec<Warning>);
+
+impl Warnings {
+    pub fn add(&mut self, kind: &'static str, element: impl Into<String>, message: impl Into<String>) {
+        self.0.push(Warning { kind, element: element.into(), message: message.into() });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        self.0.iter().map(|w| serde_json::json!({ "kind": w.kind, "element": w.element, "message": w.message })).collect::<Vec<_>>().into()
+    }
+}
+
+/// Content of the drawing that isn't combined: images (unless traced),
+/// filtered content as `filters` has it, and masks (unless thresholded)
+/// and clip paths other than viewports, which are ignored.
+pub fn unsupported(tree: &usvg::Tree, trace: bool, masks: bool, filters: Policy) -> Warnings {
+    let mut warnings = Warnings::default();
+    let mut seen = 0;
+    walk(tree.root(), trace, masks, filters, &mut seen, &mut warnings);
+    warnings
+}
+
+/// What usvg drops without a trace, found in the source: text, which is
+/// never combined and has no fonts to be drawn with, `<foreignObject>`
+/// content, and references to files outside the document, which aren't
+/// loaded, unless `external` images are, which say so themselves.
+pub fn in_source(xml: &str, external: bool) -> Warnings {
+    let mut warnings = Warnings::default();
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let Ok(doc) = roxmltree::Document::parse_with_options(xml, options) else {
+        return warnings;
+    };
+    for (n, node) in doc.descendants().filter(|n| n.is_element()).enumerate() {
+        let tag = node.tag_name().name();
+        let name = || node.attribute("id").map_or_else(|| format!("<{tag}> #{}", n + 1), str::to_string);
+        if tag == "text" {
+            warnings.add("text", name(), "text isn't combined; convert it to paths");
+        }
+        if tag == "foreignObject" {
+            warnings.add("foreign-object", name(), "content from outside SVG isn't drawn, left out");
+        }
+        let href = node.attributes().find(|a| a.name() == "href").map(|a| a.value());
+        let loaded = external && tag == "image";
+        if let Some(href) = href.filter(|h| !h.starts_with('#') && !h.starts_with("data:") && !loaded) {
+            warnings.add("external-reference", name(), format!("{href} is outside the document and isn't loaded"));
+        }
+    }
+    warnings
+}
+
+fn walk(group: &usvg::Group, trace: bool, masks: bool, filters: Policy, seen: &mut usize, warnings: &mut Warnings) {
+    for node in group.children() {
+        *seen += 1;
+        let name = |kind: &str| if node.id().is_empty() { format!("<{kind}> #{seen}") } else { node.id().to_string() };
+        match node {
+            usvg::Node::Group(g) => {
+                // usvg wraps an element with effects in a group of its own
+                let inner = g.children().first().map(|c| c.id()).filter(|id| !id.is_empty());
+                let name = |kind: &str| if g.id().is_empty() { inner.map_or_else(|| name(kind), str::to_string) } else { name(kind) };
+                if !g.filters().is_empty() {
+                    match filters {
+                        Policy::Skip => warnings.add("filter", name("g"), "filtered content is left out"),
+                        Policy::Shadow if !crate::filter::shadows(g).is_empty() => {
+                            warnings.add("filter", name("g"), "only the offset of its shadow is kept, not its blur or color")
+                        }
+                        _ => warnings.add("filter", name("g"), "filter effects are ignored"),
+                    }
+                }
+                if g.mask().is_some() && !masks {
+                    warnings.add("mask", name("g"), "masks are ignored, the content is combined whole");
+                }
+                if g.clip_path().is_some_and(|clip| crate::mask::viewport(clip).is_none()) {
+                    warnings.add("clip-path", name("g"), "clip paths are ignored, the content is combined whole");
+                }
+                if g.children().is_empty() && !g.id().is_empty() {
+                    warnings.add("empty-group", name("g"), "group has nothing in it");
+                }
+                if g.filters().is_empty() || filters != Policy::Skip {
+                    walk(g, trace, masks, filters, seen, warnings);
+                }
+            }
+            usvg::Node::Image(_) if !trace => warnings.add("image", name("image"), "images are dropped unless traced with --trace"),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use clipper2::*;
+
+use crate::clip;
+use crate::parts::Part;
+
+type Pt = (f64, f64);
+
+/// Cut lines added for weeding vinyl: a box around everything and straight
+/// lines across the waste between parts.
+pub struct Weeding {
+    pub weed_box: Vec<Pt>,
+    pub lines: Vec<Vec<Pt>>,
+}
+
+/// Box `margin` away from the result, with a vertical (horizontal) line in
+/// every gap between the parts' horizontal (vertical) extents, plus a grid
+/// every `spacing` units when given. Lines are clipped to the waste only.
+pub fn weed<P: PointScaler>(paths: &Paths<P>, parts: &[Part], margin: f64, spacing: Option<f64>) -> Weeding {
+    let b = paths.bounds();
+    let (x0, y0, x1, y1) = (b.min.x() - margin, b.min.y() - margin, b.max.x() + margin, b.max.y() + margin);
+
+    let extents: Vec<Bounds<P>> = parts.iter().filter_map(|p| paths.get(p.outer).map(|r| r.bounds())).collect();
+    let mut xs = gaps(extents.iter().map(|e| (e.min.x(), e.max.x())).collect());
+    let mut ys = gaps(extents.iter().map(|e| (e.min.y(), e.max.y())).collect());
+    if let Some(step) = spacing.filter(|s| *s > 0.0) {
+        xs.extend(steps(x0, x1, step));
+        ys.extend(steps(y0, y1, step));
+    }
+
+    let mut lines = Vec::new();
+    for x in xs {
+        lines.extend(clip::clip_polyline(&[(x, y0), (x, y1)], paths, false));
+    }
+    for y in ys {
+        lines.extend(clip::clip_polyline(&[(x0, y), (x1, y)], paths, false));
+    }
+
+    Weeding { weed_box: vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)], lines }
+}
+
+/// Midpoints of the gaps left between overlapping intervals.
+fn gaps(mut spans: Vec<(f64, f64)>) -> Vec<f64> {
+    spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut mids = Vec::new();
+    let mut reach = f64::NEG_INFINITY;
+    for (lo, hi) in spans {
+        if reach.is_finite() && lo > reach {
+            mids.push((reach + lo) / 2.0);
+        }
+        reach = reach.max(hi);
+    }
+    mids
+}
+
+fn steps(from: f64, to: f64, step: f64) -> impl Iterator<Item = f64> {
+    let n = ((to - from) / step).floor() as usize;
+    (1..=n).map(move |k| from + k as f64 * step).filter(move |v| *v < to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64) -> Vec<Pt> {
+        vec![(x, y), (x + size, y), (x + size, y + size), (x, y + size)]
+    }
+
+    fn length(lines: &[Vec<Pt>]) -> f64 {
+        lines.iter().flat_map(|l| l.windows(2)).map(|w| (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1)).sum()
+    }
+
+    #[test]
+    fn gaps_are_between_intervals_that_do_not_overlap() {
+        assert_eq!(gaps(vec![(20.0, 30.0), (0.0, 10.0)]), [15.0]);
+        assert!(gaps(vec![(0.0, 10.0), (5.0, 20.0)]).is_empty());
+        // one long interval covers what lies within it
+        assert_eq!(gaps(vec![(0.0, 50.0), (10.0, 20.0), (60.0, 70.0)]), [55.0]);
+        assert!(gaps(vec![]).is_empty());
+    }
+
+    #[test]
+    fn grid_lines_fall_strictly_inside() {
+        assert_eq!(steps(0.0, 30.0, 10.0).collect::<Vec<_>>(), [10.0, 20.0]);
+        assert_eq!(steps(-5.0, 20.0, 10.0).collect::<Vec<_>>(), [5.0, 15.0]);
+    }
+
+    #[test]
+    fn waste_is_boxed_and_split_between_parts() {
+        let paths: Paths<Centi> = vec![square(0.0, 0.0, 10.0), square(20.0, 0.0, 10.0)].into();
+        let parts = crate::parts::parts(&paths);
+        let weeding = weed(&paths, &parts, 2.0, None);
+        assert_eq!(weeding.weed_box, [(-2.0, -2.0), (32.0, -2.0), (32.0, 12.0), (-2.0, 12.0)]);
+        assert_eq!(weeding.lines.len(), 1);
+        assert!(weeding.lines[0].iter().all(|p| (p.0 - 15.0).abs() < 1e-6));
+        assert!((length(&weeding.lines) - 14.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn grid_lines_are_kept_off_the_parts() {
+        let paths: Paths<Centi> = vec![square(0.0, 0.0, 10.0)].into();
+        let parts = crate::parts::parts(&paths);
+        let weeding = weed(&paths, &parts, 5.0, Some(10.0));
+        // lines at x = 5 and y = 5, each cut through by the part
+        assert_eq!(weeding.lines.len(), 4);
+        assert!((length(&weeding.lines) - 4.0 * 5.0).abs() < 1e-6);
+    }
+}